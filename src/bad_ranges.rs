@@ -0,0 +1,115 @@
+use crate::file_utils::from_orig_file_size::calc_data_chunk_count;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::range_set::RangeSet;
+use crate::sbx_specs::{ver_to_data_size, Version};
+
+pub const FILL_MARKER: &[u8; 16] = b"<BLKAR:BAD DATA>";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FillPolicy {
+    Zeros,
+    Marker,
+    Hole,
+}
+
+// chunk indices are tracked as ranges, as containers
+// may have billions of chunks
+#[derive(Clone, Debug)]
+pub struct ChunkTracker {
+    version: Version,
+    recovered: RangeSet,
+    failed: RangeSet,
+}
+
+pub fn string_to_fill_policy(string: &str) -> Result<FillPolicy, ()> {
+    match string.to_lowercase().as_str() {
+        "zeros" => Ok(FillPolicy::Zeros),
+        "marker" => Ok(FillPolicy::Marker),
+        "hole" => Ok(FillPolicy::Hole),
+        _ => Err(()),
+    }
+}
+
+// fills the buffer with the marker pattern, aligned to
+// the position of the buffer in the output file
+pub fn fill_marker(buf: &mut [u8], pos: u64) {
+    let len = FILL_MARKER.len() as u64;
+
+    for (i, p) in buf.iter_mut().enumerate() {
+        *p = FILL_MARKER[((pos + i as u64) % len) as usize];
+    }
+}
+
+impl ChunkTracker {
+    pub fn new(version: Version) -> ChunkTracker {
+        ChunkTracker {
+            version,
+            recovered: RangeSet::new(),
+            failed: RangeSet::new(),
+        }
+    }
+
+    pub fn mark_recovered(&mut self, index: u64) {
+        self.recovered.insert(index);
+    }
+
+    // a chunk may be seen more than once, e.g. unordered container,
+    // a chunk recovered at any point is not counted as failed
+    pub fn mark_failed(&mut self, index: u64) {
+        self.failed.insert(index);
+    }
+
+    // returns byte ranges [start, end) of the original file,
+    // chunks never seen are counted as bad only if unseen_is_bad is set
+    pub fn bad_ranges(&self, orig_file_size: Option<u64>, unseen_is_bad: bool) -> Vec<(u64, u64)> {
+        let data_size = ver_to_data_size(self.version) as u64;
+
+        let chunk_count = match orig_file_size {
+            Some(size) => calc_data_chunk_count(self.version, size),
+            None => std::cmp::max(
+                self.recovered.end().unwrap_or(0),
+                self.failed.end().unwrap_or(0),
+            ),
+        };
+
+        let bad_chunks = if unseen_is_bad {
+            self.recovered.gaps(0, chunk_count)
+        } else {
+            self.failed.difference(&self.recovered, 0, chunk_count)
+        };
+
+        bad_chunks
+            .into_iter()
+            .map(|(start, end_exc)| {
+                let start = start * data_size;
+                let end_exc = end_exc * data_size;
+                match orig_file_size {
+                    Some(size) => (start, std::cmp::min(end_exc, size)),
+                    None => (start, end_exc),
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn write_to_file(ranges: &[(u64, u64)], path: &str) -> Result<(), Error> {
+    let mut string = String::with_capacity(200);
+    for &(start, end_exc) in ranges.iter() {
+        string.push_str(&format!("range={}-{}\n", start, end_exc));
+    }
+
+    let mut writer = FileWriter::new(
+        path,
+        FileWriterParam {
+            read: false,
+            append: false,
+            truncate: true,
+            buffered: true,
+        },
+    )?;
+
+    writer.write(string.as_bytes())?;
+
+    Ok(())
+}
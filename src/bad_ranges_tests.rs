@@ -0,0 +1,90 @@
+#![cfg(test)]
+
+use crate::bad_ranges::*;
+use crate::sbx_specs::Version;
+
+#[test]
+fn test_string_to_fill_policy() {
+    assert_eq!(Ok(FillPolicy::Zeros), string_to_fill_policy("zeros"));
+    assert_eq!(Ok(FillPolicy::Marker), string_to_fill_policy("MARKER"));
+    assert_eq!(Ok(FillPolicy::Hole), string_to_fill_policy("Hole"));
+    assert_eq!(Err(()), string_to_fill_policy("zero"));
+}
+
+#[test]
+fn test_fill_marker_aligned_to_pos() {
+    let mut buf = [0; 20];
+
+    fill_marker(&mut buf, 14);
+
+    assert_eq!(b"A>", &buf[0..2]);
+    assert_eq!(FILL_MARKER, &buf[2..18]);
+    assert_eq!(b"<B", &buf[18..20]);
+}
+
+#[test]
+fn test_bad_ranges_failed_only() {
+    let mut tracker = ChunkTracker::new(Version::V1);
+
+    tracker.mark_recovered(0);
+    tracker.mark_failed(1);
+    tracker.mark_failed(2);
+    tracker.mark_recovered(4);
+    tracker.mark_failed(5);
+
+    assert_eq!(
+        vec![(496, 496 * 3), (496 * 5, 496 * 6)],
+        tracker.bad_ranges(None, false)
+    );
+    assert_eq!(
+        vec![(496, 496 * 3), (496 * 5, 2700)],
+        tracker.bad_ranges(Some(2700), false)
+    );
+}
+
+#[test]
+fn test_bad_ranges_unseen_is_bad() {
+    let mut tracker = ChunkTracker::new(Version::V1);
+
+    tracker.mark_recovered(0);
+    tracker.mark_recovered(2);
+
+    assert_eq!(vec![(496, 496 * 2)], tracker.bad_ranges(None, true));
+    assert_eq!(
+        vec![(496, 496 * 2), (496 * 3, 2000)],
+        tracker.bad_ranges(Some(2000), true)
+    );
+    assert_eq!(
+        Vec::<(u64, u64)>::new(),
+        tracker.bad_ranges(Some(2000), false)
+    );
+}
+
+#[test]
+fn test_recovered_overrides_failed() {
+    let mut tracker = ChunkTracker::new(Version::V1);
+
+    tracker.mark_failed(0);
+    tracker.mark_recovered(0);
+    tracker.mark_failed(0);
+
+    assert_eq!(Vec::<(u64, u64)>::new(), tracker.bad_ranges(None, true));
+}
+
+#[test]
+fn test_bad_ranges_out_of_order() {
+    let mut tracker = ChunkTracker::new(Version::V1);
+
+    tracker.mark_failed(6);
+    tracker.mark_recovered(3);
+    tracker.mark_failed(1);
+    tracker.mark_recovered(0);
+    tracker.mark_failed(2);
+    tracker.mark_recovered(6);
+
+    assert_eq!(vec![(496, 496 * 3)], tracker.bad_ranges(None, false));
+    assert_eq!(
+        vec![(496, 496 * 3), (496 * 4, 496 * 6)],
+        tracker.bad_ranges(None, true)
+    );
+}
@@ -11,33 +11,48 @@ fn real_main() -> i32 {
         .about("Archive with forward error correction and sector level recoverability")
         .subcommand(cli_calc::sub_command())
         .subcommand(cli_check::sub_command())
+        .subcommand(cli_convert::sub_command())
         .subcommand(cli_decode::sub_command())
+        .subcommand(cli_diff::sub_command())
         .subcommand(cli_encode::sub_command())
         .subcommand(cli_repair::sub_command())
         .subcommand(cli_rescue::sub_command())
+        .subcommand(cli_rollback::sub_command())
+        .subcommand(cli_scan::sub_command())
         .subcommand(cli_show::sub_command())
         .subcommand(cli_sort::sub_command())
         .subcommand(cli_update::sub_command())
+        .subcommand(cli_verify::sub_command())
         .get_matches();
 
     if let Some(matches) = matches.subcommand_matches("calc") {
         cli_calc::calc(matches)
     } else if let Some(matches) = matches.subcommand_matches("check") {
         cli_check::check(matches)
+    } else if let Some(matches) = matches.subcommand_matches("convert") {
+        cli_convert::convert(matches)
     } else if let Some(matches) = matches.subcommand_matches("decode") {
         cli_decode::decode(matches)
+    } else if let Some(matches) = matches.subcommand_matches("diff") {
+        cli_diff::diff(matches)
     } else if let Some(matches) = matches.subcommand_matches("encode") {
         cli_encode::encode(matches)
     } else if let Some(matches) = matches.subcommand_matches("repair") {
         cli_repair::repair(matches)
     } else if let Some(matches) = matches.subcommand_matches("rescue") {
         cli_rescue::rescue(matches)
+    } else if let Some(matches) = matches.subcommand_matches("rollback") {
+        cli_rollback::rollback(matches)
+    } else if let Some(matches) = matches.subcommand_matches("scan") {
+        cli_scan::scan(matches)
     } else if let Some(matches) = matches.subcommand_matches("show") {
         cli_show::show(matches)
     } else if let Some(matches) = matches.subcommand_matches("sort") {
         cli_sort::sort(matches)
     } else if let Some(matches) = matches.subcommand_matches("update") {
         cli_update::update(matches)
+    } else if let Some(matches) = matches.subcommand_matches("verify") {
+        cli_verify::verify(matches)
     } else {
         exit_with_msg!(ok json_printer::JSONPrinter::new(false, output_channel::OutputChannel::Stdout)
                        => "Invoke with -h or --help for help message\n");
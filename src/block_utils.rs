@@ -228,33 +228,49 @@ pub fn get_ref_block(
             }
         }
 
-        match ref_block_choice {
-            RefBlockChoice::Any => {
-                if let Some(_) = meta_block {
-                    break;
-                }
-                if let Some(_) = data_block {
-                    break;
-                }
-            }
-            RefBlockChoice::Prefer(bt) | RefBlockChoice::MustBe(bt) => match bt {
-                BlockType::Meta => {
-                    if let Some(_) = meta_block {
-                        break;
-                    }
-                }
-                BlockType::Data => {
-                    if let Some(_) = data_block {
-                        break;
-                    }
-                }
-            },
+        if ref_block_found(ref_block_choice, &meta_block, &data_block) {
+            break;
         }
     }
 
     reporter.stop();
 
-    Ok(match ref_block_choice {
+    let res = choose_ref_block(ref_block_choice, meta_block, data_block);
+
+    // prefer the final metadata block of a streamed container
+    if let Some((pos, ref block)) = res {
+        if block.is_meta() && block.get_TRL().unwrap().is_some() {
+            if let Some((trailing_pos, trailing_block)) =
+                get_trailing_meta_block(in_file, seek_to + pos, block, seek_to + required_len)?
+            {
+                return Ok(Some((trailing_pos - seek_to, trailing_block)));
+            }
+        }
+    }
+
+    Ok(res)
+}
+
+fn ref_block_found(
+    ref_block_choice: RefBlockChoice,
+    meta_block: &Option<(u64, Block)>,
+    data_block: &Option<(u64, Block)>,
+) -> bool {
+    match ref_block_choice {
+        RefBlockChoice::Any => meta_block.is_some() || data_block.is_some(),
+        RefBlockChoice::Prefer(bt) | RefBlockChoice::MustBe(bt) => match bt {
+            BlockType::Meta => meta_block.is_some(),
+            BlockType::Data => data_block.is_some(),
+        },
+    }
+}
+
+fn choose_ref_block(
+    ref_block_choice: RefBlockChoice,
+    meta_block: Option<(u64, Block)>,
+    data_block: Option<(u64, Block)>,
+) -> Option<(u64, Block)> {
+    match ref_block_choice {
         RefBlockChoice::Any => match (meta_block, data_block) {
             (Some(m), _) => Some(m),
             (_, Some(d)) => Some(d),
@@ -282,7 +298,121 @@ pub fn get_ref_block(
                 (_, _) => None,
             },
         },
-    })
+    }
+}
+
+// same as get_ref_block, but scans an in-memory buffer, e.g. the initial
+// window read from a non-seekable input
+pub fn get_ref_block_from_buffer(
+    buffer: &[u8],
+    ref_block_choice: RefBlockChoice,
+) -> Option<(u64, Block)> {
+    let mut block = Block::dummy();
+
+    let mut meta_block = None;
+    let mut data_block = None;
+
+    let mut pos: usize = 0;
+
+    while pos + SBX_SCAN_BLOCK_SIZE <= buffer.len() {
+        if block
+            .sync_from_buffer_header_only(&buffer[pos..pos + SBX_SCAN_BLOCK_SIZE])
+            .is_err()
+        {
+            pos += SBX_SCAN_BLOCK_SIZE;
+            continue;
+        }
+
+        let block_size = ver_to_block_size(block.get_version());
+
+        if pos + block_size > buffer.len() {
+            break;
+        }
+
+        if block
+            .sync_from_buffer(&buffer[pos..pos + block_size], None, None)
+            .is_err()
+        {
+            pos += block_size;
+            continue;
+        }
+
+        match block.block_type() {
+            BlockType::Meta => {
+                if meta_block.is_none() {
+                    meta_block = Some((pos as u64, block.clone()));
+                }
+            }
+            BlockType::Data => {
+                if data_block.is_none() {
+                    data_block = Some((pos as u64, block.clone()));
+                }
+            }
+        }
+
+        if ref_block_found(ref_block_choice, &meta_block, &data_block) {
+            break;
+        }
+
+        pos += block_size;
+    }
+
+    choose_ref_block(ref_block_choice, meta_block, data_block)
+}
+
+// looks for the final metadata block appended after the last data block,
+// going backwards from end_pos until a valid block of the same container is found
+pub fn get_trailing_meta_block(
+    in_file: &str,
+    ref_block_pos: u64,
+    ref_block: &Block,
+    end_pos: u64,
+) -> Result<Option<(u64, Block)>, Error> {
+    let version = ref_block.get_version();
+
+    let block_size = ver_to_block_size(version) as u64;
+
+    if end_pos < ref_block_pos + block_size {
+        return Ok(None);
+    }
+
+    let block_count = (end_pos - ref_block_pos) / block_size;
+
+    let header_pred = header_pred_same_ver_uid!(ref_block);
+
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let mut block = Block::dummy();
+
+    let mut reader = FileReader::new(
+        in_file,
+        FileReaderParam {
+            write: false,
+            buffered: false,
+        },
+    )?;
+
+    for block_index in (1..block_count).rev() {
+        let pos = ref_block_pos + block_index * block_size;
+
+        reader.seek(SeekFrom::Start(pos))?;
+
+        let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
+
+        if read_res.eof_seen {
+            continue;
+        }
+
+        if let Ok(()) = block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+            if block.is_meta() && block.get_TRL().unwrap().is_none() {
+                return Ok(Some((pos, block)));
+            } else {
+                return Ok(None);
+            }
+        }
+    }
+
+    Ok(None)
 }
 
 pub fn guess_burst_err_resistance_level(
@@ -350,9 +480,6 @@ pub fn guess_burst_err_resistance_level(
     let mut seq_nums: SmallVec<[Option<u32>; BLOCKS_TO_SAMPLE_BASE_NUM]> =
         smallvec![None; blocks_to_sample];
 
-    let mut mismatches_for_level: [usize; SBX_MAX_BURST_ERR_RESISTANCE + 1] =
-        [0; SBX_MAX_BURST_ERR_RESISTANCE + 1];
-
     let mut blocks_processed = 0;
 
     let header_pred = header_pred_same_ver_uid!(ref_block);
@@ -381,6 +508,72 @@ pub fn guess_burst_err_resistance_level(
         blocks_processed += 1;
     }
 
+    Ok(guess_burst_err_resistance_level_from_seq_nums(
+        &seq_nums,
+        data_shards,
+        parity_shards,
+    ))
+}
+
+// same as guess_burst_err_resistance_level, but samples the blocks of an
+// in-memory buffer starting at from_pos
+pub fn guess_burst_err_resistance_level_from_buffer(
+    buffer: &[u8],
+    from_pos: usize,
+    ref_block_pos: u64,
+    ref_block: &Block,
+) -> Result<Option<usize>, Error> {
+    let rs_enabled = ver_uses_rs(ref_block.get_version());
+
+    if !rs_enabled {
+        return Ok(None);
+    }
+
+    let data_shards = get_RSD_from_ref_block!(
+        ref_block_pos,
+        ref_block,
+        "guess the burst error resistance level"
+    );
+    let parity_shards = get_RSP_from_ref_block!(
+        ref_block_pos,
+        ref_block,
+        "guess the burst error resistance level"
+    );
+
+    let mut block = Block::dummy();
+
+    let block_size = ver_to_block_size(ref_block.get_version());
+
+    let blocks_to_sample = (1 + parity_shards) + SBX_MAX_BURST_ERR_RESISTANCE + 1;
+
+    let header_pred = header_pred_same_ver_uid!(ref_block);
+
+    let seq_nums: Vec<Option<u32>> = buffer[from_pos..]
+        .chunks_exact(block_size)
+        .take(blocks_to_sample)
+        .map(
+            |chunk| match block.sync_from_buffer(chunk, Some(&header_pred), None) {
+                Ok(()) => Some(block.get_seq_num()),
+                Err(_) => None,
+            },
+        )
+        .collect();
+
+    Ok(guess_burst_err_resistance_level_from_seq_nums(
+        &seq_nums,
+        data_shards,
+        parity_shards,
+    ))
+}
+
+fn guess_burst_err_resistance_level_from_seq_nums(
+    seq_nums: &[Option<u32>],
+    data_shards: usize,
+    parity_shards: usize,
+) -> Option<usize> {
+    let mut mismatches_for_level: [usize; SBX_MAX_BURST_ERR_RESISTANCE + 1] =
+        [0; SBX_MAX_BURST_ERR_RESISTANCE + 1];
+
     // count mismatches
     for level in 0..mismatches_for_level.len() {
         for index in 0..seq_nums.len() {
@@ -416,9 +609,9 @@ pub fn guess_burst_err_resistance_level(
     }
 
     if same_as_best_guess == mismatches_for_level.len() {
-        Ok(None)
+        None
     } else {
-        Ok(Some(best_guess))
+        Some(best_guess)
     }
 }
 
@@ -1,10 +1,15 @@
 use crate::block_utils::RefBlockChoice;
 use crate::cli_utils::setup_ctrlc_handler;
+use crate::damage_map::DamageMap;
 use crate::file_reader::{FileReader, FileReaderParam};
 use crate::file_utils;
 use crate::general_error::Error;
 use crate::hash_stats::HashStats;
+use crate::health_map;
+use crate::health_map::{BlockHealth, HealthMap, MapStyle};
+use crate::integer_utils::IntegerUtils;
 use crate::json_printer::{BracketType, JSONPrinter};
+use crate::log::*;
 use crate::misc_utils;
 use crate::misc_utils::RequiredLenAndSeekTo;
 use crate::misc_utils::{PositionOrLength, RangeEnd};
@@ -14,11 +19,14 @@ use crate::sbx_block;
 use crate::sbx_block::{Block, BlockType};
 use crate::sbx_container_content;
 use crate::sbx_specs::Version;
-use crate::sbx_specs::{ver_to_block_size, ver_to_usize, SBX_LARGEST_BLOCK_SIZE};
+use crate::sbx_specs::{ver_to_block_size, ver_to_usize, ver_uses_rs, SBX_LARGEST_BLOCK_SIZE};
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
 use crate::time_utils;
 use std::fmt;
 use std::io::SeekFrom;
 use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 
 pub enum HashAction {
@@ -40,6 +48,13 @@ pub struct Param {
     hash_action: HashAction,
     burst: Option<usize>,
     in_file: String,
+    log_file: Option<String>,
+    damage_map_file: Option<String>,
+    forecast: bool,
+    map_style: Option<MapStyle>,
+    map_file: Option<String>,
+    blocks_per_cell: Option<u64>,
+    sparse_map_file: Option<String>,
     verbose: bool,
     pr_verbosity_level: PRVerbosityLevel,
 }
@@ -58,6 +73,13 @@ impl Param {
         hash_action: HashAction,
         burst: Option<usize>,
         in_file: &str,
+        log_file: Option<&str>,
+        damage_map_file: Option<&str>,
+        forecast: bool,
+        map_style: Option<MapStyle>,
+        map_file: Option<&str>,
+        blocks_per_cell: Option<u64>,
+        sparse_map_file: Option<&str>,
         verbose: bool,
         pr_verbosity_level: PRVerbosityLevel,
     ) -> Param {
@@ -74,6 +96,25 @@ impl Param {
             hash_action,
             burst,
             in_file: String::from(in_file),
+            log_file: match log_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            damage_map_file: match damage_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            forecast,
+            map_style,
+            map_file: match map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            blocks_per_cell,
+            sparse_map_file: match sparse_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
             verbose,
             pr_verbosity_level,
         }
@@ -83,6 +124,7 @@ impl Param {
 #[derive(Clone, Debug)]
 struct CheckStats {
     block_size: u64,
+    pub bytes_processed: u64,
     pub meta_or_par_blocks_decoded: u64,
     pub data_or_par_blocks_decoded: u64,
     pub blocks_decode_failed: u64,
@@ -100,6 +142,7 @@ impl CheckStats {
 
         CheckStats {
             block_size: ver_to_block_size(ref_block.get_version()) as u64,
+            bytes_processed: 0,
             meta_or_par_blocks_decoded: 0,
             data_or_par_blocks_decoded: 0,
             blocks_decode_failed: 0,
@@ -118,10 +161,132 @@ impl CheckStats {
     }
 }
 
+struct DamageMapper {
+    map: DamageMap,
+    meta_enabled: Option<bool>,
+    last_seq_num: Option<u32>,
+}
+
+impl DamageMapper {
+    fn new(
+        ref_block_pos: u64,
+        ref_block: &Block,
+        data_par_burst: Option<(usize, usize, usize)>,
+    ) -> DamageMapper {
+        use crate::file_utils::from_orig_file_size::calc_data_block_count_exc_burst_gaps;
+
+        let version = ref_block.get_version();
+        let block_size = ver_to_block_size(version) as u64;
+
+        // a data block sitting at the position it would be written to
+        // with metadata enabled means there is a metadata block in front of it
+        let meta_enabled = if ref_block.is_meta() {
+            Some(true)
+        } else {
+            Some(
+                sbx_block::calc_data_block_write_pos(
+                    version,
+                    ref_block.get_seq_num(),
+                    Some(true),
+                    data_par_burst,
+                ) == ref_block_pos - ref_block_pos % block_size,
+            )
+        };
+
+        let last_seq_num = if ref_block.is_meta() {
+            ref_block
+                .get_FSZ()
+                .unwrap()
+                .map(|x| calc_data_block_count_exc_burst_gaps(version, data_par_burst, x) as u32)
+        } else {
+            None
+        };
+
+        DamageMapper {
+            map: DamageMap::new(&ref_block.get_uid(), version, data_par_burst),
+            meta_enabled,
+            last_seq_num,
+        }
+    }
+
+    fn check_block(&mut self, pos: u64, block: Option<&Block>, blank: bool, omitted: bool) {
+        let block_size = ver_to_block_size(self.map.version()) as u64;
+
+        let seq_num = sbx_block::calc_seq_num_at_index(
+            pos / block_size,
+            self.meta_enabled,
+            self.map.data_par_burst(),
+        );
+
+        if let Some(last_seq_num) = self.last_seq_num {
+            // skip the gaps left by burst error resistance after the last block set
+            if seq_num > last_seq_num {
+                return;
+            }
+        }
+
+        let block_okay = match block {
+            Some(block) => block.get_seq_num() == seq_num,
+            // blank blocks can only be told apart from the end
+            // of the container when the file size is known
+            None => omitted || (blank && self.last_seq_num.is_none()),
+        };
+
+        if !block_okay {
+            if seq_num == 0 {
+                self.map.add_meta_block(pos);
+            } else {
+                self.map.add_data_block(seq_num, pos);
+            }
+        }
+    }
+
+    fn add_missing_blocks(&mut self, end_pos: u64) {
+        let version = self.map.version();
+        let data_par_burst = self.map.data_par_burst();
+
+        let last_seq_num = match self.last_seq_num {
+            None => return,
+            Some(x) => x,
+        };
+
+        let meta_block_pos_s = if ver_uses_rs(version) {
+            sbx_block::calc_meta_block_all_write_pos_s(version, data_par_burst).to_vec()
+        } else if self.meta_enabled == Some(true) {
+            vec![0]
+        } else {
+            vec![]
+        };
+
+        for &pos in meta_block_pos_s.iter() {
+            if pos >= end_pos {
+                self.map.add_meta_block(pos);
+            }
+        }
+
+        for seq_num in 1..=last_seq_num {
+            let pos = sbx_block::calc_data_block_write_pos(
+                version,
+                seq_num,
+                self.meta_enabled,
+                data_par_burst,
+            );
+
+            if pos >= end_pos {
+                self.map.add_data_block(seq_num, pos);
+            }
+        }
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct Stats {
     version: Version,
     check_stats: Option<CheckStats>,
+    damage_map: Option<DamageMap>,
+    forecast: bool,
+    health_map: Option<HealthMap>,
+    map_style: Option<MapStyle>,
     do_hash: bool,
     recorded_hash: Option<HashBytes>,
     hash_result: Option<Result<(HashStats, HashBytes), Error>>,
@@ -133,6 +298,10 @@ impl Stats {
         Stats {
             version: ref_block.get_version(),
             check_stats: None,
+            damage_map: None,
+            forecast: false,
+            health_map: None,
+            map_style: None,
             do_hash,
             recorded_hash: None,
             hash_result: None,
@@ -141,6 +310,45 @@ impl Stats {
     }
 }
 
+impl Log for CheckStats {
+    fn serialize(&self) -> String {
+        let mut string = String::with_capacity(200);
+        string.push_str(&format!("bytes_processed={}\n", self.bytes_processed));
+        string.push_str(&format!(
+            "meta_blocks_decoded={}\n",
+            self.meta_or_par_blocks_decoded
+        ));
+        string.push_str(&format!(
+            "data_blocks_decoded={}\n",
+            self.data_or_par_blocks_decoded
+        ));
+        string.push_str(&format!("blocks_failed={}\n", self.blocks_decode_failed));
+        string.push_str(&format!("okay_blank_blocks={}\n", self.okay_blank_blocks));
+
+        string
+    }
+
+    fn deserialize(&mut self, input: &[u8]) -> Result<(), ()> {
+        let fields = parse_u64_fields(
+            input,
+            &[
+                "bytes_processed",
+                "meta_blocks_decoded",
+                "data_blocks_decoded",
+                "blocks_failed",
+                "okay_blank_blocks",
+            ],
+        )?;
+
+        self.bytes_processed = u64::round_down_to_multiple(fields[0], self.block_size);
+        self.meta_or_par_blocks_decoded = fields[1];
+        self.data_or_par_blocks_decoded = fields[2];
+        self.blocks_decode_failed = fields[3];
+        self.okay_blank_blocks = fields[4];
+        Ok(())
+    }
+}
+
 impl ProgressReport for CheckStats {
     fn start_time_mut(&mut self) -> &mut f64 {
         &mut self.start_time
@@ -159,6 +367,28 @@ impl ProgressReport for CheckStats {
     }
 }
 
+// margins are negative for unrecoverable block sets, which the JSON
+// printer would quote, so they are written out as numbers here
+//
+// this is only used after other fields, so a comma is always needed
+fn write_parity_margin(
+    f: &mut fmt::Formatter,
+    json_printer: &JSONPrinter,
+    label: &str,
+    margin: i64,
+) -> fmt::Result {
+    if json_printer.json_enabled() {
+        writeln!(
+            f,
+            ",\"{}\": {}",
+            misc_utils::to_camelcase(label.trim()),
+            margin
+        )
+    } else {
+        writeln!(f, "{} : {}", label, margin)
+    }
+}
+
 impl fmt::Display for Stats {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         let block_size = ver_to_block_size(self.version);
@@ -214,6 +444,122 @@ impl fmt::Display for Stats {
                 "Number of blocks failed check            : {}",
                 check_stats.blocks_decode_failed
             )?;
+            if let Some(damage_map) = &self.damage_map {
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of damaged block sets             : {}",
+                    damage_map.block_sets().len()
+                )?;
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of unrecoverable block sets       : {}",
+                    damage_map.unrecoverable_block_set_count()
+                )?;
+
+                if let (true, Some((_, parity, _))) = (self.forecast, damage_map.data_par_burst()) {
+                    let block_sets = damage_map.block_sets();
+
+                    if json_printer.json_enabled() {
+                        json_printer.write_open_bracket(
+                            f,
+                            Some("damaged block sets"),
+                            BracketType::Square,
+                        )?;
+
+                        for (index, blocks) in block_sets.iter() {
+                            json_printer.write_open_bracket(f, None, BracketType::Curly)?;
+                            write_maybe_json!(f, json_printer, "index : {}", index)?;
+                            write_maybe_json!(
+                                f,
+                                json_printer,
+                                "damaged blocks : {}",
+                                blocks.len()
+                            )?;
+                            write_parity_margin(
+                                f,
+                                json_printer,
+                                "parity margin",
+                                damage_map.parity_margin(blocks.len()),
+                            )?;
+                            json_printer.write_close_bracket(f)?;
+                        }
+
+                        json_printer.write_close_bracket(f)?;
+                    } else {
+                        let block_sets_str: Vec<String> = block_sets
+                            .iter()
+                            .map(|(index, blocks)| {
+                                format!(
+                                    "{} (margin {})",
+                                    index,
+                                    damage_map.parity_margin(blocks.len())
+                                )
+                            })
+                            .collect();
+
+                        write_if!(not_json => f, json_printer =>
+                                  "Damaged block sets                       : {}",
+                                  if block_sets_str.is_empty() {
+                                      String::from("None")
+                                  } else {
+                                      block_sets_str.join(", ")
+                                  };
+                        )?;
+                    }
+
+                    match damage_map.worst_block_set() {
+                        None => write_maybe_json!(
+                            f,
+                            json_printer,
+                            "Worst block set                          : {}",
+                            null_if_json_else_NA!(json_printer)
+                        )?,
+                        Some((index, count)) => {
+                            if json_printer.json_enabled() {
+                                json_printer.write_open_bracket(
+                                    f,
+                                    Some("worst block set"),
+                                    BracketType::Curly,
+                                )?;
+                                write_maybe_json!(f, json_printer, "index : {}", index)?;
+                                write_maybe_json!(f, json_printer, "damaged blocks : {}", count)?;
+                                write_parity_margin(
+                                    f,
+                                    json_printer,
+                                    "parity margin",
+                                    damage_map.parity_margin(count),
+                                )?;
+                                json_printer.write_close_bracket(f)?;
+                            } else {
+                                write_if!(not_json => f, json_printer =>
+                                          "Worst block set                          : {} ({} damaged, parity margin {})",
+                                          index,
+                                          count,
+                                          damage_map.parity_margin(count);
+                                )?;
+                            }
+                        }
+                    }
+
+                    write_parity_margin(
+                        f,
+                        json_printer,
+                        "Minimum parity margin                   ",
+                        match damage_map.worst_block_set() {
+                            None => parity as i64,
+                            Some((_, count)) => damage_map.parity_margin(count),
+                        },
+                    )?;
+                    write_maybe_json!(
+                        f,
+                        json_printer,
+                        "Fully repairable                         : {}",
+                        damage_map.unrecoverable_block_set_count() == 0
+                    )?;
+                }
+            }
 
             let (hour, minute, second) = time_utils::seconds_to_hms(check_time_elapsed);
             write_maybe_json!(
@@ -280,6 +626,18 @@ impl fmt::Display for Stats {
             minute,
             second
         )?;
+        if let Some(damage_map) = &self.damage_map {
+            if self.forecast && damage_map.data_par_burst().is_some() {
+                match damage_map.unrecoverable_block_set_count() {
+                    0 => {
+                        write_if!(not_json => f, json_printer => "All block sets are fully repairable";)?;
+                    }
+                    n => {
+                        write_if!(not_json => f, json_printer => "Not fully repairable, {} block set(s) have more damaged blocks than parity blocks", n;)?;
+                    }
+                }
+            }
+        }
         if self.do_hash {
             match (&self.recorded_hash, &self.hash_result) {
                 (Some(recorded_hash), Some(Ok((_, computed_hash)))) => {
@@ -304,6 +662,61 @@ impl fmt::Display for Stats {
             }
         }
 
+        if let (Some(health_map), Some(map_style)) = (&self.health_map, self.map_style) {
+            if json_printer.json_enabled() {
+                json_printer.write_open_bracket(f, Some("health map"), BracketType::Curly)?;
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "blocks per cell : {}",
+                    health_map.blocks_per_cell()
+                )?;
+                write_maybe_json!(f, json_printer, "cells : {}", health_map.cells().len())?;
+                json_printer.write_open_bracket(f, Some("damaged cells"), BracketType::Square)?;
+                for (i, cell) in health_map.cells().iter().enumerate() {
+                    match cell.health() {
+                        None | Some(BlockHealth::Good) => continue,
+                        _ => {}
+                    }
+
+                    json_printer.write_open_bracket(f, None, BracketType::Curly)?;
+                    write_maybe_json!(f, json_printer, "index : {}", i)?;
+                    write_maybe_json!(f, json_printer, "pos : {}", health_map.cell_pos(i))?;
+                    write_maybe_json!(f, json_printer, "good : {}", cell.good)?;
+                    write_maybe_json!(f, json_printer, "blank : {}", cell.blank)?;
+                    write_maybe_json!(f, json_printer, "repairable : {}", cell.repairable)?;
+                    write_maybe_json!(f, json_printer, "corrupt : {}", cell.corrupt)?;
+                    json_printer.write_close_bracket(f)?;
+                }
+                json_printer.write_close_bracket(f)?;
+                json_printer.write_close_bracket(f)?;
+            } else {
+                let rows = health_map.to_rows(map_style);
+                let pos_width = format!(
+                    "{}",
+                    health_map.cell_pos(rows.len().saturating_sub(1) * health_map::MAP_WIDTH)
+                )
+                .len();
+
+                writeln!(f)?;
+                writeln!(
+                    f,
+                    "Health map, {} block(s) per cell, rows start at the byte shown :",
+                    health_map.blocks_per_cell()
+                )?;
+                for (i, row) in rows.iter().enumerate() {
+                    writeln!(
+                        f,
+                        "{:>width$} {}",
+                        health_map.cell_pos(i * health_map::MAP_WIDTH),
+                        row,
+                        width = pos_width
+                    )?;
+                }
+                writeln!(f, "Legend : {}", health_map::legend(map_style))?;
+            }
+        }
+
         json_printer.write_close_bracket(f)?;
 
         Ok(())
@@ -316,6 +729,10 @@ fn check_blocks(
     required_len: u64,
     seek_to: u64,
     ref_block: &Block,
+    data_par_burst: Option<(usize, usize, usize)>,
+    sparse_map: &Option<SparseMap>,
+    damage_mapper: &mut Option<DamageMapper>,
+    health_map: &mut Option<HealthMap>,
 ) -> Result<CheckStats, Error> {
     let stats = Arc::new(Mutex::new(CheckStats::new(ref_block, required_len)));
 
@@ -347,15 +764,24 @@ fn check_blocks(
 
     let block_size = ver_to_block_size(version);
 
+    let log_handler = match param.log_file {
+        None => LogHandler::new(None, &stats),
+        Some(ref f) => LogHandler::new(Some(f), &stats),
+    };
+
+    // read from log file and update stats if the log file exists
+    log_handler.read_from_file()?;
+
     let mut block_pos: u64;
-    let mut bytes_processed: u64 = 0;
+    let mut bytes_processed: u64 = stats.lock().unwrap().bytes_processed;
 
     let header_pred = header_pred_same_ver_uid!(ref_block);
 
+    log_handler.start();
     reporter.start();
 
-    // seek to calculated position
-    reader.seek(SeekFrom::Start(seek_to))?;
+    // seek to calculated position, skipping the part already checked
+    reader.seek(SeekFrom::Start(seek_to + bytes_processed))?;
 
     if param.verbose {
         json_printer.print_open_bracket(Some("blocks failed"), BracketType::Square);
@@ -368,6 +794,8 @@ fn check_blocks(
 
         break_if_reached_required_len!(bytes_processed, required_len);
 
+        log_handler.pop_error()?;
+
         let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
 
         block_pos = bytes_processed;
@@ -375,7 +803,49 @@ fn check_blocks(
 
         break_if_eof_seen!(read_res);
 
-        match block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+        let sync_res = block.sync_from_buffer(&buffer, Some(&header_pred), None);
+
+        let blank = misc_utils::buffer_is_blank(sbx_block::slice_buf(version, &buffer));
+
+        // hole left by a block omitted when encoding a sparse container
+        let omitted = blank
+            && match sparse_map {
+                None => false,
+                Some(sparse_map) => sparse_map.contains(sbx_block::calc_seq_num_at_index(
+                    (seek_to + block_pos) / block_size as u64,
+                    Some(true),
+                    data_par_burst,
+                )),
+            };
+
+        if let Some(ref mut health_map) = health_map {
+            let health = match sync_res {
+                Ok(_) => BlockHealth::Good,
+                Err(_) => {
+                    if omitted || (!param.report_blank && blank) {
+                        BlockHealth::Blank
+                    } else {
+                        BlockHealth::Corrupt
+                    }
+                }
+            };
+
+            health_map.add_block(seek_to + block_pos, health);
+        }
+
+        if let Some(ref mut damage_mapper) = damage_mapper {
+            damage_mapper.check_block(
+                seek_to + block_pos,
+                match sync_res {
+                    Ok(_) => Some(&block),
+                    Err(_) => None,
+                },
+                blank,
+                omitted,
+            );
+        }
+
+        match sync_res {
             Ok(_) => match block.block_type() {
                 BlockType::Meta => {
                     stats.meta_or_par_blocks_decoded += 1;
@@ -387,8 +857,8 @@ fn check_blocks(
             Err(_) => {
                 // only report error if the buffer is not completely blank
                 // unless report blank is true
-                if misc_utils::buffer_is_blank(sbx_block::slice_buf(version, &buffer)) {
-                    if param.report_blank {
+                if blank {
+                    if param.report_blank && !omitted {
                         if json_printer.json_enabled() {
                             if param.verbose {
                                 json_printer.print_open_bracket(None, BracketType::Curly);
@@ -415,6 +885,8 @@ fn check_blocks(
                 }
             }
         }
+
+        stats.bytes_processed = bytes_processed;
     }
 
     if param.verbose {
@@ -426,22 +898,35 @@ fn check_blocks(
     }
 
     reporter.stop();
+    log_handler.stop();
 
     let stats = stats.lock().unwrap().clone();
 
     Ok(stats)
 }
 
+fn get_data_par_burst(
+    param: &Param,
+    ref_block_pos: u64,
+    ref_block: &Block,
+) -> Result<Option<(usize, usize, usize)>, Error> {
+    Ok(get_data_par_burst!(
+        param,
+        ref_block_pos,
+        ref_block,
+        "check"
+    ))
+}
+
 fn hash(
     param: &Param,
     ctrlc_stop_flag: &Arc<AtomicBool>,
     orig_file_size: u64,
-    ref_block_pos: u64,
+    data_par_burst: Option<(usize, usize, usize)>,
     ref_block: &Block,
+    sparse_map: &Option<SparseMap>,
     hash_ctx: hash::Ctx,
 ) -> Result<(HashStats, HashBytes), Error> {
-    let data_par_burst = get_data_par_burst!(param, ref_block_pos, ref_block, "check");
-
     sbx_container_content::hash(
         &param.json_printer,
         param.pr_verbosity_level,
@@ -450,6 +935,7 @@ fn hash(
         &param.in_file,
         orig_file_size,
         ref_block,
+        sparse_map,
         hash_ctx,
     )
 }
@@ -486,6 +972,17 @@ pub fn check_file(param: &Param) -> Result<Option<Stats>, Error> {
 
     let mut stats = Stats::new(&ref_block, do_hash, &param.json_printer);
 
+    // holes left by the blocks recorded as omitted are not damage
+    let sparse_map = sparse_map::load(
+        &param.json_printer,
+        &ref_block,
+        Some(&param.in_file),
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+    )?;
+
     let (orig_file_size, hash_ctx) = if do_hash {
         if ref_block.is_data() {
             return Err(Error::with_msg("Reference block is not a metadata block"));
@@ -520,25 +1017,114 @@ pub fn check_file(param: &Param) -> Result<Option<Stats>, Error> {
         (None, None)
     };
 
+    // block sets of RS containers are mapped for the forecast and for marking
+    // repairable cells of the health map, but failing to determine the
+    // RS parameters only skips these
+    let map_block_sets = do_check
+        && ver_uses_rs(ref_block.get_version())
+        && ref_block.is_meta()
+        && (param.forecast || param.map_style.is_some() || param.map_file.is_some());
+
+    let data_par_burst = if do_hash
+        || map_block_sets
+        || sparse_map.is_some()
+        || (do_check && param.damage_map_file.is_some())
+    {
+        Some(get_data_par_burst(param, ref_block_pos, &ref_block))
+    } else {
+        None
+    };
+
     if do_check {
-        stats.check_stats = Some(check_blocks(
+        // the position of a hole tells which block was omitted
+        let sparse_data_par_burst = match data_par_burst {
+            Some(Ok(x)) => x,
+            Some(Err(ref e)) if sparse_map.is_some() => return Err(e.clone()),
+            _ => None,
+        };
+
+        let mut damage_mapper = match data_par_burst {
+            Some(Ok(data_par_burst)) if map_block_sets || param.damage_map_file.is_some() => {
+                Some(DamageMapper::new(ref_block_pos, &ref_block, data_par_burst))
+            }
+            Some(Err(ref e)) if param.damage_map_file.is_some() => return Err(e.clone()),
+            _ => None,
+        };
+
+        let mut health_map = if param.map_style.is_some() || param.map_file.is_some() {
+            use crate::file_utils::from_container_size::calc_total_block_count;
+
+            Some(HealthMap::new(
+                seek_to,
+                ver_to_block_size(ref_block.get_version()) as u64,
+                calc_total_block_count(ref_block.get_version(), required_len),
+                param.blocks_per_cell,
+            ))
+        } else {
+            None
+        };
+
+        let check_stats = check_blocks(
             param,
             &ctrlc_stop_flag,
             required_len,
             seek_to,
             &ref_block,
-        )?)
+            sparse_data_par_burst,
+            &sparse_map,
+            &mut damage_mapper,
+            &mut health_map,
+        )?;
+
+        if let Some(mut damage_mapper) = damage_mapper {
+            // blocks past the end of the container are missing entirely,
+            // which is only known if the check reached the end of the container
+            if !ctrlc_stop_flag.load(Ordering::SeqCst) && seek_to + required_len == file_size {
+                damage_mapper.add_missing_blocks(seek_to + check_stats.bytes_processed);
+            }
+
+            if let Some(ref f) = param.damage_map_file {
+                damage_mapper.map.write_to_file(f)?;
+            }
+
+            if let Some(ref mut health_map) = health_map {
+                health_map.mark_repairable(&damage_mapper.map);
+            }
+
+            // block sets mapped only for the health map are not reported
+            if param.forecast || param.damage_map_file.is_some() {
+                stats.forecast = param.forecast;
+                stats.damage_map = Some(damage_mapper.map);
+            }
+        }
+
+        if let Some(health_map) = health_map {
+            if let Some(ref f) = param.map_file {
+                health_map.write_to_file(f)?;
+            }
+
+            if param.map_style.is_some() {
+                stats.map_style = param.map_style;
+                stats.health_map = Some(health_map);
+            }
+        }
+
+        stats.check_stats = Some(check_stats);
     }
 
     if do_hash {
-        let hash_result = hash(
-            param,
-            &ctrlc_stop_flag,
-            orig_file_size.unwrap(),
-            ref_block_pos,
-            &ref_block,
-            hash_ctx.unwrap(),
-        );
+        let hash_result = match data_par_burst.unwrap() {
+            Ok(data_par_burst) => hash(
+                param,
+                &ctrlc_stop_flag,
+                orig_file_size.unwrap(),
+                data_par_burst,
+                &ref_block,
+                &sparse_map,
+                hash_ctx.unwrap(),
+            ),
+            Err(e) => Err(e),
+        };
 
         stats.hash_result = Some(hash_result);
     }
@@ -1,8 +1,13 @@
 use crate::cli_utils::*;
 use crate::file_utils;
 use crate::json_printer::BracketType;
-use crate::sbx_specs::{ver_to_block_size, ver_to_data_size, ver_to_usize, ver_uses_rs};
+use crate::sbx_specs::{
+    ver_to_block_size, ver_to_data_size, ver_to_usize, ver_uses_rs, SBX_MAX_BURST_ERR_RESISTANCE,
+};
+use crate::simulate;
 use clap::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::str::FromStr;
 
 pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
@@ -24,6 +29,51 @@ This means this option has no effect for version 17, 18, 19.",
         .arg(rs_data_arg())
         .arg(rs_parity_arg())
         .arg(burst_arg())
+        .arg(
+            protect_goals_arg()
+                .long("solve")
+                .conflicts_with("no_meta")
+                .help(PROTECT_GOALS_ARG_HELP_MSG),
+        )
+        .arg(
+            Arg::with_name("simulate")
+                .value_name("MODEL")
+                .long("simulate")
+                .takes_value(true)
+                .help(
+                    "Run trials of injected damage on the block layout of the container,
+and report the chance of full recovery and the expected bytes lost.
+MODEL is a comma separated list of damage applied in each trial :
+    random=PERCENT    lose each block with PERCENT% chance
+    burst=SIZE        lose a contiguous region of SIZE bytes,
+    burst=MIN-MAX     or of a size picked uniformly from MIN to MAX,
+                      SIZE may end with K, M or G (1024 based)
+    bursts=COUNT      number of bursts (defaults to 1)
+    wipe=PERCENT      lose a contiguous region of PERCENT% of the
+                      container
+Damaged blocks are treated as missing, and a block set is recovered
+if no more blocks than the parity shard count are missing.",
+                ),
+        )
+        .arg(
+            Arg::with_name("trials")
+                .value_name("COUNT")
+                .long("trials")
+                .takes_value(true)
+                .requires("simulate")
+                .help("Number of trials to run in simulation (defaults to 1000)"),
+        )
+        .arg(
+            Arg::with_name("seed")
+                .value_name("SEED")
+                .long("seed")
+                .takes_value(true)
+                .requires("simulate")
+                .help(
+                    "Seed for the random number generator used in simulation, for
+reproducible results (defaults to a random seed)",
+                ),
+        )
         .arg(json_arg())
 }
 
@@ -32,15 +82,52 @@ pub fn calc<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     json_printer.print_open_bracket(None, BracketType::Curly);
 
-    let (version, data_par_burst) = get_ver_and_data_par_burst_w_defaults!(matches, json_printer);
-
-    let meta_enabled = Some(get_meta_enabled!(matches));
-
     let in_file_size = match u64::from_str(matches.value_of("in_file_size").unwrap()) {
         Ok(x) => x,
         Err(_) => exit_with_msg!(usr json_printer => "Invalid file size"),
     };
 
+    let damage_model = match matches.value_of("simulate") {
+        None => None,
+        Some(x) => match simulate::string_to_damage_model(x) {
+            Ok(x) => Some(x),
+            Err(_) => exit_with_msg!(usr json_printer => "Invalid damage model"),
+        },
+    };
+
+    let trials = match matches.value_of("trials") {
+        None => simulate::DEFAULT_TRIAL_COUNT,
+        Some(x) => match u64::from_str(x) {
+            Ok(x) if x > 0 => x,
+            Ok(_) | Err(_) => exit_with_msg!(usr json_printer => "Invalid trial count"),
+        },
+    };
+
+    let mut rng = match matches.value_of("seed") {
+        None => StdRng::from_entropy(),
+        Some(x) => match u64::from_str(x) {
+            Ok(x) => StdRng::seed_from_u64(x),
+            Err(_) => exit_with_msg!(usr json_printer => "Invalid seed"),
+        },
+    };
+
+    let (version, data_par_burst) = if matches.is_present("protect_goals") {
+        let (version, data_par_burst, solution) = get_ver_and_data_par_burst_from_goals!(
+            matches,
+            in_file_size,
+            SBX_MAX_BURST_ERR_RESISTANCE,
+            json_printer
+        );
+
+        report_protect_solution(&json_printer, &solution);
+
+        (version, data_par_burst)
+    } else {
+        get_ver_and_data_par_burst_w_defaults!(matches, json_printer)
+    };
+
+    let meta_enabled = Some(get_meta_enabled!(matches));
+
     let out_file_size = file_utils::from_orig_file_size::calc_container_size(
         version,
         meta_enabled,
@@ -250,5 +337,46 @@ pub fn calc<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     json_printer.print_close_bracket();
 
+    if let Some(damage_model) = damage_model {
+        let layout = simulate::Layout::new(version, meta_enabled, data_par_burst, in_file_size);
+
+        let stats = simulate::simulate(&layout, &damage_model, trials, &mut rng);
+
+        json_printer.print_open_bracket(Some("simulation"), BracketType::Curly);
+
+        print_if!(not_json => json_printer => "";);
+        print_if!(not_json => json_printer => "Survivability simulation";);
+        print_if!(not_json => json_printer => "========================================";);
+        print_maybe_json!(
+            json_printer,
+            "    Trial count                  : {}",
+            stats.trials
+        );
+        print_maybe_json!(
+            json_printer,
+            "    Full recovery probability    : {}",
+            stats.full_recovery_prob()
+            => force_quotes
+        );
+        print_maybe_json!(
+            json_printer,
+            "    Expected bytes lost          : {:.2}",
+            stats.expected_bytes_lost()
+        );
+        print_maybe_json!(
+            json_printer,
+            "    Worst case bytes lost        : {}",
+            stats.max_bytes_lost
+        );
+        print_maybe_json!(
+            json_printer,
+            "    Metadata loss probability    : {}",
+            stats.all_meta_lost_prob()
+            => force_quotes
+        );
+
+        json_printer.print_close_bracket();
+    }
+
     exit_with_msg!(ok json_printer => "")
 }
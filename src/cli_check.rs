@@ -2,6 +2,8 @@ use crate::check_core;
 use crate::check_core::HashAction;
 use crate::check_core::Param;
 use crate::cli_utils::*;
+use crate::health_map;
+use crate::health_map::MapStyle;
 use crate::json_printer::BracketType;
 use clap::*;
 
@@ -13,13 +15,81 @@ pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
         .arg(pr_verbosity_level_arg())
         .arg(Arg::with_name("report_blank").long("report-blank").help(
             "Completely blank blocks are ignored by default.
-Specify this if you want blkar to report blank blocks as well.",
+Specify this if you want blkar to report blank blocks as well.
+Holes of blocks recorded in the sparse map are never reported.",
         ))
         .arg(verbose_arg().help("Show reference block info, show individual check results"))
         .arg(from_byte_arg().help(FROM_BYTE_ARG_HELP_MSG_REF_BLOCK))
         .arg(to_byte_inc_arg())
         .arg(to_byte_exc_arg())
         .arg(force_misalign_arg())
+        .arg(log_arg().conflicts_with("hash_only"))
+        .arg(
+            damage_map_arg()
+                .help(
+                    "Write the positions and sequence numbers of invalid or missing
+blocks to MAPFILE, grouped by Reed-Solomon block set. MAPFILE
+can then be passed to repair and decode via --damage-map.",
+                )
+                .conflicts_with("from_pos")
+                .conflicts_with("to_pos_inc")
+                .conflicts_with("to_pos_exc")
+                .conflicts_with("log_file")
+                .conflicts_with("hash_only"),
+        )
+        .arg(
+            Arg::with_name("forecast")
+                .long("forecast")
+                .help(
+                    "Group damaged blocks by Reed-Solomon block set and report the
+parity margin of each set, i.e. whether repair would succeed.
+This is done only for versions 17, 18, 19 and if the reference
+block is a metadata block.",
+                )
+                .conflicts_with("log_file")
+                .conflicts_with("hash_only"),
+        )
+        .arg(
+            Arg::with_name("map")
+                .long("map")
+                .help(
+                    "Show a heat map of the checked part of the container, where each
+cell covers a number of blocks and shows the worst state amongst
+them, i.e. good, blank, repairable or corrupt. Blocks are only
+marked as repairable for versions 17, 18, 19.",
+                )
+                .conflicts_with("log_file")
+                .conflicts_with("hash_only"),
+        )
+        .arg(
+            Arg::with_name("map_unicode")
+                .long("map-unicode")
+                .help("Draw the heat map with Unicode block characters instead of ASCII")
+                .requires("map"),
+        )
+        .arg(
+            Arg::with_name("map_file")
+                .long("map-file")
+                .value_name("FILE")
+                .takes_value(true)
+                .help(
+                    "Write the heat map to FILE as an image. The format is picked
+by the extension of FILE, which must be .svg or .png. This
+can be used with or without --map.",
+                )
+                .conflicts_with("log_file")
+                .conflicts_with("hash_only"),
+        )
+        .arg(
+            Arg::with_name("map_cell_blocks")
+                .long("map-cell-blocks")
+                .value_name("N")
+                .takes_value(true)
+                .help(
+                    "Number of blocks covered by each cell of the heat map. Defaults
+to the smallest number which keeps the map within 16 rows.",
+                ),
+        )
         .arg(ref_from_byte_arg())
         .arg(ref_to_byte_inc_arg())
         .arg(ref_to_byte_exc_arg())
@@ -28,8 +98,9 @@ Specify this if you want blkar to report blank blocks as well.",
 Use this if the level used by the container is above 1000,
 as blkar will only guess up to 1000. Or use this when blkar
 fails to guess correctly. blkar uses this value only if
---hash is specified.",
+--hash is specified or the container is sparse.",
         ))
+        .arg(sparse_map_arg())
         .arg(guess_burst_from_byte_arg())
         .arg(
             Arg::with_name("hash")
@@ -86,6 +157,34 @@ pub fn check<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let burst = get_burst_opt!(matches, json_printer);
 
+    let map_style = if matches.is_present("map_unicode") {
+        Some(MapStyle::Unicode)
+    } else if matches.is_present("map") {
+        Some(MapStyle::Ascii)
+    } else {
+        None
+    };
+
+    let map_file = matches.value_of("map_file");
+
+    if let Some(f) = map_file {
+        if health_map::path_to_image_format(f).is_none() {
+            exit_with_msg!(usr json_printer => "Unrecognised image format of \"{}\", expected .svg or .png", f);
+        }
+
+        exit_if_file!(is_dir f
+                      => json_printer
+                      => "{} is a directory", f);
+    }
+
+    let blocks_per_cell = match matches.value_of("map_cell_blocks") {
+        None => None,
+        Some(x) => match x.parse::<u64>() {
+            Ok(x) if x > 0 => Some(x),
+            _ => exit_with_msg!(usr json_printer => "Invalid number of blocks per cell"),
+        },
+    };
+
     let param = Param::new(
         get_ref_block_choice!(matches),
         ref_from_pos,
@@ -99,6 +198,13 @@ pub fn check<'a>(matches: &ArgMatches<'a>) -> i32 {
         hash_action,
         burst,
         in_file,
+        matches.value_of("log_file"),
+        matches.value_of("damage_map"),
+        matches.is_present("forecast"),
+        map_style,
+        map_file,
+        blocks_per_cell,
+        matches.value_of("sparse_map"),
         matches.is_present("verbose"),
         pr_verbosity_level,
     );
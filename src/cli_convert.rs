@@ -0,0 +1,102 @@
+use crate::cli_utils::*;
+use crate::convert_core;
+use crate::convert_core::Param;
+use crate::json_printer::BracketType;
+use crate::sbx_specs::ver_uses_rs;
+use clap::*;
+use std::str::FromStr;
+
+pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("convert")
+        .about("Convert SBX container to another SBX version or Reed-Solomon configuration")
+        .arg(in_file_arg().help("SBX container to convert"))
+        .arg(
+            out_file_arg()
+                .required(true)
+                .help("Converted SBX container. File UID and metadata of INFILE are kept."),
+        )
+        .arg(force_arg().help("Force overwrite even if OUTFILE exists"))
+        .arg(pr_verbosity_level_arg())
+        .arg(sbx_version_arg())
+        .arg(rs_data_arg())
+        .arg(rs_parity_arg())
+        .arg(burst_arg().help(
+            "Burst error resistance level to use for the output container.
+Defaults to 0 when --sbx-version is specified.",
+        ))
+        .arg(
+            Arg::with_name("in_burst")
+                .value_name("LEVEL")
+                .long("in-burst")
+                .takes_value(true)
+                .help(
+                    "Burst error resistance level used by INFILE. Defaults to guessing
+the level (guesses up to 1000).",
+                ),
+        )
+        .arg(sparse_map_arg())
+        .arg(verbose_arg().help("Show reference block info"))
+        .arg(json_arg())
+}
+
+pub fn convert<'a>(matches: &ArgMatches<'a>) -> i32 {
+    let json_printer = get_json_printer!(matches);
+
+    json_printer.print_open_bracket(None, BracketType::Curly);
+
+    let in_file = get_in_file!(matches, json_printer);
+    let out_file = matches.value_of("out_file").unwrap();
+
+    exit_if_file!(has_no_file_name_part out_file
+                  => json_printer
+                  => "File name \"{}\" does not have a file name component", out_file);
+
+    exit_if_file!(is_dir out_file
+                  => json_printer
+                  => "File \"{}\" is a directory", out_file);
+
+    let (version, data_par_burst) = get_ver_and_data_par_burst_w_defaults!(matches, json_printer);
+
+    let in_burst = match matches.value_of("in_burst") {
+        None => None,
+        Some(x) => match usize::from_str(x) {
+            Ok(x) => Some(x),
+            Err(_) => {
+                exit_with_msg!(usr json_printer => "Failed to parse burst error resistance level of input container")
+            }
+        },
+    };
+
+    if let (Ok(x), Ok(y)) = (
+        std::fs::canonicalize(in_file),
+        std::fs::canonicalize(out_file),
+    ) {
+        if x == y {
+            exit_with_msg!(usr json_printer => "Input and output must be different files");
+        }
+    }
+
+    exit_if_file!(exists out_file
+                  => matches.is_present("force")
+                  => json_printer
+                  => "File \"{}\" already exists", out_file);
+
+    let pr_verbosity_level = get_pr_verbosity_level!(matches, json_printer);
+
+    let param = Param::new(
+        version,
+        data_par_burst,
+        &json_printer,
+        in_file,
+        in_burst,
+        out_file,
+        matches.value_of("sparse_map"),
+        matches.is_present("verbose"),
+        pr_verbosity_level,
+    );
+    match convert_core::convert_file(&param) {
+        Ok(Some(s)) => exit_with_msg!(ok json_printer => "{}", s),
+        Ok(None) => exit_with_msg!(ok json_printer => ""),
+        Err(e) => exit_with_msg!(op json_printer => "{}", e),
+    }
+}
@@ -1,3 +1,4 @@
+use crate::bad_ranges;
 use crate::cli_utils::*;
 use crate::decode_core;
 use crate::decode_core::Param;
@@ -10,7 +11,12 @@ use std::sync::Arc;
 pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("decode")
         .about("Decode SBX container")
-        .arg(in_file_arg().help("SBX container to decode"))
+        .arg(in_file_arg().help(
+            "SBX container to decode. Supply - to use stdin as input.
+If input is stdin, the container is decoded in a single pass,
+block sets are repaired in memory where possible, and the
+options for ranges, multi-pass and log file are not supported.",
+        ))
         .arg(out_arg().help(
             "Decoded file name. Supply - to use stdout as output. Use ./- for files named -.
 If output is stdout, progress text and final stats are outputted to stderr instead.
@@ -41,12 +47,56 @@ to update it gradually. Ignored if output is stdout.",
         .arg(to_byte_inc_arg())
         .arg(to_byte_exc_arg())
         .arg(force_misalign_arg())
+        .arg(log_arg().help(
+            "Log file to keep track of the progress to survive interruptions.
+If LOGFILE exists, decoding continues from the last completed
+position recorded in it, and OUT is not truncated. Note that you
+should use the same log file for the same files and range
+specified in the initial run. Not supported when OUT is stdout.",
+        ))
+        .arg(damage_map_arg().help(
+            "Damage map produced by blkar check --damage-map. The ranges of
+the original file that cannot be recovered from the damaged blocks
+recorded in MAPFILE are reported after decoding.",
+        ))
+        .arg(
+            Arg::with_name("bad_ranges")
+                .value_name("FILE")
+                .long("bad-ranges")
+                .takes_value(true)
+                .conflicts_with_all(&["multi_pass", "multi_pass_no_skip", "log_file"])
+                .help(
+                    "Write the byte ranges of the original file which could not be
+recovered to FILE, one range=FROM-TO per line (TO is exclusive).
+If OUT is a file, data chunks not found in the container are
+included as well.",
+                ),
+        )
+        .arg(
+            Arg::with_name("fill")
+                .value_name("POLICY")
+                .long("fill")
+                .takes_value(true)
+                .conflicts_with_all(&["multi_pass", "multi_pass_no_skip", "log_file"])
+                .help(
+                    "What to put in place of the data chunks which could not be
+recovered, one of (case-insensitive) :
+    zeros
+    marker (repeating pattern \"<BLKAR:BAD DATA>\")
+    hole   (leave the range unwritten, not supported when
+            OUT is stdout)
+If not specified, zeros are used when OUT is stdout, and
+the range is left unwritten otherwise.",
+                ),
+        )
+        .arg(sparse_map_arg())
         .arg(burst_arg().help(
             "Burst error resistance level used by the container.
 Use this if the level used by the container is above 1000,
 as blkar will only guess up to 1000. Or use this when blkar
 fails to guess correctly. blkar uses this value only if
-output is stdout and container version is RS enabled.",
+input or output is stdout/stdin and container version is
+RS enabled.",
         ))
         .arg(verbose_arg().help("Show reference block info"))
         .arg(json_arg())
@@ -100,7 +150,28 @@ pub fn decode<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let burst = get_burst_opt!(matches, json_printer);
 
-    let in_file = get_in_file!(matches, json_printer);
+    let in_file = get_in_file!(accept_stdin matches, json_printer);
+
+    if file_utils::check_if_file_is_stdin(in_file) {
+        for &(arg, opt) in [
+            ("multi_pass", "--multi-pass"),
+            ("multi_pass_no_skip", "--multi-pass-no-skip"),
+            ("log_file", "--log"),
+            ("from_pos", "--from"),
+            ("to_pos_inc", "--to-inc"),
+            ("to_pos_exc", "--to-exc"),
+            ("ref_from_pos", "--ref-from"),
+            ("ref_to_pos_inc", "--ref-to-inc"),
+            ("ref_to_pos_exc", "--ref-to-exc"),
+            ("guess_burst_from_pos", "--guess-burst-from"),
+        ]
+        .iter()
+        {
+            if matches.is_present(arg) {
+                exit_with_msg!(usr json_printer => "{} cannot be used when input is stdin", opt);
+            }
+        }
+    }
 
     let from_pos = get_from_pos!(matches, json_printer);
     let to_pos = get_to_pos!(matches, json_printer);
@@ -110,6 +181,14 @@ pub fn decode<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let guess_burst_from_pos = get_guess_burst_from_pos!(matches, json_printer);
 
+    let fill = match matches.value_of("fill") {
+        None => None,
+        Some(x) => match bad_ranges::string_to_fill_policy(x) {
+            Ok(x) => Some(x),
+            Err(_) => exit_with_msg!(usr json_printer => "Invalid fill policy"),
+        },
+    };
+
     let param = Param::new(
         get_ref_block_choice!(matches),
         ref_from_pos,
@@ -123,6 +202,11 @@ pub fn decode<'a>(matches: &ArgMatches<'a>) -> i32 {
         matches.is_present("force_misalign"),
         in_file,
         out,
+        matches.value_of("log_file"),
+        matches.value_of("damage_map"),
+        matches.value_of("bad_ranges"),
+        fill,
+        matches.value_of("sparse_map"),
         matches.is_present("verbose"),
         pr_verbosity_level,
         burst,
@@ -0,0 +1,60 @@
+use crate::cli_utils::*;
+use crate::diff_core;
+use crate::diff_core::Param;
+use crate::json_printer::BracketType;
+use clap::*;
+
+pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("diff")
+        .about("Compare the metadata and stored data of two SBX containers")
+        .arg(in_file_arg().value_name("A").help("First SBX container"))
+        .arg(
+            Arg::with_name("in_file_b")
+                .value_name("B")
+                .required(true)
+                .index(2)
+                .help(
+                    "Second SBX container. The containers are aligned by sequence
+number, so they may use different SBX versions or burst error
+resistance levels.",
+                ),
+        )
+        .arg(no_meta_arg())
+        .arg(pr_verbosity_level_arg())
+        .arg(verbose_arg().help("List all changed and uncompared ranges"))
+        .arg(json_arg())
+}
+
+pub fn diff<'a>(matches: &ArgMatches<'a>) -> i32 {
+    let json_printer = get_json_printer!(matches);
+
+    json_printer.print_open_bracket(None, BracketType::Curly);
+
+    let pr_verbosity_level = get_pr_verbosity_level!(matches, json_printer);
+
+    let in_file_a = get_in_file!(matches, json_printer);
+
+    let in_file_b = matches.value_of("in_file_b").unwrap();
+
+    exit_if_file!(does_not_exist in_file_b
+                  => json_printer
+                  => "File \"{}\" does not exist", in_file_b);
+
+    exit_if_file!(is_dir in_file_b
+                  => json_printer
+                  => "File \"{}\" is a directory", in_file_b);
+
+    let param = Param::new(
+        get_ref_block_choice!(matches),
+        in_file_a,
+        in_file_b,
+        &json_printer,
+        matches.is_present("verbose"),
+        pr_verbosity_level,
+    );
+    match diff_core::diff_files(&param) {
+        Ok(Some(s)) => exit_with_msg!(ok json_printer => "{}", s),
+        Ok(None) => exit_with_msg!(ok json_printer => ""),
+        Err(e) => exit_with_msg!(op json_printer => "{}", e),
+    }
+}
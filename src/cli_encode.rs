@@ -4,14 +4,18 @@ use crate::encode_core::Param;
 use crate::file_utils;
 use crate::json_printer::BracketType;
 use crate::misc_utils;
+use crate::misc_utils::{PositionOrLength, RequiredLenAndSeekTo};
 use crate::multihash;
+use crate::output_channel::OutputChannel;
 use crate::rand_utils;
 use crate::sbx_specs::{
     ver_to_block_size, ver_to_data_size, ver_to_usize, ver_uses_rs, SBX_FILE_UID_LEN,
+    SBX_MAX_BURST_ERR_RESISTANCE,
 };
 use crate::time_utils;
 use clap::*;
 use std::str::FromStr;
+use std::sync::Arc;
 use std::time::UNIX_EPOCH;
 
 pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
@@ -24,9 +28,21 @@ pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
         .arg(out_arg().help(
             "SBX container name (defaults to INFILE.sbx or INFILE.ecsbx). If OUT is a
 directory, then the container is stored as OUT/INFILE.sbx or
-OUT/INFILE.ecsbx (only the file part of INFILE is used).",
+OUT/INFILE.ecsbx (only the file part of INFILE is used).
+Supply - to stream the container to stdout, in which case the final
+metadata blocks are appended after the last data block, and burst
+error resistance level defaults to and must be 0.",
         ))
         .arg(force_arg().help("Force overwrite even if OUT exists"))
+        .arg(log_arg().help(
+            "Log file to keep track of the progress to survive interruptions.
+If LOGFILE exists, encoding continues from the last completed block
+set recorded in it, after checking the end of OUT matches the log,
+and the resulting container is identical to one from an uninterrupted
+run. Note that you should use the same log file for the same files,
+range and options specified in the initial run. Log file cannot be
+used when input is stdin or output is stdout.",
+        ))
         .arg(
             Arg::with_name("hash_type")
                 .value_name("HASH-TYPE")
@@ -66,6 +82,27 @@ then blkar will make an incorrect guess, and you will need to
 specify it explicitly in repair and sort mode. Show mode does
 not rely on burst level, but provides an option for enabling
 automatic guessing.",
+        ))
+        .arg(
+            protect_goals_arg()
+                .long("protect")
+                .help(PROTECT_GOALS_ARG_HELP_MSG),
+        )
+        .arg(
+            Arg::with_name("sparse")
+                .long("sparse")
+                .conflicts_with("no_meta")
+                .help(
+                    "Do not write blocks which only carry zeros (e.g. holes of sparse
+files or disk images), leaving holes in the SBX container instead.
+The number of blocks omitted is recorded in the metadata block,
+and the blocks omitted are recorded in the sparse map, so only
+those are treated as chunks of zeros by other modes.",
+                ),
+        )
+        .arg(sparse_map_arg().requires("sparse").help(
+            "Write the sparse map to SPARSEMAP instead of the container path
+with the .sparse extension appended.",
         ))
         .arg(
             Arg::with_name("info_only")
@@ -76,7 +113,19 @@ automatic guessing.",
 }
 
 pub fn encode<'a>(matches: &ArgMatches<'a>) -> i32 {
-    let json_printer = get_json_printer!(matches);
+    let mut json_printer = get_json_printer!(matches);
+
+    let out_is_stdout = match matches.value_of("out") {
+        None => false,
+        Some(x) => file_utils::check_if_file_is_stdout(x),
+    };
+
+    // update json_printer output channel if stdout is going to be used by container output
+    if out_is_stdout {
+        Arc::get_mut(&mut json_printer)
+            .unwrap()
+            .set_output_channel(OutputChannel::Stderr);
+    }
 
     json_printer.print_open_bracket(None, BracketType::Curly);
 
@@ -93,11 +142,61 @@ pub fn encode<'a>(matches: &ArgMatches<'a>) -> i32 {
         }
     }
 
-    let (version, data_par_burst) = get_ver_and_data_par_burst_w_defaults!(matches, json_printer);
+    let in_file = get_in_file!(accept_stdin matches, json_printer);
 
-    let out_extension = if ver_uses_rs(version) { "ecsbx" } else { "sbx" };
+    let from_pos = get_from_pos!(matches, json_printer);
+    let to_pos = get_to_pos!(matches, json_printer);
 
-    let in_file = get_in_file!(accept_stdin matches, json_printer);
+    let (version, data_par_burst) = if matches.is_present("protect_goals") {
+        if file_utils::check_if_file_is_stdin(in_file) {
+            exit_with_msg!(usr json_printer => "Protection goals cannot be used when input is stdin");
+        }
+
+        let in_file_size = match file_utils::get_file_size(in_file) {
+            Ok(x) => x,
+            Err(_) => exit_with_msg!(usr json_printer => "Failed to get file size of \"{}\"",
+                                     in_file),
+        };
+
+        let RequiredLenAndSeekTo { required_len, .. } =
+            misc_utils::calc_required_len_and_seek_to_from_byte_range(
+                from_pos,
+                to_pos,
+                true,
+                0,
+                PositionOrLength::Len(in_file_size),
+                None,
+            );
+
+        // streamed containers are written sequentially, so burst error resistance is not available
+        let max_burst = if out_is_stdout {
+            0
+        } else {
+            SBX_MAX_BURST_ERR_RESISTANCE
+        };
+
+        let (version, data_par_burst, solution) =
+            get_ver_and_data_par_burst_from_goals!(matches, required_len, max_burst, json_printer);
+
+        report_protect_solution(&json_printer, &solution);
+
+        (version, data_par_burst)
+    } else {
+        let (version, data_par_burst) =
+            get_ver_and_data_par_burst_w_defaults!(matches, json_printer);
+
+        // streamed containers are written sequentially, so no burst error resistance by default
+        let data_par_burst = match data_par_burst {
+            Some((data, parity, _)) if out_is_stdout && matches.value_of("burst").is_none() => {
+                Some((data, parity, 0))
+            }
+            x => x,
+        };
+
+        (version, data_par_burst)
+    };
+
+    let out_extension = if ver_uses_rs(version) { "ecsbx" } else { "sbx" };
 
     let out = match matches.value_of("out") {
         None => {
@@ -108,7 +207,9 @@ pub fn encode<'a>(matches: &ArgMatches<'a>) -> i32 {
             }
         }
         Some(x) => {
-            if file_utils::check_if_file_is_dir(x) {
+            if out_is_stdout {
+                String::from(x)
+            } else if file_utils::check_if_file_is_dir(x) {
                 if file_utils::check_if_file_is_stdin(in_file) {
                     exit_with_msg!(usr json_printer => "Explicit output file name is required when input is stdin");
                 }
@@ -133,9 +234,6 @@ pub fn encode<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let meta_enabled = get_meta_enabled!(matches);
 
-    let from_pos = get_from_pos!(matches, json_printer);
-    let to_pos = get_to_pos!(matches, json_printer);
-
     if matches.is_present("info_only") {
         json_printer.print_open_bracket(Some("stats"), BracketType::Curly);
 
@@ -260,10 +358,16 @@ pub fn encode<'a>(matches: &ArgMatches<'a>) -> i32 {
 
         exit_with_msg!(ok json_printer => "")
     } else {
-        exit_if_file!(exists &out
-                      => matches.is_present("force")
-                      => json_printer
-                      => "File \"{}\" already exists", out);
+        let log_file = matches.value_of("log_file");
+
+        // whether the log file allows writing to an existing container
+        // is only known once it is read, so the check is left to encode
+        if !out_is_stdout && log_file.is_none() {
+            exit_if_file!(exists &out
+                          => matches.is_present("force")
+                          => json_printer
+                          => "File \"{}\" already exists", out);
+        }
 
         let in_file = if file_utils::check_if_file_is_stdin(in_file) {
             None
@@ -282,6 +386,10 @@ pub fn encode<'a>(matches: &ArgMatches<'a>) -> i32 {
             to_pos,
             in_file,
             &out,
+            matches.is_present("force"),
+            log_file,
+            matches.is_present("sparse"),
+            matches.value_of("sparse_map"),
             pr_verbosity_level,
         );
         match encode_core::encode_file(&param) {
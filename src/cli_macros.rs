@@ -334,6 +334,48 @@ macro_rules! get_ver_and_data_par_burst_w_defaults {
     }}
 }
 
+macro_rules! get_ver_and_data_par_burst_from_goals {
+    (
+        $matches:expr, $in_file_size:expr, $max_burst:expr, $json_printer:expr
+    ) => {{
+        use crate::protect;
+        use crate::sbx_specs::{string_to_ver, Version};
+
+        let goals = match protect::string_to_goals($matches.value_of("protect_goals").unwrap()) {
+            Ok(x)  => x,
+            Err(()) => {
+                exit_with_msg!(usr $json_printer => "Invalid protection goals");
+            }
+        };
+
+        let versions = match $matches.value_of("sbx_version") {
+            None    => vec![Version::V17, Version::V18, Version::V19],
+            Some(x) => {
+                match string_to_ver(&x) {
+                    Ok(v) if ver_uses_rs(v) => vec![v],
+                    Ok(_)                   => {
+                        exit_with_msg!(usr $json_printer => "Protection goals require a version which uses Reed-Solomon erasure code");
+                    },
+                    Err(()) => {
+                        exit_with_msg!(usr $json_printer => "Invalid SBX version");
+                    }
+                }
+            }
+        };
+
+        if $in_file_size == 0 {
+            exit_with_msg!(usr $json_printer => "Protection goals cannot be solved for an empty input");
+        }
+
+        match protect::solve(&versions, &goals, $in_file_size, $max_burst) {
+            Some(s) => (s.version, Some(s.data_par_burst), s),
+            None    => {
+                exit_with_msg!(usr $json_printer => "No configuration satisfies the protection goals");
+            }
+        }
+    }}
+}
+
 macro_rules! check_data_parity_shards {
     (
         $data_shards:expr, $parity_shards:expr, $json_printer:expr
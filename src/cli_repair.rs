@@ -1,5 +1,7 @@
 use crate::cli_utils::*;
+use crate::file_utils;
 use crate::json_printer::BracketType;
+use crate::misc_utils;
 use crate::repair_core;
 use crate::repair_core::Param;
 use clap::*;
@@ -8,6 +10,20 @@ pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
     SubCommand::with_name("repair")
         .about("Repair SBX container")
         .arg(in_file_arg().help("SBX container to repair"))
+        .arg(out_arg().help(
+            "Repaired SBX container. If specified, valid blocks are copied and
+repaired blocks are written to OUT instead, and INFILE is left
+untouched. If OUT is a directory, then the container is stored as
+OUT/INFILE (only the file part of INFILE is used). Ignored if
+--dry-run is supplied.",
+        ))
+        .arg(
+            force_arg()
+                .help("Force overwrite even if OUT exists")
+                .requires("out"),
+        )
+        .arg(multi_pass_arg().requires("out"))
+        .arg(multi_pass_no_skip_arg().requires("out"))
         .arg(pr_verbosity_level_arg())
         .arg(burst_arg().help(
             "Burst error resistance level used by the container.
@@ -25,6 +41,30 @@ fails to guess correctly.",
                 .long("skip-warning")
                 .help("Skip warning about in-place automatic repairs"),
         )
+        .arg(log_arg().help(
+            "Log file to keep track of the progress to survive interruptions.
+If LOGFILE exists, repairing continues from the last completed
+block set recorded in it. Note that you should use the same log
+file for the same container specified in the initial run.",
+        ))
+        .arg(
+            damage_map_arg()
+                .help(
+                    "Damage map produced by blkar check --damage-map. Only the
+metadata blocks and block sets recorded in MAPFILE are visited
+instead of scanning the whole container.",
+                )
+                .conflicts_with("out"),
+        )
+        .arg(Arg::with_name("salvage").long("salvage").help(
+            "If a block set has more invalid blocks than parity blocks, use the
+blocks which have an intact header but fail the CRC check as well.
+Each byte column of the block set is reconstructed separately, and
+the result is accepted only if all such blocks then pass the CRC
+check.",
+        ))
+        .arg(journal_arg().conflicts_with("out"))
+        .arg(sparse_map_arg())
         .arg(dry_run_arg().help("Only do repairs in memory. The container will not be modified."))
         .arg(json_arg().help(
             "Output information in JSON format. Note that blkar does not
@@ -45,6 +85,42 @@ pub fn repair<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let burst = get_burst_opt!(matches, json_printer);
 
+    let force = matches.is_present("force");
+    let multi_pass = get_multi_pass!(matches, json_printer);
+    let dry_run = matches.is_present("dry_run");
+
+    let log_file = matches.value_of("log_file");
+
+    // continuing from an existing log file writes to the existing output
+    let resuming = match log_file {
+        None => false,
+        Some(f) => file_utils::check_if_file_exists(f),
+    };
+
+    let out = match matches.value_of("out") {
+        None => None,
+        Some(x) => {
+            let out = if file_utils::check_if_file_is_dir(x) {
+                let in_file = file_utils::get_file_name_part_of_path(in_file).unwrap();
+                misc_utils::make_path(&[x, &in_file])
+            } else {
+                String::from(x)
+            };
+
+            exit_if_file!(exists &out
+                          => force || multi_pass.is_some() || dry_run || resuming
+                          => json_printer
+                          => "File \"{}\" already exists", out);
+
+            Some(out)
+        }
+    };
+
+    let out: Option<&str> = match out {
+        Some(ref x) if !dry_run => Some(x),
+        _ => None,
+    };
+
     if matches.is_present("dry_run") && !json_printer.json_enabled() {
         print_block!(
             "Note : This is a dry run only, the container is not modified.";
@@ -54,6 +130,7 @@ pub fn repair<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     if !matches.is_present("skip_warning")
         && !matches.is_present("dry_run")
+        && out.is_none()
         && !json_printer.json_enabled()
     {
         print_block!(
@@ -74,6 +151,13 @@ pub fn repair<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let param = Param::new(
         in_file,
+        out,
+        multi_pass,
+        log_file,
+        matches.value_of("damage_map"),
+        matches.value_of("journal_file"),
+        matches.value_of("sparse_map"),
+        matches.is_present("salvage"),
         matches.is_present("dry_run"),
         &json_printer,
         matches.is_present("verbose"),
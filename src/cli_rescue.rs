@@ -39,6 +39,27 @@ range specified in the initial run.",
                 ),
         )
         .arg(only_pick_uid_arg())
+        .arg(
+            Arg::with_name("name_from_meta")
+                .long("name-from-meta")
+                .help(
+                    "Name output of a UID using the SBX container name (or file name
+with .sbx appended if the former is not available) recorded in
+metadata block once a metadata block of the UID is seen. A number
+is appended to the name if it collides with an existing file. The
+mapping from UID to name is recorded in OUTDIR/blkar_rescue_manifest
+and in the log file (if specified).",
+                ),
+        )
+        .arg(
+            Arg::with_name("skip_read_errors")
+                .long("skip-read-errors")
+                .help(
+                    "Skip over regions which fail to be read instead of stopping, 128
+bytes at a time. Skipped regions are reported and recorded in
+the log file (if specified).",
+                ),
+        )
         .arg(force_misalign_arg())
         .arg(pr_verbosity_level_arg())
         .arg(from_byte_arg().help(FROM_BYTE_ARG_HELP_MSG_SCAN))
@@ -95,6 +116,8 @@ pub fn rescue<'a>(matches: &ArgMatches<'a>) -> i32 {
         &json_printer,
         block_type,
         uid,
+        matches.is_present("name_from_meta"),
+        matches.is_present("skip_read_errors"),
         pr_verbosity_level,
     );
     match rescue_core::rescue_from_file(&param) {
@@ -0,0 +1,66 @@
+use crate::cli_utils::*;
+use crate::json_printer::BracketType;
+use crate::rollback_core;
+use crate::rollback_core::Param;
+use clap::*;
+
+pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("rollback")
+        .about("Restore SBX container to its state prior to repair or update using a journal")
+        .arg(
+            Arg::with_name("journal_file")
+                .value_name("JOURNAL")
+                .required(true)
+                .index(1)
+                .help("Journal written by repair or update via --journal"),
+        )
+        .arg(
+            Arg::with_name("container")
+                .value_name("CONTAINER")
+                .index(2)
+                .help(
+                    "SBX container to restore. Defaults to the container recorded
+in JOURNAL.",
+                ),
+        )
+        .arg(force_arg().help(
+            "Restore even if blocks in the container were modified after the
+journal entries were recorded",
+        ))
+        .arg(verbose_arg().help("Show blocks modified after the journal entries were recorded"))
+        .arg(dry_run_arg().help("Only verify the journal. The container will not be modified."))
+        .arg(json_arg())
+}
+
+pub fn rollback<'a>(matches: &ArgMatches<'a>) -> i32 {
+    let json_printer = get_json_printer!(matches);
+
+    json_printer.print_open_bracket(None, BracketType::Curly);
+
+    let journal_file = matches.value_of("journal_file").unwrap();
+
+    exit_if_file!(does_not_exist journal_file
+                  => json_printer
+                  => "File \"{}\" does not exist", journal_file);
+
+    if matches.is_present("dry_run") && !json_printer.json_enabled() {
+        print_block!(
+            "Note : This is a dry run only, the container is not modified.";
+            "";
+        );
+    }
+
+    let param = Param::new(
+        journal_file,
+        matches.value_of("container"),
+        matches.is_present("force"),
+        matches.is_present("dry_run"),
+        &json_printer,
+        matches.is_present("verbose"),
+    );
+    match rollback_core::rollback(&param) {
+        Ok(Some(s)) => exit_with_msg!(ok json_printer => "{}", s),
+        Ok(None) => exit_with_msg!(ok json_printer => ""),
+        Err(e) => exit_with_msg!(op json_printer => "{}", e),
+    }
+}
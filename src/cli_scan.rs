@@ -0,0 +1,49 @@
+use crate::cli_utils::*;
+use crate::json_printer::BracketType;
+use crate::sbx_specs::SBX_FILE_UID_LEN;
+use crate::scan_core;
+use crate::scan_core::Param;
+use clap::*;
+
+pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("scan")
+        .about("Scan file/block device for SBX blocks and report an inventory of the containers found")
+        .arg(in_file_arg().help("File/block device to scan for SBX blocks"))
+        .arg(only_pick_uid_arg())
+        .arg(force_misalign_arg())
+        .arg(pr_verbosity_level_arg())
+        .arg(from_byte_arg().help(FROM_BYTE_ARG_HELP_MSG_SCAN))
+        .arg(to_byte_inc_arg())
+        .arg(to_byte_exc_arg())
+        .arg(json_arg())
+}
+
+pub fn scan<'a>(matches: &ArgMatches<'a>) -> i32 {
+    let json_printer = get_json_printer!(matches);
+
+    json_printer.print_open_bracket(None, BracketType::Curly);
+
+    let in_file = get_in_file!(matches, json_printer);
+
+    let pr_verbosity_level = get_pr_verbosity_level!(matches, json_printer);
+
+    let from_pos = get_from_pos!(matches, json_printer);
+    let to_pos = get_to_pos!(matches, json_printer);
+
+    let mut temp_uid = [0; SBX_FILE_UID_LEN];
+    let uid: Option<&[u8; SBX_FILE_UID_LEN]> = get_uid!(matches, temp_uid, json_printer);
+
+    let param = Param::new(
+        matches.is_present("force_misalign"),
+        &json_printer,
+        from_pos,
+        to_pos,
+        in_file,
+        uid,
+        pr_verbosity_level,
+    );
+    match scan_core::scan_file(&param) {
+        Ok(s) => exit_with_msg!(ok json_printer => "{}", s),
+        Err(e) => exit_with_msg!(op json_printer => "{}", e),
+    }
+}
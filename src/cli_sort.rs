@@ -4,6 +4,7 @@ use crate::json_printer::BracketType;
 use crate::misc_utils;
 use crate::sort_core;
 use crate::sort_core::Param;
+use crate::sort_in_place_core;
 use clap::*;
 
 pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
@@ -36,6 +37,60 @@ original container and uses the result.",
         .arg(Arg::with_name("report_blank").long("report-blank").help(
             "Failure to sort completely blank blocks are ignored by default.
 Specify this if you want blkar to report said failures as well.",
+        ))
+        .arg(log_arg().help(
+            "Log file to keep track of the progress to survive interruptions.
+If LOGFILE exists, sorting continues from the last completed
+position recorded in it, and OUTFILE is not truncated. Note that
+you should use the same log file for the same files and range
+specified in the initial run.",
+        ))
+        .arg(
+            Arg::with_name("in_place")
+                .long("in-place")
+                .help(
+                    "Sort the blocks within INFILE instead of writing to OUT, so no
+space for a second copy is needed. Blocks are moved by following
+cycles with a bounded buffer, and every move is recorded to a
+journal first so sorting can resume after a crash.",
+                )
+                .conflicts_with_all(&[
+                    "out",
+                    "multi_pass",
+                    "multi_pass_no_skip",
+                    "dry_run",
+                    "from_pos",
+                    "to_pos_inc",
+                    "to_pos_exc",
+                    "force_misalign",
+                    "log_file",
+                ]),
+        )
+        .arg(
+            Arg::with_name("merge")
+                .value_name("FILE")
+                .long("merge")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Additional SBX container or fragment of the same UID to sort into
+OUT. Can be specified multiple times. The reference block is
+chosen across all inputs, and every valid block from every input
+is placed into the single output.",
+                )
+                .conflicts_with_all(&["in_place", "log_file"]),
+        )
+        .arg(journal_arg().requires("in_place").help(
+            "Journal for in-place sorting (defaults to INFILE.sort_journal).
+If JOURNAL exists, sorting resumes from the state recorded in it.
+JOURNAL is removed once sorting completes.",
+        ))
+        .arg(sparse_map_arg().help(
+            "Sparse map recording the blocks omitted by blkar encode --sparse.
+Defaults to INFILE with the .sparse extension appended. Use this
+to sort a container rescued from a sparse container, as the sparse
+map is copied next to OUT.",
         ))
         .arg(verbose_arg().help("Show reference block info"))
         .arg(json_arg())
@@ -48,6 +103,42 @@ pub fn sort<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let in_file = get_in_file!(matches, json_printer);
 
+    if matches.is_present("in_place") {
+        let journal_file = match matches.value_of("journal_file") {
+            None => format!("{}.sort_journal", in_file),
+            Some(x) => String::from(x),
+        };
+
+        let burst = get_burst_opt!(matches, json_printer);
+
+        let pr_verbosity_level = get_pr_verbosity_level!(matches, json_printer);
+
+        let ref_from_pos = get_ref_from_pos!(matches, json_printer);
+        let ref_to_pos = get_ref_to_pos!(matches, json_printer);
+
+        let guess_burst_from_pos = get_guess_burst_from_pos!(matches, json_printer);
+
+        let param = sort_in_place_core::Param::new(
+            get_ref_block_choice!(matches),
+            ref_from_pos,
+            ref_to_pos,
+            matches.is_present("report_blank"),
+            guess_burst_from_pos,
+            &json_printer,
+            in_file,
+            &journal_file,
+            matches.value_of("sparse_map"),
+            matches.is_present("verbose"),
+            pr_verbosity_level,
+            burst,
+        );
+        match sort_in_place_core::sort_file_in_place(&param) {
+            Ok(Some(s)) => exit_with_msg!(ok json_printer => "{}", s),
+            Ok(None) => exit_with_msg!(ok json_printer => ""),
+            Err(e) => exit_with_msg!(op json_printer => "{}", e),
+        }
+    }
+
     let out = match matches.value_of("out") {
         None => format!("{}.sorted", in_file),
         Some(x) => {
@@ -66,8 +157,16 @@ pub fn sort<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let burst = get_burst_opt!(matches, json_printer);
 
+    let log_file = matches.value_of("log_file");
+
+    // continuing from an existing log file writes to the existing output
+    let resuming = match log_file {
+        None => false,
+        Some(f) => file_utils::check_if_file_exists(f),
+    };
+
     exit_if_file!(exists &out
-                  => force || multi_pass != None || dry_run
+                  => force || multi_pass != None || dry_run || resuming
                   => json_printer
                   => "File \"{}\" already exists", out);
 
@@ -83,6 +182,21 @@ pub fn sort<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let guess_burst_from_pos = get_guess_burst_from_pos!(matches, json_printer);
 
+    let merge_files: Vec<&str> = match matches.values_of("merge") {
+        None => Vec::new(),
+        Some(x) => x.collect(),
+    };
+
+    for f in merge_files.iter() {
+        exit_if_file!(does_not_exist f
+                      => json_printer
+                      => "File \"{}\" does not exist", f);
+
+        exit_if_file!(is_dir f
+                      => json_printer
+                      => "File \"{}\" is a directory", f);
+    }
+
     let param = Param::new(
         get_ref_block_choice!(matches),
         ref_from_pos,
@@ -95,7 +209,10 @@ pub fn sort<'a>(matches: &ArgMatches<'a>) -> i32 {
         to_pos,
         matches.is_present("force_misalign"),
         in_file,
+        &merge_files,
         out,
+        log_file,
+        matches.value_of("sparse_map"),
         matches.is_present("verbose"),
         pr_verbosity_level,
         burst,
@@ -1,8 +1,14 @@
 use crate::cli_utils::*;
 use crate::json_printer::BracketType;
+use crate::metadata_edit;
+use crate::metadata_edit::MetadataEdit;
+use crate::misc_utils;
 use crate::multihash;
+use crate::rand_utils;
+use crate::sbx_block;
 use crate::sbx_block::Metadata;
 use crate::sbx_block::MetadataID;
+use crate::sbx_specs::SBX_FILE_UID_LEN;
 use crate::update_core;
 use crate::update_core::Param;
 use clap::*;
@@ -28,6 +34,7 @@ fails to guess correctly.",
                 .long("skip-warning")
                 .help("Skip warning about in-place updates"),
         )
+        .arg(journal_arg())
         .arg(dry_run_arg().help("Only do updates in memory. The container will not be modified."))
         .arg(json_arg().help(
             "Output information in JSON format. Note that blkar does not
@@ -85,6 +92,57 @@ blake2s-256",
                 .help("Remove SBX container stored data hash")
                 .conflicts_with("hash_type"),
         )
+        .arg(
+            Arg::with_name("set")
+                .value_name("ID=VALUE")
+                .long("set")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1)
+                .help(
+                    "Set metadata field ID to VALUE. Can be specified multiple times.
+ID may be one of (case-insensitive) :
+FNM, SNM    - string
+FSZ         - file size in bytes
+FDT, SDT    - seconds since epoch, or \"YYYY-MM-DD hh:mm:ss\" in UTC
+HSH         - HASH-TYPE:HEX, see --hash for supported hash types
+RSD, RSP    - Reed-Solomon shard counts, only for versions 17, 18, 19",
+                ),
+        )
+        .arg(
+            Arg::with_name("new_uid")
+                .value_name("HEX|random")
+                .long("new-uid")
+                .takes_value(true)
+                .help(
+                    "Change the UID of the container to the specified 6 bytes in hex,
+or to a randomly generated one if \"random\" is specified. The UID
+is rewritten in every block, so this reads the entire container.",
+                ),
+        )
+        .arg(
+            Arg::with_name("add_meta")
+                .long("add-meta")
+                .conflicts_with("new_uid")
+                .help(
+                    "Add a metadata block to a container encoded with --no-meta, with FSZ
+and HSH rebuilt from the stored data, or rebuild missing or corrupt
+metadata blocks of a container from the intact ones. The first block
+of the container needs to be free for the added metadata block, which
+is the case after sorting the container.",
+                ),
+        )
+        .arg(
+            Arg::with_name("from_json")
+                .value_name("FILE")
+                .long("from-json")
+                .takes_value(true)
+                .help(
+                    "Read metadata changes from FILE, which contains a JSON object mapping
+field IDs to values, e.g. {\"FNM\": \"a.txt\", \"FDT\": 1500000000}.
+A value of null removes the field. Values are the same as for --set.",
+                ),
+        )
 }
 
 pub fn update<'a>(matches: &ArgMatches<'a>) -> i32 {
@@ -106,42 +164,94 @@ pub fn update<'a>(matches: &ArgMatches<'a>) -> i32 {
         },
     };
 
-    let metas_to_update = {
-        let mut res = smallvec![];
+    let edits = {
+        let mut res: Vec<MetadataEdit> = Vec::new();
+
+        if let Some(f) = matches.value_of("from_json") {
+            exit_if_file!(does_not_exist f
+                          => json_printer
+                          => "File \"{}\" does not exist", f);
+
+            let input = match std::fs::read_to_string(f) {
+                Ok(x) => x,
+                Err(e) => exit_with_msg!(usr json_printer => "Failed to read \"{}\" : {}", f, e),
+            };
+
+            match metadata_edit::parse_json(&input) {
+                Ok(x) => res.extend(x),
+                Err(e) => exit_with_msg!(usr json_printer => "{}", e),
+            }
+        }
+        if let Some(xs) = matches.values_of("set") {
+            for x in xs {
+                match metadata_edit::parse_assignment(x) {
+                    Ok(x) => res.push(x),
+                    Err(e) => exit_with_msg!(usr json_printer => "{}", e),
+                }
+            }
+        }
 
         if let Some(x) = matches.value_of("fnm") {
-            res.push(Metadata::FNM(x.to_string()))
+            res.push(MetadataEdit::Set(Metadata::FNM(x.to_string())))
         }
         if let Some(x) = matches.value_of("snm") {
-            res.push(Metadata::SNM(x.to_string()))
+            res.push(MetadataEdit::Set(Metadata::SNM(x.to_string())))
         }
         if let Some(_) = matches.value_of("hash_type") {
             let hash_type = hash_type.unwrap();
             let dummy_hash = multihash::hash::Ctx::new(hash_type)
                 .unwrap()
                 .finish_into_bytes();
-            res.push(Metadata::HSH((hash_type, dummy_hash)))
+            res.push(MetadataEdit::Set(Metadata::HSH((hash_type, dummy_hash))))
         }
 
-        res
-    };
-
-    let metas_to_remove = {
-        let mut res = smallvec![];
-
         if matches.is_present("no_fnm") {
-            res.push(MetadataID::FNM)
+            res.push(MetadataEdit::Remove(MetadataID::FNM))
         }
         if matches.is_present("no_snm") {
-            res.push(MetadataID::SNM)
+            res.push(MetadataEdit::Remove(MetadataID::SNM))
         }
         if matches.is_present("no_hsh") {
-            res.push(MetadataID::HSH)
+            res.push(MetadataEdit::Remove(MetadataID::HSH))
         }
 
         res
     };
 
+    for (i, edit) in edits.iter().enumerate() {
+        let id = metadata_edit::edit_to_meta_id(edit);
+        if edits[..i]
+            .iter()
+            .any(|x| metadata_edit::edit_to_meta_id(x) == id)
+        {
+            exit_with_msg!(usr json_printer => "Metadata field {} is specified more than once",
+                           sbx_block::meta_id_to_str(id));
+        }
+    }
+
+    let mut new_uid: [u8; SBX_FILE_UID_LEN] = [0; SBX_FILE_UID_LEN];
+    let new_uid = match matches.value_of("new_uid") {
+        None => None,
+        Some("random") => {
+            rand_utils::fill_random_bytes(&mut new_uid);
+            Some(&new_uid)
+        }
+        Some(x) => {
+            parse_uid!(new_uid, x, json_printer);
+            Some(&new_uid)
+        }
+    };
+
+    let mut metas_to_update = smallvec![];
+    let mut metas_to_remove = smallvec![];
+
+    for edit in edits.into_iter() {
+        match edit {
+            MetadataEdit::Set(m) => metas_to_update.push(m),
+            MetadataEdit::Remove(id) => metas_to_remove.push(id),
+        }
+    }
+
     if matches.is_present("dry_run") && !json_printer.json_enabled() {
         print_block!(
             "Note : This is a dry run only, the container is not modified.";
@@ -149,6 +259,17 @@ pub fn update<'a>(matches: &ArgMatches<'a>) -> i32 {
         );
     }
 
+    // a rerun with random would pick yet another UID, so an interrupted
+    // run can only be finished by giving the UID picked here explicitly
+    if matches.value_of("new_uid") == Some("random") && !json_printer.json_enabled() {
+        print_block!(
+            "Note : The randomly picked new UID is {}, specify it via --new-uid",
+            misc_utils::bytes_to_upper_hex_string(new_uid.unwrap());
+            "       instead of random if this update needs to be run again.";
+            "";
+        );
+    }
+
     if !matches.is_present("skip_warning")
         && !matches.is_present("dry_run")
         && !json_printer.json_enabled()
@@ -171,11 +292,14 @@ pub fn update<'a>(matches: &ArgMatches<'a>) -> i32 {
 
     let mut param = Param::new(
         in_file,
+        matches.value_of("journal_file"),
         matches.is_present("dry_run"),
         metas_to_update,
         metas_to_remove,
         &json_printer,
         hash_type,
+        new_uid,
+        matches.is_present("add_meta"),
         matches.is_present("verbose"),
         pr_verbosity_level,
         burst,
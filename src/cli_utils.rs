@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 use crate::json_printer::{BracketType, JSONPrinter};
+use crate::protect::Solution;
 use crate::sbx_block;
 use crate::sbx_specs::ver_to_usize;
 use clap::*;
@@ -76,6 +77,54 @@ it gradually.",
         .conflicts_with("multi_pass")
 }
 
+pub fn journal_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("journal_file")
+        .value_name("JOURNAL")
+        .long("journal")
+        .takes_value(true)
+        .help(
+            "Record the original bytes of every block before it is overwritten
+to JOURNAL, so the container can be restored via blkar rollback
+JOURNAL. If JOURNAL exists, new records are appended to it, and
+rolling back restores the state prior to the first run.",
+        )
+}
+
+pub fn log_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("log_file")
+        .value_name("LOGFILE")
+        .long("log")
+        .takes_value(true)
+        .help(
+            "Log file to keep track of the progress to survive interruptions.
+If LOGFILE exists, the run continues from the last completed
+position recorded in it. Note that you should use the same log
+file for the same files and range specified in the initial run.",
+        )
+}
+
+pub fn damage_map_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("damage_map")
+        .value_name("MAPFILE")
+        .long("damage-map")
+        .takes_value(true)
+        .help(
+            "Damage map produced by blkar check --damage-map. Only the
+damaged blocks recorded in MAPFILE are considered.",
+        )
+}
+
+pub fn sparse_map_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("sparse_map")
+        .value_name("SPARSEMAP")
+        .long("sparse-map")
+        .takes_value(true)
+        .help(
+            "Sparse map recording the blocks omitted by blkar encode --sparse.
+Defaults to the container path with the .sparse extension appended.",
+        )
+}
+
 pub fn only_pick_uid_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("uid")
         .value_name("UID-HEX")
@@ -267,6 +316,27 @@ pub fn rs_parity_arg<'a, 'b>() -> Arg<'a, 'b> {
         .help("Reed-Solomon parity shard count")
 }
 
+pub fn protect_goals_arg<'a, 'b>() -> Arg<'a, 'b> {
+    Arg::with_name("protect_goals")
+        .value_name("GOALS")
+        .takes_value(true)
+        .conflicts_with_all(&["rs_data", "rs_parity", "burst"])
+}
+
+pub const PROTECT_GOALS_ARG_HELP_MSG: &str =
+    "Pick the SBX version, RS data and parity shard counts and burst
+error resistance level from GOALS, a comma separated list of :
+    burst=SIZE        survive any contiguous loss of SIZE bytes,
+                      SIZE may end with K, M or G (1024 based)
+    random=PERCENT    survive PERCENT% of blocks lost at random
+                      (chance of losing any block set below 1e-6)
+    overhead=PERCENT  keep container size within PERCENT% above
+                      file size
+e.g. burst=1M,overhead=25%. The smallest container satisfying the
+burst and random goals is picked, or if only the overhead goal is
+given, the one with the most parity. Only version 17, 18 and 19 are
+considered, unless the version is specified explicitly.";
+
 pub fn report_ref_block_info(
     json_printer: &JSONPrinter,
     ref_block_pos: u64,
@@ -307,6 +377,54 @@ pub fn report_ref_block_info(
     }
 }
 
+pub fn report_protect_solution(json_printer: &JSONPrinter, solution: &Solution) {
+    let (data, parity, burst) = solution.data_par_burst;
+
+    json_printer.print_open_bracket(Some("protection"), BracketType::Curly);
+
+    print_if!(not_json => json_printer =>
+              "Protection goals solution";
+              "========================================";);
+    print_maybe_json!(
+        json_printer,
+        "    SBX container version        : {}",
+        ver_to_usize(solution.version)
+    );
+    print_maybe_json!(json_printer, "    RS data   shard count        : {}", data);
+    print_maybe_json!(
+        json_printer,
+        "    RS parity shard count        : {}",
+        parity
+    );
+    print_maybe_json!(json_printer, "    Burst error resistance level : {}", burst);
+    print_maybe_json!(
+        json_printer,
+        "    Max contiguous loss          : {}",
+        solution.max_burst_loss
+    );
+    match solution.random_loss_failure_prob {
+        None => print_maybe_json!(
+            json_printer,
+            "    Random loss failure chance   : {}",
+            null_if_json_else_NA!(json_printer)
+        ),
+        Some(x) => print_maybe_json!(json_printer, "    Random loss failure chance   : {:e}", x),
+    }
+    print_maybe_json!(
+        json_printer,
+        "    SBX container size           : {}",
+        solution.container_size
+    );
+    print_maybe_json!(
+        json_printer,
+        "    Overhead                     : {:.2}%",
+        solution.overhead_percent
+    );
+    print_if!(not_json => json_printer => "";);
+
+    json_printer.print_close_bracket();
+}
+
 pub fn guess_burst_arg<'a, 'b>() -> Arg<'a, 'b> {
     Arg::with_name("guess_burst").long("guess-burst").help(
         "Guess burst error resistance level (guesses up to 1000) at start.
@@ -0,0 +1,72 @@
+use crate::cli_utils::*;
+use crate::json_printer::BracketType;
+use crate::verify_core;
+use crate::verify_core::Param;
+use clap::*;
+
+pub fn sub_command<'a, 'b>() -> App<'a, 'b> {
+    SubCommand::with_name("verify")
+        .about("Verify data stored in SBX container against the original file")
+        .arg(in_file_arg().help("SBX container to verify"))
+        .arg(
+            Arg::with_name("source_file")
+                .value_name("SOURCE")
+                .required(true)
+                .index(2)
+                .help(
+                    "Original file to compare the stored data against. This does not
+require the container to have a metadata block or the hash field.",
+                ),
+        )
+        .arg(no_meta_arg())
+        .arg(burst_arg().help(
+            "Burst error resistance level. Note that blkar only guesses up to
+1000 in the absence of this option. blkar uses this value only if
+the container is sparse.",
+        ))
+        .arg(sparse_map_arg())
+        .arg(pr_verbosity_level_arg())
+        .arg(
+            verbose_arg()
+                .help("Show reference block info, list all mismatching and missing ranges"),
+        )
+        .arg(json_arg())
+}
+
+pub fn verify<'a>(matches: &ArgMatches<'a>) -> i32 {
+    let json_printer = get_json_printer!(matches);
+
+    json_printer.print_open_bracket(None, BracketType::Curly);
+
+    let pr_verbosity_level = get_pr_verbosity_level!(matches, json_printer);
+
+    let in_file = get_in_file!(matches, json_printer);
+
+    let source_file = matches.value_of("source_file").unwrap();
+
+    let burst = get_burst_opt!(matches, json_printer);
+
+    exit_if_file!(does_not_exist source_file
+                  => json_printer
+                  => "File \"{}\" does not exist", source_file);
+
+    exit_if_file!(is_dir source_file
+                  => json_printer
+                  => "File \"{}\" is a directory", source_file);
+
+    let param = Param::new(
+        get_ref_block_choice!(matches),
+        in_file,
+        source_file,
+        burst,
+        matches.value_of("sparse_map"),
+        &json_printer,
+        matches.is_present("verbose"),
+        pr_verbosity_level,
+    );
+    match verify_core::verify_file(&param) {
+        Ok(Some(s)) => exit_with_msg!(ok json_printer => "{}", s),
+        Ok(None) => exit_with_msg!(ok json_printer => ""),
+        Err(e) => exit_with_msg!(op json_printer => "{}", e),
+    }
+}
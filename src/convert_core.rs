@@ -0,0 +1,716 @@
+use crate::block_utils::RefBlockChoice;
+use crate::cli_utils::setup_ctrlc_handler;
+use crate::data_block_buffer::{DataBlockBuffer, InputType, OutputType, Slot};
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::json_printer::{BracketType, JSONPrinter};
+use crate::misc_utils;
+use crate::multihash::*;
+use crate::progress_report::*;
+use crate::sbx_block;
+use crate::sbx_block::{make_too_much_meta_err_string, Block, BlockType, Header, Metadata};
+use crate::sbx_specs::Version;
+use crate::sbx_specs::{
+    ver_to_data_size, ver_to_max_data_file_size, ver_to_usize, ver_uses_rs, SBX_FILE_UID_LEN,
+    SBX_LARGEST_BLOCK_SIZE,
+};
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
+use crate::time_utils;
+use crate::writer::{Writer, WriterType};
+use std::fmt;
+use std::io::SeekFrom;
+use std::sync::mpsc::channel;
+use std::sync::mpsc::sync_channel;
+use std::sync::Barrier;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+const PIPELINE_BUFFER_IN_ROTATION: usize = 9;
+
+struct ReaderStats {
+    bytes_processed: u64,
+    data_blocks_decoded: u64,
+    blank_blocks_decoded: u64,
+}
+
+pub struct Param {
+    version: Version,
+    data_par_burst: Option<(usize, usize, usize)>,
+    json_printer: Arc<JSONPrinter>,
+    in_file: String,
+    // burst error resistance level of the input container
+    burst: Option<usize>,
+    out_file: String,
+    sparse_map_file: Option<String>,
+    verbose: bool,
+    pr_verbosity_level: PRVerbosityLevel,
+}
+
+impl Param {
+    pub fn new(
+        version: Version,
+        data_par_burst: Option<(usize, usize, usize)>,
+        json_printer: &Arc<JSONPrinter>,
+        in_file: &str,
+        in_burst: Option<usize>,
+        out_file: &str,
+        sparse_map_file: Option<&str>,
+        verbose: bool,
+        pr_verbosity_level: PRVerbosityLevel,
+    ) -> Param {
+        Param {
+            version,
+            data_par_burst,
+            json_printer: Arc::clone(json_printer),
+            in_file: String::from(in_file),
+            burst: in_burst,
+            out_file: String::from(out_file),
+            sparse_map_file: match sparse_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            verbose,
+            pr_verbosity_level,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    uid: [u8; SBX_FILE_UID_LEN],
+    in_version: Version,
+    out_version: Version,
+    file_size: u64,
+    bytes_processed: u64,
+    pub data_blocks_decoded: u64,
+    pub blank_blocks_decoded: u64,
+    pub meta_blocks_written: u64,
+    pub data_blocks_written: u64,
+    pub parity_blocks_written: u64,
+    pub out_file_size: u64,
+    recorded_hash: Option<HashBytes>,
+    computed_hash: Option<HashBytes>,
+    start_time: f64,
+    end_time: f64,
+    json_printer: Arc<JSONPrinter>,
+}
+
+impl Stats {
+    pub fn new(
+        ref_block: &Block,
+        out_version: Version,
+        file_size: u64,
+        json_printer: &Arc<JSONPrinter>,
+    ) -> Stats {
+        Stats {
+            uid: ref_block.get_uid(),
+            in_version: ref_block.get_version(),
+            out_version,
+            file_size,
+            bytes_processed: 0,
+            data_blocks_decoded: 0,
+            blank_blocks_decoded: 0,
+            meta_blocks_written: 0,
+            data_blocks_written: 0,
+            parity_blocks_written: 0,
+            out_file_size: 0,
+            recorded_hash: ref_block.get_HSH().unwrap().cloned(),
+            computed_hash: None,
+            start_time: 0.,
+            end_time: 0.,
+            json_printer: Arc::clone(json_printer),
+        }
+    }
+}
+
+impl ProgressReport for Stats {
+    fn start_time_mut(&mut self) -> &mut f64 {
+        &mut self.start_time
+    }
+
+    fn end_time_mut(&mut self) -> &mut f64 {
+        &mut self.end_time
+    }
+
+    fn units_so_far(&self) -> u64 {
+        self.bytes_processed
+    }
+
+    fn total_units(&self) -> Option<u64> {
+        Some(self.file_size)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let blocks_written =
+            self.meta_blocks_written + self.data_blocks_written + self.parity_blocks_written;
+        let time_elapsed = (self.end_time - self.start_time) as i64;
+        let (hour, minute, second) = time_utils::seconds_to_hms(time_elapsed);
+
+        let json_printer = &self.json_printer;
+
+        json_printer.write_open_bracket(f, Some("stats"), BracketType::Curly)?;
+
+        write_maybe_json!(
+            f,
+            json_printer,
+            "File UID                            : {}",
+            misc_utils::bytes_to_upper_hex_string(&self.uid)
+                => force_quotes
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX version of input                : {}",
+            ver_to_usize(self.in_version)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX version of output               : {}",
+            ver_to_usize(self.out_version)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks decoded (data)     : {}",
+            self.data_blocks_decoded
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blank blocks decoded      : {}",
+            self.blank_blocks_decoded
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks written            : {}",
+            blocks_written
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks written (metadata) : {}",
+            self.meta_blocks_written
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks written (data)     : {}",
+            self.data_blocks_written
+        )?;
+        if ver_uses_rs(self.out_version) {
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Number of blocks written (parity)   : {}",
+                self.parity_blocks_written
+            )?;
+        }
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Amount of data converted (bytes)    : {}",
+            self.bytes_processed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX container size                  : {}",
+            self.out_file_size
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Recorded hash                       : {}",
+            match self.recorded_hash {
+                None => null_if_json_else_NA!(json_printer).to_string(),
+                Some(ref h) => format!(
+                    "{} - {}",
+                    hash_type_to_string(h.0),
+                    misc_utils::bytes_to_lower_hex_string(&h.1)
+                ),
+            }
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Hash of converted data              : {}",
+            match (&self.recorded_hash, &self.computed_hash) {
+                (None, None) => null_if_json_else_NA!(json_printer).to_string(),
+                (Some(_), None) => null_if_json_else!(
+                    json_printer,
+                    "N/A - recorded hash type is not supported by blkar"
+                )
+                .to_string(),
+                (_, Some(h)) => format!(
+                    "{} - {}",
+                    hash_type_to_string(h.0),
+                    misc_utils::bytes_to_lower_hex_string(&h.1)
+                ),
+            }
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Time elapsed                        : {:02}:{:02}:{:02}",
+            hour,
+            minute,
+            second
+        )?;
+
+        json_printer.write_close_bracket(f)?;
+
+        Ok(())
+    }
+}
+
+// metadata of the input container is carried over, apart from the fields
+// which describe the layout of the input container
+fn make_meta_block(param: &Param, ref_block: &Block) -> Block {
+    let mut block = Block::new(param.version, &ref_block.get_uid(), BlockType::Meta);
+
+    let out_file_name = file_utils::get_file_name_part_of_path(&param.out_file).unwrap();
+
+    let metas = block.metas_mut().unwrap();
+
+    let mut snm_added = false;
+
+    for meta in ref_block.metas().unwrap().iter() {
+        match meta {
+            Metadata::SNM(_) => {
+                metas.push(Metadata::SNM(out_file_name.clone()));
+                snm_added = true;
+            }
+            Metadata::RSD(_) | Metadata::RSP(_) | Metadata::SPR(_) | Metadata::TRL(_) => {}
+            _ => metas.push(meta.clone()),
+        }
+    }
+
+    if !snm_added {
+        metas.push(Metadata::SNM(out_file_name));
+    }
+
+    if let Some((data, parity, _)) = param.data_par_burst {
+        metas.push(Metadata::RSD(data as u8));
+        metas.push(Metadata::RSP(parity as u8));
+    }
+
+    block
+}
+
+fn write_meta_blocks(param: &Param, block: &mut Block, writer: &mut Writer) -> Result<u64, Error> {
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    match block.sync_to_buffer(None, &mut buffer) {
+        Ok(()) => {}
+        Err(sbx_block::Error::TooMuchMetadata(ref m)) => {
+            return Err(Error::with_msg(&make_too_much_meta_err_string(
+                block.get_version(),
+                m,
+            )));
+        }
+        Err(_) => unreachable!(),
+    }
+
+    let write_pos_s =
+        sbx_block::calc_meta_block_all_write_pos_s(param.version, param.data_par_burst);
+
+    for &p in write_pos_s.iter() {
+        writer.seek(SeekFrom::Start(p)).unwrap()?;
+
+        writer.write(sbx_block::slice_buf(block.get_version(), &buffer))?;
+    }
+
+    Ok(write_pos_s.len() as u64)
+}
+
+// reads the data block with the given seq num from the input container,
+// returns whether the block is an omitted block of a sparse container
+fn read_data_block(
+    reader: &mut FileReader,
+    version: Version,
+    data_par_burst: Option<(usize, usize, usize)>,
+    sparse_map: &Option<SparseMap>,
+    header_pred: &dyn Fn(&Header) -> bool,
+    seq_num: u32,
+    buffer: &mut [u8],
+) -> Result<bool, Error> {
+    let mut block = Block::dummy();
+
+    let pos = sbx_block::calc_data_block_write_pos(version, seq_num, None, data_par_burst);
+
+    reader.seek(SeekFrom::Start(pos))?;
+
+    let read_res = reader.read(sbx_block::slice_buf_mut(version, buffer))?;
+
+    let decode_successful = !read_res.eof_seen
+        && match block.sync_from_buffer(buffer, Some(header_pred), None) {
+            Ok(()) => block.get_seq_num() == seq_num,
+            Err(_) => false,
+        };
+
+    if decode_successful {
+        Ok(false)
+    } else if !read_res.eof_seen
+        && sparse_map::block_is_omitted(sparse_map, seq_num, sbx_block::slice_buf(version, buffer))
+    {
+        Ok(true)
+    } else {
+        Err(Error::with_msg(&format!(
+            "Failed to decode data block with seq num {} at byte {} (0x{:X}), please repair the container before converting",
+            seq_num, pos, pos
+        )))
+    }
+}
+
+pub fn convert_file(param: &Param) -> Result<Option<Stats>, Error> {
+    let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
+
+    let json_printer = &param.json_printer;
+
+    let (ref_block_pos, ref_block) = get_ref_block!( no_force_misalign =>
+        param,
+        None,
+        None,
+        json_printer,
+        RefBlockChoice::MustBe(BlockType::Meta),
+        ctrlc_stop_flag
+    );
+
+    if ref_block.get_TRL().unwrap().is_some() {
+        return Err(Error::with_msg(&format!(
+            "Reference block at byte {} (0x{:X}) is a provisional metadata block, the final metadata block of the streamed container is required to convert",
+            ref_block_pos, ref_block_pos
+        )));
+    }
+
+    let in_version = ref_block.get_version();
+
+    let in_data_par_burst =
+        get_data_par_burst!(no_offset => param, ref_block_pos, ref_block, "convert");
+
+    let file_size = match ref_block.get_FSZ().unwrap() {
+        Some(x) => x,
+        None => {
+            return Err(Error::with_msg(&format!(
+                "Reference block at byte {} (0x{:X}) does not have FSZ field (must be present to convert)",
+                ref_block_pos, ref_block_pos
+            )));
+        }
+    };
+
+    let max_file_size = ver_to_max_data_file_size(param.version, param.data_par_burst);
+
+    if file_size > max_file_size {
+        return Err(Error::with_msg(&format!(
+            "File size recorded in \"{}\" exceeds the maximum supported file size of the output container, file size : {}, max : {}",
+            param.in_file, file_size, max_file_size
+        )));
+    }
+
+    // only the blocks recorded as omitted are taken as chunks of zeros
+    let sparse_map = sparse_map::load(
+        json_printer,
+        &ref_block,
+        Some(&param.in_file),
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+    )?;
+
+    let stats = Arc::new(Mutex::new(Stats::new(
+        &ref_block,
+        param.version,
+        file_size,
+        json_printer,
+    )));
+
+    let mut reader = FileReader::new(
+        &param.in_file,
+        FileReaderParam {
+            write: false,
+            buffered: true,
+        },
+    )?;
+
+    let mut writer = Writer::new(WriterType::File(FileWriter::new(
+        &param.out_file,
+        FileWriterParam {
+            read: false,
+            append: false,
+            truncate: true,
+            buffered: false,
+        },
+    )?));
+
+    let reporter = ProgressReporter::new(
+        &stats,
+        "SBX block conversion progress",
+        "bytes",
+        param.pr_verbosity_level,
+        param.json_printer.json_enabled(),
+    );
+
+    // the hash is recomputed over the re-packed data to verify the output
+    let hash_ctx = Arc::new(Mutex::new(match ref_block.get_HSH().unwrap() {
+        None => None,
+        Some(&(ht, _)) => hash::Ctx::new(ht).ok(),
+    }));
+
+    let header_pred = header_pred_same_ver_uid!(ref_block);
+
+    {
+        let mut meta_block = make_meta_block(param, &ref_block);
+
+        let meta_blocks_written = write_meta_blocks(param, &mut meta_block, &mut writer)?;
+
+        stats.lock().unwrap().meta_blocks_written = meta_blocks_written;
+    }
+
+    let (to_encoder, from_reader) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
+    let (to_writer, from_encoder) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
+    let (to_reader, from_writer) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
+    let (error_tx_reader, error_rx) = channel::<Error>();
+    let error_tx_encoder = error_tx_reader.clone();
+    let error_tx_writer = error_tx_reader.clone();
+
+    let worker_shutdown_barrier = Arc::new(Barrier::new(3));
+
+    // push buffers into pipeline
+    let buffers = DataBlockBuffer::new_multi(
+        param.version,
+        Some(&ref_block.get_uid()),
+        InputType::Data,
+        OutputType::Block,
+        param.data_par_burst,
+        true,
+        false,
+        PIPELINE_BUFFER_IN_ROTATION,
+    );
+
+    for buffer in buffers.into_iter() {
+        to_reader.send(Some(buffer)).unwrap();
+    }
+
+    reporter.start();
+
+    let reader_thread = {
+        let hash_ctx = Arc::clone(&hash_ctx);
+        let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+        let ctrlc_stop_flag = Arc::clone(&ctrlc_stop_flag);
+        let in_data_size = ver_to_data_size(in_version);
+        let out_data_size = ver_to_data_size(param.version);
+
+        thread::spawn(move || {
+            let mut run = true;
+            let mut hash_ctx = hash_ctx.lock().unwrap();
+            let mut seq_num = 1;
+            let mut bytes_left = file_size;
+            let mut bytes_processed = 0;
+            let mut block_buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+            // data read from the input container not yet placed into the output
+            let mut carry: Vec<u8> = Vec::with_capacity(in_data_size + out_data_size);
+
+            while let Some(mut buffer) = from_writer.recv().unwrap() {
+                if !run {
+                    break;
+                }
+
+                let mut data_blocks_decoded = 0;
+                let mut blank_blocks_decoded = 0;
+
+                while !buffer.is_full() {
+                    stop_run_if_atomic_bool!(run => ctrlc_stop_flag);
+
+                    while carry.len() < out_data_size && bytes_left > 0 {
+                        // parity blocks of the input container are not needed
+                        if let Some((data, parity, _)) = in_data_par_burst {
+                            while sbx_block::seq_num_is_parity(seq_num, data, parity) {
+                                seq_num += 1;
+                            }
+                        }
+
+                        match read_data_block(
+                            &mut reader,
+                            in_version,
+                            in_data_par_burst,
+                            &sparse_map,
+                            &header_pred,
+                            seq_num,
+                            &mut block_buffer,
+                        ) {
+                            Ok(blank) => {
+                                if blank {
+                                    blank_blocks_decoded += 1;
+                                } else {
+                                    data_blocks_decoded += 1;
+                                }
+                            }
+                            Err(e) => {
+                                run = false;
+                                error_tx_reader.send(e).unwrap();
+                                break;
+                            }
+                        }
+
+                        let len = std::cmp::min(in_data_size as u64, bytes_left) as usize;
+
+                        carry.extend_from_slice(
+                            &sbx_block::slice_data_buf(in_version, &block_buffer)[..len],
+                        );
+
+                        bytes_left -= len as u64;
+                        seq_num += 1;
+                    }
+
+                    if !run || carry.is_empty() {
+                        run = false;
+                        break;
+                    }
+
+                    let Slot {
+                        block: _,
+                        slot,
+                        read_pos: _,
+                        content_len_exc_header,
+                    } = buffer.get_slot().unwrap();
+
+                    let len = std::cmp::min(out_data_size, carry.len());
+
+                    slot[..len].copy_from_slice(&carry[..len]);
+
+                    if let Some(ref mut hash_ctx) = *hash_ctx {
+                        hash_ctx.update(&carry[..len]);
+                    }
+
+                    carry.drain(..len);
+
+                    bytes_processed += len as u64;
+
+                    if len < out_data_size {
+                        *content_len_exc_header = Some(len);
+                        run = false;
+                        break;
+                    }
+                }
+
+                let reader_stats = ReaderStats {
+                    bytes_processed,
+                    data_blocks_decoded,
+                    blank_blocks_decoded,
+                };
+
+                to_encoder.send(Some((reader_stats, buffer))).unwrap();
+            }
+
+            worker_shutdown!(to_encoder, shutdown_barrier);
+        })
+    };
+
+    let encoder_thread = {
+        let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+
+        thread::spawn(move || {
+            while let Some((reader_stats, mut buffer)) = from_reader.recv().unwrap() {
+                if let Err(e) = buffer.encode() {
+                    error_tx_encoder.send(e).unwrap();
+                    break;
+                }
+
+                to_writer.send(Some((reader_stats, buffer))).unwrap();
+            }
+
+            worker_shutdown!(to_writer, shutdown_barrier);
+        })
+    };
+
+    let writer_thread = {
+        let stats = Arc::clone(&stats);
+        let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+
+        thread::spawn(move || {
+            while let Some((reader_stats, mut buffer)) = from_encoder.recv().unwrap() {
+                if let Err(e) = buffer.write(&mut writer) {
+                    error_tx_writer.send(e).unwrap();
+                    break;
+                }
+
+                {
+                    let (data_blocks, _, parity_blocks) = buffer.data_padding_parity_block_count();
+
+                    let mut stats = stats.lock().unwrap();
+
+                    stats.bytes_processed = reader_stats.bytes_processed;
+                    stats.data_blocks_decoded += reader_stats.data_blocks_decoded;
+                    stats.blank_blocks_decoded += reader_stats.blank_blocks_decoded;
+                    stats.data_blocks_written += data_blocks as u64;
+                    stats.parity_blocks_written += parity_blocks as u64;
+                }
+
+                buffer.reset();
+
+                to_reader.send(Some(buffer)).unwrap();
+            }
+
+            worker_shutdown!(to_reader, shutdown_barrier);
+        })
+    };
+
+    reader_thread.join().unwrap();
+    encoder_thread.join().unwrap();
+    writer_thread.join().unwrap();
+
+    if let Ok(err) = error_rx.try_recv() {
+        return Err(err);
+    }
+
+    reporter.stop();
+
+    let bytes_processed = stats.lock().unwrap().bytes_processed;
+
+    // interrupted before all data was converted
+    if bytes_processed < file_size {
+        return Err(Error::with_msg(&format!(
+            "Conversion interrupted, output file \"{}\" is incomplete",
+            param.out_file
+        )));
+    }
+
+    let computed_hash = Arc::try_unwrap(hash_ctx)
+        .unwrap()
+        .into_inner()
+        .unwrap()
+        .map(|ctx| ctx.finish_into_hash_bytes());
+
+    let mut stats = stats.lock().unwrap().clone();
+
+    stats.out_file_size = file_utils::from_orig_file_size::calc_container_size(
+        param.version,
+        Some(true),
+        param.data_par_burst,
+        file_size,
+    );
+
+    stats.computed_hash = computed_hash;
+
+    if let (Some(recorded_hash), Some(computed_hash)) = (&stats.recorded_hash, &stats.computed_hash)
+    {
+        if recorded_hash != computed_hash {
+            return Err(Error::with_msg(&format!(
+                "Hash of converted data does not match the recorded hash, recorded : {}, computed : {}",
+                misc_utils::bytes_to_lower_hex_string(&recorded_hash.1),
+                misc_utils::bytes_to_lower_hex_string(&computed_hash.1)
+            )));
+        }
+    }
+
+    Ok(Some(stats))
+}
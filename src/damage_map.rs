@@ -0,0 +1,385 @@
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::misc_utils;
+use crate::sbx_block;
+use crate::sbx_specs::{
+    string_to_ver, ver_to_block_size, ver_to_data_size, ver_to_usize, Version, SBX_FILE_UID_LEN,
+    SBX_FIRST_DATA_SEQ_NUM,
+};
+use std::collections::BTreeMap;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DamagedBlock {
+    pub seq_num: u32,
+    pub pos: u64,
+}
+
+#[derive(Clone, Debug)]
+pub struct DamageMap {
+    uid: [u8; SBX_FILE_UID_LEN],
+    version: Version,
+    data_par_burst: Option<(usize, usize, usize)>,
+    meta_blocks: Vec<u64>,
+    data_blocks: Vec<DamagedBlock>,
+}
+
+mod parsers {
+    use crate::sbx_specs::SBX_FILE_UID_LEN;
+    use nom::character::complete::digit1;
+    use nom::character::complete::newline;
+
+    type Range<'a> = (&'a [u8], &'a [u8]);
+
+    type DataBlockParseResult<'a> = (&'a [u8], &'a [u8], Range<'a>);
+
+    type DamageMapParseResult<'a> = (
+        &'a [u8],
+        &'a [u8],
+        Option<(&'a [u8], &'a [u8], &'a [u8])>,
+        Vec<Range<'a>>,
+        Vec<DataBlockParseResult<'a>>,
+    );
+
+    named!(uid_p <&[u8]>,
+           do_parse!(
+               _id : tag!(b"uid=") >>
+                   uid : take!(SBX_FILE_UID_LEN * 2) >> _n : newline >>
+                   (uid)
+           )
+    );
+
+    named!(version_p <&[u8]>,
+           do_parse!(
+               _id : tag!(b"version=") >>
+                   ver : digit1 >> _n : newline >>
+                   (ver)
+           )
+    );
+
+    named!(data_par_burst_p <(&[u8], &[u8], &[u8])>,
+           do_parse!(
+               _id : tag!(b"data_par_burst=") >>
+                   data   : digit1 >> _c : char!(',') >>
+                   parity : digit1 >> _c : char!(',') >>
+                   burst  : digit1 >> _n : newline >>
+                   ((data, parity, burst))
+           )
+    );
+
+    named!(range_p <Range>,
+           do_parse!(
+               _id : tag!(b"range=") >>
+                   start   : digit1 >> _d : char!('-') >>
+                   end_exc : digit1 >>
+                   ((start, end_exc))
+           )
+    );
+
+    named!(meta_p <Range>,
+           do_parse!(
+               _id : tag!(b"meta ") >>
+                   range : range_p >> _n : newline >>
+                   (range)
+           )
+    );
+
+    named!(data_p <DataBlockParseResult>,
+           do_parse!(
+               _id : tag!(b"set=") >>
+                   set     : digit1 >>
+                   _id : tag!(b" seq_num=") >>
+                   seq_num : digit1 >> _s : char!(' ') >>
+                   range   : range_p >> _n : newline >>
+                   ((set, seq_num, range))
+           )
+    );
+
+    named!(pub damage_map_p <DamageMapParseResult>,
+           do_parse!(
+               uid            : uid_p >>
+                   ver            : version_p >>
+                   data_par_burst : opt!(data_par_burst_p) >>
+                   metas          : many0!(meta_p) >>
+                   datas          : many0!(data_p) >>
+                   ((uid, ver, data_par_burst, metas, datas))
+           )
+    );
+}
+
+fn parse_num<T: std::str::FromStr>(bytes: &[u8]) -> Result<T, ()> {
+    match std::str::from_utf8(bytes) {
+        Ok(x) => match x.parse::<T>() {
+            Ok(x) => Ok(x),
+            Err(_) => Err(()),
+        },
+        Err(_) => Err(()),
+    }
+}
+
+impl DamageMap {
+    pub fn new(
+        uid: &[u8; SBX_FILE_UID_LEN],
+        version: Version,
+        data_par_burst: Option<(usize, usize, usize)>,
+    ) -> DamageMap {
+        DamageMap {
+            uid: *uid,
+            version,
+            data_par_burst,
+            meta_blocks: Vec::new(),
+            data_blocks: Vec::new(),
+        }
+    }
+
+    pub fn uid(&self) -> &[u8; SBX_FILE_UID_LEN] {
+        &self.uid
+    }
+
+    pub fn version(&self) -> Version {
+        self.version
+    }
+
+    pub fn data_par_burst(&self) -> Option<(usize, usize, usize)> {
+        self.data_par_burst
+    }
+
+    pub fn meta_blocks(&self) -> &[u64] {
+        &self.meta_blocks
+    }
+
+    pub fn add_meta_block(&mut self, pos: u64) {
+        self.meta_blocks.push(pos);
+    }
+
+    pub fn add_data_block(&mut self, seq_num: u32, pos: u64) {
+        self.data_blocks.push(DamagedBlock { seq_num, pos });
+    }
+
+    pub fn block_set_size(&self) -> u32 {
+        match self.data_par_burst {
+            Some((data, parity, _)) => (data + parity) as u32,
+            None => 1,
+        }
+    }
+
+    pub fn block_set_index(&self, seq_num: u32) -> u64 {
+        ((seq_num - SBX_FIRST_DATA_SEQ_NUM) / self.block_set_size()) as u64
+    }
+
+    pub fn block_set_first_seq_num(&self, block_set_index: u64) -> u32 {
+        block_set_index as u32 * self.block_set_size() + SBX_FIRST_DATA_SEQ_NUM
+    }
+
+    pub fn block_sets(&self) -> BTreeMap<u64, Vec<DamagedBlock>> {
+        let mut block_sets: BTreeMap<u64, Vec<DamagedBlock>> = BTreeMap::new();
+
+        for block in self.data_blocks.iter() {
+            block_sets
+                .entry(self.block_set_index(block.seq_num))
+                .or_default()
+                .push(*block);
+        }
+
+        for blocks in block_sets.values_mut() {
+            blocks.sort_by_key(|block| block.seq_num);
+            blocks.dedup();
+        }
+
+        block_sets
+    }
+
+    pub fn block_set_is_recoverable(&self, damaged_block_count: usize) -> bool {
+        match self.data_par_burst {
+            Some((_, parity, _)) => damaged_block_count <= parity,
+            None => damaged_block_count == 0,
+        }
+    }
+
+    pub fn parity_margin(&self, damaged_block_count: usize) -> i64 {
+        let parity = match self.data_par_burst {
+            Some((_, parity, _)) => parity,
+            None => 0,
+        };
+
+        parity as i64 - damaged_block_count as i64
+    }
+
+    pub fn worst_block_set(&self) -> Option<(u64, usize)> {
+        let mut worst: Option<(u64, usize)> = None;
+
+        for (&index, blocks) in self.block_sets().iter() {
+            match worst {
+                Some((_, count)) if count >= blocks.len() => {}
+                _ => worst = Some((index, blocks.len())),
+            }
+        }
+
+        worst
+    }
+
+    pub fn unrecoverable_block_set_count(&self) -> u64 {
+        self.block_sets()
+            .values()
+            .filter(|blocks| !self.block_set_is_recoverable(blocks.len()))
+            .count() as u64
+    }
+
+    pub fn unrecoverable_data_ranges(&self, orig_file_size: Option<u64>) -> Vec<(u64, u64)> {
+        let data_size = ver_to_data_size(self.version) as u64;
+        let data_par = self.data_par_burst.map(|(data, parity, _)| (data, parity));
+
+        let mut ranges: Vec<(u64, u64)> = Vec::new();
+
+        for blocks in self.block_sets().values() {
+            if self.block_set_is_recoverable(blocks.len()) {
+                continue;
+            }
+
+            for block in blocks.iter() {
+                let start = match sbx_block::calc_data_chunk_write_pos(
+                    self.version,
+                    block.seq_num,
+                    data_par,
+                ) {
+                    Some(x) => x,
+                    None => continue,
+                };
+                let end_exc = match orig_file_size {
+                    Some(size) => {
+                        if start >= size {
+                            continue;
+                        }
+                        std::cmp::min(start + data_size, size)
+                    }
+                    None => start + data_size,
+                };
+
+                match ranges.last_mut() {
+                    Some(last) if last.1 == start => last.1 = end_exc,
+                    _ => ranges.push((start, end_exc)),
+                }
+            }
+        }
+
+        ranges
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), Error> {
+        let block_size = ver_to_block_size(self.version) as u64;
+
+        let mut string = String::with_capacity(200);
+        string.push_str(&format!(
+            "uid={}\n",
+            misc_utils::bytes_to_upper_hex_string(&self.uid)
+        ));
+        string.push_str(&format!("version={}\n", ver_to_usize(self.version)));
+        if let Some((data, parity, burst)) = self.data_par_burst {
+            string.push_str(&format!("data_par_burst={},{},{}\n", data, parity, burst));
+        }
+        let mut meta_blocks = self.meta_blocks.clone();
+        meta_blocks.sort();
+        meta_blocks.dedup();
+        for &pos in meta_blocks.iter() {
+            string.push_str(&format!("meta range={}-{}\n", pos, pos + block_size));
+        }
+        for (set, blocks) in self.block_sets().iter() {
+            for block in blocks.iter() {
+                string.push_str(&format!(
+                    "set={} seq_num={} range={}-{}\n",
+                    set,
+                    block.seq_num,
+                    block.pos,
+                    block.pos + block_size
+                ));
+            }
+        }
+
+        let mut writer = FileWriter::new(
+            path,
+            FileWriterParam {
+                read: false,
+                append: false,
+                truncate: true,
+                buffered: true,
+            },
+        )?;
+
+        writer.write(string.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn read_from_file(path: &str) -> Result<DamageMap, Error> {
+        let file_size = file_utils::get_file_size(path)?;
+
+        let mut reader = FileReader::new(
+            path,
+            FileReaderParam {
+                write: false,
+                buffered: false,
+            },
+        )?;
+
+        // leave a trailing zero byte so the parsers never run out of input
+        let mut buffer = vec![0; file_size as usize + 1];
+        let _len_read = reader.read(&mut buffer[..file_size as usize])?;
+
+        match DamageMap::parse(&buffer) {
+            Ok(map) => Ok(map),
+            Err(()) => Err(Error::with_msg(&format!(
+                "Failed to parse damage map \"{}\"",
+                path
+            ))),
+        }
+    }
+
+    pub fn parse(input: &[u8]) -> Result<DamageMap, ()> {
+        let (uid_str, ver, data_par_burst, metas, datas) = match parsers::damage_map_p(input) {
+            Ok((rest, x)) => {
+                // only the zero padding may be left over
+                if !misc_utils::buffer_is_blank(rest) {
+                    return Err(());
+                }
+                x
+            }
+            Err(_) => return Err(()),
+        };
+
+        let mut uid = [0; SBX_FILE_UID_LEN];
+        match std::str::from_utf8(uid_str) {
+            Ok(x) => match misc_utils::hex_string_to_bytes(x) {
+                Ok(x) => uid.copy_from_slice(&x),
+                Err(_) => return Err(()),
+            },
+            Err(_) => return Err(()),
+        }
+
+        let version = match std::str::from_utf8(ver) {
+            Ok(x) => string_to_ver(x)?,
+            Err(_) => return Err(()),
+        };
+
+        let data_par_burst = match data_par_burst {
+            Some((data, parity, burst)) => Some((
+                parse_num::<usize>(data)?,
+                parse_num::<usize>(parity)?,
+                parse_num::<usize>(burst)?,
+            )),
+            None => None,
+        };
+
+        let mut map = DamageMap::new(&uid, version, data_par_burst);
+
+        for (start, _) in metas.into_iter() {
+            map.add_meta_block(parse_num(start)?);
+        }
+
+        for (_, seq_num, (start, _)) in datas.into_iter() {
+            map.add_data_block(parse_num(seq_num)?, parse_num(start)?);
+        }
+
+        Ok(map)
+    }
+}
@@ -0,0 +1,143 @@
+#![cfg(test)]
+
+use crate::damage_map::*;
+use crate::sbx_specs::Version;
+
+#[test]
+fn test_block_sets_grouping() {
+    let mut map = DamageMap::new(&[0; 6], Version::V17, Some((10, 2, 0)));
+
+    map.add_data_block(13, 0);
+    map.add_data_block(1, 0);
+    map.add_data_block(12, 0);
+    map.add_data_block(25, 0);
+    map.add_data_block(1, 0);
+
+    let block_sets = map.block_sets();
+
+    assert_eq!(3, block_sets.len());
+    assert_eq!(2, block_sets[&0].len());
+    assert_eq!(1, block_sets[&0][0].seq_num);
+    assert_eq!(12, block_sets[&0][1].seq_num);
+    assert_eq!(1, block_sets[&1].len());
+    assert_eq!(1, block_sets[&2].len());
+
+    assert_eq!(13, map.block_set_first_seq_num(1));
+}
+
+#[test]
+fn test_unrecoverable_block_set_count() {
+    let mut map = DamageMap::new(&[0; 6], Version::V17, Some((10, 2, 0)));
+
+    map.add_data_block(1, 0);
+    map.add_data_block(2, 0);
+    assert_eq!(0, map.unrecoverable_block_set_count());
+
+    map.add_data_block(3, 0);
+    assert_eq!(1, map.unrecoverable_block_set_count());
+
+    map.add_data_block(13, 0);
+    assert_eq!(1, map.unrecoverable_block_set_count());
+}
+
+#[test]
+fn test_parity_margin_and_worst_block_set() {
+    let mut map = DamageMap::new(&[0; 6], Version::V17, Some((10, 2, 0)));
+
+    assert_eq!(None, map.worst_block_set());
+
+    map.add_data_block(13, 0);
+    map.add_data_block(25, 0);
+    map.add_data_block(26, 0);
+    map.add_data_block(27, 0);
+    map.add_data_block(37, 0);
+    map.add_data_block(38, 0);
+    map.add_data_block(39, 0);
+
+    assert_eq!(Some((2, 3)), map.worst_block_set());
+    assert_eq!(2, map.parity_margin(0));
+    assert_eq!(1, map.parity_margin(1));
+    assert_eq!(-1, map.parity_margin(3));
+}
+
+#[test]
+fn test_parity_margin_non_rs() {
+    let map = DamageMap::new(&[0; 6], Version::V1, None);
+
+    assert_eq!(0, map.parity_margin(0));
+    assert_eq!(-1, map.parity_margin(1));
+}
+
+#[test]
+fn test_unrecoverable_data_ranges_rs() {
+    let mut map = DamageMap::new(&[0; 6], Version::V17, Some((3, 1, 0)));
+
+    // seq num 4 is parity, so only data chunks 0 and 1 are lost
+    map.add_data_block(1, 0);
+    map.add_data_block(2, 0);
+    map.add_data_block(4, 0);
+
+    assert_eq!(vec![(0, 496 * 2)], map.unrecoverable_data_ranges(None));
+    assert_eq!(vec![(0, 600)], map.unrecoverable_data_ranges(Some(600)));
+}
+
+#[test]
+fn test_unrecoverable_data_ranges_non_rs() {
+    let mut map = DamageMap::new(&[0; 6], Version::V1, None);
+
+    map.add_data_block(3, 0);
+    map.add_data_block(1, 0);
+    map.add_data_block(2, 0);
+    map.add_data_block(10, 0);
+
+    assert_eq!(
+        vec![(0, 496 * 3), (496 * 9, 496 * 10)],
+        map.unrecoverable_data_ranges(None)
+    );
+    assert_eq!(vec![(0, 1000)], map.unrecoverable_data_ranges(Some(1000)));
+}
+
+#[test]
+fn test_parse_simple_cases() {
+    let input = b"uid=0102030405AB
+version=17
+data_par_burst=10,2,0
+meta range=0-4096
+set=0 seq_num=1 range=12288-16384
+set=1 seq_num=13 range=61440-65536
+\0";
+
+    let map = DamageMap::parse(input).unwrap();
+
+    assert_eq!(&[1, 2, 3, 4, 5, 0xAB], map.uid());
+    assert_eq!(Version::V17, map.version());
+    assert_eq!(Some((10, 2, 0)), map.data_par_burst());
+    assert_eq!(&[0], map.meta_blocks());
+
+    let block_sets = map.block_sets();
+    assert_eq!(2, block_sets.len());
+    assert_eq!(13, block_sets[&1][0].seq_num);
+    assert_eq!(61440, block_sets[&1][0].pos);
+}
+
+#[test]
+fn test_parse_non_rs() {
+    let input = b"uid=0102030405AB
+version=1
+set=2 seq_num=3 range=1536-2048
+\0";
+
+    let map = DamageMap::parse(input).unwrap();
+
+    assert_eq!(None, map.data_par_burst());
+    assert_eq!(0, map.meta_blocks().len());
+    assert_eq!(3, map.block_sets()[&2][0].seq_num);
+}
+
+#[test]
+fn test_parse_invalid_cases() {
+    assert!(DamageMap::parse(b"uid=0102030405AB\nversion=5\n\0").is_err());
+    assert!(DamageMap::parse(b"uid=01020304ZZAB\nversion=1\n\0").is_err());
+    assert!(DamageMap::parse(b"version=1\n\0").is_err());
+    assert!(DamageMap::parse(b"uid=0102030405AB\nversion=1\nset=0 seq_num=1\n\0").is_err());
+}
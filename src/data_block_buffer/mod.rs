@@ -7,6 +7,7 @@ use crate::sbx_specs::{ver_to_block_size, ver_to_data_size, SBX_FILE_UID_LEN};
 use crate::sbx_specs::{
     ver_uses_rs, Version, SBX_FIRST_DATA_SEQ_NUM, SBX_LARGEST_BLOCK_SIZE, SBX_LAST_SEQ_NUM,
 };
+use crate::sparse_map::SparseMap;
 use crate::writer::Writer;
 use rayon::prelude::*;
 use reed_solomon_erasure::galois_8::ReedSolomon;
@@ -529,12 +530,30 @@ impl Lot {
         self.padding_byte_count_in_non_padding_blocks
     }
 
-    fn write(&mut self, seek: bool, writer: &mut Writer) -> Result<(), Error> {
+    fn write(
+        &mut self,
+        seek: bool,
+        mut sparse_map: Option<&mut SparseMap>,
+        writer: &mut Writer,
+    ) -> Result<(), Error> {
         assert!(self.output_type != OutputType::Disabled);
 
         for (slot_index, slot) in self.data.chunks_mut(self.block_size).enumerate() {
             if slot_index < self.slots_used {
                 if let Some(write_pos) = self.slot_write_pos[slot_index] {
+                    // blocks carrying only zeros are left as holes
+                    if let Some(ref mut sparse_map) = sparse_map {
+                        if self.output_type == OutputType::Block
+                            && misc_utils::buffer_is_blank(sbx_block::slice_data_buf(
+                                self.version,
+                                slot,
+                            ))
+                        {
+                            sparse_map.add_seq_num(self.blocks[slot_index].get_seq_num());
+                            continue;
+                        }
+                    }
+
                     if seek {
                         writer.seek(SeekFrom::Start(write_pos)).unwrap()?;
                     }
@@ -690,6 +709,12 @@ impl DataBlockBuffer {
         res
     }
 
+    // used when continuing from a point where seq nums before
+    // the start of the buffers have already been used
+    pub fn skip_seq_nums(&mut self, count: u32) {
+        self.start_seq_num = self.start_seq_num.and_then(|x| x.checked_add(count));
+    }
+
     pub fn lot_count(&self) -> usize {
         self.lots.len()
     }
@@ -831,21 +856,40 @@ impl DataBlockBuffer {
         })
     }
 
-    fn write_internal(&mut self, seek: bool, writer: &mut Writer) -> Result<(), Error> {
+    fn write_internal(
+        &mut self,
+        seek: bool,
+        mut sparse_map: Option<&mut SparseMap>,
+        writer: &mut Writer,
+    ) -> Result<(), Error> {
         self.calc_slot_write_pos();
 
         for lot in self.lots.iter_mut() {
-            lot.write(seek, writer)?;
+            let sparse_map = match sparse_map {
+                Some(ref mut x) => Some(&mut **x),
+                None => None,
+            };
+
+            lot.write(seek, sparse_map, writer)?;
         }
 
         Ok(())
     }
 
     pub fn write(&mut self, writer: &mut Writer) -> Result<(), Error> {
-        self.write_internal(true, writer)
+        self.write_internal(true, None, writer)
     }
 
     pub fn write_no_seek(&mut self, writer: &mut Writer) -> Result<(), Error> {
-        self.write_internal(false, writer)
+        self.write_internal(false, None, writer)
+    }
+
+    // blocks skipped are recorded in the sparse map
+    pub fn write_skip_blank(
+        &mut self,
+        writer: &mut Writer,
+        sparse_map: &mut SparseMap,
+    ) -> Result<(), Error> {
+        self.write_internal(true, Some(sparse_map), writer)
     }
 }
@@ -1,14 +1,19 @@
 #![allow(dead_code)]
+use crate::bad_ranges;
+use crate::bad_ranges::{ChunkTracker, FillPolicy};
 use crate::block_utils;
 use crate::block_utils::RefBlockChoice;
-use crate::cli_utils::setup_ctrlc_handler;
+use crate::cli_utils::{report_ref_block_info, setup_ctrlc_handler};
+use crate::damage_map::DamageMap;
 use crate::data_block_buffer::{BlockArrangement, DataBlockBuffer, InputType, OutputType, Slot};
 use crate::file_reader::{FileReader, FileReaderParam};
 use crate::file_utils;
 use crate::file_writer::{FileWriter, FileWriterParam};
 use crate::general_error::Error;
 use crate::hash_stats::HashStats;
+use crate::integer_utils::IntegerUtils;
 use crate::json_printer::{BracketType, JSONPrinter};
+use crate::log::*;
 use crate::misc_utils;
 use crate::misc_utils::MultiPassType;
 use crate::misc_utils::RequiredLenAndSeekTo;
@@ -16,17 +21,22 @@ use crate::misc_utils::{PositionOrLength, RangeEnd};
 use crate::multihash;
 use crate::multihash::*;
 use crate::progress_report::*;
+use crate::rs_codec::{RSCodecState, RSRepairer};
 use crate::sbx_block;
 use crate::sbx_block::Block;
 use crate::sbx_specs::Version;
 use crate::sbx_specs::{
     ver_to_block_size, ver_to_data_size, ver_to_usize, ver_uses_rs, SBX_FILE_UID_LEN,
-    SBX_LARGEST_BLOCK_SIZE,
+    SBX_FIRST_DATA_SEQ_NUM, SBX_LARGEST_BLOCK_SIZE, SBX_MAX_BURST_ERR_RESISTANCE,
 };
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
+use crate::stdin_error::{to_err, StdinError};
 use crate::time_utils;
 use crate::writer::{Writer, WriterType};
+use std::collections::BTreeMap;
 use std::fmt;
-use std::io::SeekFrom;
+use std::io::{ErrorKind, Read, SeekFrom};
 use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::channel;
 use std::sync::mpsc::sync_channel;
@@ -40,6 +50,20 @@ const BLANK_BUFFER: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
 
 const PIPELINE_BUFFER_IN_ROTATION: usize = 9;
 
+// enough blocks to find the reference block and to guess the burst error
+// resistance level when the input is stdin
+const STDIN_WINDOW_SIZE: usize =
+    (1 + 256 + SBX_MAX_BURST_ERR_RESISTANCE + 1) * SBX_LARGEST_BLOCK_SIZE;
+
+struct SendToWriter {
+    bytes_processed: u64,
+    meta_blocks_decoded: u64,
+    data_blocks_decoded: u64,
+    parity_blocks_decoded: u64,
+    blank_blocks_decoded: u64,
+    blocks_decode_failed: u64,
+}
+
 pub enum WriteTo {
     File,
     Stdout,
@@ -84,18 +108,23 @@ pub struct Stats {
     uid: [u8; SBX_FILE_UID_LEN],
     version: Version,
     block_size: u64,
+    bytes_processed: u64,
     pub meta_blocks_decoded: u64,
     pub data_blocks_decoded: u64,
     pub parity_blocks_decoded: u64,
+    pub blank_blocks_decoded: u64,
     pub blocks_decode_failed: DecodeFailStats,
     pub in_file_size: u64,
     pub out_file_size: u64,
-    total_blocks: u64,
+    total_blocks: Option<u64>,
     start_time: f64,
     end_time: f64,
     pub recorded_hash: Option<multihash::HashBytes>,
     pub computed_hash: Option<multihash::HashBytes>,
     hash_stats: Option<HashStats>,
+    unrecoverable_ranges: Option<Vec<(u64, u64)>>,
+    sparse: bool,
+    blocks_repaired: Option<u64>,
     json_printer: Arc<JSONPrinter>,
 }
 
@@ -172,6 +201,15 @@ impl fmt::Display for Stats {
                 padding,
                 self.parity_blocks_decoded
             )?;
+            if self.sparse {
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blank blocks (sparse)        {}: {}",
+                    padding,
+                    self.blank_blocks_decoded
+                )?;
+            }
             match self.blocks_decode_failed {
                 DecodeFailStats::Total(x) => write_maybe_json!(
                     f,
@@ -200,6 +238,15 @@ impl fmt::Display for Stats {
                     )?;
                 }
             };
+            if let Some(x) = self.blocks_repaired {
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blocks repaired in memory    {}: {}",
+                    padding,
+                    x
+                )?;
+            }
             write_maybe_json!(
                 f,
                 json_printer,
@@ -332,6 +379,15 @@ impl fmt::Display for Stats {
                 padding,
                 self.data_blocks_decoded
             )?;
+            if self.sparse {
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blank blocks (sparse)     {}: {}",
+                    padding,
+                    self.blank_blocks_decoded
+                )?;
+            }
             match self.blocks_decode_failed {
                 DecodeFailStats::Total(x) => write_maybe_json!(
                     f,
@@ -438,6 +494,38 @@ impl fmt::Display for Stats {
                 )?;
             }
         }
+        if let Some(ref ranges) = self.unrecoverable_ranges {
+            if json_printer.json_enabled() {
+                json_printer.write_open_bracket(
+                    f,
+                    Some("unrecoverable ranges"),
+                    BracketType::Square,
+                )?;
+
+                for &(start, end_exc) in ranges.iter() {
+                    json_printer.write_open_bracket(f, None, BracketType::Curly)?;
+                    write_maybe_json!(f, json_printer, "from : {}", start)?;
+                    write_maybe_json!(f, json_printer, "to   : {}", end_exc - 1)?;
+                    json_printer.write_close_bracket(f)?;
+                }
+
+                json_printer.write_close_bracket(f)?;
+            } else {
+                let ranges_str: Vec<String> = ranges
+                    .iter()
+                    .map(|&(start, end_exc)| format!("{}-{}", start, end_exc - 1))
+                    .collect();
+
+                write_if!(not_json => f, json_printer =>
+                          "Unrecoverable ranges of output file : {}",
+                          if ranges_str.is_empty() {
+                              String::from("None")
+                          } else {
+                              ranges_str.join(", ")
+                          };
+                )?;
+            }
+        }
         match (recorded_hash, computed_hash) {
             (Some(recorded_hash), Some(computed_hash)) => {
                 if recorded_hash.1 == computed_hash.1 {
@@ -477,6 +565,11 @@ pub struct Param {
     force_misalign: bool,
     in_file: String,
     out_file: Option<String>,
+    log_file: Option<String>,
+    damage_map_file: Option<String>,
+    bad_ranges_file: Option<String>,
+    fill: Option<FillPolicy>,
+    sparse_map_file: Option<String>,
     verbose: bool,
     pr_verbosity_level: PRVerbosityLevel,
     burst: Option<usize>,
@@ -496,6 +589,11 @@ impl Param {
         force_misalign: bool,
         in_file: &str,
         out_file: Option<&str>,
+        log_file: Option<&str>,
+        damage_map_file: Option<&str>,
+        bad_ranges_file: Option<&str>,
+        fill: Option<FillPolicy>,
+        sparse_map_file: Option<&str>,
         verbose: bool,
         pr_verbosity_level: PRVerbosityLevel,
         burst: Option<usize>,
@@ -516,6 +614,23 @@ impl Param {
                 None => None,
                 Some(x) => Some(String::from(x)),
             },
+            log_file: match log_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            damage_map_file: match damage_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            bad_ranges_file: match bad_ranges_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            fill,
+            sparse_map_file: match sparse_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
             verbose,
             pr_verbosity_level,
             burst,
@@ -546,18 +661,23 @@ impl Stats {
             uid: ref_block.get_uid(),
             version,
             block_size: ver_to_block_size(version) as u64,
+            bytes_processed: 0,
             blocks_decode_failed,
             meta_blocks_decoded: 0,
             data_blocks_decoded: 0,
             parity_blocks_decoded: 0,
+            blank_blocks_decoded: 0,
             in_file_size,
             out_file_size: 0,
-            total_blocks,
+            total_blocks: Some(total_blocks),
             start_time: 0.,
             end_time: 0.,
             recorded_hash: None,
             computed_hash: None,
             hash_stats: None,
+            unrecoverable_ranges: None,
+            sparse: ref_block.is_meta() && ref_block.get_SPR().unwrap().is_some(),
+            blocks_repaired: None,
             json_printer: Arc::clone(json_printer),
         }
     }
@@ -636,10 +756,66 @@ impl Stats {
         self.meta_blocks_decoded
             + self.data_blocks_decoded
             + self.parity_blocks_decoded
+            + self.blank_blocks_decoded
             + blocks_decode_failed
     }
 }
 
+impl Log for Stats {
+    fn serialize(&self) -> String {
+        let mut string = String::with_capacity(200);
+        string.push_str(&format!("bytes_processed={}\n", self.bytes_processed));
+        string.push_str(&format!(
+            "meta_blocks_decoded={}\n",
+            self.meta_blocks_decoded
+        ));
+        string.push_str(&format!(
+            "data_blocks_decoded={}\n",
+            self.data_blocks_decoded
+        ));
+        string.push_str(&format!(
+            "parity_blocks_decoded={}\n",
+            self.parity_blocks_decoded
+        ));
+        string.push_str(&format!("blocks_failed={}\n", self.blocks_failed()));
+        string.push_str(&format!(
+            "blank_blocks_decoded={}\n",
+            self.blank_blocks_decoded
+        ));
+
+        string
+    }
+
+    fn deserialize(&mut self, input: &[u8]) -> Result<(), ()> {
+        let fields = parse_u64_fields(
+            input,
+            &[
+                "bytes_processed",
+                "meta_blocks_decoded",
+                "data_blocks_decoded",
+                "parity_blocks_decoded",
+                "blocks_failed",
+            ],
+        )?;
+
+        match self.blocks_decode_failed {
+            DecodeFailStats::Total(ref mut x) => *x = fields[4],
+            DecodeFailStats::Breakdown(_) => return Err(()),
+        }
+
+        self.bytes_processed = u64::round_down_to_multiple(fields[0], self.block_size);
+        self.meta_blocks_decoded = fields[1];
+        self.data_blocks_decoded = fields[2];
+        self.parity_blocks_decoded = fields[3];
+        // logs written before sparse containers were introduced do not have this field
+        self.blank_blocks_decoded = match parse_u64_fields(input, &["blank_blocks_decoded"]) {
+            Ok(fields) => fields[0],
+            Err(()) => 0,
+        };
+        Ok(())
+    }
+}
+
 impl ProgressReport for Stats {
     fn start_time_mut(&mut self) -> &mut f64 {
         &mut self.start_time
@@ -654,7 +830,7 @@ impl ProgressReport for Stats {
     }
 
     fn total_units(&self) -> Option<u64> {
-        Some(self.total_blocks * self.block_size)
+        self.total_blocks.map(|x| x * self.block_size)
     }
 }
 
@@ -720,14 +896,53 @@ fn write_blank_chunk(
     Ok(())
 }
 
+fn fill_ranges(writer: &mut Writer, ranges: &[(u64, u64)], fill: FillPolicy) -> Result<(), Error> {
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    for &(start, end_exc) in ranges.iter() {
+        let mut pos = start;
+
+        while pos < end_exc {
+            let len = std::cmp::min(buffer.len() as u64, end_exc - pos) as usize;
+
+            match fill {
+                FillPolicy::Zeros | FillPolicy::Hole => misc_utils::fill_zeros(&mut buffer[..len]),
+                FillPolicy::Marker => bad_ranges::fill_marker(&mut buffer[..len], pos),
+            }
+
+            if let Some(r) = writer.seek(SeekFrom::Start(pos)) {
+                r?;
+            }
+            writer.write(&buffer[..len])?;
+
+            pos += len as u64;
+        }
+    }
+
+    Ok(())
+}
+
 pub fn decode(
     param: &Param,
     ref_block_pos: u64,
     ref_block: &Block,
+    sparse_map: &Arc<Option<SparseMap>>,
     ctrlc_stop_flag: &Arc<AtomicBool>,
 ) -> Result<(Stats, Option<HashBytes>), Error> {
     let version = ref_block.get_version();
 
+    if param.out_file.is_none() && param.log_file.is_some() {
+        return Err(Error::with_msg(
+            "Log file cannot be used when output is stdout",
+        ));
+    }
+
+    if param.out_file.is_none() && param.fill == Some(FillPolicy::Hole) {
+        return Err(Error::with_msg(
+            "Holes cannot be left in output when output is stdout",
+        ));
+    }
+
     let in_file_size = file_utils::get_file_size(&param.in_file)?;
 
     let orig_file_size = if ref_block.is_meta() {
@@ -757,18 +972,7 @@ pub fn decode(
         },
     )?;
 
-    let writer = Arc::new(Mutex::new(match param.out_file {
-        Some(ref out_file) => Writer::new(WriterType::File(FileWriter::new(
-            out_file,
-            FileWriterParam {
-                read: param.multi_pass == Some(MultiPassType::SkipGood),
-                append: false,
-                truncate: param.multi_pass == None,
-                buffered: false,
-            },
-        )?)),
-        None => Writer::new(WriterType::Stdout(std::io::stdout())),
-    }));
+    let writer: Arc<Mutex<Writer>>;
 
     let stats: Arc<Mutex<Stats>>;
 
@@ -802,6 +1006,19 @@ pub fn decode(
 
     let header_pred = header_pred_same_ver_uid!(ref_block);
 
+    // keep track of which data chunks were recovered
+    let chunk_tracker = Arc::new(Mutex::new(
+        if param.bad_ranges_file.is_some() || param.fill.is_some() {
+            Some(ChunkTracker::new(version))
+        } else {
+            None
+        },
+    ));
+
+    let fill = param.fill;
+
+    let block_size = ver_to_block_size(version) as u64;
+
     // calulate length to read and position to seek to
     let RequiredLenAndSeekTo {
         required_len,
@@ -816,7 +1033,7 @@ pub fn decode(
     );
 
     match param.out_file {
-        Some(_) => {
+        Some(ref out_file) => {
             // output to file
             stats = Arc::new(Mutex::new(Stats::new(
                 &ref_block,
@@ -826,6 +1043,28 @@ pub fn decode(
                 &param.json_printer,
             )));
 
+            let log_handler = Arc::new(match param.log_file {
+                None => LogHandler::new(None, &stats),
+                Some(ref f) => LogHandler::new(Some(f), &stats),
+            });
+
+            // read from log file and update stats if the log file exists
+            log_handler.read_from_file()?;
+
+            let bytes_processed = stats.lock().unwrap().bytes_processed;
+
+            // do not truncate when continuing from log file,
+            // otherwise the output decoded so far would be lost
+            writer = Arc::new(Mutex::new(Writer::new(WriterType::File(FileWriter::new(
+                out_file,
+                FileWriterParam {
+                    read: param.multi_pass == Some(MultiPassType::SkipGood),
+                    append: false,
+                    truncate: param.multi_pass == None && bytes_processed == 0,
+                    buffered: false,
+                },
+            )?))));
+
             reporter = ProgressReporter::new(
                 &stats,
                 "Data decoding progress",
@@ -862,19 +1101,21 @@ pub fn decode(
                 to_reader.send(Some(buffer)).unwrap();
             }
 
+            log_handler.start();
             reporter.start();
 
             let reader_thread = {
-                let stats = Arc::clone(&stats);
                 let ctrlc_stop_flag = Arc::clone(ctrlc_stop_flag);
                 let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+                let chunk_tracker = Arc::clone(&chunk_tracker);
+                let sparse_map = Arc::clone(sparse_map);
 
-                // seek to calculated position
-                reader.seek(SeekFrom::Start(seek_to))?;
+                // seek to calculated position, skipping the part already decoded
+                reader.seek(SeekFrom::Start(seek_to + bytes_processed))?;
 
                 thread::spawn(move || {
                     let mut run = true;
-                    let mut bytes_processed: u64 = 0;
+                    let mut bytes_processed = bytes_processed;
 
                     while let Some(mut buffer) = from_writer.recv().unwrap() {
                         if !run {
@@ -884,6 +1125,7 @@ pub fn decode(
                         let mut meta_blocks_decoded = 0;
                         let mut data_blocks_decoded = 0;
                         let mut parity_blocks_decoded = 0;
+                        let mut blank_blocks_decoded = 0;
                         let mut blocks_decode_failed: u64 = 0;
 
                         while !buffer.is_full() {
@@ -891,6 +1133,8 @@ pub fn decode(
 
                             stop_run_if_reached_required_len!(run => bytes_processed, required_len);
 
+                            let read_pos = seek_to + bytes_processed;
+
                             let Slot {
                                 block,
                                 slot,
@@ -909,6 +1153,19 @@ pub fn decode(
 
                                     match block.sync_from_buffer(slot, Some(&header_pred), None) {
                                         Ok(()) => {
+                                            if let Some(ref mut tracker) =
+                                                *chunk_tracker.lock().unwrap()
+                                            {
+                                                if let Some(index) =
+                                                    sbx_block::calc_data_chunk_write_index(
+                                                        block.get_seq_num(),
+                                                        data_par_shards,
+                                                    )
+                                                {
+                                                    tracker.mark_recovered(index);
+                                                }
+                                            }
+
                                             // update stats
                                             if block.is_meta() {
                                                 meta_blocks_decoded += 1;
@@ -928,8 +1185,37 @@ pub fn decode(
                                             }
                                         }
                                         Err(_) => {
+                                            let seq_num = sbx_block::calc_seq_num_at_index(
+                                                read_pos / block_size,
+                                                Some(true),
+                                                data_par_burst,
+                                            );
+
+                                            // omitted block of a sparse container
+                                            if sparse_map::block_is_omitted(
+                                                &sparse_map,
+                                                seq_num,
+                                                slot,
+                                            ) {
+                                                blank_blocks_decoded += 1;
+
+                                                if let Some(ref mut tracker) =
+                                                    *chunk_tracker.lock().unwrap()
+                                                {
+                                                    if let Some(index) =
+                                                        sbx_block::calc_data_chunk_write_index(
+                                                            seq_num,
+                                                            data_par_shards,
+                                                        )
+                                                    {
+                                                        tracker.mark_recovered(index);
+                                                    }
+                                                }
+                                            } else {
+                                                blocks_decode_failed += 1;
+                                            }
+
                                             buffer.cancel_slot();
-                                            blocks_decode_failed += 1;
                                         }
                                     }
                                 }
@@ -940,19 +1226,16 @@ pub fn decode(
                             }
                         }
 
-                        {
-                            let mut stats = stats.lock().unwrap();
+                        let send_to_writer = SendToWriter {
+                            bytes_processed,
+                            meta_blocks_decoded,
+                            data_blocks_decoded,
+                            parity_blocks_decoded,
+                            blank_blocks_decoded,
+                            blocks_decode_failed,
+                        };
 
-                            stats.meta_blocks_decoded += meta_blocks_decoded;
-                            stats.data_blocks_decoded += data_blocks_decoded;
-                            stats.parity_blocks_decoded += parity_blocks_decoded;
-
-                            for _ in 0..blocks_decode_failed {
-                                stats.incre_blocks_failed();
-                            }
-                        }
-
-                        to_writer.send(Some(buffer)).unwrap();
+                        to_writer.send(Some((send_to_writer, buffer))).unwrap();
                     }
 
                     worker_shutdown!(to_writer, shutdown_barrier);
@@ -962,16 +1245,49 @@ pub fn decode(
             let writer_thread = {
                 let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
                 let writer = Arc::clone(&writer);
+                let stats = Arc::clone(&stats);
+                let log_handler = Arc::clone(&log_handler);
 
                 thread::spawn(move || {
                     let writer = &mut writer.lock().unwrap();
 
-                    while let Some(mut buffer) = from_reader.recv().unwrap() {
+                    while let Some((send_to_writer, mut buffer)) = from_reader.recv().unwrap() {
+                        // check if there's any error in log handling
+                        if let Err(e) = log_handler.pop_error() {
+                            error_tx_writer.send(e).unwrap();
+                            break;
+                        }
+
                         if let Err(e) = buffer.write(writer) {
                             error_tx_writer.send(e).unwrap();
                             break;
                         }
 
+                        // only update stats after the blocks are written,
+                        // so the log file never runs ahead of the output
+                        {
+                            let SendToWriter {
+                                bytes_processed,
+                                meta_blocks_decoded,
+                                data_blocks_decoded,
+                                parity_blocks_decoded,
+                                blank_blocks_decoded,
+                                blocks_decode_failed,
+                            } = send_to_writer;
+
+                            let mut stats = stats.lock().unwrap();
+
+                            stats.bytes_processed = bytes_processed;
+                            stats.meta_blocks_decoded += meta_blocks_decoded;
+                            stats.data_blocks_decoded += data_blocks_decoded;
+                            stats.parity_blocks_decoded += parity_blocks_decoded;
+                            stats.blank_blocks_decoded += blank_blocks_decoded;
+
+                            for _ in 0..blocks_decode_failed {
+                                stats.incre_blocks_failed();
+                            }
+                        }
+
                         buffer.reset();
 
                         to_reader.send(Some(buffer)).unwrap();
@@ -987,6 +1303,8 @@ pub fn decode(
             if let Ok(err) = error_rx.try_recv() {
                 return Err(err);
             }
+
+            log_handler.stop();
         }
         None => {
             // output to stdout
@@ -997,6 +1315,10 @@ pub fn decode(
                 }
             }
 
+            writer = Arc::new(Mutex::new(Writer::new(WriterType::Stdout(
+                std::io::stdout(),
+            ))));
+
             stats = Arc::new(Mutex::new(Stats::new(
                 &ref_block,
                 WriteTo::Stdout,
@@ -1095,6 +1417,8 @@ pub fn decode(
                         let ctrlc_stop_flag = Arc::clone(ctrlc_stop_flag);
                         let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
                         let stats = Arc::clone(&stats);
+                        let chunk_tracker = Arc::clone(&chunk_tracker);
+                        let sparse_map = Arc::clone(sparse_map);
                         let uid = ref_block.get_uid();
 
                         thread::spawn(move || {
@@ -1104,6 +1428,7 @@ pub fn decode(
                             let mut data_blocks_decoded = 0;
                             let mut parity_blocks_decoded = 0;
                             let mut data_blocks_failed = 0;
+                            let mut data_blocks_blank = 0;
 
                             while let Some(mut buffer) = from_writer.recv().unwrap() {
                                 if !run {
@@ -1111,6 +1436,7 @@ pub fn decode(
                                 }
 
                                 let mut data_blocks_failed_this_iteration = 0;
+                                let mut blank_blocks_decoded_this_iteration = 0;
                                 let mut parity_blocks_failed_this_iteration = 0;
 
                                 while !buffer.is_full() {
@@ -1144,6 +1470,15 @@ pub fn decode(
                                                     _ => false,
                                                 };
 
+                                            // omitted block of a sparse container
+                                            let blank = !decode_successful
+                                                && !read_res.eof_seen
+                                                && sparse_map::block_is_omitted(
+                                                    &sparse_map,
+                                                    seq_num,
+                                                    slot,
+                                                );
+
                                             if sbx_block::seq_num_is_meta(seq_num) {
                                                 unreachable!();
                                             } else if sbx_block::seq_num_is_parity(
@@ -1151,6 +1486,8 @@ pub fn decode(
                                             ) {
                                                 if decode_successful {
                                                     parity_blocks_decoded += 1;
+                                                } else if blank {
+                                                    blank_blocks_decoded_this_iteration += 1;
                                                 } else {
                                                     parity_blocks_failed_this_iteration += 1;
                                                 }
@@ -1158,11 +1495,40 @@ pub fn decode(
                                                 // save space by not storing parity blocks
                                                 buffer.cancel_slot();
                                             } else {
+                                                let chunk_index =
+                                                    sbx_block::calc_data_chunk_write_index(
+                                                        seq_num,
+                                                        data_par_shards,
+                                                    );
+
                                                 if decode_successful {
                                                     data_blocks_decoded += 1;
+
+                                                    if let (Some(ref mut tracker), Some(index)) = (
+                                                        &mut *chunk_tracker.lock().unwrap(),
+                                                        chunk_index,
+                                                    ) {
+                                                        tracker.mark_recovered(index);
+                                                    }
                                                 } else {
-                                                    data_blocks_failed_this_iteration += 1;
-                                                    data_blocks_failed += 1;
+                                                    if blank {
+                                                        blank_blocks_decoded_this_iteration += 1;
+                                                        data_blocks_blank += 1;
+                                                    } else {
+                                                        data_blocks_failed_this_iteration += 1;
+                                                        data_blocks_failed += 1;
+                                                    }
+
+                                                    if let (Some(ref mut tracker), Some(index)) = (
+                                                        &mut *chunk_tracker.lock().unwrap(),
+                                                        chunk_index,
+                                                    ) {
+                                                        if blank {
+                                                            tracker.mark_recovered(index);
+                                                        } else {
+                                                            tracker.mark_failed(index);
+                                                        }
+                                                    }
 
                                                     // replace with a blank block
                                                     block.set_version(version);
@@ -1171,11 +1537,27 @@ pub fn decode(
 
                                                     misc_utils::fill_zeros(slot);
 
+                                                    if let (
+                                                        Some(FillPolicy::Marker),
+                                                        Some(index),
+                                                        false,
+                                                    ) = (fill, chunk_index, blank)
+                                                    {
+                                                        bad_ranges::fill_marker(
+                                                            sbx_block::slice_data_buf_mut(
+                                                                version, slot,
+                                                            ),
+                                                            index * data_size as u64,
+                                                        );
+                                                    }
+
                                                     block.sync_to_buffer(None, slot).unwrap();
                                                 }
 
                                                 if let Some(count) = total_data_chunk_count {
-                                                    if data_blocks_decoded + data_blocks_failed
+                                                    if data_blocks_decoded
+                                                        + data_blocks_failed
+                                                        + data_blocks_blank
                                                         == count
                                                     {
                                                         *content_len_exc_header =
@@ -1198,6 +1580,8 @@ pub fn decode(
                                     let mut stats = stats.lock().unwrap();
 
                                     stats.data_blocks_decoded = data_blocks_decoded;
+                                    stats.blank_blocks_decoded +=
+                                        blank_blocks_decoded_this_iteration;
                                     for _ in 0..data_blocks_failed_this_iteration {
                                         stats.incre_data_blocks_failed();
                                     }
@@ -1295,6 +1679,8 @@ pub fn decode(
                             &ref_block,
                             data_par_burst,
                         )?;
+                        let chunk_tracker = Arc::clone(&chunk_tracker);
+                        let sparse_map = Arc::clone(sparse_map);
                         let uid = ref_block.get_uid();
 
                         thread::spawn(move || {
@@ -1305,6 +1691,7 @@ pub fn decode(
                             let mut data_blocks_decoded = 0;
                             let mut parity_blocks_decoded = 0;
                             let mut data_blocks_failed = 0;
+                            let mut data_blocks_blank = 0;
 
                             while let Some(mut buffer) = from_writer.recv().unwrap() {
                                 if !run {
@@ -1313,6 +1700,7 @@ pub fn decode(
 
                                 let mut meta_blocks_failed_this_iteration = 0;
                                 let mut data_blocks_failed_this_iteration = 0;
+                                let mut blank_blocks_decoded_this_iteration = 0;
                                 let mut parity_blocks_failed_this_iteration = 0;
 
                                 while !buffer.is_full() {
@@ -1356,6 +1744,15 @@ pub fn decode(
 
                                             let mut cancel_slot = false;
 
+                                            // omitted block of a sparse container
+                                            let blank = !decode_successful
+                                                && !read_res.eof_seen
+                                                && sparse_map::block_is_omitted(
+                                                    &sparse_map,
+                                                    seq_num,
+                                                    slot,
+                                                );
+
                                             if sbx_block::seq_num_is_meta(seq_num) {
                                                 // do nothing if block is meta
                                                 if decode_successful {
@@ -1372,6 +1769,8 @@ pub fn decode(
                                             ) {
                                                 if decode_successful {
                                                     parity_blocks_decoded += 1;
+                                                } else if blank {
+                                                    blank_blocks_decoded_this_iteration += 1;
                                                 } else {
                                                     parity_blocks_failed_this_iteration += 1;
                                                 }
@@ -1379,11 +1778,40 @@ pub fn decode(
                                                 // save space by not storing parity blocks
                                                 cancel_slot = true;
                                             } else {
+                                                let chunk_index =
+                                                    sbx_block::calc_data_chunk_write_index(
+                                                        seq_num,
+                                                        data_par_shards,
+                                                    );
+
                                                 if decode_successful {
                                                     data_blocks_decoded += 1;
+
+                                                    if let (Some(ref mut tracker), Some(index)) = (
+                                                        &mut *chunk_tracker.lock().unwrap(),
+                                                        chunk_index,
+                                                    ) {
+                                                        tracker.mark_recovered(index);
+                                                    }
                                                 } else {
-                                                    data_blocks_failed_this_iteration += 1;
-                                                    data_blocks_failed += 1;
+                                                    if blank {
+                                                        blank_blocks_decoded_this_iteration += 1;
+                                                        data_blocks_blank += 1;
+                                                    } else {
+                                                        data_blocks_failed_this_iteration += 1;
+                                                        data_blocks_failed += 1;
+                                                    }
+
+                                                    if let (Some(ref mut tracker), Some(index)) = (
+                                                        &mut *chunk_tracker.lock().unwrap(),
+                                                        chunk_index,
+                                                    ) {
+                                                        if blank {
+                                                            tracker.mark_recovered(index);
+                                                        } else {
+                                                            tracker.mark_failed(index);
+                                                        }
+                                                    }
 
                                                     // replace with a blank block
                                                     block.set_version(version);
@@ -1392,6 +1820,20 @@ pub fn decode(
 
                                                     misc_utils::fill_zeros(slot);
 
+                                                    if let (
+                                                        Some(FillPolicy::Marker),
+                                                        Some(index),
+                                                        false,
+                                                    ) = (fill, chunk_index, blank)
+                                                    {
+                                                        bad_ranges::fill_marker(
+                                                            sbx_block::slice_data_buf_mut(
+                                                                version, slot,
+                                                            ),
+                                                            index * data_size as u64,
+                                                        );
+                                                    }
+
                                                     block.sync_to_buffer(None, slot).unwrap();
                                                 }
                                             }
@@ -1400,7 +1842,9 @@ pub fn decode(
                                                 buffer.cancel_slot();
                                             } else {
                                                 if let Some(count) = total_data_chunk_count {
-                                                    if data_blocks_decoded + data_blocks_failed
+                                                    if data_blocks_decoded
+                                                        + data_blocks_failed
+                                                        + data_blocks_blank
                                                         == count
                                                     {
                                                         *content_len_exc_header =
@@ -1429,6 +1873,8 @@ pub fn decode(
                                     }
 
                                     stats.data_blocks_decoded = data_blocks_decoded;
+                                    stats.blank_blocks_decoded +=
+                                        blank_blocks_decoded_this_iteration;
                                     for _ in 0..data_blocks_failed_this_iteration {
                                         stats.incre_data_blocks_failed();
                                     }
@@ -1505,6 +1951,24 @@ pub fn decode(
 
     reporter.stop();
 
+    // chunks which were never seen are missing from output file as well,
+    // but are simply not part of the output if output is stdout
+    let bad_ranges = match chunk_tracker.lock().unwrap().take() {
+        None => None,
+        Some(tracker) => Some(tracker.bad_ranges(orig_file_size, param.out_file.is_some())),
+    };
+
+    if let Some(ref ranges) = bad_ranges {
+        if param.out_file.is_some() {
+            match fill {
+                None | Some(FillPolicy::Hole) => {}
+                Some(fill) => fill_ranges(&mut writer.lock().unwrap(), ranges, fill)?,
+            }
+        }
+    }
+
+    stats.lock().unwrap().unrecoverable_ranges = bad_ranges;
+
     // truncate file possibly
     if ref_block.is_meta() {
         match ref_block.get_FSZ().unwrap() {
@@ -1547,6 +2011,533 @@ pub fn decode(
     Ok((res, hash_bytes))
 }
 
+fn read_fully<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, Error> {
+    let mut len_read = 0;
+
+    while len_read < buf.len() {
+        match reader.read(&mut buf[len_read..]) {
+            Ok(0) => break,
+            Ok(len) => len_read += len,
+            Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+            Err(e) => return Err(to_err(StdinError::new(e.kind()))),
+        }
+    }
+
+    Ok(len_read)
+}
+
+fn read_stdin_window() -> Result<Vec<u8>, Error> {
+    let mut window = vec![0; STDIN_WINDOW_SIZE];
+
+    let len_read = read_fully(&mut std::io::stdin(), &mut window)?;
+
+    window.truncate(len_read);
+
+    Ok(window)
+}
+
+struct StdinDecoder<'a> {
+    param: &'a Param,
+    ref_block: &'a Block,
+    data_par: Option<(usize, usize)>,
+    writer: Writer,
+    hash_ctx: Option<hash::Ctx>,
+    chunk_tracker: Option<ChunkTracker>,
+    rs_repairer: Option<RSRepairer>,
+    orig_file_size: Option<u64>,
+    bytes_written: u64,
+    stats: &'a Arc<Mutex<Stats>>,
+    reporter: &'a ProgressReporter<Stats>,
+}
+
+impl<'a> StdinDecoder<'a> {
+    fn total_data_chunk_count(&self) -> Option<u64> {
+        use crate::file_utils::from_orig_file_size::calc_data_chunk_count;
+
+        self.orig_file_size
+            .map(|size| calc_data_chunk_count(self.ref_block.get_version(), size))
+    }
+
+    // repairs the block set in memory if necessary, then writes out its data chunks
+    fn finish_block_set(
+        &mut self,
+        set_index: u64,
+        mut blocks: Vec<Option<Vec<u8>>>,
+    ) -> Result<(), Error> {
+        let version = self.ref_block.get_version();
+        let data_size = ver_to_data_size(version) as u64;
+
+        let (data_shards, _) = self.data_par.unwrap_or((1, 0));
+
+        let first_seq_num = (set_index * blocks.len() as u64) as u32 + SBX_FIRST_DATA_SEQ_NUM;
+
+        let data_missing = blocks[..data_shards].iter().any(|b| b.is_none());
+        let any_present = blocks.iter().any(|b| b.is_some());
+
+        if let (Some(ref mut rs_repairer), true, true) =
+            (&mut self.rs_repairer, data_missing, any_present)
+        {
+            let mut codec_state = RSCodecState::NotReady;
+
+            for b in blocks.iter() {
+                codec_state = match b {
+                    Some(b) => {
+                        rs_repairer.get_block_buffer().copy_from_slice(b);
+                        rs_repairer.mark_present()
+                    }
+                    None => rs_repairer.mark_missing(),
+                };
+            }
+
+            assert_eq!(codec_state, RSCodecState::Ready);
+
+            let (repair_stats, repaired_blocks) = rs_repairer.repair_with_block_sync(first_seq_num);
+
+            if repair_stats.missing_count > 0 {
+                print_if!(verbose not_json => self.param, self.reporter, self.param.json_printer =>
+                          "{}", repair_stats;);
+            }
+
+            let mut block = Block::dummy();
+
+            for &(_, block_buf) in repaired_blocks.iter() {
+                block.sync_from_buffer(block_buf, None, None).unwrap();
+
+                let index = (block.get_seq_num() - first_seq_num) as usize;
+
+                blocks[index] = Some(block_buf.to_vec());
+            }
+
+            if let Some(ref mut x) = self.stats.lock().unwrap().blocks_repaired {
+                *x += repaired_blocks.len() as u64;
+            }
+        }
+
+        let mut fill_buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+        for (i, b) in blocks[..data_shards].iter().enumerate() {
+            let chunk_index = set_index * data_shards as u64 + i as u64;
+
+            if let Some(count) = self.total_data_chunk_count() {
+                if chunk_index >= count {
+                    break;
+                }
+            }
+
+            let pos = chunk_index * data_size;
+
+            let len = match self.orig_file_size {
+                Some(size) => std::cmp::min(data_size, size - pos),
+                None => data_size,
+            } as usize;
+
+            let slice = match b {
+                Some(b) => {
+                    if let Some(ref mut tracker) = self.chunk_tracker {
+                        tracker.mark_recovered(chunk_index);
+                    }
+
+                    &sbx_block::slice_data_buf(version, b)[..len]
+                }
+                None => {
+                    if let Some(ref mut tracker) = self.chunk_tracker {
+                        tracker.mark_failed(chunk_index);
+                    }
+
+                    // chunks are filled in at the end when output is a file
+                    if self.param.out_file.is_some() {
+                        continue;
+                    }
+
+                    if self.param.fill == Some(FillPolicy::Marker) {
+                        bad_ranges::fill_marker(&mut fill_buffer[..len], pos);
+                    } else {
+                        misc_utils::fill_zeros(&mut fill_buffer[..len]);
+                    }
+
+                    &fill_buffer[..len]
+                }
+            };
+
+            if let Some(r) = self.writer.seek(SeekFrom::Start(pos)) {
+                r?;
+            }
+            self.writer.write(slice)?;
+
+            if let Some(ref mut ctx) = self.hash_ctx {
+                ctx.update(slice);
+            }
+
+            self.bytes_written += len as u64;
+        }
+
+        Ok(())
+    }
+}
+
+// decodes a container read from stdin in a single pass
+//
+// block sets are kept in memory until the input has moved far enough past
+// them with respect to the burst error resistance level, after which they
+// are repaired if necessary and written out in order
+fn decode_from_stdin(
+    param: &Param,
+    window: Vec<u8>,
+    ref_block_pos: u64,
+    ref_block: &Block,
+    sparse_map: &Option<SparseMap>,
+    ctrlc_stop_flag: &Arc<AtomicBool>,
+) -> Result<(Stats, Option<HashBytes>), Error> {
+    let version = ref_block.get_version();
+
+    if param.out_file.is_none() && param.fill == Some(FillPolicy::Hole) {
+        return Err(Error::with_msg(
+            "Holes cannot be left in output when output is stdout",
+        ));
+    }
+
+    let rs_enabled = ver_uses_rs(version);
+
+    if rs_enabled {
+        // must be metadata block, and must contain fields `RSD`, `RSP`
+        return_if_ref_not_meta!(ref_block_pos, ref_block, "decode");
+    }
+
+    let block_size = ver_to_block_size(version);
+
+    // the container is assumed to start at the first block boundary of the input
+    let start_pos = (ref_block_pos % block_size as u64) as usize;
+
+    let window_eof_seen = window.len() < STDIN_WINDOW_SIZE;
+
+    let data_par_burst = if rs_enabled {
+        let data = get_RSD_from_ref_block!(ref_block_pos, ref_block, "decode");
+        let parity = get_RSP_from_ref_block!(ref_block_pos, ref_block, "decode");
+
+        let burst = match param.burst {
+            Some(x) => x,
+            None => match block_utils::guess_burst_err_resistance_level_from_buffer(
+                &window,
+                start_pos,
+                ref_block_pos,
+                ref_block,
+            )? {
+                Some(x) => x,
+                // the entire container is in memory, so the level does not matter
+                None if window_eof_seen => 0,
+                None => {
+                    return Err(Error::with_msg(
+                        "Failed to guess burst resistance level, please specify via --burst option",
+                    ));
+                }
+            },
+        };
+
+        print_if!(verbose not_json => param, param.json_printer =>
+                  "Using burst error resistance level {} for the container", burst;
+                  "";);
+
+        print_field_if_json!(
+            param.json_printer,
+            "burst error resistance level : {}",
+            burst
+        );
+
+        Some((data, parity, burst))
+    } else {
+        None
+    };
+
+    let (data_shards, parity_shards, burst) = data_par_burst.unwrap_or((1, 0, 0));
+
+    let block_set_size = data_shards + parity_shards;
+
+    // a block set is finished once the input is two burst groups past it
+    let burst_group_size = std::cmp::max(burst, 1) as u64;
+
+    // metadata of a streamed container is only final in the last metadata block
+    let provisional = ref_block.is_meta() && ref_block.get_TRL().unwrap().is_some();
+
+    let stats = Arc::new(Mutex::new(Stats::new(
+        ref_block,
+        WriteTo::File,
+        0,
+        0,
+        &param.json_printer,
+    )));
+
+    {
+        let mut stats = stats.lock().unwrap();
+        stats.total_blocks = None;
+        stats.bytes_processed = start_pos as u64;
+        if rs_enabled {
+            stats.blocks_repaired = Some(0);
+        }
+    }
+
+    let writer = match param.out_file {
+        Some(ref f) => Writer::new(WriterType::File(FileWriter::new(
+            f,
+            FileWriterParam {
+                read: false,
+                append: false,
+                truncate: true,
+                buffered: true,
+            },
+        )?)),
+        None => Writer::new(WriterType::Stdout(std::io::stdout())),
+    };
+
+    // output file is hashed after decoding, as failed chunks are filled in at the end
+    let hash_ctx = if param.out_file.is_none() && ref_block.is_meta() {
+        match ref_block.get_HSH().unwrap() {
+            Some((ht, _)) => hash::Ctx::new(*ht).ok(),
+            None => None,
+        }
+    } else {
+        None
+    };
+
+    let reporter = ProgressReporter::new(
+        &stats,
+        "Data decoding progress",
+        "bytes",
+        param.pr_verbosity_level,
+        param.json_printer.json_enabled(),
+    );
+
+    let mut decoder = StdinDecoder {
+        param,
+        ref_block,
+        data_par: data_par_burst.map(|(data, parity, _)| (data, parity)),
+        writer,
+        hash_ctx,
+        chunk_tracker: if param.bad_ranges_file.is_some() || param.fill.is_some() {
+            Some(ChunkTracker::new(version))
+        } else {
+            None
+        },
+        rs_repairer: data_par_burst.map(|(data, parity, burst)| {
+            RSRepairer::new(&param.json_printer, ref_block, data, parity, burst)
+        }),
+        orig_file_size: if ref_block.is_meta() && !provisional {
+            ref_block.get_FSZ().unwrap()
+        } else {
+            None
+        },
+        bytes_written: 0,
+        stats: &stats,
+        reporter: &reporter,
+    };
+
+    let header_pred = header_pred_same_ver_uid!(ref_block);
+
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let mut block = Block::dummy();
+
+    let mut final_meta_block: Option<Block> = None;
+
+    let mut block_sets: BTreeMap<u64, Vec<Option<Vec<u8>>>> = BTreeMap::new();
+
+    let mut next_set_index: u64 = 0;
+    let mut last_set_index: Option<u64> = None;
+
+    let stdin = std::io::stdin();
+    let mut reader = (&window[start_pos..]).chain(stdin.lock());
+
+    reporter.start();
+
+    let mut block_index: u64 = 0;
+
+    loop {
+        break_if_atomic_bool!(ctrlc_stop_flag);
+
+        let slot = &mut buffer[..block_size];
+
+        let len_read = read_fully(&mut reader, slot)?;
+
+        stats.lock().unwrap().bytes_processed += len_read as u64;
+
+        if len_read < block_size {
+            break;
+        }
+
+        let seq_num = match block.sync_from_buffer(slot, Some(&header_pred), None) {
+            Ok(()) => {
+                let mut stats = stats.lock().unwrap();
+
+                if block.is_meta() {
+                    stats.meta_blocks_decoded += 1;
+
+                    if block.get_TRL().unwrap().is_none() {
+                        final_meta_block = Some(block.clone());
+                    }
+
+                    None
+                } else {
+                    if block.is_parity(data_shards, parity_shards) {
+                        stats.parity_blocks_decoded += 1;
+                    } else {
+                        stats.data_blocks_decoded += 1;
+                    }
+
+                    Some(block.get_seq_num())
+                }
+            }
+            Err(_) => {
+                let seq_num = sbx_block::calc_seq_num_at_index(block_index, None, data_par_burst);
+
+                if sparse_map::block_is_omitted(sparse_map, seq_num, slot) {
+                    stats.lock().unwrap().blank_blocks_decoded += 1;
+
+                    // replace with a block of zeros
+                    sparse_map::fill_omitted_block(version, &ref_block.get_uid(), seq_num, slot);
+
+                    Some(seq_num)
+                } else {
+                    stats.lock().unwrap().incre_blocks_failed();
+
+                    None
+                }
+            }
+        };
+
+        block_index += 1;
+
+        if let Some(seq_num) = seq_num {
+            let data_index = (seq_num - SBX_FIRST_DATA_SEQ_NUM) as u64;
+            let set_index = data_index / block_set_size as u64;
+
+            // ignore blocks of block sets which were finished already
+            if set_index >= next_set_index {
+                block_sets
+                    .entry(set_index)
+                    .or_insert_with(|| vec![None; block_set_size])
+                    [(data_index % block_set_size as u64) as usize] = Some(slot.to_vec());
+
+                last_set_index = Some(match last_set_index {
+                    None => set_index,
+                    Some(x) => std::cmp::max(x, set_index),
+                });
+            }
+        }
+
+        if let Some(last_set_index) = last_set_index {
+            while next_set_index / burst_group_size + 2 <= last_set_index / burst_group_size {
+                let blocks = block_sets
+                    .remove(&next_set_index)
+                    .unwrap_or_else(|| vec![None; block_set_size]);
+
+                decoder.finish_block_set(next_set_index, blocks)?;
+
+                next_set_index += 1;
+            }
+        }
+    }
+
+    // use the final metadata of a streamed container
+    if let Some(ref block) = final_meta_block {
+        if provisional {
+            decoder.orig_file_size = block.get_FSZ().unwrap();
+        }
+    }
+
+    // finish the remaining block sets, including the ones never seen
+    let end_set_index = {
+        let last_set_count = match last_set_index {
+            None => 0,
+            Some(x) => x + 1,
+        };
+
+        match decoder.total_data_chunk_count() {
+            None => last_set_count,
+            Some(count) => std::cmp::max(
+                last_set_count,
+                u64::round_up_to_multiple(count, data_shards as u64) / data_shards as u64,
+            ),
+        }
+    };
+
+    if !ctrlc_stop_flag.load(std::sync::atomic::Ordering::SeqCst) {
+        while next_set_index < end_set_index {
+            let blocks = block_sets
+                .remove(&next_set_index)
+                .unwrap_or_else(|| vec![None; block_set_size]);
+
+            decoder.finish_block_set(next_set_index, blocks)?;
+
+            next_set_index += 1;
+        }
+    }
+
+    reporter.stop();
+
+    let orig_file_size = decoder.orig_file_size;
+
+    let bad_ranges = decoder
+        .chunk_tracker
+        .take()
+        .map(|tracker| tracker.bad_ranges(orig_file_size, param.out_file.is_some()));
+
+    if let Some(ref ranges) = bad_ranges {
+        if param.out_file.is_some() {
+            match param.fill {
+                None | Some(FillPolicy::Hole) => {}
+                Some(fill) => fill_ranges(&mut decoder.writer, ranges, fill)?,
+            }
+        }
+    }
+
+    // truncate file possibly
+    if let Some(size) = orig_file_size {
+        if let Some(r) = decoder.writer.set_len(size) {
+            r?;
+        }
+    }
+
+    if !ref_block.is_meta() && !param.json_printer.json_enabled() {
+        print_block!(param.json_printer.output_channel() =>
+            "";
+            "Warning :";
+            "";
+            "    Reference block is not a metadata block, output file";
+            "    may contain data padding.";
+            "";)
+    }
+
+    let recorded_hash = match (&final_meta_block, provisional) {
+        (Some(block), true) => block.get_HSH().unwrap(),
+        (None, true) => None,
+        (_, false) => {
+            if ref_block.is_meta() {
+                ref_block.get_HSH().unwrap()
+            } else {
+                None
+            }
+        }
+    };
+
+    let hash_bytes = decoder
+        .hash_ctx
+        .take()
+        .map(|ctx| ctx.finish_into_hash_bytes());
+
+    let out_file_size = match decoder.writer.get_file_size() {
+        Some(r) => r?,
+        None => decoder.bytes_written,
+    };
+
+    let mut stats = stats.lock().unwrap();
+
+    stats.in_file_size = stats.bytes_processed;
+    stats.recorded_hash = recorded_hash.cloned();
+    stats.out_file_size = out_file_size;
+    stats.unrecoverable_ranges = bad_ranges;
+
+    Ok((stats.clone(), hash_bytes))
+}
+
 fn hash(
     param: &Param,
     ref_block: &Block,
@@ -1678,7 +2669,45 @@ pub fn decode_file(param: &Param) -> Result<Option<Stats>, Error> {
 
     let json_printer = &param.json_printer;
 
-    let (ref_block_pos, ref_block) = get_ref_block!(param, json_printer, ctrlc_stop_flag);
+    // stdin cannot be rewound, so an initial window is kept in memory instead
+    let stdin_window = if file_utils::check_if_file_is_stdin(&param.in_file) {
+        Some(read_stdin_window()?)
+    } else {
+        None
+    };
+
+    let (ref_block_pos, ref_block) = match stdin_window {
+        None => get_ref_block!(param, json_printer, ctrlc_stop_flag),
+        Some(ref window) => {
+            let (ref_block_pos, ref_block) =
+                match block_utils::get_ref_block_from_buffer(window, param.ref_block_choice) {
+                    None => return Err(Error::with_msg("Failed to find reference block")),
+                    Some(x) => x,
+                };
+
+            if param.verbose {
+                print_if!(not_json => json_printer => "";);
+                report_ref_block_info(json_printer, ref_block_pos, &ref_block);
+                print_if!(not_json => json_printer => "";);
+            }
+
+            (ref_block_pos, ref_block)
+        }
+    };
+
+    // only the blocks recorded as omitted are decoded as chunks of zeros
+    let sparse_map = sparse_map::load(
+        json_printer,
+        &ref_block,
+        match stdin_window {
+            None => Some(&param.in_file),
+            Some(_) => None,
+        },
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+    )?;
 
     // get FNM of ref_block
     let recorded_file_name: Option<String> = if ref_block.is_data() {
@@ -1722,9 +2751,15 @@ pub fn decode_file(param: &Param) -> Result<Option<Stats>, Error> {
         }
     };
 
+    // continuing from an existing log file writes to the existing output
+    let resuming = match param.log_file {
+        None => false,
+        Some(ref f) => file_utils::check_if_file_exists(f),
+    };
+
     // check if can write out
     if let Some(ref out_file_path) = out_file_path {
-        if !param.force_write && param.multi_pass == None {
+        if !param.force_write && param.multi_pass == None && !resuming {
             if file_utils::check_if_file_exists(out_file_path) {
                 return Err(Error::with_msg(&format!(
                     "File \"{}\" already exists",
@@ -1753,12 +2788,78 @@ pub fn decode_file(param: &Param) -> Result<Option<Stats>, Error> {
         param.force_misalign,
         &param.in_file,
         out_file_path,
+        match param.log_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+        match param.damage_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+        match param.bad_ranges_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+        param.fill,
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
         param.verbose,
         param.pr_verbosity_level,
         param.burst,
     );
 
-    let (mut stats, hash_res) = decode(&param, ref_block_pos, &ref_block, &ctrlc_stop_flag)?;
+    let damage_map = match param.damage_map_file {
+        None => None,
+        Some(ref f) => {
+            let damage_map = DamageMap::read_from_file(f)?;
+
+            if damage_map.uid() != &ref_block.get_uid()
+                || damage_map.version() != ref_block.get_version()
+            {
+                return Err(Error::with_msg(&format!(
+                    "Damage map \"{}\" does not match the container",
+                    f
+                )));
+            }
+
+            Some(damage_map)
+        }
+    };
+
+    let (mut stats, hash_res) = match stdin_window {
+        None => decode(
+            &param,
+            ref_block_pos,
+            &ref_block,
+            &Arc::new(sparse_map),
+            &ctrlc_stop_flag,
+        )?,
+        Some(window) => decode_from_stdin(
+            &param,
+            window,
+            ref_block_pos,
+            &ref_block,
+            &sparse_map,
+            &ctrlc_stop_flag,
+        )?,
+    };
+
+    // ranges found during decoding take precedence over the forecast from damage map
+    if let (Some(damage_map), None) = (damage_map, &stats.unrecoverable_ranges) {
+        let orig_file_size = if ref_block.is_data() {
+            None
+        } else {
+            ref_block.get_FSZ().unwrap()
+        };
+
+        stats.unrecoverable_ranges = Some(damage_map.unrecoverable_data_ranges(orig_file_size));
+    }
+
+    if let (Some(ref f), Some(ref ranges)) = (&param.bad_ranges_file, &stats.unrecoverable_ranges) {
+        bad_ranges::write_to_file(ranges, f)?;
+    }
 
     match hash_res {
         Some(r) => {
@@ -0,0 +1,963 @@
+use crate::bad_ranges::ChunkTracker;
+use crate::block_utils;
+use crate::block_utils::RefBlockChoice;
+use crate::cli_utils::setup_ctrlc_handler;
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::general_error::Error;
+use crate::json_printer::{BracketType, JSONPrinter};
+use crate::misc_utils;
+use crate::progress_report::*;
+use crate::sbx_block;
+use crate::sbx_block::{Block, MetadataID};
+use crate::sbx_specs::{
+    ver_to_block_size, ver_to_data_size, ver_to_usize, ver_uses_rs, Version,
+    SBX_FIRST_DATA_SEQ_NUM, SBX_LARGEST_BLOCK_SIZE, SBX_LAST_SEQ_NUM,
+};
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
+use crate::time_utils;
+use crate::verify_core::{compare_chunk, normalize_ranges, range_len_sum, write_range};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io::SeekFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+const META_IDS: [MetadataID; 10] = [
+    MetadataID::FNM,
+    MetadataID::SNM,
+    MetadataID::FSZ,
+    MetadataID::FDT,
+    MetadataID::SDT,
+    MetadataID::HSH,
+    MetadataID::RSD,
+    MetadataID::RSP,
+    MetadataID::SPR,
+    MetadataID::TRL,
+];
+
+pub struct Param {
+    ref_block_choice: RefBlockChoice,
+    file_a: String,
+    file_b: String,
+    json_printer: Arc<JSONPrinter>,
+    verbose: bool,
+    pr_verbosity_level: PRVerbosityLevel,
+}
+
+impl Param {
+    pub fn new(
+        ref_block_choice: RefBlockChoice,
+        file_a: &str,
+        file_b: &str,
+        json_printer: &Arc<JSONPrinter>,
+        verbose: bool,
+        pr_verbosity_level: PRVerbosityLevel,
+    ) -> Param {
+        Param {
+            ref_block_choice,
+            file_a: String::from(file_a),
+            file_b: String::from(file_b),
+            json_printer: Arc::clone(json_printer),
+            verbose,
+            pr_verbosity_level,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MetaDiff {
+    pub field: &'static str,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    version_a: Version,
+    version_b: Version,
+    file_size_a: Option<u64>,
+    file_size_b: Option<u64>,
+    blocks_processed: u64,
+    bytes_processed: u64,
+    total_bytes: u64,
+    pub chunks_compared: u64,
+    pub chunks_changed: u64,
+    pub blocks_decode_failed_a: u64,
+    pub blocks_decode_failed_b: u64,
+    meta_diffs: Vec<MetaDiff>,
+    changed_ranges: Vec<(u64, u64)>,
+    uncompared_ranges: Vec<(u64, u64)>,
+    verbose: bool,
+    start_time: f64,
+    end_time: f64,
+    json_printer: Arc<JSONPrinter>,
+}
+
+impl Stats {
+    fn new(
+        side_a: &Side,
+        side_b: &Side,
+        total_bytes: u64,
+        verbose: bool,
+        json_printer: &Arc<JSONPrinter>,
+    ) -> Stats {
+        Stats {
+            version_a: side_a.version,
+            version_b: side_b.version,
+            file_size_a: side_a.file_size,
+            file_size_b: side_b.file_size,
+            blocks_processed: 0,
+            bytes_processed: 0,
+            total_bytes,
+            chunks_compared: 0,
+            chunks_changed: 0,
+            blocks_decode_failed_a: 0,
+            blocks_decode_failed_b: 0,
+            meta_diffs: diff_metadata(&side_a.ref_block, &side_b.ref_block),
+            changed_ranges: Vec::new(),
+            uncompared_ranges: Vec::new(),
+            verbose,
+            start_time: 0.,
+            end_time: 0.,
+            json_printer: Arc::clone(json_printer),
+        }
+    }
+
+    pub fn data_identical(&self) -> bool {
+        self.changed_ranges.is_empty() && self.uncompared_ranges.is_empty()
+    }
+}
+
+impl ProgressReport for Stats {
+    fn start_time_mut(&mut self) -> &mut f64 {
+        &mut self.start_time
+    }
+
+    fn end_time_mut(&mut self) -> &mut f64 {
+        &mut self.end_time
+    }
+
+    fn units_so_far(&self) -> u64 {
+        self.bytes_processed
+    }
+
+    fn total_units(&self) -> Option<u64> {
+        Some(self.total_bytes)
+    }
+}
+
+fn write_meta_diff(
+    f: &mut fmt::Formatter,
+    json_printer: &JSONPrinter,
+    diff: &MetaDiff,
+) -> fmt::Result {
+    if json_printer.json_enabled() {
+        json_printer.write_open_bracket(f, None, BracketType::Curly)?;
+        write_maybe_json!(f, json_printer, "field : {}", diff.field)?;
+        for &(label, val) in [("a", &diff.a), ("b", &diff.b)].iter() {
+            match val {
+                None => write_maybe_json!(f, json_printer, "{} : null", label)?,
+                Some(x) => write_maybe_json!(f, json_printer, "{} : {}", label, x => force_quotes)?,
+            }
+        }
+        json_printer.write_close_bracket(f)
+    } else {
+        writeln!(
+            f,
+            "  {:<4} : {}  ->  {}",
+            diff.field,
+            diff.a.as_ref().map_or("N/A", |x| x.as_str()),
+            diff.b.as_ref().map_or("N/A", |x| x.as_str())
+        )
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let time_elapsed = (self.end_time - self.start_time) as i64;
+        let (hour, minute, second) = time_utils::seconds_to_hms(time_elapsed);
+
+        let json_printer = &self.json_printer;
+
+        json_printer.write_open_bracket(f, Some("stats"), BracketType::Curly)?;
+
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX version of A                     : {}",
+            ver_to_usize(self.version_a)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX version of B                     : {}",
+            ver_to_usize(self.version_b)
+        )?;
+        for &(label, size) in [
+            ("Recorded file size of A             ", self.file_size_a),
+            ("Recorded file size of B             ", self.file_size_b),
+        ]
+        .iter()
+        {
+            write_maybe_json!(
+                f,
+                json_printer,
+                "{} : {}",
+                label,
+                match size {
+                    None => null_if_json_else_NA!(json_printer).to_string(),
+                    Some(x) => x.to_string(),
+                }
+            )?;
+        }
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks processed           : {}",
+            self.blocks_processed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of data chunks compared       : {}",
+            self.chunks_compared
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of data chunks changed        : {}",
+            self.chunks_changed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of invalid blocks in A        : {}",
+            self.blocks_decode_failed_a
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of invalid blocks in B        : {}",
+            self.blocks_decode_failed_b
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of changed bytes              : {}",
+            range_len_sum(&self.changed_ranges)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of changed ranges             : {}",
+            self.changed_ranges.len()
+        )?;
+        write_range(
+            f,
+            json_printer,
+            Some("first changed range"),
+            "First changed range                 ",
+            self.changed_ranges.first(),
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of uncompared bytes           : {}",
+            range_len_sum(&self.uncompared_ranges)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of uncompared ranges          : {}",
+            self.uncompared_ranges.len()
+        )?;
+        write_range(
+            f,
+            json_printer,
+            Some("first uncompared range"),
+            "First uncompared range              ",
+            self.uncompared_ranges.first(),
+        )?;
+        if self.verbose {
+            for &(name, label, ranges) in [
+                ("changed ranges", "Changed range   ", &self.changed_ranges),
+                (
+                    "uncompared ranges",
+                    "Uncompared range",
+                    &self.uncompared_ranges,
+                ),
+            ]
+            .iter()
+            {
+                json_printer.write_open_bracket(f, Some(name), BracketType::Square)?;
+                for range in ranges.iter() {
+                    write_range(f, json_printer, None, label, Some(range))?;
+                }
+                json_printer.write_close_bracket(f)?;
+            }
+        }
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of metadata differences       : {}",
+            self.meta_diffs.len()
+        )?;
+        if !self.meta_diffs.is_empty() {
+            write_if!(not_json => f, json_printer => "Metadata differences (A  ->  B) :";)?;
+        }
+        json_printer.write_open_bracket(f, Some("metadata differences"), BracketType::Square)?;
+        for diff in self.meta_diffs.iter() {
+            write_meta_diff(f, json_printer, diff)?;
+        }
+        json_printer.write_close_bracket(f)?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Time elapsed                         : {:02}:{:02}:{:02}",
+            hour,
+            minute,
+            second
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Data identical                       : {}",
+            self.data_identical()
+        )?;
+        if self.data_identical() {
+            write_if!(not_json => f, json_printer => "The containers store the same data";)?;
+        } else {
+            write_if!(not_json => f, json_printer => "The containers do NOT store the same data";)?;
+        }
+
+        json_printer.write_close_bracket(f)?;
+
+        Ok(())
+    }
+}
+
+fn meta_to_string(block: &Block, id: MetadataID) -> Option<String> {
+    if !block.is_meta() {
+        return None;
+    }
+
+    match block.get_meta_ref_by_id(id) {
+        Ok(Some(m)) => Some(m.to_string()),
+        _ => None,
+    }
+}
+
+/// Lists the fields which differ between the two reference blocks
+///
+/// Metadata fields of a data block are treated as absent.
+pub fn diff_metadata(a: &Block, b: &Block) -> Vec<MetaDiff> {
+    let mut res = Vec::new();
+
+    let ver_a = ver_to_usize(a.get_version()).to_string();
+    let ver_b = ver_to_usize(b.get_version()).to_string();
+    if ver_a != ver_b {
+        res.push(MetaDiff {
+            field: "VER",
+            a: Some(ver_a),
+            b: Some(ver_b),
+        });
+    }
+
+    let uid_a = misc_utils::bytes_to_upper_hex_string(&a.get_uid());
+    let uid_b = misc_utils::bytes_to_upper_hex_string(&b.get_uid());
+    if uid_a != uid_b {
+        res.push(MetaDiff {
+            field: "UID",
+            a: Some(uid_a),
+            b: Some(uid_b),
+        });
+    }
+
+    for &id in META_IDS.iter() {
+        let val_a = meta_to_string(a, id);
+        let val_b = meta_to_string(b, id);
+
+        if val_a != val_b {
+            res.push(MetaDiff {
+                field: sbx_block::meta_id_to_str(id),
+                a: val_a,
+                b: val_b,
+            });
+        }
+    }
+
+    res
+}
+
+struct Side {
+    file: String,
+    ref_block_pos: u64,
+    ref_block: Block,
+    version: Version,
+    data_par: Option<(usize, usize)>,
+    data_par_burst: Option<(usize, usize, usize)>,
+    // whether the position of a data block can be calculated from its seq num
+    layout_known: bool,
+    file_size: Option<u64>,
+    sparse_map: Option<SparseMap>,
+}
+
+/// Positions of data chunks not found at their calculated positions, stored as
+/// runs of consecutive data chunks held by consecutive blocks
+///
+/// The runs are keyed by the index of the first data chunk, and mapped to the
+/// position of the first block and the length of the run.
+struct ChunkIndex {
+    block_size: u64,
+    runs: BTreeMap<u64, (u64, u64)>,
+    last_run: Option<u64>,
+}
+
+impl ChunkIndex {
+    fn new(version: Version) -> ChunkIndex {
+        ChunkIndex {
+            block_size: ver_to_block_size(version) as u64,
+            runs: BTreeMap::new(),
+            last_run: None,
+        }
+    }
+
+    fn get(&self, index: u64) -> Option<u64> {
+        match self.runs.range(..=index).next_back() {
+            Some((&start, &(pos, len))) if index < start + len => {
+                Some(pos + (index - start) * self.block_size)
+            }
+            _ => None,
+        }
+    }
+
+    // only the first position seen for a data chunk is kept
+    fn insert(&mut self, index: u64, pos: u64) {
+        if self.get(index).is_some() {
+            return;
+        }
+
+        if let Some(start) = self.last_run {
+            let run = self.runs.get_mut(&start).unwrap();
+            if start + run.1 == index && run.0 + run.1 * self.block_size == pos {
+                run.1 += 1;
+                return;
+            }
+        }
+
+        self.runs.insert(index, (pos, 1));
+        self.last_run = Some(index);
+    }
+}
+
+fn get_side(
+    file: &str,
+    param: &Param,
+    ctrlc_stop_flag: &AtomicBool,
+) -> Result<Option<Side>, Error> {
+    let (ref_block_pos, ref_block) = match block_utils::get_ref_block(
+        file,
+        None,
+        None,
+        false,
+        param.ref_block_choice,
+        param.pr_verbosity_level,
+        param.json_printer.json_enabled(),
+        ctrlc_stop_flag,
+    )? {
+        None => {
+            if ctrlc_stop_flag.load(Ordering::SeqCst) {
+                return Ok(None);
+            } else {
+                return Err(Error::with_msg(&format!(
+                    "Failed to find reference block in \"{}\"",
+                    file
+                )));
+            }
+        }
+        Some(x) => x,
+    };
+
+    let version = ref_block.get_version();
+
+    // only the shard counts are needed to place the data chunks
+    let data_par = if ver_uses_rs(version) {
+        Some((
+            get_RSD_from_ref_block!(ref_block_pos, ref_block, "diff"),
+            get_RSP_from_ref_block!(ref_block_pos, ref_block, "diff"),
+        ))
+    } else {
+        None
+    };
+
+    // the burst error resistance level is needed to calculate the position of
+    // a data block, which saves indexing the data chunks of B, and tells which
+    // blocks were omitted from a sparse container
+    let (data_par_burst, layout_known) = match data_par {
+        Some((data, parity)) => match block_utils::guess_burst_err_resistance_level(
+            file,
+            None,
+            false,
+            ref_block_pos,
+            &ref_block,
+        )? {
+            Some(burst) => (Some((data, parity, burst)), true),
+            None => (None, false),
+        },
+        None => (None, ref_block.is_meta()),
+    };
+
+    let sparse_map = sparse_map::load(&param.json_printer, &ref_block, Some(file), None)?;
+
+    if sparse_map.is_some() && !layout_known {
+        return Err(Error::with_msg(&format!(
+            "Failed to guess burst resistance level of sparse container \"{}\"",
+            file
+        )));
+    }
+
+    let file_size = if ref_block.is_meta() {
+        ref_block.get_FSZ().unwrap()
+    } else {
+        None
+    };
+
+    Ok(Some(Side {
+        file: String::from(file),
+        ref_block_pos,
+        ref_block,
+        version,
+        data_par,
+        data_par_burst,
+        layout_known,
+        file_size,
+        sparse_map,
+    }))
+}
+
+fn calc_data_chunk_seq_num(index: u64, data_par: Option<(usize, usize)>) -> Option<u32> {
+    let seq_num_index = match data_par {
+        None => index,
+        Some((data, parity)) => index / data as u64 * (data + parity) as u64 + index % data as u64,
+    };
+
+    if seq_num_index > u64::from(SBX_LAST_SEQ_NUM - SBX_FIRST_DATA_SEQ_NUM) {
+        None
+    } else {
+        Some(SBX_FIRST_DATA_SEQ_NUM + seq_num_index as u32)
+    }
+}
+
+fn calc_data_chunk_block_pos(side: &Side, index: u64) -> Option<u64> {
+    if !side.layout_known {
+        return None;
+    }
+
+    let block_size = ver_to_block_size(side.version) as u64;
+
+    calc_data_chunk_seq_num(index, side.data_par).map(|seq_num| {
+        side.ref_block_pos % block_size
+            + sbx_block::calc_data_block_write_pos(
+                side.version,
+                seq_num,
+                Some(true),
+                side.data_par_burst,
+            )
+    })
+}
+
+// replaces the hole left by a block omitted from a sparse container
+// with the block of zeros it stands for
+fn fill_if_omitted(side: &Side, pos: u64, buffer: &mut [u8]) {
+    let version = side.version;
+
+    let seq_num = sbx_block::calc_seq_num_at_index(
+        pos / ver_to_block_size(version) as u64,
+        Some(true),
+        side.data_par_burst,
+    );
+
+    if sparse_map::block_is_omitted(
+        &side.sparse_map,
+        seq_num,
+        sbx_block::slice_buf(version, buffer),
+    ) {
+        sparse_map::fill_omitted_block(
+            version,
+            &side.ref_block.get_uid(),
+            seq_num,
+            sbx_block::slice_buf_mut(version, buffer),
+        );
+    }
+}
+
+/// Loads data chunk `index` of `side` into `buffer`, trying the calculated
+/// position of its block first, then the position recorded in `chunk_index`
+///
+/// Returns whether the data chunk was loaded.
+fn load_data_chunk(
+    side: &Side,
+    chunk_index: &ChunkIndex,
+    reader: &mut FileReader,
+    index: u64,
+    block: &mut Block,
+    buffer: &mut [u8],
+) -> Result<bool, Error> {
+    let header_pred = header_pred_same_ver_uid!(side.ref_block);
+
+    for pos in [
+        calc_data_chunk_block_pos(side, index),
+        chunk_index.get(index),
+    ]
+    .iter()
+    .filter_map(|&x| x)
+    {
+        reader.seek(SeekFrom::Start(pos))?;
+        let read_res = reader.read(sbx_block::slice_buf_mut(side.version, buffer))?;
+        if read_res.eof_seen {
+            continue;
+        }
+
+        fill_if_omitted(side, pos, buffer);
+
+        if block
+            .sync_from_buffer(buffer, Some(&header_pred), None)
+            .is_ok()
+            && block.is_data()
+            && sbx_block::calc_data_chunk_write_index(block.get_seq_num(), side.data_par)
+                == Some(index)
+        {
+            return Ok(true);
+        }
+    }
+
+    Ok(false)
+}
+
+/// Goes through the container of `side` block by block, and calls `f` with the
+/// block position, the data chunk index and the data chunk of each valid data block
+///
+/// Returns the number of blocks which failed to decode.
+fn scan_data_blocks<F>(
+    ctrlc_stop_flag: &AtomicBool,
+    side: &Side,
+    stats: &Arc<Mutex<Stats>>,
+    bytes_offset: u64,
+    mut f: F,
+) -> Result<u64, Error>
+where
+    F: FnMut(u64, u64, &[u8]) -> Result<(), Error>,
+{
+    let version = side.version;
+    let block_size = ver_to_block_size(version) as u64;
+
+    let mut reader = FileReader::new(
+        &side.file,
+        FileReaderParam {
+            write: false,
+            buffered: true,
+        },
+    )?;
+
+    let header_pred = header_pred_same_ver_uid!(side.ref_block);
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let mut blocks_decode_failed = 0;
+
+    let mut pos = side.ref_block_pos % block_size;
+
+    reader.seek(SeekFrom::Start(pos))?;
+
+    loop {
+        break_if_atomic_bool!(ctrlc_stop_flag);
+
+        let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
+
+        break_if_eof_seen!(read_res);
+
+        fill_if_omitted(side, pos, &mut buffer);
+
+        match block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+            Ok(()) => {
+                if block.is_data() {
+                    if let Some(index) =
+                        sbx_block::calc_data_chunk_write_index(block.get_seq_num(), side.data_par)
+                    {
+                        f(pos, index, sbx_block::slice_data_buf(version, &buffer))?;
+                    }
+                }
+            }
+            Err(_) => {
+                if !misc_utils::buffer_is_blank(sbx_block::slice_buf(version, &buffer)) {
+                    blocks_decode_failed += 1;
+                }
+            }
+        }
+
+        pos += block_size;
+
+        let mut stats = stats.lock().unwrap();
+
+        stats.blocks_processed += 1;
+        stats.bytes_processed = bytes_offset + pos;
+    }
+
+    Ok(blocks_decode_failed)
+}
+
+pub fn diff_files(param: &Param) -> Result<Option<Stats>, Error> {
+    let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
+
+    let json_printer = &param.json_printer;
+
+    let side_a = match get_side(&param.file_a, param, &ctrlc_stop_flag)? {
+        None => return Ok(None),
+        Some(x) => x,
+    };
+    let side_b = match get_side(&param.file_b, param, &ctrlc_stop_flag)? {
+        None => return Ok(None),
+        Some(x) => x,
+    };
+
+    let data_size_a = ver_to_data_size(side_a.version) as u64;
+    let data_size_b = ver_to_data_size(side_b.version) as u64;
+
+    let container_size_a = file_utils::get_file_size(&side_a.file)?;
+    let container_size_b = file_utils::get_file_size(&side_b.file)?;
+
+    let stats = Arc::new(Mutex::new(Stats::new(
+        &side_a,
+        &side_b,
+        container_size_a + container_size_b,
+        param.verbose,
+        json_printer,
+    )));
+
+    let reporter = ProgressReporter::new(
+        &stats,
+        "Container comparison progress",
+        "bytes",
+        param.pr_verbosity_level,
+        json_printer.json_enabled(),
+    );
+
+    let mut changed_ranges: Vec<(u64, u64)> = Vec::new();
+
+    let mut chunk_tracker_a = ChunkTracker::new(side_a.version);
+    let mut chunk_tracker_b = ChunkTracker::new(side_b.version);
+
+    // data chunks of B not found at their calculated positions, e.g. B is not sorted
+    let mut chunk_index_b = ChunkIndex::new(side_b.version);
+
+    // one past the largest index of the data chunks of B
+    let mut chunk_end_index_b = 0;
+
+    reporter.start();
+
+    // 1st pass: index the data chunks of B
+    let blocks_decode_failed_b =
+        scan_data_blocks(&ctrlc_stop_flag, &side_b, &stats, 0, |pos, index, _| {
+            if calc_data_chunk_block_pos(&side_b, index) != Some(pos) {
+                chunk_index_b.insert(index, pos);
+            }
+            chunk_tracker_b.mark_recovered(index);
+
+            chunk_end_index_b = std::cmp::max(chunk_end_index_b, index + 1);
+
+            Ok(())
+        })?;
+
+    // 2nd pass: go through A and compare against the overlapping data chunks of B
+    let mut reader_b = FileReader::new(
+        &side_b.file,
+        FileReaderParam {
+            write: false,
+            buffered: true,
+        },
+    )?;
+    let mut block_b = Block::dummy();
+    let mut buffer_b: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+    let mut cached_index_b: Option<u64> = None;
+
+    let mut chunks_compared = 0;
+    let mut chunks_changed = 0;
+
+    // end of the data of A, used when A does not record the file size
+    let mut data_end_a = 0;
+
+    let blocks_decode_failed_a = if ctrlc_stop_flag.load(Ordering::SeqCst) {
+        0
+    } else {
+        scan_data_blocks(
+            &ctrlc_stop_flag,
+            &side_a,
+            &stats,
+            container_size_b,
+            |_, index, data| {
+                let chunk_pos = index * data_size_a;
+
+                let chunk_len = match side_a.file_size {
+                    Some(size) if chunk_pos >= size => return Ok(()),
+                    Some(size) => std::cmp::min(data_size_a, size - chunk_pos),
+                    None => data_size_a,
+                };
+                let chunk_end = chunk_pos + chunk_len;
+
+                chunk_tracker_a.mark_recovered(index);
+
+                data_end_a = std::cmp::max(data_end_a, chunk_end);
+
+                let mut matched = true;
+
+                let mut start = chunk_pos;
+
+                while start < chunk_end {
+                    let index_b = start / data_size_b;
+                    let chunk_pos_b = index_b * data_size_b;
+                    let end = std::cmp::min(chunk_end, chunk_pos_b + data_size_b);
+
+                    let data_a = &data[(start - chunk_pos) as usize..(end - chunk_pos) as usize];
+
+                    match side_b.file_size {
+                        // rest of the chunk is past the end of B
+                        Some(size_b) if start >= size_b => {
+                            matched &= compare_chunk(
+                                start,
+                                &data[(start - chunk_pos) as usize..chunk_len as usize],
+                                &[],
+                                side_a.file_size.is_some(),
+                                &mut changed_ranges,
+                            );
+                            break;
+                        }
+                        _ => {}
+                    }
+
+                    // load the data chunk of B if present, missing ones are
+                    // reported as uncompared ranges later
+                    let loaded = if cached_index_b == Some(index_b) {
+                        true
+                    } else if load_data_chunk(
+                        &side_b,
+                        &chunk_index_b,
+                        &mut reader_b,
+                        index_b,
+                        &mut block_b,
+                        &mut buffer_b,
+                    )? {
+                        cached_index_b = Some(index_b);
+                        true
+                    } else {
+                        cached_index_b = None;
+                        false
+                    };
+
+                    if loaded {
+                        let len_b = match side_b.file_size {
+                            Some(size_b) => std::cmp::min(end, size_b) - start,
+                            None => end - start,
+                        };
+                        let from = (start - chunk_pos_b) as usize;
+                        let data_b = &sbx_block::slice_data_buf(side_b.version, &buffer_b)
+                            [from..from + len_b as usize];
+
+                        matched &= compare_chunk(
+                            start,
+                            data_a,
+                            data_b,
+                            side_a.file_size.is_some(),
+                            &mut changed_ranges,
+                        );
+                    }
+
+                    start = end;
+                }
+
+                chunks_compared += 1;
+                if !matched {
+                    chunks_changed += 1;
+                }
+
+                Ok(())
+            },
+        )?
+    };
+
+    // 3rd pass: data chunks of B past the end of A are not visited when going
+    // through A, so they are compared against nothing, i.e. reported as changed
+    if !ctrlc_stop_flag.load(Ordering::SeqCst) {
+        let data_end_a = side_a.file_size.unwrap_or(data_end_a);
+
+        for index in data_end_a / data_size_b..chunk_end_index_b {
+            break_if_atomic_bool!(ctrlc_stop_flag);
+
+            let chunk_pos = index * data_size_b;
+
+            let chunk_len = match side_b.file_size {
+                Some(size) if chunk_pos >= size => break,
+                Some(size) => std::cmp::min(data_size_b, size - chunk_pos),
+                None => data_size_b,
+            };
+
+            if !load_data_chunk(
+                &side_b,
+                &chunk_index_b,
+                &mut reader_b,
+                index,
+                &mut block_b,
+                &mut buffer_b,
+            )? {
+                continue;
+            }
+
+            let data_b = sbx_block::slice_data_buf(side_b.version, &buffer_b);
+
+            let start = std::cmp::max(chunk_pos, data_end_a);
+
+            compare_chunk(
+                start,
+                &data_b[(start - chunk_pos) as usize..chunk_len as usize],
+                &[],
+                side_b.file_size.is_some(),
+                &mut changed_ranges,
+            );
+        }
+    }
+
+    reporter.stop();
+
+    // difference in recorded file sizes
+    if let (Some(size_a), Some(size_b)) = (side_a.file_size, side_b.file_size) {
+        if size_a != size_b {
+            changed_ranges.push((std::cmp::min(size_a, size_b), std::cmp::max(size_a, size_b)));
+        }
+    }
+
+    normalize_ranges(&mut changed_ranges);
+
+    // only the part present in both files can be compared
+    let common_size = match (side_a.file_size, side_b.file_size) {
+        (Some(a), Some(b)) => Some(std::cmp::min(a, b)),
+        (Some(x), None) | (None, Some(x)) => Some(x),
+        (None, None) => None,
+    };
+
+    let mut uncompared_ranges = chunk_tracker_a.bad_ranges(common_size, true);
+    uncompared_ranges.extend(chunk_tracker_b.bad_ranges(common_size, true));
+    normalize_ranges(&mut uncompared_ranges);
+
+    let mut stats = stats.lock().unwrap().clone();
+
+    stats.chunks_compared = chunks_compared;
+    stats.chunks_changed = chunks_changed;
+    stats.blocks_decode_failed_a = blocks_decode_failed_a;
+    stats.blocks_decode_failed_b = blocks_decode_failed_b;
+    stats.changed_ranges = changed_ranges;
+    stats.uncompared_ranges = uncompared_ranges;
+
+    Ok(Some(stats))
+}
@@ -0,0 +1,54 @@
+#![cfg(test)]
+
+use crate::diff_core::*;
+use crate::sbx_block::{Block, BlockType, Metadata};
+use crate::sbx_specs::Version;
+
+#[test]
+fn test_diff_metadata_same_block() {
+    let mut block = Block::new(Version::V1, &[1; 6], BlockType::Meta);
+    block
+        .update_metas(&[Metadata::FNM(String::from("abc")), Metadata::FSZ(100)])
+        .unwrap();
+
+    assert!(diff_metadata(&block, &block).is_empty());
+}
+
+#[test]
+fn test_diff_metadata_changed_fields() {
+    let mut a = Block::new(Version::V1, &[1; 6], BlockType::Meta);
+    a.update_metas(&[Metadata::FNM(String::from("abc")), Metadata::FSZ(100)])
+        .unwrap();
+
+    let mut b = Block::new(Version::V17, &[1; 6], BlockType::Meta);
+    b.update_metas(&[
+        Metadata::FNM(String::from("abc")),
+        Metadata::FSZ(200),
+        Metadata::RSD(10),
+    ])
+    .unwrap();
+
+    let diffs = diff_metadata(&a, &b);
+
+    let fields: Vec<&str> = diffs.iter().map(|d| d.field).collect();
+    assert_eq!(vec!["VER", "FSZ", "RSD"], fields);
+
+    assert_eq!(Some(String::from("100")), diffs[1].a);
+    assert_eq!(Some(String::from("200")), diffs[1].b);
+    assert_eq!(None, diffs[2].a);
+    assert_eq!(Some(String::from("10")), diffs[2].b);
+}
+
+#[test]
+fn test_diff_metadata_data_block_has_no_fields() {
+    let mut a = Block::new(Version::V1, &[1; 6], BlockType::Meta);
+    a.update_metas(&[Metadata::FSZ(100)]).unwrap();
+
+    let b = Block::new(Version::V1, &[2; 6], BlockType::Data);
+
+    let diffs = diff_metadata(&a, &b);
+
+    let fields: Vec<&str> = diffs.iter().map(|d| d.field).collect();
+    assert_eq!(vec!["UID", "FSZ"], fields);
+    assert_eq!(None, diffs[1].b);
+}
@@ -4,7 +4,9 @@ use crate::file_reader::{FileReader, FileReaderParam};
 use crate::file_utils;
 use crate::file_writer::{FileWriter, FileWriterParam};
 use crate::general_error::Error;
+use crate::hash_stats::HashStats;
 use crate::json_printer::{BracketType, JSONPrinter};
+use crate::log::*;
 use crate::misc_utils;
 use crate::misc_utils::RequiredLenAndSeekTo;
 use crate::misc_utils::{PositionOrLength, RangeEnd};
@@ -17,13 +19,16 @@ use crate::sbx_specs::Version;
 use crate::sbx_specs::{
     ver_forces_meta_enabled, ver_to_block_size, ver_to_data_size,
     ver_to_last_data_seq_num_exc_parity, ver_to_max_data_file_size, ver_to_usize, ver_uses_rs,
-    SBX_FILE_UID_LEN, SBX_LARGEST_BLOCK_SIZE,
+    SBX_FILE_UID_LEN, SBX_FIRST_DATA_SEQ_NUM, SBX_LARGEST_BLOCK_SIZE,
 };
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
 use crate::time_utils;
 use crate::writer::{Writer, WriterType};
 use std::fmt;
 use std::fs;
 use std::io::SeekFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::channel;
 use std::sync::mpsc::sync_channel;
 use std::sync::Barrier;
@@ -33,18 +38,41 @@ use std::time::UNIX_EPOCH;
 
 const PIPELINE_BUFFER_IN_ROTATION: usize = 9;
 
+const REHASH_BUFFER_SIZE: usize = 4096 * 50;
+
 const SEQ_NUM_OVERFLOW_MSG: &str = "Block seq num already at max, addition causes overflow. This might be due to file size being changed during the encoding, or too much data from stdin";
 
+#[derive(Clone, Debug, Default)]
+struct Checkpoint {
+    next_seq_num: u32,
+    bytes_processed: u64,
+    data_blocks_written: u64,
+    parity_blocks_written: u64,
+    blank_blocks_omitted: u64,
+    sparse_map: Option<SparseMap>,
+    start_time: u64,
+    version: u64,
+    data_par_burst: (u64, u64, u64),
+}
+
+struct ReaderCheckpoint {
+    next_seq_num: u32,
+    bytes_processed: u64,
+}
+
 #[derive(Clone, Debug)]
 pub struct Stats {
     uid: [u8; SBX_FILE_UID_LEN],
     version: Version,
+    data_par_burst: Option<(usize, usize, usize)>,
     chunk_size: u64,
     hash_bytes: Option<multihash::HashBytes>,
+    checkpoint: Checkpoint,
     pub meta_blocks_written: u64,
     pub data_blocks_written: u64,
     pub parity_blocks_written: u64,
     pub data_padding_bytes: usize,
+    pub blank_blocks_omitted: Option<u64>,
     pub in_file_size: u64,
     pub out_file_size: u64,
     total_data_blocks: Option<u64>,
@@ -122,6 +150,14 @@ impl fmt::Display for Stats {
                 "Number of blocks written (parity)   : {}",
                 parity_blocks_written
             )?;
+            if let Some(x) = self.blank_blocks_omitted {
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blank blocks omitted      : {}",
+                    x
+                )?;
+            }
             write_maybe_json!(
                 f,
                 json_printer,
@@ -205,6 +241,14 @@ impl fmt::Display for Stats {
                 "Number of blocks written (data)     : {}",
                 data_blocks_written
             )?;
+            if let Some(x) = self.blank_blocks_omitted {
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blank blocks omitted      : {}",
+                    x
+                )?;
+            }
             write_maybe_json!(
                 f,
                 json_printer,
@@ -265,6 +309,11 @@ pub struct Param {
     to_pos: Option<RangeEnd<u64>>,
     in_file: Option<String>,
     out_file: String,
+    force_write: bool,
+    log_file: Option<String>,
+    streaming: bool,
+    sparse: bool,
+    sparse_map_file: Option<String>,
     pr_verbosity_level: PRVerbosityLevel,
 }
 
@@ -280,6 +329,10 @@ impl Param {
         to_pos: Option<RangeEnd<u64>>,
         in_file: Option<&str>,
         out_file: &str,
+        force_write: bool,
+        log_file: Option<&str>,
+        sparse: bool,
+        sparse_map_file: Option<&str>,
         pr_verbosity_level: PRVerbosityLevel,
     ) -> Param {
         Param {
@@ -297,6 +350,11 @@ impl Param {
                 Some(f) => Some(String::from(f)),
             },
             out_file: String::from(out_file),
+            force_write,
+            log_file: log_file.map(String::from),
+            streaming: file_utils::check_if_file_is_stdout(out_file),
+            sparse,
+            sparse_map_file: sparse_map_file.map(String::from),
             pr_verbosity_level,
         }
     }
@@ -308,12 +366,18 @@ impl Stats {
         Stats {
             uid: param.uid,
             version: param.version,
+            data_par_burst: param.data_par_burst,
             chunk_size: ver_to_data_size(param.version) as u64,
             hash_bytes: None,
+            checkpoint: Checkpoint {
+                next_seq_num: SBX_FIRST_DATA_SEQ_NUM,
+                ..Checkpoint::default()
+            },
             meta_blocks_written: 0,
             data_blocks_written: 0,
             parity_blocks_written: 0,
             data_padding_bytes: 0,
+            blank_blocks_omitted: if param.sparse { Some(0) } else { None },
             total_data_blocks: match required_len {
                 Some(len) => Some(calc_data_chunk_count(param.version, len)),
                 None => None,
@@ -331,6 +395,128 @@ impl Stats {
 
         self.data_blocks_written as u64 * data_size as u64 - self.data_padding_bytes as u64
     }
+
+    // only complete buffers are recorded in log file, so the data
+    // encoded so far always ends at a block set boundary
+    fn checkpoint(&mut self, reader_checkpoint: ReaderCheckpoint, sparse_map: &Option<SparseMap>) {
+        let ReaderCheckpoint {
+            next_seq_num,
+            bytes_processed,
+        } = reader_checkpoint;
+
+        self.checkpoint = Checkpoint {
+            next_seq_num,
+            bytes_processed,
+            data_blocks_written: self.data_blocks_written,
+            parity_blocks_written: self.parity_blocks_written,
+            blank_blocks_omitted: self.blank_blocks_omitted.unwrap_or(0),
+            sparse_map: sparse_map.clone(),
+            start_time: self.start_time as u64,
+            ..Checkpoint::default()
+        };
+    }
+}
+
+impl Log for Stats {
+    fn serialize(&self) -> String {
+        let checkpoint = &self.checkpoint;
+
+        let mut uid = [0; 8];
+        uid[2..].copy_from_slice(&self.uid);
+
+        let (data, parity, burst) = self.data_par_burst.unwrap_or((0, 0, 0));
+
+        let mut string = String::with_capacity(300);
+        string.push_str(&format!("uid={}\n", u64::from_be_bytes(uid)));
+        string.push_str(&format!("version={}\n", ver_to_usize(self.version)));
+        string.push_str(&format!("rs_data={}\n", data));
+        string.push_str(&format!("rs_parity={}\n", parity));
+        string.push_str(&format!("burst={}\n", burst));
+        string.push_str(&format!("next_seq_num={}\n", checkpoint.next_seq_num));
+        string.push_str(&format!("bytes_processed={}\n", checkpoint.bytes_processed));
+        string.push_str(&format!(
+            "data_blocks_written={}\n",
+            checkpoint.data_blocks_written
+        ));
+        string.push_str(&format!(
+            "parity_blocks_written={}\n",
+            checkpoint.parity_blocks_written
+        ));
+        string.push_str(&format!(
+            "blank_blocks_omitted={}\n",
+            checkpoint.blank_blocks_omitted
+        ));
+        string.push_str(&format!("start_time={}\n", checkpoint.start_time));
+        // kept last as the fields above are parsed up to the first unknown line
+        if let Some(ref sparse_map) = checkpoint.sparse_map {
+            string.push_str(&sparse_map.seq_nums_to_string(u64::from(checkpoint.next_seq_num)));
+        }
+
+        string
+    }
+
+    fn deserialize(&mut self, input: &[u8]) -> Result<(), ()> {
+        let fields = parse_u64_fields(
+            input,
+            &[
+                "uid",
+                "next_seq_num",
+                "bytes_processed",
+                "data_blocks_written",
+                "parity_blocks_written",
+                "blank_blocks_omitted",
+                "start_time",
+                "version",
+                "rs_data",
+                "rs_parity",
+                "burst",
+            ],
+        )?;
+
+        if fields[0] >= 1 << (8 * SBX_FILE_UID_LEN)
+            || fields[1] < u64::from(SBX_FIRST_DATA_SEQ_NUM)
+            || fields[1] > u64::from(u32::MAX)
+        {
+            return Err(());
+        }
+
+        self.uid
+            .copy_from_slice(&fields[0].to_be_bytes()[8 - SBX_FILE_UID_LEN..]);
+        self.data_blocks_written = fields[3];
+        self.parity_blocks_written = fields[4];
+        let sparse_map = match self.blank_blocks_omitted {
+            Some(ref mut x) => {
+                *x = fields[5];
+
+                let mut sparse_map = SparseMap::new(&self.uid);
+
+                if let Some(pos) = input.windows(10).position(|w| w == b"\nseq_nums=") {
+                    sparse_map.add_seq_nums_from_bytes(&input[pos + 1..])?;
+                }
+
+                if sparse_map.block_count() != fields[5] {
+                    return Err(());
+                }
+
+                Some(sparse_map)
+            }
+            None => None,
+        };
+
+        self.checkpoint = Checkpoint {
+            next_seq_num: fields[1] as u32,
+            bytes_processed: fields[2],
+            data_blocks_written: fields[3],
+            parity_blocks_written: fields[4],
+            blank_blocks_omitted: fields[5],
+            sparse_map,
+            start_time: fields[6],
+            version: fields[7],
+            data_par_burst: (fields[8], fields[9], fields[10]),
+        };
+
+        Ok(())
+    }
 }
 
 impl ProgressReport for Stats {
@@ -364,6 +550,8 @@ fn pack_metadata(
 ) {
     block.set_seq_num(0);
 
+    let provisional = hash.is_none();
+
     let metas = block.metas_mut().unwrap();
 
     {
@@ -378,8 +566,10 @@ fn pack_metadata(
     }
     {
         // add SBX file name
-        let file_name = file_utils::get_file_name_part_of_path(&param.out_file).unwrap();
-        metas.push(Metadata::SNM(file_name));
+        if !param.streaming {
+            let file_name = file_utils::get_file_name_part_of_path(&param.out_file).unwrap();
+            metas.push(Metadata::SNM(file_name));
+        }
     }
     {
         // add file size
@@ -423,6 +613,20 @@ fn pack_metadata(
             metas.push(Metadata::RSP(param.data_par_burst.unwrap().1 as u8));
         }
     }
+    {
+        // add number of blank blocks omitted, kept last as older
+        // versions stop parsing at the first unknown field
+        if let Some(x) = stats.blank_blocks_omitted {
+            metas.push(Metadata::SPR(x));
+        }
+    }
+    {
+        // mark the provisional metadata block of a streamed container,
+        // the final metadata blocks are appended after the last data block
+        if param.streaming && provisional {
+            metas.push(Metadata::TRL(1));
+        }
+    }
 }
 
 fn write_meta_blocks(
@@ -462,7 +666,10 @@ fn write_meta_blocks(
         sbx_block::calc_meta_block_all_write_pos_s(param.version, param.data_par_burst);
 
     for &p in write_pos_s.iter() {
-        writer.seek(SeekFrom::Start(p)).unwrap()?;
+        // metadata blocks are written one after another when streaming
+        if !param.streaming {
+            writer.seek(SeekFrom::Start(p)).unwrap()?;
+        }
 
         writer.write(sbx_block::slice_buf(block.get_version(), &buffer))?;
 
@@ -474,6 +681,136 @@ fn write_meta_blocks(
     Ok(())
 }
 
+// check the last block set recorded in the log file is present in
+// the container before continuing to append to it
+fn check_container_tail(
+    param: &Param,
+    next_seq_num: u32,
+    sparse_map: &Option<SparseMap>,
+) -> Result<(), Error> {
+    let mut reader = FileReader::new(
+        &param.out_file,
+        FileReaderParam {
+            write: false,
+            buffered: false,
+        },
+    )?;
+
+    let block_size = ver_to_block_size(param.version);
+
+    let block_set_size = match param.data_par_burst {
+        None => 1,
+        Some((data, parity, _)) => (data + parity) as u32,
+    };
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    for seq_num in next_seq_num - block_set_size..next_seq_num {
+        let write_pos = sbx_block::calc_data_block_write_pos(
+            param.version,
+            seq_num,
+            Some(param.meta_enabled),
+            param.data_par_burst,
+        );
+
+        reader.seek(SeekFrom::Start(write_pos))?;
+
+        let read_res = reader.read(&mut buffer[..block_size])?;
+
+        // blank blocks may have been omitted, leaving a hole
+        if sparse_map::block_is_omitted(sparse_map, seq_num, &buffer[..read_res.len_read]) {
+            continue;
+        }
+
+        let pred = |header: &sbx_block::Header| {
+            header.version == param.version && header.uid == param.uid && header.seq_num == seq_num
+        };
+
+        if read_res.len_read < block_size
+            || block
+                .sync_from_buffer(&buffer[..block_size], Some(&pred), None)
+                .is_err()
+        {
+            return Err(Error::with_msg(&format!(
+                "Container \"{}\" does not match log file \"{}\", failed to find block with seq num {} at {}",
+                param.out_file,
+                param.log_file.as_ref().unwrap(),
+                seq_num,
+                write_pos
+            )));
+        }
+    }
+
+    Ok(())
+}
+
+fn rehash_input(
+    param: &Param,
+    ctrlc_stop_flag: &Arc<AtomicBool>,
+    seek_to: u64,
+    len: u64,
+    hash_ctx: &mut multihash::hash::Ctx,
+) -> Result<(), Error> {
+    let mut reader = FileReader::new(
+        param.in_file.as_ref().unwrap(),
+        FileReaderParam {
+            write: false,
+            buffered: true,
+        },
+    )?;
+
+    reader.seek(SeekFrom::Start(seek_to))?;
+
+    let stats = Arc::new(Mutex::new(HashStats::new(len)));
+
+    let reporter = ProgressReporter::new(
+        &stats,
+        "Input rehashing progress",
+        "bytes",
+        param.pr_verbosity_level,
+        param.json_printer.json_enabled(),
+    );
+
+    let mut buffer = vec![0; REHASH_BUFFER_SIZE];
+
+    reporter.start();
+
+    while stats.lock().unwrap().bytes_processed < len {
+        if ctrlc_stop_flag.load(Ordering::SeqCst) {
+            reporter.stop();
+
+            return Err(Error::with_msg(&format!(
+                "Rehashing interrupted, log file \"{}\" is left unchanged",
+                param.log_file.as_ref().unwrap()
+            )));
+        }
+
+        let bytes_left = len - stats.lock().unwrap().bytes_processed;
+        let read_len = std::cmp::min(bytes_left, REHASH_BUFFER_SIZE as u64) as usize;
+
+        let read_res = reader.read(&mut buffer[..read_len])?;
+
+        if read_res.len_read == 0 {
+            reporter.stop();
+
+            return Err(Error::with_msg(&format!(
+                "Input file \"{}\" is shorter than recorded in log file \"{}\"",
+                param.in_file.as_ref().unwrap(),
+                param.log_file.as_ref().unwrap()
+            )));
+        }
+
+        hash_ctx.update(&buffer[..read_res.len_read]);
+
+        stats.lock().unwrap().bytes_processed += read_res.len_read as u64;
+    }
+
+    reporter.stop();
+
+    Ok(())
+}
+
 pub fn encode_file(param: &Param) -> Result<Stats, Error> {
     let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
 
@@ -489,15 +826,33 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
         None => Reader::new(ReaderType::Stdin(std::io::stdin())),
     };
 
-    let writer = Arc::new(Mutex::new(Writer::new(WriterType::File(FileWriter::new(
-        &param.out_file,
-        FileWriterParam {
-            read: false,
-            append: false,
-            truncate: true,
-            buffered: false,
-        },
-    )?))));
+    if param.streaming {
+        if param.sparse {
+            return Err(Error::with_msg(
+                "Holes cannot be left in output when output is stdout",
+            ));
+        }
+
+        if let Some((_, _, burst)) = param.data_par_burst {
+            if burst > 0 {
+                return Err(Error::with_msg(
+                    "Burst error resistance level must be 0 when output is stdout",
+                ));
+            }
+        }
+
+        if param.log_file.is_some() {
+            return Err(Error::with_msg(
+                "Log file cannot be used when output is stdout",
+            ));
+        }
+    }
+
+    if param.in_file.is_none() && param.log_file.is_some() {
+        return Err(Error::with_msg(
+            "Log file cannot be used when input is stdin",
+        ));
+    }
 
     let metadata = match reader.metadata() {
         Some(m) => Some(m?),
@@ -549,6 +904,75 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
     // setup stats
     let stats = Arc::new(Mutex::new(Stats::new(param, required_len)));
 
+    let log_handler = Arc::new(match param.log_file {
+        None => LogHandler::new(None, &stats),
+        Some(ref f) => LogHandler::new(Some(f), &stats),
+    });
+
+    // read from log file and update stats if the log file exists
+    log_handler.read_from_file()?;
+
+    let checkpoint = stats.lock().unwrap().checkpoint.clone();
+
+    // a log file without any complete block set recorded is the same as no log file
+    let resuming = checkpoint.next_seq_num > SBX_FIRST_DATA_SEQ_NUM;
+
+    if !param.streaming
+        && !param.force_write
+        && !resuming
+        && file_utils::check_if_file_exists(&param.out_file)
+    {
+        return Err(Error::with_msg(&format!(
+            "File \"{}\" already exists",
+            param.out_file
+        )));
+    }
+
+    if resuming {
+        let (data, parity, burst) = param.data_par_burst.unwrap_or((0, 0, 0));
+
+        if checkpoint.version != ver_to_usize(param.version) as u64
+            || checkpoint.data_par_burst != (data as u64, parity as u64, burst as u64)
+        {
+            return Err(Error::with_msg(&format!(
+                "Log file \"{}\" was written for SBX version {}, RS data {}, RS parity {}, burst level {}, which differ from the current options",
+                param.log_file.as_ref().unwrap(),
+                checkpoint.version,
+                checkpoint.data_par_burst.0,
+                checkpoint.data_par_burst.1,
+                checkpoint.data_par_burst.2
+            )));
+        }
+    }
+
+    // continue with the UID used by the container encoded so far
+    let param = &Param {
+        uid: stats.lock().unwrap().uid,
+        ..param.clone()
+    };
+
+    if resuming {
+        check_container_tail(param, checkpoint.next_seq_num, &checkpoint.sparse_map)?;
+    }
+
+    // do not truncate when continuing from log file,
+    // otherwise the container encoded so far would be lost
+    let writer = if param.streaming {
+        Writer::new(WriterType::Stdout(std::io::stdout()))
+    } else {
+        Writer::new(WriterType::File(FileWriter::new(
+            &param.out_file,
+            FileWriterParam {
+                read: false,
+                append: false,
+                truncate: !resuming,
+                buffered: false,
+            },
+        )?))
+    };
+
+    let writer = Arc::new(Mutex::new(writer));
+
     // setup reporter
     let reporter = ProgressReporter::new(
         &stats,
@@ -559,24 +983,36 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
     );
 
     // set up hash state
-    let hash_ctx = if param.meta_enabled {
-        Arc::new(Mutex::new(Some(
-            multihash::hash::Ctx::new(param.hash_type).unwrap(),
-        )))
-    } else {
+    let hash_ctx = if !param.meta_enabled {
         Arc::new(Mutex::new(None))
+    } else {
+        let mut ctx = multihash::hash::Ctx::new(param.hash_type).unwrap();
+
+        // the hash state is not recorded in log file,
+        // so rebuild it from the input encoded so far
+        if resuming {
+            rehash_input(
+                param,
+                &ctrlc_stop_flag,
+                seek_to.unwrap(),
+                checkpoint.bytes_processed,
+                &mut ctx,
+            )?;
+        }
+
+        Arc::new(Mutex::new(Some(ctx)))
     };
 
-    // seek to calculated position
+    // seek to calculated position, skipping the part already encoded
     if let Some(seek_to) = seek_to {
-        if let Some(r) = reader.seek(SeekFrom::Start(seek_to)) {
+        if let Some(r) = reader.seek(SeekFrom::Start(seek_to + checkpoint.bytes_processed)) {
             r?;
         }
     }
 
     let mut block_for_seq_num_check = Block::dummy();
 
-    block_for_seq_num_check.set_seq_num(0);
+    block_for_seq_num_check.set_seq_num(checkpoint.next_seq_num - 1);
 
     if param.meta_enabled {
         // write dummy metadata block
@@ -612,12 +1048,23 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
         PIPELINE_BUFFER_IN_ROTATION,
     );
 
-    for buffer in buffers.into_iter() {
+    for mut buffer in buffers.into_iter() {
+        if resuming {
+            buffer.skip_seq_nums(checkpoint.next_seq_num - 1);
+        }
+
         to_reader.send(Some(buffer)).unwrap();
     }
 
     reporter.start();
 
+    // keep the encoding time recorded in metadata the same as the initial run
+    if resuming {
+        stats.lock().unwrap().start_time = checkpoint.start_time as f64;
+    }
+
+    log_handler.start();
+
     let reader_thread = {
         let version = param.version;
         let hash_ctx = Arc::clone(&hash_ctx);
@@ -626,12 +1073,17 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
         let data_par_burst = param.data_par_burst;
         let last_data_seq_num_exc_parity =
             ver_to_last_data_seq_num_exc_parity(version, data_par_burst);
+        let log_enabled = param.log_file.is_some();
+        let bytes_processed = checkpoint.bytes_processed;
+        let ctrlc_stop_flag = Arc::clone(&ctrlc_stop_flag);
 
         thread::spawn(move || {
             let mut run = true;
-            let mut bytes_processed = 0;
+            let mut interrupted = false;
+            let mut bytes_processed = bytes_processed;
             let mut hash_ctx = hash_ctx.lock().unwrap();
-            let mut last_data_block_exc_parity_seen = false;
+            let mut last_data_block_exc_parity_seen =
+                block_for_seq_num_check.get_seq_num() >= last_data_seq_num_exc_parity;
 
             while let Some(mut buffer) = from_writer.recv().unwrap() {
                 if !run {
@@ -704,39 +1156,42 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
                     }
                 }
 
-                to_encoder.send(Some(buffer)).unwrap();
+                let reader_checkpoint = if log_enabled && buffer.is_full() {
+                    Some(ReaderCheckpoint {
+                        next_seq_num: block_for_seq_num_check.get_seq_num() + 1,
+                        bytes_processed,
+                    })
+                } else {
+                    // when interrupted, drop the incomplete buffer so
+                    // the container does not run past the log file
+                    if log_enabled && ctrlc_stop_flag.load(Ordering::SeqCst) {
+                        interrupted = true;
+                        break;
+                    }
+
+                    None
+                };
+
+                to_encoder.send(Some((reader_checkpoint, buffer))).unwrap();
             }
 
             worker_shutdown!(to_encoder, shutdown_barrier);
+
+            interrupted
         })
     };
 
     let encoder_thread = {
-        let stats = Arc::clone(&stats);
         let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
-        let data_size = ver_to_data_size(param.version);
 
         thread::spawn(move || {
-            while let Some(mut buffer) = from_reader.recv().unwrap() {
+            while let Some((reader_checkpoint, mut buffer)) = from_reader.recv().unwrap() {
                 if let Err(e) = buffer.encode() {
                     error_tx_encoder.send(e).unwrap();
                     break;
                 }
 
-                let (data_blocks, padding_blocks, parity_blocks) =
-                    buffer.data_padding_parity_block_count();
-                let padding_byte_count = buffer.padding_byte_count_in_non_padding_blocks();
-
-                {
-                    let mut stats = stats.lock().unwrap();
-
-                    stats.data_blocks_written += data_blocks as u64;
-                    stats.parity_blocks_written += parity_blocks as u64;
-
-                    stats.data_padding_bytes += padding_byte_count + padding_blocks * data_size;
-                }
-
-                to_writer.send(Some(buffer)).unwrap();
+                to_writer.send(Some((reader_checkpoint, buffer))).unwrap();
             }
 
             worker_shutdown!(to_writer, shutdown_barrier);
@@ -745,34 +1200,97 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
 
     let writer_thread = {
         let writer = Arc::clone(&writer);
+        let stats = Arc::clone(&stats);
+        let log_handler = Arc::clone(&log_handler);
         let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+        let mut sparse_map = if !param.sparse {
+            None
+        } else if resuming {
+            checkpoint.sparse_map.clone()
+        } else {
+            Some(SparseMap::new(&param.uid))
+        };
+        let streaming = param.streaming;
+        let data_size = ver_to_data_size(param.version);
 
         thread::spawn(move || {
             let writer = &mut writer.lock().unwrap();
 
-            while let Some(mut buffer) = from_encoder.recv().unwrap() {
-                if let Err(e) = buffer.write(writer) {
+            while let Some((reader_checkpoint, mut buffer)) = from_encoder.recv().unwrap() {
+                // check if there's any error in log handling
+                if let Err(e) = log_handler.pop_error() {
+                    error_tx_writer.send(e).unwrap();
+                    break;
+                }
+
+                let write_res = if let Some(ref mut sparse_map) = sparse_map {
+                    buffer.write_skip_blank(writer, sparse_map)
+                } else if streaming {
+                    buffer.write_no_seek(writer)
+                } else {
+                    buffer.write(writer)
+                };
+
+                if let Err(e) = write_res {
                     error_tx_writer.send(e).unwrap();
                     break;
                 }
 
+                // only update stats after the blocks are written,
+                // so the log file never runs ahead of the container
+                {
+                    let (data_blocks, padding_blocks, parity_blocks) =
+                        buffer.data_padding_parity_block_count();
+                    let padding_byte_count = buffer.padding_byte_count_in_non_padding_blocks();
+
+                    let mut stats = stats.lock().unwrap();
+
+                    stats.data_blocks_written += data_blocks as u64;
+                    stats.parity_blocks_written += parity_blocks as u64;
+
+                    stats.data_padding_bytes += padding_byte_count + padding_blocks * data_size;
+
+                    if let Some(ref sparse_map) = sparse_map {
+                        stats.blank_blocks_omitted = Some(sparse_map.block_count());
+                    }
+
+                    if let Some(reader_checkpoint) = reader_checkpoint {
+                        stats.checkpoint(reader_checkpoint, &sparse_map);
+                    }
+                }
+
                 buffer.reset();
 
                 to_reader.send(Some(buffer)).unwrap();
             }
 
             worker_shutdown!(to_reader, shutdown_barrier);
+
+            sparse_map
         })
     };
 
-    reader_thread.join().unwrap();
+    let interrupted = reader_thread.join().unwrap();
     encoder_thread.join().unwrap();
-    writer_thread.join().unwrap();
+    let sparse_map = writer_thread.join().unwrap();
 
     if let Ok(err) = error_rx.try_recv() {
         return Err(err);
     }
 
+    log_handler.stop();
+
+    // the container is incomplete, the next run with
+    // the same log file continues from the last checkpoint
+    if interrupted {
+        reporter.stop();
+
+        return Err(Error::with_msg(&format!(
+            "Encoding interrupted, progress is recorded in log file \"{}\"",
+            param.log_file.as_ref().unwrap()
+        )));
+    }
+
     let data_bytes_encoded = stats.lock().unwrap().data_bytes_encoded();
 
     if param.meta_enabled {
@@ -783,7 +1301,8 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
             .unwrap()
             .finish_into_hash_bytes();
 
-        // write actual medata blocks
+        // write actual medata blocks, which are appended
+        // after the last data block when streaming
         write_meta_blocks(
             param,
             &stats,
@@ -791,7 +1310,7 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
             Some(data_bytes_encoded),
             Some(hash_bytes.clone()),
             &mut writer.lock().unwrap(),
-            false,
+            param.streaming,
         )?;
 
         // record hash in stats
@@ -808,6 +1327,33 @@ pub fn encode_file(param: &Param) -> Result<Stats, Error> {
         data_bytes_encoded,
     );
 
+    if param.streaming && param.meta_enabled {
+        let trailing_block_count =
+            sbx_block::calc_meta_block_all_write_pos_s(param.version, param.data_par_burst).len();
+
+        stats.lock().unwrap().out_file_size +=
+            (trailing_block_count * ver_to_block_size(param.version)) as u64;
+    }
+
+    // blocks omitted at the end would otherwise leave the container short
+    if param.sparse {
+        let out_file_size = stats.lock().unwrap().out_file_size;
+        let mut writer = writer.lock().unwrap();
+
+        if writer.get_file_size().unwrap()? < out_file_size {
+            writer.set_len(out_file_size).unwrap()?;
+        }
+    }
+
+    if let Some(sparse_map) = sparse_map {
+        let sparse_map_file = match param.sparse_map_file {
+            Some(ref f) => f.clone(),
+            None => sparse_map::default_file(&param.out_file),
+        };
+
+        sparse_map.write_to_file(&sparse_map_file)?;
+    }
+
     let stats = stats.lock().unwrap().clone();
 
     Ok(stats)
@@ -1,4 +1,5 @@
 #![allow(dead_code)]
+use crate::file_error::{to_err, FileError};
 use crate::file_reader::{FileReader, FileReaderParam};
 use crate::general_error::Error;
 use crate::sbx_block;
@@ -29,6 +30,20 @@ pub fn get_file_size(file: &str) -> Result<u64, Error> {
     Ok(reader.get_file_size()?)
 }
 
+pub fn rename_file(from: &str, to: &str) -> Result<(), Error> {
+    match fs::rename(from, to) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_err(FileError::new(e.kind(), from))),
+    }
+}
+
+pub fn remove_file(file: &str) -> Result<(), Error> {
+    match fs::remove_file(file) {
+        Ok(()) => Ok(()),
+        Err(e) => Err(to_err(FileError::new(e.kind(), file))),
+    }
+}
+
 pub fn check_if_file_exists(file: &str) -> bool {
     Path::new(file).exists()
 }
@@ -155,6 +155,22 @@ impl FileWriter {
         })
     }
 
+    pub fn sync(&mut self) -> Result<(), Error> {
+        use self::FileHandle::*;
+        let res = match self.file {
+            Buffered(ref mut f) => {
+                flush!(self => f);
+
+                f.get_ref().sync_data()
+            }
+            Unbuffered(ref f) => f.sync_data(),
+        };
+        match res {
+            Ok(_) => Ok(()),
+            Err(e) => Err(to_err(FileError::new(e.kind(), &self.path))),
+        }
+    }
+
     pub fn set_len(&mut self, size: u64) -> Result<(), Error> {
         match file_op!(self set_len => size) {
             Ok(_) => Ok(()),
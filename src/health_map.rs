@@ -0,0 +1,398 @@
+use crate::damage_map::DamageMap;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::integer_utils::IntegerUtils;
+
+pub const MAP_WIDTH: usize = 64;
+
+const DEFAULT_MAX_ROWS: u64 = 16;
+
+const SVG_CELL_SIZE: usize = 10;
+const PNG_CELL_SIZE: usize = 8;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BlockHealth {
+    Good,
+    Blank,
+    Repairable,
+    Corrupt,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Cell {
+    pub good: u64,
+    pub blank: u64,
+    pub repairable: u64,
+    pub corrupt: u64,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapStyle {
+    Ascii,
+    Unicode,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ImageFormat {
+    Svg,
+    Png,
+}
+
+#[derive(Clone, Debug)]
+pub struct HealthMap {
+    start_pos: u64,
+    block_size: u64,
+    blocks_per_cell: u64,
+    cells: Vec<Cell>,
+    corrupt_blocks: Vec<u64>,
+}
+
+impl Cell {
+    /// Returns the worst health of the blocks in the cell
+    pub fn health(&self) -> Option<BlockHealth> {
+        if self.corrupt > 0 {
+            Some(BlockHealth::Corrupt)
+        } else if self.repairable > 0 {
+            Some(BlockHealth::Repairable)
+        } else if self.blank > 0 {
+            Some(BlockHealth::Blank)
+        } else if self.good > 0 {
+            Some(BlockHealth::Good)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn health_to_char(health: Option<BlockHealth>, style: MapStyle) -> char {
+    use self::BlockHealth::*;
+
+    match style {
+        MapStyle::Ascii => match health {
+            None => ' ',
+            Some(Good) => '.',
+            Some(Blank) => '_',
+            Some(Repairable) => 'o',
+            Some(Corrupt) => 'X',
+        },
+        MapStyle::Unicode => match health {
+            None => ' ',
+            Some(Good) => '░',
+            Some(Blank) => '·',
+            Some(Repairable) => '▒',
+            Some(Corrupt) => '█',
+        },
+    }
+}
+
+fn health_to_rgb(health: Option<BlockHealth>) -> [u8; 3] {
+    use self::BlockHealth::*;
+
+    match health {
+        None => [0xFF, 0xFF, 0xFF],
+        Some(Good) => [0x4C, 0xAF, 0x50],
+        Some(Blank) => [0xBD, 0xBD, 0xBD],
+        Some(Repairable) => [0xFF, 0xC1, 0x07],
+        Some(Corrupt) => [0xF4, 0x43, 0x36],
+    }
+}
+
+pub fn legend(style: MapStyle) -> String {
+    use self::BlockHealth::*;
+
+    format!(
+        "{} good  {} blank  {} repairable  {} corrupt",
+        health_to_char(Some(Good), style),
+        health_to_char(Some(Blank), style),
+        health_to_char(Some(Repairable), style),
+        health_to_char(Some(Corrupt), style),
+    )
+}
+
+pub fn path_to_image_format(path: &str) -> Option<ImageFormat> {
+    let path = path.to_lowercase();
+
+    if path.ends_with(".svg") {
+        Some(ImageFormat::Svg)
+    } else if path.ends_with(".png") {
+        Some(ImageFormat::Png)
+    } else {
+        None
+    }
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc: u32 = 0xFFFF_FFFF;
+
+    for &b in bytes.iter() {
+        crc ^= b as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xEDB8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+
+    for &x in bytes.iter() {
+        a = (a + x as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    (b << 16) | a
+}
+
+fn push_png_chunk(png: &mut Vec<u8>, chunk_type: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let crc_start = png.len();
+    png.extend_from_slice(chunk_type);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[crc_start..]);
+
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// Encodes RGB pixels as a PNG, using stored (uncompressed) deflate blocks
+pub fn encode_png(width: usize, height: usize, rgb: &[u8]) -> Vec<u8> {
+    assert_eq!(width * height * 3, rgb.len());
+
+    // each scanline is prefixed with filter type 0 (none)
+    let mut raw = Vec::with_capacity((width * 3 + 1) * height);
+    for row in rgb.chunks(width * 3) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    if raw.is_empty() {
+        zlib.extend_from_slice(&[0x01, 0x00, 0x00, 0xFF, 0xFF]);
+    }
+    let block_count = usize::round_up_to_multiple(raw.len(), 0xFFFF) / 0xFFFF;
+    for (i, block) in raw.chunks(0xFFFF).enumerate() {
+        let len = block.len() as u16;
+        zlib.push(if i + 1 == block_count { 0x01 } else { 0x00 });
+        zlib.extend_from_slice(&len.to_le_bytes());
+        zlib.extend_from_slice(&(!len).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&(width as u32).to_be_bytes());
+    ihdr.extend_from_slice(&(height as u32).to_be_bytes());
+    // bit depth 8, colour type 2 (RGB), default compression, filter and interlace
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+
+    let mut png = Vec::with_capacity(zlib.len() + 100);
+    png.extend_from_slice(b"\x89PNG\r\n\x1a\n");
+    push_png_chunk(&mut png, b"IHDR", &ihdr);
+    push_png_chunk(&mut png, b"IDAT", &zlib);
+    push_png_chunk(&mut png, b"IEND", &[]);
+
+    png
+}
+
+impl HealthMap {
+    pub fn new(
+        start_pos: u64,
+        block_size: u64,
+        total_blocks: u64,
+        blocks_per_cell: Option<u64>,
+    ) -> HealthMap {
+        let max_cells = MAP_WIDTH as u64 * DEFAULT_MAX_ROWS;
+
+        let blocks_per_cell = match blocks_per_cell {
+            Some(x) => std::cmp::max(x, 1),
+            None => std::cmp::max(
+                u64::round_up_to_multiple(total_blocks, max_cells) / max_cells,
+                1,
+            ),
+        };
+
+        let cell_count = u64::round_up_to_multiple(total_blocks, blocks_per_cell) / blocks_per_cell;
+
+        HealthMap {
+            start_pos,
+            block_size,
+            blocks_per_cell,
+            cells: vec![Cell::default(); cell_count as usize],
+            corrupt_blocks: Vec::new(),
+        }
+    }
+
+    pub fn blocks_per_cell(&self) -> u64 {
+        self.blocks_per_cell
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    pub fn cell_pos(&self, index: usize) -> u64 {
+        self.start_pos + index as u64 * self.blocks_per_cell * self.block_size
+    }
+
+    fn cell_index(&self, pos: u64) -> usize {
+        ((pos - self.start_pos) / self.block_size / self.blocks_per_cell) as usize
+    }
+
+    pub fn add_block(&mut self, pos: u64, health: BlockHealth) {
+        let index = self.cell_index(pos);
+
+        if index >= self.cells.len() {
+            self.cells.resize(index + 1, Cell::default());
+        }
+
+        let cell = &mut self.cells[index];
+
+        match health {
+            BlockHealth::Good => cell.good += 1,
+            BlockHealth::Blank => cell.blank += 1,
+            BlockHealth::Repairable => cell.repairable += 1,
+            BlockHealth::Corrupt => {
+                cell.corrupt += 1;
+                self.corrupt_blocks.push(pos);
+            }
+        }
+    }
+
+    /// Marks corrupt blocks as repairable if they are metadata blocks, or belong
+    /// to block sets with enough parity blocks left, according to the damage map
+    pub fn mark_repairable(&mut self, damage_map: &DamageMap) {
+        if damage_map.data_par_burst().is_none() {
+            return;
+        }
+
+        let mut repairable: Vec<u64> = damage_map.meta_blocks().to_vec();
+        for blocks in damage_map.block_sets().values() {
+            if damage_map.block_set_is_recoverable(blocks.len()) {
+                repairable.extend(blocks.iter().map(|block| block.pos));
+            }
+        }
+        repairable.sort();
+        repairable.dedup();
+
+        let corrupt_blocks = std::mem::take(&mut self.corrupt_blocks);
+
+        for pos in corrupt_blocks.into_iter() {
+            if repairable.binary_search(&pos).is_ok() {
+                let index = self.cell_index(pos);
+                let cell = &mut self.cells[index];
+
+                cell.corrupt -= 1;
+                cell.repairable += 1;
+            } else {
+                self.corrupt_blocks.push(pos);
+            }
+        }
+    }
+
+    pub fn to_rows(&self, style: MapStyle) -> Vec<String> {
+        self.cells
+            .chunks(MAP_WIDTH)
+            .map(|row| {
+                row.iter()
+                    .map(|cell| health_to_char(cell.health(), style))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn to_svg(&self) -> String {
+        let rows = usize::round_up_to_multiple(self.cells.len(), MAP_WIDTH) / MAP_WIDTH;
+        let width = MAP_WIDTH * SVG_CELL_SIZE;
+        let height = rows * SVG_CELL_SIZE;
+
+        let mut svg = String::with_capacity(self.cells.len() * 150 + 200);
+
+        svg.push_str(&format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\">\n",
+            width, height, width, height
+        ));
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            let [r, g, b] = health_to_rgb(cell.health());
+
+            svg.push_str(&format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#{:02X}{:02X}{:02X}\"><title>byte {}: {} good, {} blank, {} repairable, {} corrupt</title></rect>\n",
+                (i % MAP_WIDTH) * SVG_CELL_SIZE,
+                (i / MAP_WIDTH) * SVG_CELL_SIZE,
+                SVG_CELL_SIZE - 1,
+                SVG_CELL_SIZE - 1,
+                r,
+                g,
+                b,
+                self.cell_pos(i),
+                cell.good,
+                cell.blank,
+                cell.repairable,
+                cell.corrupt,
+            ));
+        }
+
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+
+    pub fn to_png(&self) -> Vec<u8> {
+        let rows = usize::round_up_to_multiple(self.cells.len(), MAP_WIDTH) / MAP_WIDTH;
+        let width = MAP_WIDTH * PNG_CELL_SIZE;
+        let height = rows * PNG_CELL_SIZE;
+
+        let mut rgb = vec![0xFF; width * height * 3];
+
+        for (i, cell) in self.cells.iter().enumerate() {
+            let colour = health_to_rgb(cell.health());
+
+            let x0 = (i % MAP_WIDTH) * PNG_CELL_SIZE;
+            let y0 = (i / MAP_WIDTH) * PNG_CELL_SIZE;
+
+            // leave a one pixel gap between cells
+            for y in y0..y0 + PNG_CELL_SIZE - 1 {
+                for x in x0..x0 + PNG_CELL_SIZE - 1 {
+                    let p = (y * width + x) * 3;
+                    rgb[p..p + 3].copy_from_slice(&colour);
+                }
+            }
+        }
+
+        encode_png(width, height, &rgb)
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), Error> {
+        let bytes = match path_to_image_format(path) {
+            Some(ImageFormat::Svg) => self.to_svg().into_bytes(),
+            Some(ImageFormat::Png) => self.to_png(),
+            None => {
+                return Err(Error::with_msg(&format!(
+                    "Unrecognised image format of \"{}\", expected .svg or .png",
+                    path
+                )))
+            }
+        };
+
+        let mut writer = FileWriter::new(
+            path,
+            FileWriterParam {
+                read: false,
+                append: false,
+                truncate: true,
+                buffered: true,
+            },
+        )?;
+
+        writer.write(&bytes)?;
+
+        Ok(())
+    }
+}
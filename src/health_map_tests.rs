@@ -0,0 +1,124 @@
+#![cfg(test)]
+
+use crate::damage_map::DamageMap;
+use crate::health_map::*;
+use crate::sbx_specs::Version;
+
+#[test]
+fn test_blocks_per_cell_default() {
+    assert_eq!(1, HealthMap::new(0, 512, 0, None).blocks_per_cell());
+    assert_eq!(1, HealthMap::new(0, 512, 1024, None).blocks_per_cell());
+    assert_eq!(2, HealthMap::new(0, 512, 1025, None).blocks_per_cell());
+    assert_eq!(7, HealthMap::new(0, 512, 7000, Some(7)).blocks_per_cell());
+    assert_eq!(1, HealthMap::new(0, 512, 7000, Some(0)).blocks_per_cell());
+}
+
+#[test]
+fn test_cell_shows_worst_health() {
+    let mut map = HealthMap::new(1024, 512, 8, Some(4));
+
+    map.add_block(1024, BlockHealth::Good);
+    map.add_block(1024 + 512, BlockHealth::Blank);
+    map.add_block(1024 + 4 * 512, BlockHealth::Good);
+    map.add_block(1024 + 5 * 512, BlockHealth::Corrupt);
+    map.add_block(1024 + 6 * 512, BlockHealth::Blank);
+
+    assert_eq!(2, map.cells().len());
+    assert_eq!(Some(BlockHealth::Blank), map.cells()[0].health());
+    assert_eq!(Some(BlockHealth::Corrupt), map.cells()[1].health());
+    assert_eq!(1024 + 4 * 512, map.cell_pos(1));
+
+    assert_eq!(vec![String::from("_X")], map.to_rows(MapStyle::Ascii));
+    assert_eq!(vec![String::from("·█")], map.to_rows(MapStyle::Unicode));
+}
+
+#[test]
+fn test_rows_wrap_at_map_width() {
+    let mut map = HealthMap::new(0, 128, MAP_WIDTH as u64 + 1, Some(1));
+
+    for i in 0..MAP_WIDTH as u64 + 1 {
+        map.add_block(i * 128, BlockHealth::Good);
+    }
+
+    let rows = map.to_rows(MapStyle::Ascii);
+
+    assert_eq!(2, rows.len());
+    assert_eq!(MAP_WIDTH, rows[0].len());
+    assert_eq!(".", rows[1]);
+}
+
+#[test]
+fn test_mark_repairable() {
+    let mut damage_map = DamageMap::new(&[0; 6], Version::V17, Some((10, 2, 0)));
+    let mut map = HealthMap::new(0, 512, 30, Some(1));
+
+    // block set 0 has two damaged blocks, which is within parity
+    for &(seq_num, pos) in [(1, 512), (2, 1024)].iter() {
+        damage_map.add_data_block(seq_num, pos);
+        map.add_block(pos, BlockHealth::Corrupt);
+    }
+    // block set 1 has three damaged blocks, which is not
+    for &(seq_num, pos) in [(13, 13 * 512), (14, 14 * 512), (15, 15 * 512)].iter() {
+        damage_map.add_data_block(seq_num, pos);
+        map.add_block(pos, BlockHealth::Corrupt);
+    }
+    damage_map.add_meta_block(0);
+    map.add_block(0, BlockHealth::Corrupt);
+
+    map.mark_repairable(&damage_map);
+
+    assert_eq!(Some(BlockHealth::Repairable), map.cells()[0].health());
+    assert_eq!(Some(BlockHealth::Repairable), map.cells()[1].health());
+    assert_eq!(Some(BlockHealth::Repairable), map.cells()[2].health());
+    assert_eq!(Some(BlockHealth::Corrupt), map.cells()[13].health());
+    assert_eq!(Some(BlockHealth::Corrupt), map.cells()[15].health());
+    assert_eq!(None, map.cells()[16].health());
+}
+
+#[test]
+fn test_mark_repairable_skips_non_rs() {
+    let mut damage_map = DamageMap::new(&[0; 6], Version::V1, None);
+    let mut map = HealthMap::new(0, 512, 4, Some(1));
+
+    damage_map.add_data_block(1, 512);
+    map.add_block(512, BlockHealth::Corrupt);
+
+    map.mark_repairable(&damage_map);
+
+    assert_eq!(Some(BlockHealth::Corrupt), map.cells()[1].health());
+}
+
+#[test]
+fn test_path_to_image_format() {
+    assert_eq!(Some(ImageFormat::Svg), path_to_image_format("map.svg"));
+    assert_eq!(Some(ImageFormat::Png), path_to_image_format("MAP.PNG"));
+    assert_eq!(None, path_to_image_format("map.txt"));
+}
+
+#[test]
+fn test_encode_png_structure() {
+    let png = encode_png(2, 1, &[0xFF, 0, 0, 0, 0xFF, 0]);
+
+    assert_eq!(b"\x89PNG\r\n\x1a\n", &png[0..8]);
+    assert_eq!(b"IHDR", &png[12..16]);
+    // width and height
+    assert_eq!(&[0, 0, 0, 2, 0, 0, 0, 1], &png[16..24]);
+    // CRC of IHDR chunk
+    assert_eq!(&[0x7B, 0x40, 0xE8, 0xDD], &png[29..33]);
+    assert_eq!(b"IEND", &png[png.len() - 8..png.len() - 4]);
+    assert_eq!(&[0xAE, 0x42, 0x60, 0x82], &png[png.len() - 4..]);
+}
+
+#[test]
+fn test_svg_has_one_rect_per_cell() {
+    let mut map = HealthMap::new(0, 512, 3, Some(1));
+
+    map.add_block(0, BlockHealth::Good);
+    map.add_block(512, BlockHealth::Corrupt);
+
+    let svg = map.to_svg();
+
+    assert!(svg.starts_with("<svg "));
+    assert_eq!(3, svg.matches("<rect ").count());
+    assert!(svg.contains("#F44336"));
+}
@@ -0,0 +1,295 @@
+use crate::crc_ccitt::crc_ccitt_generic;
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use std::io::SeekFrom;
+
+const JOURNAL_SIGNATURE: &[u8; 8] = b"BLKARJNL";
+const JOURNAL_VERSION: u8 = 1;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalHeader {
+    pub container: String,
+    pub container_size: u64,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JournalEntry {
+    pub pos: u64,
+    pub new_len: u32,
+    pub new_crc: u16,
+    pub orig_bytes: Vec<u8>,
+}
+
+#[derive(Clone, Debug)]
+pub struct JournalContent {
+    pub header: JournalHeader,
+    pub entries: Vec<JournalEntry>,
+    pub incomplete_last_entry: bool,
+    // length up to the end of the last complete entry
+    pub complete_len: u64,
+}
+
+pub struct Journal {
+    writer: FileWriter,
+}
+
+mod parsers {
+    use super::JOURNAL_SIGNATURE;
+    use nom::number::complete::{be_u16, be_u32, be_u64, be_u8};
+
+    type EntryParseResult<'a> = (u64, u32, u32, u16, u16, &'a [u8]);
+
+    named!(pub header_p <(u8, u64, &[u8])>,
+           do_parse!(
+               _sig : tag!(JOURNAL_SIGNATURE) >>
+                   ver            : be_u8 >>
+                   container_size : be_u64 >>
+                   path_len       : be_u32 >>
+                   path           : take!(path_len) >>
+                   ((ver, container_size, path))
+           )
+    );
+
+    named!(pub entry_p <EntryParseResult>,
+           do_parse!(
+               pos      : be_u64 >>
+                   orig_len : be_u32 >>
+                   new_len  : be_u32 >>
+                   new_crc  : be_u16 >>
+                   crc      : be_u16 >>
+                   orig     : take!(orig_len) >>
+                   ((pos, orig_len, new_len, new_crc, crc, orig))
+           )
+    );
+}
+
+pub fn calc_crc(bytes: &[u8]) -> u16 {
+    crc_ccitt_generic(0xFFFF, bytes)
+}
+
+pub fn canonical_path(path: &str) -> String {
+    match std::fs::canonicalize(path) {
+        Ok(x) => x.to_string_lossy().to_string(),
+        Err(_) => String::from(path),
+    }
+}
+
+impl JournalHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(100);
+
+        bytes.extend_from_slice(JOURNAL_SIGNATURE);
+        bytes.push(JOURNAL_VERSION);
+        bytes.extend_from_slice(&self.container_size.to_be_bytes());
+        bytes.extend_from_slice(&(self.container.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(self.container.as_bytes());
+
+        bytes
+    }
+}
+
+impl JournalEntry {
+    fn fixed_fields_to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(18);
+
+        bytes.extend_from_slice(&self.pos.to_be_bytes());
+        bytes.extend_from_slice(&(self.orig_bytes.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.new_len.to_be_bytes());
+        bytes.extend_from_slice(&self.new_crc.to_be_bytes());
+
+        bytes
+    }
+
+    // covers the offset and lengths as well as the original bytes
+    pub fn calc_crc(&self) -> u16 {
+        let fixed = self.fixed_fields_to_bytes();
+
+        crc_ccitt_generic(calc_crc(&fixed), &self.orig_bytes)
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.fixed_fields_to_bytes();
+
+        bytes.extend_from_slice(&self.calc_crc().to_be_bytes());
+        bytes.extend_from_slice(&self.orig_bytes);
+
+        bytes
+    }
+}
+
+impl JournalContent {
+    pub fn read_from_file(path: &str) -> Result<JournalContent, Error> {
+        let file_size = file_utils::get_file_size(path)?;
+
+        let mut reader = FileReader::new(
+            path,
+            FileReaderParam {
+                write: false,
+                buffered: false,
+            },
+        )?;
+
+        let mut buffer = vec![0; file_size as usize];
+        let _len_read = reader.read(&mut buffer)?;
+
+        JournalContent::parse(&buffer, path)
+    }
+
+    pub fn parse(input: &[u8], path: &str) -> Result<JournalContent, Error> {
+        let parse_err = || Error::with_msg(&format!("Failed to parse journal \"{}\"", path));
+
+        let (mut rest, header) = match parsers::header_p(input) {
+            Ok((rest, (ver, container_size, container))) => {
+                if ver != JOURNAL_VERSION {
+                    return Err(parse_err());
+                }
+
+                match std::str::from_utf8(container) {
+                    Ok(container) => (
+                        rest,
+                        JournalHeader {
+                            container: String::from(container),
+                            container_size,
+                        },
+                    ),
+                    Err(_) => return Err(parse_err()),
+                }
+            }
+            Err(_) => return Err(parse_err()),
+        };
+
+        let mut entries = Vec::new();
+        let mut incomplete_last_entry = false;
+
+        while !rest.is_empty() {
+            match parsers::entry_p(rest) {
+                Ok((r, (pos, _, new_len, new_crc, crc, orig))) => {
+                    let entry = JournalEntry {
+                        pos,
+                        new_len,
+                        new_crc,
+                        orig_bytes: orig.to_vec(),
+                    };
+
+                    if entry.calc_crc() != crc {
+                        return Err(Error::with_msg(&format!(
+                            "Journal \"{}\" is corrupted at entry {}",
+                            path,
+                            entries.len()
+                        )));
+                    }
+
+                    entries.push(entry);
+                    rest = r;
+                }
+                Err(_) => {
+                    // an entry is only partially written if the operation was
+                    // interrupted before the corresponding block was overwritten
+                    incomplete_last_entry = true;
+                    break;
+                }
+            }
+        }
+
+        Ok(JournalContent {
+            header,
+            entries,
+            incomplete_last_entry,
+            complete_len: (input.len() - rest.len()) as u64,
+        })
+    }
+}
+
+impl Journal {
+    pub fn new(path: &str, container: &str) -> Result<Journal, Error> {
+        let container = canonical_path(container);
+
+        // continue an existing journal so rolling back restores the state
+        // prior to the first run
+        let append = file_utils::check_if_file_exists(path)
+            && file_utils::get_file_size(path)? > 0;
+
+        let content = if append {
+            let content = JournalContent::read_from_file(path)?;
+
+            if content.header.container != container {
+                return Err(Error::with_msg(&format!(
+                    "Journal \"{}\" was made for container \"{}\"",
+                    path, content.header.container
+                )));
+            }
+
+            Some(content)
+        } else {
+            None
+        };
+
+        let mut writer = FileWriter::new(
+            path,
+            FileWriterParam {
+                read: false,
+                append,
+                truncate: !append,
+                buffered: false,
+            },
+        )?;
+
+        match content {
+            None => {
+                let header = JournalHeader {
+                    container_size: file_utils::get_file_size(&container)?,
+                    container,
+                };
+
+                writer.write(&header.to_bytes())?;
+                writer.sync()?;
+            }
+            // drop the partially written entry, otherwise the entries
+            // appended after it could not be parsed
+            Some(ref content) if content.incomplete_last_entry => {
+                writer.set_len(content.complete_len)?;
+                writer.sync()?;
+            }
+            Some(_) => {}
+        }
+
+        Ok(Journal { writer })
+    }
+
+    pub fn record(&mut self, file: &mut FileReader, pos: u64, new_buf: &[u8]) -> Result<(), Error> {
+        let mut orig_buf = vec![0; new_buf.len()];
+
+        file.seek(SeekFrom::Start(pos))?;
+        let read_res = file.read(&mut orig_buf)?;
+        orig_buf.truncate(read_res.len_read);
+
+        let entry = JournalEntry {
+            pos,
+            new_len: new_buf.len() as u32,
+            new_crc: calc_crc(new_buf),
+            orig_bytes: orig_buf,
+        };
+
+        // the entry must be on disk before the container is touched
+        self.writer.write(&entry.to_bytes())?;
+        self.writer.sync()
+    }
+}
+
+pub fn write_with_journal(
+    journal: &mut Option<Journal>,
+    file: &mut FileReader,
+    pos: u64,
+    buf: &[u8],
+) -> Result<(), Error> {
+    if let Some(ref mut journal) = journal {
+        journal.record(file, pos, buf)?;
+    }
+
+    file.seek(SeekFrom::Start(pos))?;
+    file.write(buf)?;
+
+    Ok(())
+}
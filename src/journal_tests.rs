@@ -0,0 +1,91 @@
+#![cfg(test)]
+
+use crate::journal::*;
+
+fn make_journal(entries: &[JournalEntry]) -> Vec<u8> {
+    let header = JournalHeader {
+        container: String::from("/tmp/test.sbx"),
+        container_size: 4096,
+    };
+
+    let mut bytes = header.to_bytes();
+    for entry in entries.iter() {
+        bytes.extend_from_slice(&entry.to_bytes());
+    }
+
+    bytes
+}
+
+fn make_entry(pos: u64, orig: &[u8], new: &[u8]) -> JournalEntry {
+    JournalEntry {
+        pos,
+        new_len: new.len() as u32,
+        new_crc: calc_crc(new),
+        orig_bytes: orig.to_vec(),
+    }
+}
+
+#[test]
+fn test_parse_simple_cases() {
+    let entries = [
+        make_entry(0, &[1, 2, 3, 4], &[5, 6, 7, 8]),
+        make_entry(512, &[], &[1, 1]),
+    ];
+
+    let content = JournalContent::parse(&make_journal(&entries), "test").unwrap();
+
+    assert_eq!("/tmp/test.sbx", content.header.container);
+    assert_eq!(4096, content.header.container_size);
+    assert_eq!(&entries[..], &content.entries[..]);
+    assert!(!content.incomplete_last_entry);
+    assert_eq!(make_journal(&entries).len() as u64, content.complete_len);
+}
+
+#[test]
+fn test_parse_no_entries() {
+    let content = JournalContent::parse(&make_journal(&[]), "test").unwrap();
+
+    assert_eq!(0, content.entries.len());
+    assert!(!content.incomplete_last_entry);
+}
+
+#[test]
+fn test_parse_incomplete_last_entry() {
+    let entries = [
+        make_entry(0, &[1, 2, 3, 4], &[5, 6, 7, 8]),
+        make_entry(512, &[9, 9, 9, 9], &[1, 1, 1, 1]),
+    ];
+
+    let mut bytes = make_journal(&entries);
+    let len = bytes.len();
+    bytes.truncate(len - 2);
+
+    let content = JournalContent::parse(&bytes, "test").unwrap();
+
+    assert_eq!(&entries[..1], &content.entries[..]);
+    assert!(content.incomplete_last_entry);
+    assert_eq!(
+        make_journal(&entries[..1]).len() as u64,
+        content.complete_len
+    );
+}
+
+#[test]
+fn test_parse_corrupted_entry() {
+    let entries = [make_entry(0, &[1, 2, 3, 4], &[5, 6, 7, 8])];
+
+    let mut bytes = make_journal(&entries);
+    let len = bytes.len();
+    bytes[len - 1] = 0xFF;
+
+    assert!(JournalContent::parse(&bytes, "test").is_err());
+}
+
+#[test]
+fn test_parse_invalid_header() {
+    let mut bytes = make_journal(&[]);
+    bytes[0] = b'X';
+
+    assert!(JournalContent::parse(&bytes, "test").is_err());
+    assert!(JournalContent::parse(b"BLKARJNL", "test").is_err());
+}
@@ -14,3 +14,160 @@ pub fn split_key_val_pair(string: &str) -> (&str, &str) {
         strip_front_end_chars(&string[spot + 1..], " "),
     )
 }
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JSONValue {
+    Null,
+    Bool(bool),
+    Number(String),
+    String(String),
+}
+
+struct Cursor<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> Cursor<'a> {
+    fn skip_whitespace(&mut self) {
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_whitespace() {
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some((_, c)) if c == expected => Ok(()),
+            Some((i, c)) => Err(format!(
+                "Expected '{}' but found '{}' at character {}",
+                expected, c, i
+            )),
+            None => Err(format!("Expected '{}' but reached end of input", expected)),
+        }
+    }
+
+    fn parse_string(&mut self) -> Result<String, String> {
+        self.expect('"')?;
+
+        let mut res = String::new();
+        loop {
+            match self.chars.next() {
+                None => return Err(String::from("Unterminated string")),
+                Some((_, '"')) => return Ok(res),
+                Some((i, '\\')) => match self.chars.next() {
+                    Some((_, '"')) => res.push('"'),
+                    Some((_, '\\')) => res.push('\\'),
+                    Some((_, '/')) => res.push('/'),
+                    Some((_, 'b')) => res.push('\u{8}'),
+                    Some((_, 'f')) => res.push('\u{c}'),
+                    Some((_, 'n')) => res.push('\n'),
+                    Some((_, 'r')) => res.push('\r'),
+                    Some((_, 't')) => res.push('\t'),
+                    Some((_, 'u')) => {
+                        let mut hex = String::with_capacity(4);
+                        for _ in 0..4 {
+                            match self.chars.next() {
+                                Some((_, c)) => hex.push(c),
+                                None => return Err(String::from("Unterminated string")),
+                            }
+                        }
+                        match u32::from_str_radix(&hex, 16)
+                            .ok()
+                            .and_then(std::char::from_u32)
+                        {
+                            Some(c) => res.push(c),
+                            None => {
+                                return Err(format!("Invalid unicode escape at character {}", i))
+                            }
+                        }
+                    }
+                    _ => return Err(format!("Invalid escape sequence at character {}", i)),
+                },
+                Some((_, c)) => res.push(c),
+            }
+        }
+    }
+
+    fn parse_word(&mut self) -> String {
+        let mut res = String::new();
+        while let Some(&(_, c)) = self.chars.peek() {
+            if c.is_alphanumeric() || c == '-' || c == '+' || c == '.' {
+                res.push(c);
+                self.chars.next();
+            } else {
+                break;
+            }
+        }
+        res
+    }
+
+    fn parse_value(&mut self) -> Result<JSONValue, String> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some(&(_, '"')) => Ok(JSONValue::String(self.parse_string()?)),
+            Some(&(i, _)) => {
+                let word = self.parse_word();
+                match word.as_str() {
+                    "null" => Ok(JSONValue::Null),
+                    "true" => Ok(JSONValue::Bool(true)),
+                    "false" => Ok(JSONValue::Bool(false)),
+                    _ => {
+                        if word.parse::<f64>().is_ok() {
+                            Ok(JSONValue::Number(word))
+                        } else {
+                            Err(format!("Invalid value at character {}", i))
+                        }
+                    }
+                }
+            }
+            None => Err(String::from("Expected value but reached end of input")),
+        }
+    }
+}
+
+/// Parses a JSON object whose values are all scalars, keeping the order of the keys
+pub fn parse_flat_object(input: &str) -> Result<Vec<(String, JSONValue)>, String> {
+    let mut cursor = Cursor {
+        chars: input.char_indices().peekable(),
+    };
+
+    let mut res = Vec::new();
+
+    cursor.expect('{')?;
+
+    cursor.skip_whitespace();
+    if let Some(&(_, '}')) = cursor.chars.peek() {
+        cursor.chars.next();
+    } else {
+        loop {
+            let key = cursor.parse_string()?;
+            cursor.expect(':')?;
+            let value = cursor.parse_value()?;
+
+            res.push((key, value));
+
+            cursor.skip_whitespace();
+            match cursor.chars.next() {
+                Some((_, ',')) => {}
+                Some((_, '}')) => break,
+                Some((i, c)) => {
+                    return Err(format!(
+                        "Expected ',' or '}}' but found '{}' at character {}",
+                        c, i
+                    ))
+                }
+                None => return Err(String::from("Expected '}' but reached end of input")),
+            }
+        }
+    }
+
+    cursor.skip_whitespace();
+    match cursor.chars.next() {
+        None => Ok(res),
+        Some((i, _)) => Err(format!("Unexpected trailing content at character {}", i)),
+    }
+}
@@ -0,0 +1,53 @@
+#![cfg(test)]
+
+use crate::json_utils::*;
+
+#[test]
+fn test_parse_flat_object_simple_cases() {
+    assert_eq!(Ok(vec![]), parse_flat_object("{}"));
+    assert_eq!(Ok(vec![]), parse_flat_object("  { \n }  "));
+    assert_eq!(
+        Ok(vec![
+            (String::from("FNM"), JSONValue::String(String::from("abc"))),
+            (String::from("FSZ"), JSONValue::Number(String::from("1024"))),
+            (String::from("SNM"), JSONValue::Null),
+            (String::from("X"), JSONValue::Bool(true)),
+        ]),
+        parse_flat_object(r#"{"FNM": "abc", "FSZ":1024, "SNM" : null, "X": true}"#)
+    );
+}
+
+#[test]
+fn test_parse_flat_object_escapes() {
+    assert_eq!(
+        Ok(vec![(
+            String::from("a\"b"),
+            JSONValue::String(String::from("c\\d\n\u{e9}"))
+        )]),
+        parse_flat_object(r#"{"a\"b": "c\\d\né"}"#)
+    );
+}
+
+#[test]
+fn test_parse_flat_object_negative_number() {
+    assert_eq!(
+        Ok(vec![(
+            String::from("FDT"),
+            JSONValue::Number(String::from("-100"))
+        )]),
+        parse_flat_object(r#"{"FDT": -100}"#)
+    );
+}
+
+#[test]
+fn test_parse_flat_object_invalid_cases() {
+    assert!(parse_flat_object("").is_err());
+    assert!(parse_flat_object("[]").is_err());
+    assert!(parse_flat_object(r#"{"a": 1"#).is_err());
+    assert!(parse_flat_object(r#"{"a": 1,}"#).is_err());
+    assert!(parse_flat_object(r#"{"a" 1}"#).is_err());
+    assert!(parse_flat_object(r#"{"a": abc}"#).is_err());
+    assert!(parse_flat_object(r#"{"a": "b}"#).is_err());
+    assert!(parse_flat_object(r#"{"a": {"b": 1}}"#).is_err());
+    assert!(parse_flat_object(r#"{"a": 1} x"#).is_err());
+}
@@ -66,6 +66,7 @@ mod integer_utils;
 mod integer_utils_tests;
 pub mod json_printer;
 mod json_utils;
+mod json_utils_tests;
 mod misc_utils;
 mod misc_utils_tests;
 mod multihash;
@@ -80,23 +81,61 @@ mod sbx_container_content;
 pub mod sbx_specs;
 mod sbx_specs_tests;
 
+mod metadata_edit;
+mod metadata_edit_tests;
+
 mod encode_defaults;
 
 mod log;
 
+mod damage_map;
+mod damage_map_tests;
+
+mod health_map;
+mod health_map_tests;
+
+mod range_set;
+mod range_set_tests;
+
+mod bad_ranges;
+mod bad_ranges_tests;
+
+mod sparse_map;
+mod sparse_map_tests;
+
+mod journal;
+mod journal_tests;
+
+mod sort_journal;
+mod sort_journal_tests;
+
+mod protect;
+mod protect_tests;
+
+mod simulate;
+mod simulate_tests;
+
 mod rs_codec;
 
 mod data_block_buffer;
 mod rescue_buffer;
 
 mod check_core;
+mod convert_core;
 mod decode_core;
+mod diff_core;
+mod diff_core_tests;
 mod encode_core;
 mod repair_core;
 mod rescue_core;
+mod rollback_core;
+mod scan_core;
 mod show_core;
 mod sort_core;
+mod sort_in_place_core;
 mod update_core;
+mod verify_core;
+mod verify_core_tests;
 
 mod progress_report;
 
@@ -111,10 +150,15 @@ mod cli_utils;
 
 pub mod cli_calc;
 pub mod cli_check;
+pub mod cli_convert;
 pub mod cli_decode;
+pub mod cli_diff;
 pub mod cli_encode;
 pub mod cli_repair;
 pub mod cli_rescue;
+pub mod cli_rollback;
+pub mod cli_scan;
 pub mod cli_show;
 pub mod cli_sort;
 pub mod cli_update;
+pub mod cli_verify;
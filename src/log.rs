@@ -1,5 +1,6 @@
 #![allow(dead_code)]
 use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
 use crate::file_writer::{FileWriter, FileWriterParam};
 use crate::general_error::Error;
 use std::fmt;
@@ -11,8 +12,6 @@ use std::sync::Mutex;
 use std::thread;
 use std::time::Duration;
 
-const LOG_MAX_SIZE: usize = 1024;
-
 const LOG_WRITE_INTERVAL_IN_MILLISEC: u64 = 1000;
 
 pub struct LogHandler<T: 'static + Log + Send> {
@@ -59,6 +58,54 @@ pub fn to_err(e: LogError) -> super::Error {
     Error::new(ErrorKind::LogError(e))
 }
 
+mod parsers {
+    use nom::character::complete::digit1;
+    use nom::character::complete::newline;
+
+    fn is_key_char(c: u8) -> bool {
+        (c as char).is_ascii_lowercase() || c == b'_'
+    }
+
+    named!(
+        field_p<(&[u8], &[u8])>,
+        do_parse!(
+            key   : take_while1!(is_key_char) >> _e : char!('=') >>
+                value : digit1 >> _n : newline >>
+                ((key, value))
+        )
+    );
+
+    named!(pub fields_p <Vec<(&[u8], &[u8])>>,
+           many0!(field_p)
+    );
+}
+
+pub fn parse_u64_fields(input: &[u8], keys: &[&str]) -> Result<Vec<u64>, ()> {
+    let fields = match parsers::fields_p(input) {
+        Ok((_, fields)) => fields,
+        Err(_) => return Err(()),
+    };
+
+    let mut res = Vec::with_capacity(keys.len());
+
+    for key in keys.iter() {
+        let value = match fields.iter().find(|(k, _)| *k == key.as_bytes()) {
+            Some((_, v)) => v,
+            None => return Err(()),
+        };
+
+        match std::str::from_utf8(value) {
+            Ok(x) => match x.parse::<u64>() {
+                Ok(x) => res.push(x),
+                Err(_) => return Err(()),
+            },
+            Err(_) => return Err(()),
+        }
+    }
+
+    Ok(res)
+}
+
 pub trait Log {
     fn serialize(&self) -> String;
 
@@ -72,7 +119,9 @@ pub trait Log {
                 buffered: false,
             },
         )?;
-        let mut buffer: [u8; LOG_MAX_SIZE] = [0; LOG_MAX_SIZE];
+        // one extra zero byte terminates the input, as the parsers
+        // would otherwise ask for more input at the end of the log
+        let mut buffer = vec![0; reader.get_file_size()? as usize + 1];
         let _len_read = reader.read(&mut buffer)?;
 
         match self.deserialize(&buffer) {
@@ -82,20 +131,26 @@ pub trait Log {
     }
 
     fn write_to_file(&self, log_file: &str) -> Result<(), Error> {
-        let mut writer = FileWriter::new(
-            log_file,
-            FileWriterParam {
-                read: false,
-                append: false,
-                truncate: true,
-                buffered: false,
-            },
-        )?;
-        let output = self.serialize();
-
-        writer.write(output.as_bytes())?;
+        // write to a temporary file first then rename it over the log file,
+        // so an interruption midway never leaves a partially written log
+        let tmp_file = format!("{}.tmp", log_file);
+
+        {
+            let mut writer = FileWriter::new(
+                &tmp_file,
+                FileWriterParam {
+                    read: false,
+                    append: false,
+                    truncate: true,
+                    buffered: false,
+                },
+            )?;
+            let output = self.serialize();
+
+            writer.write(output.as_bytes())?;
+        }
 
-        Ok(())
+        file_utils::rename_file(&tmp_file, log_file)
     }
 }
 
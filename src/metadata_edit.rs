@@ -0,0 +1,231 @@
+use crate::general_error::Error;
+use crate::json_utils;
+use crate::json_utils::JSONValue;
+use crate::misc_utils;
+use crate::multihash;
+use crate::sbx_block;
+use crate::sbx_block::{Metadata, MetadataID};
+use crate::sbx_specs::{ver_to_max_data_file_size, ver_to_usize, ver_uses_rs, Version};
+use chrono::NaiveDateTime;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum MetadataEdit {
+    Set(Metadata),
+    Remove(MetadataID),
+}
+
+pub fn edit_to_meta_id(edit: &MetadataEdit) -> MetadataID {
+    match edit {
+        MetadataEdit::Set(m) => sbx_block::meta_to_meta_id(m),
+        MetadataEdit::Remove(id) => *id,
+    }
+}
+
+fn parse_id(string: &str) -> Result<MetadataID, Error> {
+    match sbx_block::str_to_meta_id(string) {
+        None => Err(Error::with_msg(&format!(
+            "Unrecognised metadata field \"{}\"",
+            string
+        ))),
+        // SPR and TRL describe how the container was encoded,
+        // so they are only managed by blkar itself
+        Some(MetadataID::SPR) | Some(MetadataID::TRL) => Err(Error::with_msg(&format!(
+            "Metadata field {} cannot be edited",
+            string.to_uppercase()
+        ))),
+        Some(id) => Ok(id),
+    }
+}
+
+fn parse_int<T: FromStr>(id: MetadataID, value: &str) -> Result<T, Error> {
+    match T::from_str(value) {
+        Ok(x) => Ok(x),
+        Err(_) => Err(Error::with_msg(&format!(
+            "Failed to parse \"{}\" as value of {}",
+            value,
+            sbx_block::meta_id_to_str(id)
+        ))),
+    }
+}
+
+fn parse_date_time(id: MetadataID, value: &str) -> Result<i64, Error> {
+    match NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S") {
+        Ok(x) => Ok(x.timestamp()),
+        Err(_) => parse_int(id, value),
+    }
+}
+
+fn parse_hash(value: &str) -> Result<multihash::HashBytes, Error> {
+    let invalid = || {
+        Error::with_msg(&format!(
+            "Failed to parse \"{}\" as value of HSH, expected HASH-TYPE:HEX",
+            value
+        ))
+    };
+
+    let mut parts = value.splitn(2, ':');
+
+    let hash_type = match parts.next() {
+        None => return Err(invalid()),
+        Some(x) => match multihash::string_to_hash_type(x.trim()) {
+            Ok(x) => x,
+            Err(()) => return Err(Error::with_msg(&format!("Invalid hash type \"{}\"", x))),
+        },
+    };
+
+    let digest = match parts.next() {
+        None => return Err(invalid()),
+        Some(x) => match misc_utils::hex_string_to_bytes(x.trim()) {
+            Ok(x) => x,
+            Err(_) => return Err(invalid()),
+        },
+    };
+
+    let digest_len = multihash::specs::Param::new(hash_type).digest_length as usize;
+    if digest.len() != digest_len {
+        return Err(Error::with_msg(&format!(
+            "Hash digest of {} must be {} bytes long",
+            multihash::hash_type_to_string(hash_type),
+            digest_len
+        )));
+    }
+
+    Ok((hash_type, digest))
+}
+
+fn parse_rs_shards(id: MetadataID, value: &str) -> Result<u8, Error> {
+    let x: u8 = parse_int(id, value)?;
+    if x == 0 {
+        Err(Error::with_msg(&format!(
+            "{} must be at least 1",
+            sbx_block::meta_id_to_str(id)
+        )))
+    } else {
+        Ok(x)
+    }
+}
+
+pub fn parse_value(id: MetadataID, value: &str) -> Result<Metadata, Error> {
+    use self::MetadataID::*;
+
+    Ok(match id {
+        FNM => Metadata::FNM(String::from(value)),
+        SNM => Metadata::SNM(String::from(value)),
+        FSZ => Metadata::FSZ(parse_int(id, value)?),
+        FDT => Metadata::FDT(parse_date_time(id, value)?),
+        SDT => Metadata::SDT(parse_date_time(id, value)?),
+        HSH => Metadata::HSH(parse_hash(value)?),
+        RSD => Metadata::RSD(parse_rs_shards(id, value)?),
+        RSP => Metadata::RSP(parse_rs_shards(id, value)?),
+        SPR | TRL => unreachable!(),
+    })
+}
+
+/// Parses an assignment of the form `ID=VALUE`
+pub fn parse_assignment(string: &str) -> Result<MetadataEdit, Error> {
+    let mut parts = string.splitn(2, '=');
+
+    let id = parse_id(parts.next().unwrap().trim())?;
+
+    match parts.next() {
+        None => Err(Error::with_msg(&format!(
+            "Failed to parse \"{}\", expected ID=VALUE",
+            string
+        ))),
+        Some(value) => Ok(MetadataEdit::Set(parse_value(id, value)?)),
+    }
+}
+
+/// Parses a JSON object mapping field IDs to values, where `null` removes the field
+pub fn parse_json(input: &str) -> Result<Vec<MetadataEdit>, Error> {
+    let fields = match json_utils::parse_flat_object(input) {
+        Ok(x) => x,
+        Err(e) => return Err(Error::with_msg(&format!("Failed to parse JSON : {}", e))),
+    };
+
+    let mut res = Vec::with_capacity(fields.len());
+
+    for (key, value) in fields.into_iter() {
+        let id = parse_id(&key)?;
+
+        let edit = match value {
+            JSONValue::Null => MetadataEdit::Remove(id),
+            JSONValue::String(x) | JSONValue::Number(x) => MetadataEdit::Set(parse_value(id, &x)?),
+            JSONValue::Bool(_) => {
+                return Err(Error::with_msg(&format!(
+                    "Value of {} cannot be a boolean",
+                    sbx_block::meta_id_to_str(id)
+                )))
+            }
+        };
+
+        res.push(edit);
+    }
+
+    Ok(res)
+}
+
+/// Checks the edits against the constraints of the container's version and layout
+pub fn check_edits(
+    version: Version,
+    data_par_burst: Option<(usize, usize, usize)>,
+    edits: &[MetadataEdit],
+) -> Result<(), Error> {
+    for edit in edits.iter() {
+        match edit {
+            MetadataEdit::Set(Metadata::RSD(_))
+            | MetadataEdit::Set(Metadata::RSP(_))
+            | MetadataEdit::Remove(MetadataID::RSD)
+            | MetadataEdit::Remove(MetadataID::RSP) => {
+                if !ver_uses_rs(version) {
+                    return Err(Error::with_msg(&format!(
+                        "Metadata field {} is not used by version {}",
+                        sbx_block::meta_id_to_str(edit_to_meta_id(edit)),
+                        ver_to_usize(version)
+                    )));
+                }
+
+                if let MetadataEdit::Remove(id) = edit {
+                    return Err(Error::with_msg(&format!(
+                        "Metadata field {} is required by version {}",
+                        sbx_block::meta_id_to_str(*id),
+                        ver_to_usize(version)
+                    )));
+                }
+            }
+            MetadataEdit::Set(Metadata::FSZ(x)) => {
+                let max = ver_to_max_data_file_size(version, data_par_burst);
+                if *x > max {
+                    return Err(Error::with_msg(&format!(
+                        "FSZ of {} exceeds the maximum file size of {} for the container",
+                        x, max
+                    )));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if ver_uses_rs(version) {
+        let (data, parity, _) = data_par_burst.unwrap();
+
+        let mut data = data;
+        let mut parity = parity;
+        for edit in edits.iter() {
+            match edit {
+                MetadataEdit::Set(Metadata::RSD(x)) => data = *x as usize,
+                MetadataEdit::Set(Metadata::RSP(x)) => parity = *x as usize,
+                _ => {}
+            }
+        }
+
+        if data + parity > 256 {
+            return Err(Error::with_msg(
+                "Too many shards for Reed-Solomon erasure code, RSD + RSP must be at most 256",
+            ));
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+#![cfg(test)]
+
+use crate::metadata_edit::*;
+use crate::multihash::HashType;
+use crate::sbx_block::{Metadata, MetadataID};
+use crate::sbx_specs::Version;
+
+#[test]
+fn test_parse_assignment_simple_cases() {
+    assert_eq!(
+        MetadataEdit::Set(Metadata::FNM(String::from("a=b.txt"))),
+        parse_assignment("FNM=a=b.txt").unwrap()
+    );
+    assert_eq!(
+        MetadataEdit::Set(Metadata::SNM(String::from(""))),
+        parse_assignment("snm=").unwrap()
+    );
+    assert_eq!(
+        MetadataEdit::Set(Metadata::FSZ(1024)),
+        parse_assignment("FSZ=1024").unwrap()
+    );
+    assert_eq!(
+        MetadataEdit::Set(Metadata::FDT(-5)),
+        parse_assignment("FDT=-5").unwrap()
+    );
+    assert_eq!(
+        MetadataEdit::Set(Metadata::RSD(10)),
+        parse_assignment("RSD=10").unwrap()
+    );
+}
+
+#[test]
+fn test_parse_assignment_date_time() {
+    assert_eq!(
+        MetadataEdit::Set(Metadata::SDT(1500000000)),
+        parse_assignment("SDT=2017-07-14 02:40:00").unwrap()
+    );
+}
+
+#[test]
+fn test_parse_assignment_hash() {
+    assert_eq!(
+        MetadataEdit::Set(Metadata::HSH((
+            HashType::SHA1,
+            vec![0xAB; 20].into_boxed_slice()
+        ))),
+        parse_assignment(&format!("HSH=sha1:{}", "ab".repeat(20))).unwrap()
+    );
+
+    assert!(parse_assignment(&format!("HSH=sha1:{}", "ab".repeat(19))).is_err());
+    assert!(parse_assignment("HSH=sha1").is_err());
+    assert!(parse_assignment("HSH=abc:00").is_err());
+}
+
+#[test]
+fn test_parse_assignment_invalid_cases() {
+    assert!(parse_assignment("FNM").is_err());
+    assert!(parse_assignment("ABC=1").is_err());
+    assert!(parse_assignment("FSZ=-1").is_err());
+    assert!(parse_assignment("FSZ=abc").is_err());
+    assert!(parse_assignment("RSD=0").is_err());
+    assert!(parse_assignment("RSP=256").is_err());
+    assert!(parse_assignment("SPR=1").is_err());
+    assert!(parse_assignment("TRL=1").is_err());
+}
+
+#[test]
+fn test_parse_json() {
+    assert_eq!(
+        vec![
+            MetadataEdit::Set(Metadata::FNM(String::from("a.txt"))),
+            MetadataEdit::Set(Metadata::FDT(1500000000)),
+            MetadataEdit::Set(Metadata::FSZ(10)),
+            MetadataEdit::Remove(MetadataID::SNM),
+        ],
+        parse_json(r#"{"FNM": "a.txt", "FDT": 1500000000, "FSZ": "10", "SNM": null}"#).unwrap()
+    );
+
+    assert!(parse_json(r#"{"FNM": true}"#).is_err());
+    assert!(parse_json(r#"{"TRL": null}"#).is_err());
+    assert!(parse_json(r#"{"FSZ": 1.5}"#).is_err());
+    assert!(parse_json(r#"["FNM"]"#).is_err());
+}
+
+#[test]
+fn test_check_edits_rs_fields() {
+    let rs_edits = [MetadataEdit::Set(Metadata::RSD(20))];
+
+    assert!(check_edits(Version::V1, None, &rs_edits).is_err());
+    assert!(check_edits(Version::V17, Some((10, 2, 1)), &rs_edits).is_ok());
+
+    assert!(check_edits(
+        Version::V17,
+        Some((10, 2, 1)),
+        &[MetadataEdit::Remove(MetadataID::RSP)]
+    )
+    .is_err());
+
+    assert!(check_edits(
+        Version::V18,
+        Some((10, 2, 1)),
+        &[
+            MetadataEdit::Set(Metadata::RSD(200)),
+            MetadataEdit::Set(Metadata::RSP(57))
+        ]
+    )
+    .is_err());
+    assert!(check_edits(
+        Version::V18,
+        Some((10, 2, 1)),
+        &[
+            MetadataEdit::Set(Metadata::RSD(200)),
+            MetadataEdit::Set(Metadata::RSP(56))
+        ]
+    )
+    .is_ok());
+}
+
+#[test]
+fn test_check_edits_file_size() {
+    assert!(check_edits(
+        Version::V1,
+        None,
+        &[MetadataEdit::Set(Metadata::FSZ(1 << 40))]
+    )
+    .is_ok());
+    assert!(check_edits(
+        Version::V1,
+        None,
+        &[MetadataEdit::Set(Metadata::FSZ(u64::max_value()))]
+    )
+    .is_err());
+}
@@ -210,7 +210,7 @@ macro_rules! get_data_par_burst {
         }
     }};
     (
-        $param:expr, $ref_block_pos:expr, $ref_block:expr, $purpose:expr
+        in_file => $in_file:expr, $param:expr, $ref_block_pos:expr, $ref_block:expr, $purpose:expr
     ) => {{
         use crate::sbx_specs::ver_uses_rs;
 
@@ -218,12 +218,17 @@ macro_rules! get_data_par_burst {
             Some((
                 get_RSD_from_ref_block!($ref_block_pos, $ref_block, $purpose),
                 get_RSP_from_ref_block!($ref_block_pos, $ref_block, $purpose),
-                get_burst_or_guess!($param, $ref_block_pos, $ref_block),
+                get_burst_or_guess!(in_file => $in_file, $param, $ref_block_pos, $ref_block),
             ))
         } else {
             None
         }
     }};
+    (
+        $param:expr, $ref_block_pos:expr, $ref_block:expr, $purpose:expr
+    ) => {{
+        get_data_par_burst!(in_file => $param.in_file, $param, $ref_block_pos, $ref_block, $purpose)
+    }};
 }
 
 macro_rules! return_if_not_ver_uses_rs {
@@ -276,14 +281,24 @@ macro_rules! get_burst_or_guess {
         get_burst_or_guess!($param, None, false, $ref_block_pos, $ref_block)
     }};
     (
-        $param:expr, $ref_block_pos:expr, $ref_block:expr
+        in_file => $in_file:expr, $param:expr, $ref_block_pos:expr, $ref_block:expr
     ) => {{
         let from_pos = get_guess_burst_from_pos_from_param!($param);
 
-        get_burst_or_guess!($param, from_pos, $param.force_misalign, $ref_block_pos, $ref_block)
+        get_burst_or_guess!(in_file => $in_file, $param, from_pos, $param.force_misalign, $ref_block_pos, $ref_block)
+    }};
+    (
+        $param:expr, $ref_block_pos:expr, $ref_block:expr
+    ) => {{
+        get_burst_or_guess!(in_file => $param.in_file, $param, $ref_block_pos, $ref_block)
     }};
     (
         $param:expr, $from_pos:expr, $force_misalign:expr, $ref_block_pos:expr, $ref_block:expr
+    ) => {{
+        get_burst_or_guess!(in_file => $param.in_file, $param, $from_pos, $force_misalign, $ref_block_pos, $ref_block)
+    }};
+    (
+        in_file => $in_file:expr, $param:expr, $from_pos:expr, $force_misalign:expr, $ref_block_pos:expr, $ref_block:expr
     ) => {{
         use crate::sbx_specs::*;
         use crate::block_utils;
@@ -294,7 +309,7 @@ macro_rules! get_burst_or_guess {
                                                            $ref_block,
                                                            "guess burst error resistance level");
 
-                                   unwrap_or!(block_utils::guess_burst_err_resistance_level(&$param.in_file,
+                                   unwrap_or!(block_utils::guess_burst_err_resistance_level(&$in_file,
                                                                                             $from_pos,
                                                                                             $force_misalign,
                                                                                             $ref_block_pos,
@@ -548,43 +563,6 @@ macro_rules! shadow_to_avoid_use {
     };
 }
 
-macro_rules! break_if_last {
-    (
-        internal => $cur:expr, $last:expr
-    ) => {{
-        if $cur == $last {
-            break;
-        }
-    }};
-    (
-        seq_num => $cur:expr
-    ) => {{
-        use crate::sbx_specs::SBX_LAST_SEQ_NUM;
-
-        break_if_last!(internal => $cur, SBX_LAST_SEQ_NUM);
-    }};
-    (
-        block_index => $cur:expr
-    ) => {{
-        break_if_last!(internal => $cur, std::u64::MAX);
-    }};
-}
-
-macro_rules! incre_or_break_if_last {
-    (
-        seq_num => $cur:expr
-    ) => {{
-        break_if_last!(seq_num => $cur);
-        $cur += 1;
-    }};
-    (
-        block_index => $cur:expr
-    ) => {{
-        break_if_last!(block_index => $cur);
-        $cur += 1;
-    }};
-}
-
 macro_rules! stop_run_if_last {
     (
         internal => $run:expr => $cur:expr, $last:expr
@@ -0,0 +1,297 @@
+use crate::file_utils::from_orig_file_size::{calc_container_size, calc_data_chunk_count};
+use crate::integer_utils::IntegerUtils;
+use crate::sbx_specs::{ver_to_block_size, Version, SBX_MAX_BURST_ERR_RESISTANCE};
+use std::str::FromStr;
+
+// highest accepted chance of losing any block set under the random loss model
+pub const RANDOM_LOSS_MAX_FAILURE_PROB: f64 = 1e-6;
+
+// limit of the Reed-Solomon codec over GF(2^8)
+const MAX_TOTAL_SHARD_COUNT: usize = 256;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Goals {
+    pub burst_loss: Option<u64>,
+    pub random_loss_percent: Option<f64>,
+    pub max_overhead_percent: Option<f64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Solution {
+    pub version: Version,
+    pub data_par_burst: (usize, usize, usize),
+    pub container_size: u64,
+    pub overhead_percent: f64,
+    pub max_burst_loss: u64,
+    pub random_loss_failure_prob: Option<f64>,
+}
+
+pub fn parse_size(string: &str) -> Result<u64, ()> {
+    let upper = string.to_uppercase();
+
+    let digits = upper
+        .trim_end_matches("IB")
+        .trim_end_matches('B')
+        .trim_end_matches(['K', 'M', 'G']);
+
+    let multiplier = match &upper[digits.len()..] {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1 << 10,
+        "M" | "MB" | "MIB" => 1 << 20,
+        "G" | "GB" | "GIB" => 1 << 30,
+        _ => return Err(()),
+    };
+
+    match u64::from_str(digits) {
+        Ok(x) => x.checked_mul(multiplier).ok_or(()),
+        Err(_) => Err(()),
+    }
+}
+
+pub fn parse_percent(string: &str, max: f64) -> Result<f64, ()> {
+    match f64::from_str(string.trim_end_matches('%')) {
+        Ok(x) if x > 0. && x < max => Ok(x),
+        Ok(_) | Err(_) => Err(()),
+    }
+}
+
+pub fn string_to_goals(string: &str) -> Result<Goals, ()> {
+    let mut goals = Goals::default();
+
+    for goal in string.split(',') {
+        let mut split = goal.trim().splitn(2, '=');
+
+        let key = split.next().unwrap().trim().to_lowercase();
+        let value = match split.next() {
+            None => return Err(()),
+            Some(x) => x.trim(),
+        };
+
+        match key.as_str() {
+            "burst" => goals.burst_loss = Some(parse_size(value)?),
+            "random" => goals.random_loss_percent = Some(parse_percent(value, 100.)?),
+            "overhead" => goals.max_overhead_percent = Some(parse_percent(value, f64::INFINITY)?),
+            _ => return Err(()),
+        }
+    }
+
+    Ok(goals)
+}
+
+// a contiguous loss of the given number of bytes touches at most this many
+// blocks, as the loss is not necessarily aligned to block boundaries
+pub fn calc_burst_loss_block_count(version: Version, loss: u64) -> u64 {
+    let block_size = ver_to_block_size(version) as u64;
+
+    if loss == 0 {
+        0
+    } else {
+        u64::round_up_to_multiple(loss - 1, block_size) / block_size + 1
+    }
+}
+
+// consecutive blocks belong to different block sets when burst error
+// resistance level is above 0, so each block set loses at most
+// ceil(loss block count / burst) blocks
+pub fn calc_max_burst_loss(version: Version, parity: usize, burst: usize) -> u64 {
+    let block_size = ver_to_block_size(version) as u64;
+
+    let block_count = (parity * std::cmp::max(burst, 1)) as u64;
+
+    (block_count - 1) * block_size + 1
+}
+
+// each block is assumed to be lost independently with the given
+// probability, a block set is lost when more blocks than its parity
+// shard count are lost
+pub fn calc_random_loss_failure_prob(
+    data: usize,
+    parity: usize,
+    block_set_count: u64,
+    loss_percent: f64,
+) -> f64 {
+    let total = data + parity;
+    let loss_rate = loss_percent / 100.;
+
+    // binomial distribution tail, accumulated in log space to avoid underflow
+    let mut ln_prob = total as f64 * (-loss_rate).ln_1p();
+    let ln_ratio = loss_rate.ln() - (-loss_rate).ln_1p();
+    let mut set_failure_prob = 0.;
+
+    for lost in 1..=total {
+        ln_prob += ((total - lost + 1) as f64 / lost as f64).ln() + ln_ratio;
+
+        if lost > parity {
+            set_failure_prob += ln_prob.exp();
+        }
+    }
+
+    let set_failure_prob: f64 = set_failure_prob.min(1.);
+
+    -(block_set_count as f64 * (-set_failure_prob).ln_1p()).exp_m1()
+}
+
+fn calc_overhead_percent(container_size: u64, file_size: u64) -> f64 {
+    (container_size - file_size) as f64 * 100. / file_size as f64
+}
+
+struct Candidate {
+    version: Version,
+    data_par_burst: (usize, usize, usize),
+    min_container_size: u64,
+    random_loss_failure_prob: Option<f64>,
+}
+
+// when loss goals are specified, the smallest container satisfying all goals is
+// picked, otherwise the highest parity to data ratio within the overhead limit
+// is picked, ties are broken by smaller container then smaller block sets
+pub fn solve(
+    versions: &[Version],
+    goals: &Goals,
+    file_size: u64,
+    max_burst: usize,
+) -> Option<Solution> {
+    assert!(file_size > 0);
+
+    let max_burst = std::cmp::min(max_burst, SBX_MAX_BURST_ERR_RESISTANCE);
+
+    let loss_goals_specified = goals.burst_loss.is_some() || goals.random_loss_percent.is_some();
+
+    let mut candidates = Vec::new();
+
+    for data in 1..MAX_TOTAL_SHARD_COUNT {
+        for parity in 1..=MAX_TOTAL_SHARD_COUNT - data {
+            for &version in versions.iter() {
+                let burst = match goals.burst_loss {
+                    None => 0,
+                    Some(loss) => {
+                        let block_count = calc_burst_loss_block_count(version, loss);
+
+                        if block_count <= parity as u64 {
+                            0
+                        } else {
+                            let burst = u64::round_up_to_multiple(block_count, parity as u64)
+                                / parity as u64;
+
+                            if burst > max_burst as u64 {
+                                continue;
+                            }
+
+                            burst as usize
+                        }
+                    }
+                };
+
+                let random_loss_failure_prob = match goals.random_loss_percent {
+                    None => None,
+                    Some(loss_percent) => {
+                        let block_set_count = u64::round_up_to_multiple(
+                            calc_data_chunk_count(version, file_size),
+                            data as u64,
+                        ) / data as u64;
+
+                        let prob = calc_random_loss_failure_prob(
+                            data,
+                            parity,
+                            block_set_count,
+                            loss_percent,
+                        );
+
+                        if prob > RANDOM_LOSS_MAX_FAILURE_PROB {
+                            continue;
+                        }
+
+                        Some(prob)
+                    }
+                };
+
+                // burst gaps only ever add to the container size
+                let min_container_size =
+                    calc_container_size(version, Some(true), Some((data, parity, 0)), file_size);
+
+                if let Some(max_overhead_percent) = goals.max_overhead_percent {
+                    if calc_overhead_percent(min_container_size, file_size) > max_overhead_percent {
+                        continue;
+                    }
+                }
+
+                candidates.push(Candidate {
+                    version,
+                    data_par_burst: (data, parity, burst),
+                    min_container_size,
+                    random_loss_failure_prob,
+                });
+            }
+        }
+    }
+
+    let to_solution = |c: &Candidate, container_size: u64| {
+        let (_, parity, burst) = c.data_par_burst;
+
+        Solution {
+            version: c.version,
+            data_par_burst: c.data_par_burst,
+            container_size,
+            overhead_percent: calc_overhead_percent(container_size, file_size),
+            max_burst_loss: calc_max_burst_loss(c.version, parity, burst),
+            random_loss_failure_prob: c.random_loss_failure_prob,
+        }
+    };
+
+    // without loss goals the burst level is always 0, so the lower bound is
+    // exact, and the highest parity to data ratio is preferred
+    if !loss_goals_specified {
+        return candidates
+            .iter()
+            .min_by(|a, b| {
+                let (a_data, a_parity, _) = a.data_par_burst;
+                let (b_data, b_parity, _) = b.data_par_burst;
+
+                (b_parity * a_data)
+                    .cmp(&(a_parity * b_data))
+                    .then(a.min_container_size.cmp(&b.min_container_size))
+                    .then((a_data + a_parity).cmp(&(b_data + b_parity)))
+            })
+            .map(|c| to_solution(c, c.min_container_size));
+    }
+
+    // exact container size with burst gaps is expensive to calculate, so
+    // candidates are visited in order of their lower bound
+    candidates.sort_by_key(|c| {
+        let (data, parity, burst) = c.data_par_burst;
+        (c.min_container_size, burst, data + parity)
+    });
+
+    let mut best: Option<(Solution, (u64, usize, usize))> = None;
+
+    for c in candidates.iter() {
+        if let Some((ref best, _)) = best {
+            if c.min_container_size > best.container_size {
+                break;
+            }
+        }
+
+        let container_size =
+            calc_container_size(c.version, Some(true), Some(c.data_par_burst), file_size);
+
+        if let Some(max_overhead_percent) = goals.max_overhead_percent {
+            if calc_overhead_percent(container_size, file_size) > max_overhead_percent {
+                continue;
+            }
+        }
+
+        let (data, parity, burst) = c.data_par_burst;
+        let key = (container_size, burst, data + parity);
+
+        let better = match best {
+            None => true,
+            Some((_, ref best_key)) => key < *best_key,
+        };
+
+        if better {
+            best = Some((to_solution(c, container_size), key));
+        }
+    }
+
+    best.map(|(solution, _)| solution)
+}
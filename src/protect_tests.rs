@@ -0,0 +1,177 @@
+#![cfg(test)]
+
+use crate::file_utils::from_orig_file_size::calc_container_size;
+use crate::integer_utils::IntegerUtils;
+use crate::protect::*;
+use crate::sbx_specs::Version;
+
+#[test]
+fn test_string_to_goals() {
+    assert_eq!(
+        Ok(Goals {
+            burst_loss: Some(1 << 20),
+            random_loss_percent: None,
+            max_overhead_percent: Some(25.),
+        }),
+        string_to_goals("burst=1M,overhead=25%")
+    );
+    assert_eq!(
+        Ok(Goals {
+            burst_loss: Some(3 << 10),
+            random_loss_percent: Some(0.5),
+            max_overhead_percent: None,
+        }),
+        string_to_goals("Burst = 3KiB, random=0.5")
+    );
+    assert_eq!(
+        Ok(Goals {
+            burst_loss: Some(2 << 30),
+            random_loss_percent: None,
+            max_overhead_percent: Some(150.),
+        }),
+        string_to_goals("burst=2GB,overhead=150")
+    );
+    assert_eq!(
+        Ok(Goals {
+            burst_loss: Some(100),
+            random_loss_percent: None,
+            max_overhead_percent: None,
+        }),
+        string_to_goals("burst=100")
+    );
+}
+
+#[test]
+fn test_string_to_goals_invalid() {
+    assert_eq!(Err(()), string_to_goals(""));
+    assert_eq!(Err(()), string_to_goals("burst"));
+    assert_eq!(Err(()), string_to_goals("burst=1T"));
+    assert_eq!(Err(()), string_to_goals("burst=M"));
+    assert_eq!(Err(()), string_to_goals("random=0"));
+    assert_eq!(Err(()), string_to_goals("random=100%"));
+    assert_eq!(Err(()), string_to_goals("overhead=-5"));
+    assert_eq!(Err(()), string_to_goals("parity=5"));
+}
+
+#[test]
+fn test_calc_burst_loss_block_count() {
+    assert_eq!(0, calc_burst_loss_block_count(Version::V17, 0));
+    assert_eq!(1, calc_burst_loss_block_count(Version::V17, 1));
+    assert_eq!(2, calc_burst_loss_block_count(Version::V17, 2));
+    assert_eq!(2, calc_burst_loss_block_count(Version::V17, 513));
+    assert_eq!(3, calc_burst_loss_block_count(Version::V17, 514));
+    assert_eq!(2, calc_burst_loss_block_count(Version::V18, 129));
+}
+
+#[test]
+fn test_calc_max_burst_loss() {
+    assert_eq!(1, calc_max_burst_loss(Version::V17, 1, 0));
+    assert_eq!(1, calc_max_burst_loss(Version::V17, 1, 1));
+    assert_eq!(513, calc_max_burst_loss(Version::V17, 2, 0));
+    assert_eq!(512 * 23 + 1, calc_max_burst_loss(Version::V17, 2, 12));
+
+    // the tolerated loss satisfies the goal it was solved for
+    for &loss in [1, 512, 513, 10_000, 1 << 20].iter() {
+        let block_count = calc_burst_loss_block_count(Version::V17, loss);
+        let burst = (u64::round_up_to_multiple(block_count, 3) / 3) as usize;
+        assert!(loss <= calc_max_burst_loss(Version::V17, 3, burst));
+    }
+}
+
+#[test]
+fn test_calc_random_loss_failure_prob() {
+    // single block set of one data and one parity shard is lost when both are lost
+    let prob = calc_random_loss_failure_prob(1, 1, 1, 10.);
+    assert!((prob - 0.01).abs() < 1e-12);
+
+    let prob = calc_random_loss_failure_prob(1, 1, 2, 10.);
+    assert!((prob - (1. - 0.99 * 0.99)).abs() < 1e-12);
+
+    let few_parity = calc_random_loss_failure_prob(10, 2, 1000, 1.);
+    let more_parity = calc_random_loss_failure_prob(10, 4, 1000, 1.);
+    let more_sets = calc_random_loss_failure_prob(10, 4, 100_000, 1.);
+    let more_loss = calc_random_loss_failure_prob(10, 4, 1000, 5.);
+
+    assert!(more_parity < few_parity);
+    assert!(more_parity < more_sets);
+    assert!(more_parity < more_loss);
+    assert!(more_parity > 0.);
+}
+
+#[test]
+fn test_solve_burst_goal() {
+    let versions = [Version::V17, Version::V18, Version::V19];
+    let goals = string_to_goals("burst=1M").unwrap();
+    let file_size = 100 << 20;
+
+    let solution = solve(&versions, &goals, file_size, 1000).unwrap();
+    let (data, parity, burst) = solution.data_par_burst;
+
+    assert!(solution.max_burst_loss >= 1 << 20);
+    assert!(burst <= 1000);
+    assert!(data + parity <= 256);
+    assert_eq!(
+        calc_container_size(
+            solution.version,
+            Some(true),
+            Some(solution.data_par_burst),
+            file_size
+        ),
+        solution.container_size
+    );
+    assert_eq!(None, solution.random_loss_failure_prob);
+}
+
+#[test]
+fn test_solve_without_burst_resistance() {
+    let versions = [Version::V17];
+    let goals = string_to_goals("burst=4K").unwrap();
+
+    let solution = solve(&versions, &goals, 1 << 20, 0).unwrap();
+    let (_, parity, burst) = solution.data_par_burst;
+
+    assert_eq!(0, burst);
+    assert!(parity >= 9);
+
+    // largest loss possible without burst error resistance is 255 blocks
+    let goals = string_to_goals("burst=1M").unwrap();
+    assert_eq!(None, solve(&versions, &goals, 1 << 20, 0));
+}
+
+#[test]
+fn test_solve_random_goal() {
+    let versions = [Version::V17];
+    let goals = string_to_goals("random=1%,overhead=50%").unwrap();
+
+    let solution = solve(&versions, &goals, 10 << 20, 1000).unwrap();
+
+    assert_eq!(0, solution.data_par_burst.2);
+    assert!(solution.random_loss_failure_prob.unwrap() <= RANDOM_LOSS_MAX_FAILURE_PROB);
+    assert!(solution.overhead_percent <= 50.);
+}
+
+#[test]
+fn test_solve_overhead_goal() {
+    let versions = [Version::V17, Version::V18, Version::V19];
+    let goals = string_to_goals("overhead=20").unwrap();
+    let file_size = 1 << 20;
+
+    let solution = solve(&versions, &goals, file_size, 1000).unwrap();
+    let (data, parity, burst) = solution.data_par_burst;
+
+    assert!(solution.overhead_percent <= 20.);
+    assert_eq!(0, burst);
+    // parity ratio close to the limit
+    assert!(parity as f64 / data as f64 > 0.15);
+}
+
+#[test]
+fn test_solve_infeasible() {
+    let versions = [Version::V17, Version::V18, Version::V19];
+
+    let goals = string_to_goals("burst=1M,overhead=1").unwrap();
+    assert_eq!(None, solve(&versions, &goals, 1 << 20, 1000));
+
+    let goals = string_to_goals("random=99").unwrap();
+    assert_eq!(None, solve(&versions, &goals, 1 << 20, 1000));
+}
@@ -0,0 +1,112 @@
+use std::collections::BTreeMap;
+
+// set of u64 values stored as sorted, merged ranges [start, end)
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RangeSet {
+    ranges: BTreeMap<u64, u64>,
+}
+
+impl RangeSet {
+    pub fn new() -> RangeSet {
+        RangeSet {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    pub fn insert(&mut self, x: u64) {
+        self.insert_range(x, x + 1);
+    }
+
+    pub fn insert_range(&mut self, start: u64, end_exc: u64) {
+        if start >= end_exc {
+            return;
+        }
+
+        let mut start = start;
+        let mut end_exc = end_exc;
+
+        // merge with the range before if overlapping or adjacent
+        if let Some((&s, &e)) = self.ranges.range(..=start).next_back() {
+            if e >= start {
+                start = s;
+                end_exc = std::cmp::max(end_exc, e);
+            }
+        }
+
+        // merge with the ranges after
+        let merged: Vec<u64> = self
+            .ranges
+            .range(start..=end_exc)
+            .map(|(&s, _)| s)
+            .collect();
+        for s in merged.into_iter() {
+            let e = self.ranges.remove(&s).unwrap();
+            end_exc = std::cmp::max(end_exc, e);
+        }
+
+        self.ranges.insert(start, end_exc);
+    }
+
+    pub fn contains(&self, x: u64) -> bool {
+        match self.ranges.range(..=x).next_back() {
+            Some((_, &e)) => x < e,
+            None => false,
+        }
+    }
+
+    // number of values in the set
+    pub fn len(&self) -> u64 {
+        self.ranges.iter().map(|(&s, &e)| e - s).sum()
+    }
+
+    // one past the largest value in the set
+    pub fn end(&self) -> Option<u64> {
+        self.ranges.iter().next_back().map(|(_, &e)| e)
+    }
+
+    pub fn ranges(&self) -> Vec<(u64, u64)> {
+        self.ranges.iter().map(|(&s, &e)| (s, e)).collect()
+    }
+
+    // returns the parts of [start, end) not in the set
+    pub fn gaps(&self, start: u64, end_exc: u64) -> Vec<(u64, u64)> {
+        let mut res = Vec::new();
+
+        if start >= end_exc {
+            return res;
+        }
+
+        let mut cur = start;
+
+        if let Some((_, &e)) = self.ranges.range(..=start).next_back() {
+            cur = std::cmp::max(cur, e);
+        }
+
+        for (&s, &e) in self.ranges.range(start..end_exc) {
+            if s > cur {
+                res.push((cur, s));
+            }
+            cur = std::cmp::max(cur, e);
+        }
+
+        if cur < end_exc {
+            res.push((cur, end_exc));
+        }
+
+        res
+    }
+
+    // returns the parts of the set within [start, end) not in other
+    pub fn difference(&self, other: &RangeSet, start: u64, end_exc: u64) -> Vec<(u64, u64)> {
+        let mut res = Vec::new();
+
+        for (s, e) in self.ranges().into_iter() {
+            let s = std::cmp::max(s, start);
+            let e = std::cmp::min(e, end_exc);
+
+            res.extend(other.gaps(s, e));
+        }
+
+        res
+    }
+}
@@ -0,0 +1,129 @@
+#![cfg(test)]
+
+use crate::range_set::RangeSet;
+
+#[test]
+fn test_insert_merges_adjacent_and_overlapping() {
+    let mut set = RangeSet::new();
+
+    set.insert(5);
+    set.insert(3);
+    set.insert(4);
+    set.insert_range(10, 20);
+    set.insert_range(15, 25);
+    set.insert_range(0, 1);
+
+    assert_eq!(vec![(0, 1), (3, 6), (10, 25)], set.ranges());
+
+    set.insert_range(1, 12);
+
+    assert_eq!(vec![(0, 25)], set.ranges());
+}
+
+#[test]
+fn test_insert_empty_range() {
+    let mut set = RangeSet::new();
+
+    set.insert_range(5, 5);
+    set.insert_range(6, 2);
+
+    assert_eq!(Vec::<(u64, u64)>::new(), set.ranges());
+    assert_eq!(None, set.end());
+}
+
+#[test]
+fn test_end() {
+    let mut set = RangeSet::new();
+
+    set.insert_range(2, 4);
+    set.insert_range(8, 13);
+
+    assert_eq!(Some(13), set.end());
+}
+
+#[test]
+fn test_contains() {
+    let mut set = RangeSet::new();
+
+    set.insert_range(2, 4);
+    set.insert(10);
+
+    assert!(!set.contains(0));
+    assert!(!set.contains(1));
+    assert!(set.contains(2));
+    assert!(set.contains(3));
+    assert!(!set.contains(4));
+    assert!(set.contains(10));
+    assert!(!set.contains(11));
+}
+
+#[test]
+fn test_len() {
+    let mut set = RangeSet::new();
+
+    assert_eq!(0, set.len());
+
+    set.insert_range(2, 4);
+    set.insert_range(8, 13);
+
+    assert_eq!(7, set.len());
+}
+
+#[test]
+fn test_gaps() {
+    let mut set = RangeSet::new();
+
+    set.insert_range(2, 4);
+    set.insert_range(8, 13);
+
+    assert_eq!(vec![(0, 2), (4, 8), (13, 20)], set.gaps(0, 20));
+    assert_eq!(vec![(4, 8)], set.gaps(3, 10));
+    assert_eq!(Vec::<(u64, u64)>::new(), set.gaps(9, 12));
+    assert_eq!(Vec::<(u64, u64)>::new(), set.gaps(5, 5));
+}
+
+#[test]
+fn test_difference() {
+    let mut a = RangeSet::new();
+    let mut b = RangeSet::new();
+
+    a.insert_range(0, 10);
+    a.insert_range(20, 30);
+    b.insert_range(5, 22);
+
+    assert_eq!(vec![(0, 5), (22, 30)], a.difference(&b, 0, 100));
+    assert_eq!(vec![(2, 5), (22, 25)], a.difference(&b, 2, 25));
+}
+
+quickcheck! {
+    fn qc_ranges_cover_inserted(xs: Vec<u16>) -> bool {
+        let mut set = RangeSet::new();
+
+        for &x in xs.iter() {
+            set.insert(x as u64);
+        }
+
+        let mut sorted: Vec<u64> = xs.iter().map(|&x| x as u64).collect();
+        sorted.sort();
+        sorted.dedup();
+
+        let expanded: Vec<u64> = set
+            .ranges()
+            .into_iter()
+            .flat_map(|(s, e)| s..e)
+            .collect();
+
+        expanded == sorted
+    }
+
+    fn qc_contains_matches_inserted(xs: Vec<u16>, ys: Vec<u16>) -> bool {
+        let mut set = RangeSet::new();
+
+        for &x in xs.iter() {
+            set.insert(x as u64);
+        }
+
+        xs.iter().all(|&x| set.contains(x as u64))
+            && ys.iter().all(|y| set.contains(*y as u64) == xs.contains(y))
+    }
+}
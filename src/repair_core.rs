@@ -1,9 +1,15 @@
 use crate::block_utils::RefBlockChoice;
 use crate::cli_utils::setup_ctrlc_handler;
+use crate::damage_map::DamageMap;
 use crate::file_reader::{FileReader, FileReaderParam};
 use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
 use crate::general_error::Error;
+use crate::journal;
+use crate::journal::Journal;
 use crate::json_printer::{BracketType, JSONPrinter};
+use crate::log::*;
+use crate::misc_utils::MultiPassType;
 use crate::progress_report::*;
 use crate::reader::ReadResult;
 use crate::rs_codec::RSCodecState;
@@ -13,23 +19,43 @@ use crate::sbx_block::Block;
 use crate::sbx_block::BlockType;
 use crate::sbx_block::Header;
 use crate::sbx_specs::Version;
-use crate::sbx_specs::SBX_LARGEST_BLOCK_SIZE;
 use crate::sbx_specs::{ver_to_block_size, ver_to_usize};
+use crate::sbx_specs::{SBX_FILE_UID_LEN, SBX_LARGEST_BLOCK_SIZE, SBX_LAST_SEQ_NUM};
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
 use crate::time_utils;
 use std::fmt;
 use std::io::SeekFrom;
+use std::sync::atomic::Ordering;
 use std::sync::{Arc, Mutex};
 
+#[derive(Clone, Copy, Debug, Default)]
+struct Checkpoint {
+    next_seq_num: u32,
+    meta_blocks_decoded: u64,
+    data_or_par_blocks_decoded: u64,
+    blocks_decode_failed: u64,
+    meta_blocks_repaired: u64,
+    data_or_par_blocks_repaired: u64,
+    data_or_par_blocks_repair_failed: u64,
+    data_or_par_blocks_salvaged: u64,
+    blank_blocks_omitted: u64,
+}
+
 #[derive(Clone, Debug)]
 pub struct Stats {
     version: Version,
     block_size: u64,
+    checkpoint: Checkpoint,
     pub meta_blocks_decoded: u64,
     pub data_or_par_blocks_decoded: u64,
     pub blocks_decode_failed: u64,
     pub meta_blocks_repaired: u64,
     pub data_or_par_blocks_repaired: u64,
     pub data_or_par_blocks_repair_failed: u64,
+    pub data_or_par_blocks_salvaged: u64,
+    pub blank_blocks_omitted: u64,
+    sparse: bool,
     total_blocks: u64,
     start_time: f64,
     end_time: f64,
@@ -42,12 +68,16 @@ impl Stats {
         Stats {
             version,
             block_size: ver_to_block_size(version) as u64,
+            checkpoint: Checkpoint::default(),
             blocks_decode_failed: 0,
             meta_blocks_decoded: 0,
             data_or_par_blocks_decoded: 0,
             meta_blocks_repaired: 0,
             data_or_par_blocks_repaired: 0,
             data_or_par_blocks_repair_failed: 0,
+            data_or_par_blocks_salvaged: 0,
+            blank_blocks_omitted: 0,
+            sparse: ref_block.is_meta() && ref_block.get_SPR().unwrap().is_some(),
             total_blocks,
             start_time: 0.,
             end_time: 0.,
@@ -56,7 +86,108 @@ impl Stats {
     }
 
     fn blocks_so_far(&self) -> u64 {
-        self.meta_blocks_decoded + self.data_or_par_blocks_decoded + self.blocks_decode_failed
+        self.meta_blocks_decoded
+            + self.data_or_par_blocks_decoded
+            + self.blocks_decode_failed
+            + self.blank_blocks_omitted
+    }
+
+    // only completed block sets are recorded in log file,
+    // as a partially processed block set cannot be continued
+    fn checkpoint(&mut self, next_seq_num: u32) {
+        self.checkpoint = Checkpoint {
+            next_seq_num,
+            meta_blocks_decoded: self.meta_blocks_decoded,
+            data_or_par_blocks_decoded: self.data_or_par_blocks_decoded,
+            blocks_decode_failed: self.blocks_decode_failed,
+            meta_blocks_repaired: self.meta_blocks_repaired,
+            data_or_par_blocks_repaired: self.data_or_par_blocks_repaired,
+            data_or_par_blocks_repair_failed: self.data_or_par_blocks_repair_failed,
+            data_or_par_blocks_salvaged: self.data_or_par_blocks_salvaged,
+            blank_blocks_omitted: self.blank_blocks_omitted,
+        };
+    }
+}
+
+impl Log for Stats {
+    fn serialize(&self) -> String {
+        let checkpoint = &self.checkpoint;
+
+        let mut string = String::with_capacity(300);
+        string.push_str(&format!("next_seq_num={}\n", checkpoint.next_seq_num));
+        string.push_str(&format!(
+            "meta_blocks_decoded={}\n",
+            checkpoint.meta_blocks_decoded
+        ));
+        string.push_str(&format!(
+            "data_blocks_decoded={}\n",
+            checkpoint.data_or_par_blocks_decoded
+        ));
+        string.push_str(&format!(
+            "blocks_failed={}\n",
+            checkpoint.blocks_decode_failed
+        ));
+        string.push_str(&format!(
+            "meta_blocks_repaired={}\n",
+            checkpoint.meta_blocks_repaired
+        ));
+        string.push_str(&format!(
+            "data_blocks_repaired={}\n",
+            checkpoint.data_or_par_blocks_repaired
+        ));
+        string.push_str(&format!(
+            "data_blocks_repair_failed={}\n",
+            checkpoint.data_or_par_blocks_repair_failed
+        ));
+        string.push_str(&format!(
+            "data_blocks_salvaged={}\n",
+            checkpoint.data_or_par_blocks_salvaged
+        ));
+        string.push_str(&format!(
+            "blank_blocks_omitted={}\n",
+            checkpoint.blank_blocks_omitted
+        ));
+
+        string
+    }
+
+    fn deserialize(&mut self, input: &[u8]) -> Result<(), ()> {
+        let fields = parse_u64_fields(
+            input,
+            &[
+                "next_seq_num",
+                "meta_blocks_decoded",
+                "data_blocks_decoded",
+                "blocks_failed",
+                "meta_blocks_repaired",
+                "data_blocks_repaired",
+                "data_blocks_repair_failed",
+            ],
+        )?;
+
+        if fields[0] > std::u32::MAX as u64 {
+            return Err(());
+        }
+
+        self.meta_blocks_decoded = fields[1];
+        self.data_or_par_blocks_decoded = fields[2];
+        self.blocks_decode_failed = fields[3];
+        self.meta_blocks_repaired = fields[4];
+        self.data_or_par_blocks_repaired = fields[5];
+        self.data_or_par_blocks_repair_failed = fields[6];
+        // logs written before salvaging was introduced do not have this field
+        let salvaged_fields = parse_u64_fields(input, &["data_blocks_salvaged"]);
+        self.data_or_par_blocks_salvaged = match salvaged_fields {
+            Ok(fields) => fields[0],
+            Err(()) => 0,
+        };
+        // logs written before sparse containers were handled do not have this field
+        self.blank_blocks_omitted = match parse_u64_fields(input, &["blank_blocks_omitted"]) {
+            Ok(fields) => fields[0],
+            Err(()) => 0,
+        };
+        self.checkpoint(fields[0] as u32);
+        Ok(())
     }
 }
 
@@ -118,6 +249,14 @@ impl fmt::Display for Stats {
             "Number of blocks passed check (data)     : {}",
             self.data_or_par_blocks_decoded
         )?;
+        if self.sparse {
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Number of blank blocks (sparse)          : {}",
+                self.blank_blocks_omitted
+            )?;
+        }
         write_maybe_json!(
             f,
             json_printer,
@@ -142,6 +281,12 @@ impl fmt::Display for Stats {
             "Number of blocks failed to repair (data) : {}",
             self.data_or_par_blocks_repair_failed
         )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks salvaged (data)         : {}",
+            self.data_or_par_blocks_salvaged
+        )?;
         write_maybe_json!(
             f,
             json_printer,
@@ -174,6 +319,13 @@ impl fmt::Display for Stats {
 #[derive(Clone, Debug)]
 pub struct Param {
     in_file: String,
+    out_file: Option<String>,
+    multi_pass: Option<MultiPassType>,
+    log_file: Option<String>,
+    damage_map_file: Option<String>,
+    journal_file: Option<String>,
+    sparse_map_file: Option<String>,
+    salvage: bool,
     dry_run: bool,
     json_printer: Arc<JSONPrinter>,
     verbose: bool,
@@ -184,6 +336,13 @@ pub struct Param {
 impl Param {
     pub fn new(
         in_file: &str,
+        out_file: Option<&str>,
+        multi_pass: Option<MultiPassType>,
+        log_file: Option<&str>,
+        damage_map_file: Option<&str>,
+        journal_file: Option<&str>,
+        sparse_map_file: Option<&str>,
+        salvage: bool,
         dry_run: bool,
         json_printer: &Arc<JSONPrinter>,
         verbose: bool,
@@ -192,6 +351,28 @@ impl Param {
     ) -> Param {
         Param {
             in_file: String::from(in_file),
+            out_file: match out_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            multi_pass,
+            log_file: match log_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            damage_map_file: match damage_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            journal_file: match journal_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            sparse_map_file: match sparse_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            salvage,
             dry_run,
             json_printer: Arc::clone(json_printer),
             verbose,
@@ -203,28 +384,42 @@ impl Param {
 
 fn update_rs_codec_and_stats(
     version: Version,
+    uid: &[u8; SBX_FILE_UID_LEN],
     header_pred: &dyn Fn(&Header) -> bool,
     read_res: &ReadResult,
     block: &mut Block,
     cur_seq_num: u32,
+    salvage: bool,
+    sparse_map: &Option<SparseMap>,
     rs_codec: &mut RSRepairer,
     stats: &mut Stats,
-) -> RSCodecState {
+) -> (RSCodecState, bool) {
     let block_size = ver_to_block_size(version);
 
     if read_res.len_read < block_size {
         // read an incomplete block
         stats.blocks_decode_failed += 1;
-        rs_codec.mark_missing()
-    } else if let Err(_) =
+        (rs_codec.mark_missing(), false)
+    } else if sparse_map::block_is_omitted(sparse_map, cur_seq_num, rs_codec.get_block_buffer()) {
+        // the hole stands for a block of zeros, which is not written back
+        sparse_map::fill_omitted_block(version, uid, cur_seq_num, rs_codec.get_block_buffer());
+        stats.blank_blocks_omitted += 1;
+        (rs_codec.mark_present(), false)
+    } else if let Err(e) =
         block.sync_from_buffer(rs_codec.get_block_buffer(), Some(header_pred), None)
     {
         stats.blocks_decode_failed += 1;
-        rs_codec.mark_missing()
+
+        // keep blocks with intact header for salvaging
+        if salvage && e == sbx_block::Error::InvalidCRC && block.get_seq_num() == cur_seq_num {
+            (rs_codec.mark_candidate(), false)
+        } else {
+            (rs_codec.mark_missing(), false)
+        }
     } else {
         if block.get_seq_num() != cur_seq_num {
             stats.blocks_decode_failed += 1;
-            rs_codec.mark_missing()
+            (rs_codec.mark_missing(), false)
         } else {
             if block.is_meta() {
                 stats.meta_blocks_decoded += 1;
@@ -232,23 +427,76 @@ fn update_rs_codec_and_stats(
                 stats.data_or_par_blocks_decoded += 1;
             }
 
-            rs_codec.mark_present()
+            (rs_codec.mark_present(), true)
         }
     }
 }
 
+fn write_block_to_out(
+    multi_pass: Option<MultiPassType>,
+    header_pred: &dyn Fn(&Header) -> bool,
+    data_par_burst: Option<(usize, usize, usize)>,
+    writer: &mut FileWriter,
+    pos: u64,
+    block_buf: &[u8],
+) -> Result<(), Error> {
+    let do_write = match multi_pass {
+        None | Some(MultiPassType::OverwriteAll) => true,
+        Some(MultiPassType::SkipGood) => {
+            let block_size = block_buf.len();
+
+            let mut check_buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+            let check_buffer = &mut check_buffer[..block_size];
+            let mut check_block = Block::dummy();
+
+            writer.seek(SeekFrom::Start(pos))?;
+            let read_res = writer.read(check_buffer)?;
+
+            // if block at output position is already a valid block with the
+            // expected sequence number, then don't overwrite
+            read_res.len_read < block_size
+                || match check_block.sync_from_buffer(check_buffer, Some(header_pred), None) {
+                    Ok(()) => {
+                        let expected_seq_num = sbx_block::calc_seq_num_at_index(
+                            pos / block_size as u64,
+                            None,
+                            data_par_burst,
+                        );
+                        check_block.get_seq_num() != expected_seq_num
+                    }
+                    Err(_) => true,
+                }
+        }
+    };
+
+    if do_write {
+        writer.seek(SeekFrom::Start(pos))?;
+        writer.write(block_buf)?;
+    }
+
+    Ok(())
+}
+
 fn repair_blocks_and_update_stats_using_repair_stats(
     param: &Param,
+    header_pred: &dyn Fn(&Header) -> bool,
+    data_par_burst: Option<(usize, usize, usize)>,
     cur_seq_num: u32,
     rs_codec: &mut RSRepairer,
     stats: &mut Stats,
     reader: &mut FileReader,
+    writer: &mut Option<FileWriter>,
+    journal: &mut Option<Journal>,
     reporter: &ProgressReporter<Stats>,
 ) -> Result<(), Error> {
     let (repair_stats, repaired_blocks) = rs_codec.repair_with_block_sync(cur_seq_num);
 
     if repair_stats.successful {
         stats.data_or_par_blocks_repaired += repair_stats.missing_count as u64;
+
+        if repair_stats.salvaged {
+            stats.data_or_par_blocks_salvaged += repair_stats.missing_count as u64;
+        }
     } else {
         stats.data_or_par_blocks_repair_failed += repair_stats.missing_count as u64;
     }
@@ -261,8 +509,17 @@ fn repair_blocks_and_update_stats_using_repair_stats(
     if !param.dry_run {
         // write the repaired data blocks
         for &(pos, block_buf) in repaired_blocks.iter() {
-            reader.seek(SeekFrom::Start(pos))?;
-            reader.write(&block_buf)?;
+            match writer {
+                None => journal::write_with_journal(journal, reader, pos, block_buf)?,
+                Some(ref mut writer) => write_block_to_out(
+                    param.multi_pass,
+                    header_pred,
+                    data_par_burst,
+                    writer,
+                    pos,
+                    block_buf,
+                )?,
+            }
         }
     }
 
@@ -292,22 +549,58 @@ pub fn repair_file(param: &Param) -> Result<Option<Stats>, Error> {
     let data_par_burst =
         get_data_par_burst!(no_offset => param, ref_block_pos, ref_block, "repair");
 
-    let total_block_count = {
-        use crate::file_utils::from_orig_file_size::calc_total_block_count_exc_burst_gaps;
-        match ref_block.get_FSZ().unwrap() {
-            Some(x) => calc_total_block_count_exc_burst_gaps(version, None, data_par_burst, x),
-            None => {
-                print_if!(not_json => json_printer =>
-                          "";
-                          "Warning :";
-                          "";
-                          "    No recorded file size found, using container file size to estimate total";
-                          "    number of blocks. This may overestimate total number of blocks, and may";
-                          "    show false repair/verify failures when gaps in container are encountered.";
-                          "";
-                );
-                let file_size = file_utils::get_file_size(&param.in_file)?;
-                file_size / block_size as u64
+    // holes left by the blocks recorded as omitted are zero blocks, not missing blocks
+    let sparse_map = sparse_map::load(
+        json_printer,
+        &ref_block,
+        Some(&param.in_file),
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+    )?;
+
+    let damage_map = match param.damage_map_file {
+        None => None,
+        Some(ref f) => {
+            let damage_map = DamageMap::read_from_file(f)?;
+
+            if damage_map.uid() != &ref_block.get_uid()
+                || damage_map.version() != version
+                || damage_map.data_par_burst() != data_par_burst
+            {
+                return Err(Error::with_msg(&format!(
+                    "Damage map \"{}\" does not match the container",
+                    f
+                )));
+            }
+
+            Some(damage_map)
+        }
+    };
+
+    let total_block_count = match damage_map {
+        Some(ref damage_map) => {
+            damage_map.meta_blocks().len() as u64
+                + damage_map.block_sets().len() as u64 * damage_map.block_set_size() as u64
+        }
+        None => {
+            use crate::file_utils::from_orig_file_size::calc_total_block_count_exc_burst_gaps;
+            match ref_block.get_FSZ().unwrap() {
+                Some(x) => calc_total_block_count_exc_burst_gaps(version, None, data_par_burst, x),
+                None => {
+                    print_if!(not_json => json_printer =>
+                              "";
+                              "Warning :";
+                              "";
+                              "    No recorded file size found, using container file size to estimate total";
+                              "    number of blocks. This may overestimate total number of blocks, and may";
+                              "    show false repair/verify failures when gaps in container are encountered.";
+                              "";
+                    );
+                    let file_size = file_utils::get_file_size(&param.in_file)?;
+                    file_size / block_size as u64
+                }
             }
         }
     };
@@ -321,13 +614,44 @@ pub fn repair_file(param: &Param) -> Result<Option<Stats>, Error> {
     let mut reader = FileReader::new(
         &param.in_file,
         FileReaderParam {
-            write: !param.dry_run,
+            write: !param.dry_run && param.out_file.is_none(),
             buffered: false,
         },
     )?;
 
     let mut block = Block::dummy();
 
+    let log_handler = match param.log_file {
+        None => LogHandler::new(None, &stats),
+        Some(ref f) => LogHandler::new(Some(f), &stats),
+    };
+
+    // read from log file and update stats if the log file exists
+    log_handler.read_from_file()?;
+
+    let start_seq_num = stats.lock().unwrap().checkpoint.next_seq_num;
+
+    // the original container is left untouched if output container is specified,
+    // truncate only if not continuing from log file
+    let mut writer = match param.out_file {
+        Some(ref f) if !param.dry_run => Some(FileWriter::new(
+            f,
+            FileWriterParam {
+                read: param.multi_pass == Some(MultiPassType::SkipGood),
+                append: false,
+                truncate: param.multi_pass == None && start_seq_num == 0,
+                buffered: false,
+            },
+        )?),
+        _ => None,
+    };
+
+    // record original blocks before any in-place modification
+    let mut journal = match param.journal_file {
+        Some(ref f) if !param.dry_run && writer.is_none() => Some(Journal::new(f, &param.in_file)?),
+        _ => None,
+    };
+
     let reporter = Arc::new(ProgressReporter::new(
         &stats,
         "SBX block repairing progress",
@@ -346,18 +670,26 @@ pub fn repair_file(param: &Param) -> Result<Option<Stats>, Error> {
         data_par_burst.unwrap().2,
     );
 
+    log_handler.start();
     reporter.start();
 
     json_printer.print_open_bracket(Some("metadata repairs"), BracketType::Square);
-    // replace metadata blocks with reference block if broken
-    {
+    // replace metadata blocks with reference block if broken,
+    // metadata blocks were already handled if continuing from log file
+    if start_seq_num == 0 {
         let mut stats = stats.lock().unwrap();
 
         let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
 
         ref_block.sync_to_buffer(None, &mut buffer).unwrap();
 
-        for &p in sbx_block::calc_meta_block_all_write_pos_s(version, data_par_burst).iter() {
+        // only visit the metadata blocks recorded in damage map if provided
+        let meta_block_pos_s = match damage_map {
+            None => sbx_block::calc_meta_block_all_write_pos_s(version, data_par_burst).to_vec(),
+            Some(ref damage_map) => damage_map.meta_blocks().to_vec(),
+        };
+
+        for &p in meta_block_pos_s.iter() {
             break_if_atomic_bool!(ctrlc_stop_flag);
 
             reader.seek(SeekFrom::Start(p))?;
@@ -386,17 +718,36 @@ pub fn repair_file(param: &Param) -> Result<Option<Stats>, Error> {
 
                 stats.blocks_decode_failed += 1;
 
-                reader.seek(SeekFrom::Start(p))?;
-
                 ref_block.sync_to_buffer(None, &mut buffer).unwrap();
-                if !param.dry_run {
-                    reader.write(sbx_block::slice_buf(version, &buffer))?;
+                if !param.dry_run && writer.is_none() {
+                    journal::write_with_journal(
+                        &mut journal,
+                        &mut reader,
+                        p,
+                        sbx_block::slice_buf(version, &buffer),
+                    )?;
                 }
 
                 stats.meta_blocks_repaired += 1;
             } else {
                 stats.meta_blocks_decoded += 1;
             }
+
+            // copy the valid metadata block or write the reference block to output container
+            if let Some(ref mut writer) = writer {
+                write_block_to_out(
+                    param.multi_pass,
+                    &header_pred,
+                    data_par_burst,
+                    writer,
+                    p,
+                    sbx_block::slice_buf(version, &buffer),
+                )?;
+            }
+        }
+
+        if !ctrlc_stop_flag.load(Ordering::SeqCst) {
+            stats.checkpoint(1);
         }
     }
     json_printer.print_close_bracket();
@@ -407,55 +758,129 @@ pub fn repair_file(param: &Param) -> Result<Option<Stats>, Error> {
 
     json_printer.print_open_bracket(Some("data repairs"), BracketType::Square);
     // repair data blocks
-    let mut seq_num = 1;
+    let mut next_seq_num = std::cmp::max(start_seq_num, 1);
+    let mut out_buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+    // only visit the block sets recorded in damage map if provided,
+    // skipping block sets already completed according to log file
+    let mut seq_nums: Box<dyn Iterator<Item = u32>> = match damage_map {
+        None => Box::new(next_seq_num..=SBX_LAST_SEQ_NUM),
+        Some(ref damage_map) => {
+            let block_set_size = damage_map.block_set_size();
+            let first_seq_nums: Vec<u32> = damage_map
+                .block_sets()
+                .keys()
+                .map(|&i| damage_map.block_set_first_seq_num(i))
+                .filter(|&seq_num| seq_num >= start_seq_num)
+                .collect();
+
+            Box::new(
+                first_seq_nums
+                    .into_iter()
+                    .flat_map(move |seq_num| seq_num..seq_num + block_set_size),
+            )
+        }
+    };
     loop {
         let mut stats = stats.lock().unwrap();
 
         break_if_atomic_bool!(ctrlc_stop_flag);
 
-        if stats.blocks_so_far() >= total_block_count {
+        log_handler.pop_error()?;
+
+        if damage_map.is_none() && stats.blocks_so_far() >= total_block_count {
             break;
         }
 
+        let seq_num = match seq_nums.next() {
+            None => break,
+            Some(x) => x,
+        };
+
         let pos = sbx_block::calc_data_block_write_pos(version, seq_num, None, data_par_burst);
 
         reader.seek(SeekFrom::Start(pos))?;
 
         let read_res = reader.read(rs_codec.get_block_buffer())?;
 
-        let codec_state = update_rs_codec_and_stats(
+        // keep a copy of the block as the codec moves onto the next block buffer
+        if writer.is_some() {
+            out_buffer[..block_size].copy_from_slice(&rs_codec.get_block_buffer()[..block_size]);
+        }
+
+        let (codec_state, block_present) = update_rs_codec_and_stats(
             version,
+            &ref_block.get_uid(),
             &header_pred,
             &read_res,
             &mut block,
             seq_num,
+            param.salvage,
+            &sparse_map,
             &mut rs_codec,
             &mut stats,
         );
 
+        // copy the valid block to output container
+        if block_present {
+            if let Some(ref mut writer) = writer {
+                write_block_to_out(
+                    param.multi_pass,
+                    &header_pred,
+                    data_par_burst,
+                    writer,
+                    pos,
+                    &out_buffer[..block_size],
+                )?;
+            }
+        }
+
         match codec_state {
             RSCodecState::Ready => {
                 repair_blocks_and_update_stats_using_repair_stats(
                     &param,
+                    &header_pred,
+                    data_par_burst,
                     seq_num,
                     &mut rs_codec,
                     &mut stats,
                     &mut reader,
+                    &mut writer,
+                    &mut journal,
                     &reporter,
                 )?;
+
+                stats.checkpoint(seq_num + 1);
             }
             RSCodecState::NotReady => {}
         }
 
-        incre_or_break_if_last!(seq_num => seq_num);
+        next_seq_num = seq_num.saturating_add(1);
     }
     json_printer.print_close_bracket();
 
+    if !ctrlc_stop_flag.load(Ordering::SeqCst) {
+        stats.lock().unwrap().checkpoint(next_seq_num);
+    }
+
+    // the output container has the same holes as the input container
+    if let (Some(ref mut writer), Some(ref sparse_map), Some(ref out_file)) =
+        (&mut writer, &sparse_map, &param.out_file)
+    {
+        let in_file_size = file_utils::get_file_size(&param.in_file)?;
+
+        if writer.get_file_size()? < in_file_size {
+            writer.set_len(in_file_size)?;
+        }
+
+        sparse_map.write_to_file(&sparse_map::default_file(out_file))?;
+    }
+
     if stats.lock().unwrap().blocks_decode_failed > 0 {
         print_if!(verbose not_json => param, json_printer => "";);
     }
 
     reporter.stop();
+    log_handler.stop();
 
     let stats = stats.lock().unwrap().clone();
 
@@ -5,6 +5,7 @@ use crate::sbx_block::Block;
 use crate::sbx_specs::{
     ver_to_block_size, Version, SBX_FILE_UID_LEN, SBX_FIRST_DATA_SEQ_NUM, SBX_LARGEST_BLOCK_SIZE,
 };
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::collections::LinkedList;
 
@@ -111,10 +112,23 @@ impl RescueBuffer {
         }
     }
 
-    pub fn write(&mut self, out_dir: &str) -> Result<(), Error> {
+    pub fn blocks(&self) -> &[Block] {
+        &self.blocks[0..self.slots_used]
+    }
+
+    pub fn write(
+        &mut self,
+        out_dir: &str,
+        uid_names: &BTreeMap<[u8; SBX_FILE_UID_LEN], String>,
+    ) -> Result<(), Error> {
         for (uid, l) in self.uid_to_slot_indices.iter() {
-            let uid_str = misc_utils::bytes_to_upper_hex_string(uid);
-            let path = misc_utils::make_path(&[out_dir, &uid_str]);
+            let path = match uid_names.get(uid) {
+                Some(name) => misc_utils::make_path(&[out_dir, name]),
+                None => {
+                    let uid_str = misc_utils::bytes_to_upper_hex_string(uid);
+                    misc_utils::make_path(&[out_dir, &uid_str])
+                }
+            };
 
             let mut writer = FileWriter::new(
                 &path,
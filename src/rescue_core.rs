@@ -2,6 +2,7 @@ use crate::block_utils;
 use crate::cli_utils::setup_ctrlc_handler;
 use crate::file_reader::{FileReader, FileReaderParam};
 use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
 use crate::general_error::Error;
 use crate::integer_utils::IntegerUtils;
 use crate::json_printer::{BracketType, JSONPrinter};
@@ -11,8 +12,9 @@ use crate::misc_utils::RequiredLenAndSeekTo;
 use crate::misc_utils::{PositionOrLength, RangeEnd};
 use crate::progress_report::*;
 use crate::rescue_buffer::{RescueBuffer, Slot};
-use crate::sbx_block::BlockType;
+use crate::sbx_block::{Block, BlockType};
 use crate::sbx_specs::{SBX_FILE_UID_LEN, SBX_SCAN_BLOCK_SIZE};
+use std::collections::BTreeMap;
 use std::fmt;
 use std::io::SeekFrom;
 use std::sync::mpsc::channel;
@@ -25,6 +27,8 @@ const PIPELINE_BUFFER_IN_ROTATION: usize = 9;
 
 const BLOCK_COUNT_IN_BUFFER: usize = 1000;
 
+const MANIFEST_FILE_NAME: &str = "blkar_rescue_manifest";
+
 pub struct Param {
     in_file: String,
     out_dir: String,
@@ -35,6 +39,8 @@ pub struct Param {
     json_printer: Arc<JSONPrinter>,
     only_pick_block: Option<BlockType>,
     only_pick_uid: Option<[u8; SBX_FILE_UID_LEN]>,
+    name_from_meta: bool,
+    skip_read_errors: bool,
     pr_verbosity_level: PRVerbosityLevel,
 }
 
@@ -49,6 +55,8 @@ impl Param {
         json_printer: &Arc<JSONPrinter>,
         only_pick_block: Option<BlockType>,
         only_pick_uid: Option<&[u8; SBX_FILE_UID_LEN]>,
+        name_from_meta: bool,
+        skip_read_errors: bool,
         pr_verbosity_level: PRVerbosityLevel,
     ) -> Param {
         Param {
@@ -63,10 +71,9 @@ impl Param {
             force_misalign,
             json_printer: Arc::clone(json_printer),
             only_pick_block,
-            only_pick_uid: match only_pick_uid {
-                None => None,
-                Some(x) => Some(x.clone()),
-            },
+            only_pick_uid: only_pick_uid.copied(),
+            name_from_meta,
+            skip_read_errors,
             pr_verbosity_level,
         }
     }
@@ -77,6 +84,11 @@ pub struct Stats {
     pub meta_blocks_processed: u64,
     pub data_or_par_blocks_processed: u64,
     pub bytes_processed: u64,
+    pub uid_names: BTreeMap<[u8; SBX_FILE_UID_LEN], String>,
+    pub uid_block_counts: BTreeMap<[u8; SBX_FILE_UID_LEN], (u64, u64)>,
+    pub read_error_ranges: Vec<(u64, u64)>,
+    start_pos: u64,
+    out_dir: String,
     total_bytes: u64,
     start_time: f64,
     end_time: f64,
@@ -84,11 +96,21 @@ pub struct Stats {
 }
 
 impl Stats {
-    pub fn new(required_len: u64, json_printer: &Arc<JSONPrinter>) -> Result<Stats, Error> {
+    pub fn new(
+        required_len: u64,
+        start_pos: u64,
+        out_dir: &str,
+        json_printer: &Arc<JSONPrinter>,
+    ) -> Result<Stats, Error> {
         let stats = Stats {
             meta_blocks_processed: 0,
             data_or_par_blocks_processed: 0,
             bytes_processed: 0,
+            uid_names: BTreeMap::new(),
+            uid_block_counts: BTreeMap::new(),
+            read_error_ranges: Vec::new(),
+            start_pos,
+            out_dir: String::from(out_dir),
             total_bytes: required_len,
             start_time: 0.,
             end_time: 0.,
@@ -96,6 +118,29 @@ impl Stats {
         };
         Ok(stats)
     }
+
+    fn output_path(&self, uid: &[u8; SBX_FILE_UID_LEN]) -> String {
+        match self.uid_names.get(uid) {
+            Some(name) => misc_utils::make_path(&[&self.out_dir, name]),
+            None => {
+                misc_utils::make_path(&[&self.out_dir, &misc_utils::bytes_to_upper_hex_string(uid)])
+            }
+        }
+    }
+
+    fn unreadable_bytes(&self) -> u64 {
+        self.read_error_ranges
+            .iter()
+            .map(|&(start, end_exc)| end_exc - start)
+            .sum()
+    }
+}
+
+fn add_range(ranges: &mut Vec<(u64, u64)>, start: u64, end_exc: u64) {
+    match ranges.last_mut() {
+        Some(last) if last.1 == start => last.1 = end_exc,
+        _ => ranges.push((start, end_exc)),
+    }
 }
 
 impl ProgressReport for Stats {
@@ -116,11 +161,23 @@ impl ProgressReport for Stats {
     }
 }
 
+const LOG_VERSION: usize = 2;
+
 mod parsers {
+    use crate::sbx_specs::SBX_FILE_UID_LEN;
     use nom::character::complete::digit1;
     use nom::character::complete::newline;
     use std::num::ParseIntError;
 
+    type StatsV2ParseResult<'a> = (
+        StatsParseResult,
+        (&'a [u8], &'a [u8]),
+        Vec<(&'a [u8], &'a [u8])>,
+        Vec<UidParseResult<'a>>,
+    );
+
+    type UidParseResult<'a> = (&'a [u8], &'a [u8], &'a [u8], &'a [u8], &'a [u8]);
+
     type StatsParseResult = Result<(u64, u64, u64, u64), ParseIntError>;
 
     pub fn parse_digits(bytes: &[u8], blocks: &[u8], meta: &[u8], data: &[u8]) -> StatsParseResult {
@@ -152,11 +209,70 @@ mod parsers {
                    (parse_digits(bytes, blocks, meta, data))
            )
     );
+
+    named!(
+        processed_range_p<(&[u8], &[u8])>,
+        do_parse!(
+            _id : tag!(b"processed_range=") >>
+                start   : digit1 >> _d : char!('-') >>
+                end_exc : digit1 >> _n : newline >>
+                ((start, end_exc))
+        )
+    );
+
+    named!(
+        read_error_range_p<(&[u8], &[u8])>,
+        do_parse!(
+            _id : tag!(b"read_error_range=") >>
+                start   : digit1 >> _d : char!('-') >>
+                end_exc : digit1 >> _n : newline >>
+                ((start, end_exc))
+        )
+    );
+
+    named!(
+        uid_p<UidParseResult>,
+        do_parse!(
+            _id : tag!(b"uid=") >>
+                uid    : take!(SBX_FILE_UID_LEN * 2) >>
+                _id : tag!(b" meta_blocks=") >>
+                meta   : digit1 >>
+                _id : tag!(b" data_blocks=") >>
+                data   : digit1 >>
+                _id : tag!(b" named=") >>
+                named  : alt!(tag!(b"true") | tag!(b"false")) >>
+                _id : tag!(b" output=") >>
+                output : take_until!("\n") >> _n : newline >>
+                ((uid, meta, data, named, output))
+        )
+    );
+
+    named!(pub stats_v2_p <StatsV2ParseResult>,
+           do_parse!(
+               _id : tag!(b"version=2") >> _n : newline >>
+                   counters  : stats_p >>
+                   processed : processed_range_p >>
+                   errors    : many0!(read_error_range_p) >>
+                   uids      : many0!(uid_p) >>
+                   ((counters, processed, errors, uids))
+           )
+    );
+}
+
+fn parse_u64(bytes: &[u8]) -> Result<u64, ()> {
+    match std::str::from_utf8(bytes) {
+        Ok(x) => match x.parse::<u64>() {
+            Ok(x) => Ok(x),
+            Err(_) => Err(()),
+        },
+        Err(_) => Err(()),
+    }
 }
 
 impl Log for Stats {
     fn serialize(&self) -> String {
         let mut string = String::with_capacity(200);
+        string.push_str(&format!("version={}\n", LOG_VERSION));
         string.push_str(&format!("bytes_processed={}\n", self.bytes_processed));
         string.push_str(&format!(
             "blocks_processed={}\n",
@@ -170,23 +286,109 @@ impl Log for Stats {
             "data_blocks_processed={}\n",
             self.data_or_par_blocks_processed
         ));
+        string.push_str(&format!(
+            "processed_range={}-{}\n",
+            self.start_pos,
+            self.start_pos + self.bytes_processed
+        ));
+        for &(start, end_exc) in self.read_error_ranges.iter() {
+            string.push_str(&format!("read_error_range={}-{}\n", start, end_exc));
+        }
+        for (uid, &(meta, data)) in self.uid_block_counts.iter() {
+            string.push_str(&format!(
+                "uid={} meta_blocks={} data_blocks={} named={} output={}\n",
+                misc_utils::bytes_to_upper_hex_string(uid),
+                meta,
+                data,
+                self.uid_names.contains_key(uid),
+                self.output_path(uid)
+            ));
+        }
 
         string
     }
 
     fn deserialize(&mut self, input: &[u8]) -> Result<(), ()> {
-        match parsers::stats_p(input) {
-            Ok((_, Ok((bytes, _, meta, data)))) => {
-                self.bytes_processed = u64::round_down_to_multiple(
-                    u64::ensure_at_most(self.total_bytes, bytes),
-                    SBX_SCAN_BLOCK_SIZE as u64,
-                );
-                self.meta_blocks_processed = meta;
-                self.data_or_par_blocks_processed = data;
-                Ok(())
-            }
-            _ => Err(()),
-        }
+        let ((bytes, meta, data), read_error_ranges, uid_block_counts, uid_names) =
+            match parsers::stats_v2_p(input) {
+                Ok((_, (counters, (processed_start, processed_end_exc), errors, uids))) => {
+                    let (_, _, meta, data) = match counters {
+                        Ok(x) => x,
+                        Err(_) => return Err(()),
+                    };
+
+                    // resume from the end of the processed range, which is only
+                    // usable if the range covers the start of the current range
+                    let processed_start = parse_u64(processed_start)?;
+                    let processed_end_exc = parse_u64(processed_end_exc)?;
+                    let bytes = if processed_start <= self.start_pos {
+                        processed_end_exc.saturating_sub(self.start_pos)
+                    } else {
+                        0
+                    };
+
+                    let mut read_error_ranges = Vec::with_capacity(errors.len());
+                    for (start, end_exc) in errors.into_iter() {
+                        read_error_ranges.push((parse_u64(start)?, parse_u64(end_exc)?));
+                    }
+
+                    let mut uid_block_counts = BTreeMap::new();
+                    let mut uid_names = BTreeMap::new();
+                    for (uid_str, meta, data, named, output) in uids.into_iter() {
+                        let mut uid = [0; SBX_FILE_UID_LEN];
+                        match std::str::from_utf8(uid_str) {
+                            Ok(x) => match misc_utils::hex_string_to_bytes(x) {
+                                Ok(x) => uid.copy_from_slice(&x),
+                                Err(_) => return Err(()),
+                            },
+                            Err(_) => return Err(()),
+                        }
+
+                        uid_block_counts.insert(uid, (parse_u64(meta)?, parse_u64(data)?));
+
+                        if named == b"true" {
+                            let output = match std::str::from_utf8(output) {
+                                Ok(x) => x,
+                                Err(_) => return Err(()),
+                            };
+                            match file_utils::get_file_name_part_of_path(output) {
+                                Some(name) => {
+                                    uid_names.insert(uid, name);
+                                }
+                                None => return Err(()),
+                            }
+                        }
+                    }
+
+                    (
+                        (bytes, meta, data),
+                        read_error_ranges,
+                        uid_block_counts,
+                        uid_names,
+                    )
+                }
+                // fall back to log format prior to versioning
+                Err(_) => match parsers::stats_p(input) {
+                    Ok((_, Ok((bytes, _, meta, data)))) => (
+                        (bytes, meta, data),
+                        Vec::new(),
+                        BTreeMap::new(),
+                        BTreeMap::new(),
+                    ),
+                    _ => return Err(()),
+                },
+            };
+
+        self.bytes_processed = u64::round_down_to_multiple(
+            u64::ensure_at_most(self.total_bytes, bytes),
+            SBX_SCAN_BLOCK_SIZE as u64,
+        );
+        self.meta_blocks_processed = meta;
+        self.data_or_par_blocks_processed = data;
+        self.read_error_ranges = read_error_ranges;
+        self.uid_block_counts = uid_block_counts;
+        self.uid_names = uid_names;
+        Ok(())
     }
 }
 
@@ -220,6 +422,26 @@ impl fmt::Display for Stats {
             "Number of blocks processed (data)     : {}",
             self.data_or_par_blocks_processed
         )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of bytes unreadable            : {}",
+            self.unreadable_bytes()
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of UIDs rescued                : {}",
+            self.uid_block_counts.len()
+        )?;
+        if !self.uid_names.is_empty() {
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Number of outputs named               : {}",
+                self.uid_names.len()
+            )?;
+        }
 
         json_printer.write_close_bracket(f)?;
 
@@ -227,11 +449,109 @@ impl fmt::Display for Stats {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Clone)]
 struct SendToWriter {
     bytes_processed: u64,
     meta_blocks_processed: u64,
     data_or_par_blocks_processed: u64,
+    read_error_ranges: Vec<(u64, u64)>,
+}
+
+fn output_name_from_meta(block: &Block) -> Option<String> {
+    // prefer the container name, as the rescued output is an SBX container
+    let name = match block.get_SNM().unwrap() {
+        Some(x) => file_utils::get_file_name_part_of_path(x),
+        None => match block.get_FNM().unwrap() {
+            Some(x) => match file_utils::get_file_name_part_of_path(x) {
+                Some(x) => Some(format!("{}.sbx", x)),
+                None => None,
+            },
+            None => None,
+        },
+    };
+
+    match name {
+        Some(ref x) if x.is_empty() || x.contains('\n') => None,
+        _ => name,
+    }
+}
+
+fn resolve_name_collision(
+    out_dir: &str,
+    name: &str,
+    uid_names: &BTreeMap<[u8; SBX_FILE_UID_LEN], String>,
+) -> String {
+    let name_is_taken = |x: &str| {
+        x == MANIFEST_FILE_NAME
+            || uid_names.values().any(|y| y == x)
+            || file_utils::check_if_file_exists(&misc_utils::make_path(&[out_dir, x]))
+    };
+
+    let mut candidate = String::from(name);
+    let mut count = 1;
+
+    while name_is_taken(&candidate) {
+        candidate = format!("{}.{}", name, count);
+        count += 1;
+    }
+
+    candidate
+}
+
+fn write_manifest(
+    out_dir: &str,
+    uid_names: &BTreeMap<[u8; SBX_FILE_UID_LEN], String>,
+) -> Result<(), Error> {
+    let mut writer = FileWriter::new(
+        &misc_utils::make_path(&[out_dir, MANIFEST_FILE_NAME]),
+        FileWriterParam {
+            read: false,
+            append: false,
+            truncate: true,
+            buffered: true,
+        },
+    )?;
+
+    for (uid, name) in uid_names.iter() {
+        writer.write(
+            format!("{} {}\n", misc_utils::bytes_to_upper_hex_string(uid), name).as_bytes(),
+        )?;
+    }
+
+    Ok(())
+}
+
+fn name_outputs_from_meta(
+    buffer: &RescueBuffer,
+    out_dir: &str,
+    uid_names: &mut BTreeMap<[u8; SBX_FILE_UID_LEN], String>,
+) -> Result<bool, Error> {
+    let mut updated = false;
+
+    for block in buffer.blocks().iter() {
+        if !block.is_meta() || uid_names.contains_key(&block.get_uid()) {
+            continue;
+        }
+
+        if let Some(name) = output_name_from_meta(block) {
+            let uid = block.get_uid();
+
+            let name = resolve_name_collision(out_dir, &name, uid_names);
+
+            // move blocks rescued before the metadata block was seen
+            let uid_path =
+                misc_utils::make_path(&[out_dir, &misc_utils::bytes_to_upper_hex_string(&uid)]);
+            if file_utils::check_if_file_exists(&uid_path) {
+                file_utils::rename_file(&uid_path, &misc_utils::make_path(&[out_dir, &name]))?;
+            }
+
+            uid_names.insert(uid, name);
+
+            updated = true;
+        }
+    }
+
+    Ok(updated)
 }
 
 pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
@@ -239,20 +559,27 @@ pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
 
     let file_size = file_utils::get_file_size(&param.in_file)?;
 
-    // calulate length to read
-    let RequiredLenAndSeekTo { required_len, .. } =
-        misc_utils::calc_required_len_and_seek_to_from_byte_range(
-            param.from_pos,
-            param.to_pos,
-            param.force_misalign,
-            // 0 is fine here as `bytes_so_far` doesn't affect calculation
-            // of the required length
-            0,
-            PositionOrLength::Len(file_size),
-            None,
-        );
+    // calulate length to read and the starting position of the range
+    let RequiredLenAndSeekTo {
+        required_len,
+        seek_to: start_pos,
+    } = misc_utils::calc_required_len_and_seek_to_from_byte_range(
+        param.from_pos,
+        param.to_pos,
+        param.force_misalign,
+        // 0 is fine here as `bytes_so_far` doesn't affect calculation
+        // of the required length
+        0,
+        PositionOrLength::Len(file_size),
+        None,
+    );
 
-    let stats = Arc::new(Mutex::new(Stats::new(required_len, &param.json_printer)?));
+    let stats = Arc::new(Mutex::new(Stats::new(
+        required_len,
+        start_pos,
+        &param.out_dir,
+        &param.json_printer,
+    )?));
 
     let mut reader = FileReader::new(
         &param.in_file,
@@ -314,6 +641,7 @@ pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
         let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
         let only_pick_block = param.only_pick_block;
         let only_pick_uid = param.only_pick_uid;
+        let skip_read_errors = param.skip_read_errors;
         let stats = stats.lock().unwrap();
         let mut bytes_processed = stats.bytes_processed;
         let mut meta_blocks_processed = stats.meta_blocks_processed;
@@ -327,6 +655,8 @@ pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
                     break;
                 }
 
+                let mut read_error_ranges = Vec::new();
+
                 while !buffer.is_full() {
                     stop_run_if_atomic_bool!(run => ctrlc_stop_flag);
 
@@ -337,7 +667,30 @@ pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
                     let lazy_read_res =
                         match block_utils::read_block_lazily(block, slot, &mut reader) {
                             Ok(lazy_read_res) => lazy_read_res,
-                            Err(e) => stop_run_forward_error!(run => error_tx_reader => e),
+                            Err(e) => {
+                                if !skip_read_errors {
+                                    stop_run_forward_error!(run => error_tx_reader => e);
+                                }
+
+                                // skip over the unreadable region and record it
+                                buffer.cancel_slot();
+
+                                let error_start = start_pos + bytes_processed;
+                                let error_end_exc = u64::ensure_at_most(
+                                    error_start + SBX_SCAN_BLOCK_SIZE as u64,
+                                    start_pos + required_len,
+                                );
+
+                                add_range(&mut read_error_ranges, error_start, error_end_exc);
+
+                                bytes_processed += error_end_exc - error_start;
+
+                                if let Err(e) = reader.seek(SeekFrom::Start(error_end_exc)) {
+                                    stop_run_forward_error!(run => error_tx_reader => e);
+                                }
+
+                                continue;
+                            }
                         };
 
                     bytes_processed += lazy_read_res.len_read as u64;
@@ -381,6 +734,7 @@ pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
                     bytes_processed,
                     meta_blocks_processed,
                     data_or_par_blocks_processed,
+                    read_error_ranges,
                 };
 
                 to_grouper.send(Some((send_to_writer, buffer))).unwrap();
@@ -409,6 +763,8 @@ pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
         let stats = Arc::clone(&stats);
         let out_dir = param.out_dir.clone();
         let log_handler = Arc::clone(&log_handler);
+        let name_from_meta = param.name_from_meta;
+        let mut uid_names = stats.lock().unwrap().uid_names.clone();
 
         thread::spawn(move || {
             while let Some((send_to_writer, mut buffer)) = from_grouper.recv().unwrap() {
@@ -418,27 +774,63 @@ pub fn rescue_from_file(param: &Param) -> Result<Stats, Error> {
                     break;
                 }
 
-                if let Err(e) = buffer.write(&out_dir) {
+                if name_from_meta {
+                    match name_outputs_from_meta(&buffer, &out_dir, &mut uid_names) {
+                        Ok(true) => {
+                            if let Err(e) = write_manifest(&out_dir, &uid_names) {
+                                error_tx_writer.send(e).unwrap();
+                                break;
+                            }
+
+                            stats.lock().unwrap().uid_names = uid_names.clone();
+                        }
+                        Ok(false) => {}
+                        Err(e) => {
+                            error_tx_writer.send(e).unwrap();
+                            break;
+                        }
+                    }
+                }
+
+                if let Err(e) = buffer.write(&out_dir, &uid_names) {
                     error_tx_writer.send(e).unwrap();
                     break;
                 }
 
-                buffer.reset();
-
                 {
                     let SendToWriter {
                         bytes_processed,
                         meta_blocks_processed,
                         data_or_par_blocks_processed,
+                        read_error_ranges,
                     } = send_to_writer;
 
                     let mut stats = stats.lock().unwrap();
 
+                    for block in buffer.blocks().iter() {
+                        let counts = stats
+                            .uid_block_counts
+                            .entry(block.get_uid())
+                            .or_insert((0, 0));
+
+                        if block.is_meta() {
+                            counts.0 += 1;
+                        } else {
+                            counts.1 += 1;
+                        }
+                    }
+
+                    for (start, end_exc) in read_error_ranges.into_iter() {
+                        add_range(&mut stats.read_error_ranges, start, end_exc);
+                    }
+
                     stats.bytes_processed = bytes_processed;
                     stats.meta_blocks_processed = meta_blocks_processed;
                     stats.data_or_par_blocks_processed = data_or_par_blocks_processed;
                 }
 
+                buffer.reset();
+
                 to_reader.send(Some(buffer)).unwrap();
             }
 
@@ -0,0 +1,209 @@
+use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::journal;
+use crate::journal::JournalContent;
+use crate::json_printer::{BracketType, JSONPrinter};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::SeekFrom;
+use std::sync::Arc;
+
+pub struct Param {
+    journal_file: String,
+    container: Option<String>,
+    force: bool,
+    dry_run: bool,
+    json_printer: Arc<JSONPrinter>,
+    verbose: bool,
+}
+
+impl Param {
+    pub fn new(
+        journal_file: &str,
+        container: Option<&str>,
+        force: bool,
+        dry_run: bool,
+        json_printer: &Arc<JSONPrinter>,
+        verbose: bool,
+    ) -> Param {
+        Param {
+            journal_file: String::from(journal_file),
+            container: match container {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            force,
+            dry_run,
+            json_printer: Arc::clone(json_printer),
+            verbose,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    container: String,
+    pub journal_entries: u64,
+    pub incomplete_last_entry: bool,
+    pub entries_restored: u64,
+    pub entries_mismatched: u64,
+    pub container_size: u64,
+    json_printer: Arc<JSONPrinter>,
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json_printer = &self.json_printer;
+
+        json_printer.write_open_bracket(f, Some("stats"), BracketType::Curly)?;
+
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Container                                 : {}",
+            self.container
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of journal entries                 : {}",
+            self.journal_entries
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Incomplete last entry ignored             : {}",
+            self.incomplete_last_entry
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks modified since recorded  : {}",
+            self.entries_mismatched
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks restored                 : {}",
+            self.entries_restored
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Original container size                   : {}",
+            self.container_size
+        )?;
+
+        json_printer.write_close_bracket(f)?;
+
+        Ok(())
+    }
+}
+
+pub fn rollback(param: &Param) -> Result<Option<Stats>, Error> {
+    let json_printer = &param.json_printer;
+
+    let content = JournalContent::read_from_file(&param.journal_file)?;
+
+    let container = match param.container {
+        Some(ref x) => x.clone(),
+        None => content.header.container.clone(),
+    };
+
+    if !file_utils::check_if_file_exists(&container) {
+        return Err(Error::with_msg(&format!(
+            "Container \"{}\" does not exist",
+            container
+        )));
+    }
+
+    let mut writer = FileWriter::new(
+        &container,
+        FileWriterParam {
+            read: true,
+            append: false,
+            truncate: false,
+            buffered: false,
+        },
+    )?;
+
+    let mut stats = Stats {
+        container: container.clone(),
+        journal_entries: content.entries.len() as u64,
+        incomplete_last_entry: content.incomplete_last_entry,
+        entries_restored: 0,
+        entries_mismatched: 0,
+        container_size: content.header.container_size,
+        json_printer: Arc::clone(json_printer),
+    };
+
+    // verify all entries before touching the container, entries are replayed in reverse,
+    // so a later entry at the same position is expected to be undone first
+    let mut restored: HashMap<u64, Vec<u8>> = HashMap::new();
+
+    json_printer.print_open_bracket(Some("modified blocks"), BracketType::Square);
+    for entry in content.entries.iter().rev() {
+        let cur_bytes = match restored.get(&entry.pos) {
+            Some(x) => x.clone(),
+            None => {
+                let mut buffer = vec![0; entry.new_len as usize];
+
+                writer.seek(SeekFrom::Start(entry.pos))?;
+                let read_res = writer.read(&mut buffer)?;
+                buffer.truncate(read_res.len_read);
+
+                buffer
+            }
+        };
+
+        // the block is either what the operation wrote,
+        // or still the original if the operation was interrupted
+        let matches_new = cur_bytes.len() == entry.new_len as usize
+            && journal::calc_crc(&cur_bytes) == entry.new_crc;
+        let matches_orig = cur_bytes == entry.orig_bytes;
+
+        if !matches_new && !matches_orig {
+            stats.entries_mismatched += 1;
+
+            if json_printer.json_enabled() {
+                if param.verbose {
+                    json_printer.print_open_bracket(None, BracketType::Curly);
+                    print_maybe_json!(json_printer, "pos : {}", entry.pos);
+                    json_printer.print_close_bracket();
+                }
+            } else {
+                print_if!(verbose => param =>
+                          "Block at {} (0x{:X}) was modified after the journal entry was recorded", entry.pos, entry.pos;);
+            }
+        }
+
+        restored.insert(entry.pos, entry.orig_bytes.clone());
+    }
+    json_printer.print_close_bracket();
+
+    if stats.entries_mismatched > 0 && !param.force {
+        return Err(Error::with_msg(&format!(
+            "{} block(s) in container \"{}\" were modified after the journal entries were recorded, use --force to restore anyway",
+            stats.entries_mismatched, container
+        )));
+    }
+
+    if !param.dry_run {
+        for entry in content.entries.iter().rev() {
+            writer.seek(SeekFrom::Start(entry.pos))?;
+            writer.write(&entry.orig_bytes)?;
+
+            stats.entries_restored += 1;
+        }
+
+        // blocks written past the original end of container are dropped
+        if writer.get_file_size()? != content.header.container_size {
+            writer.set_len(content.header.container_size)?;
+        }
+
+        writer.sync()?;
+    }
+
+    Ok(Some(stats))
+}
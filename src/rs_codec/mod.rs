@@ -20,7 +20,6 @@ macro_rules! assert_ready {
 
 mod repairer;
 mod repairer_tests;
-pub use self::repairer::RSRepairStats;
 pub use self::repairer::RSRepairer;
 
 #[must_use]
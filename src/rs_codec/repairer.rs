@@ -4,13 +4,17 @@ use crate::json_printer::{BracketType, JSONPrinter};
 use crate::sbx_block;
 use crate::sbx_block::Block;
 use crate::sbx_specs::{
-    ver_to_block_size, Version, SBX_FIRST_DATA_SEQ_NUM, SBX_LARGEST_BLOCK_SIZE,
+    ver_to_block_size, Version, SBX_FIRST_DATA_SEQ_NUM, SBX_HEADER_SIZE, SBX_LARGEST_BLOCK_SIZE,
 };
 use reed_solomon_erasure::galois_8::ReedSolomon;
 use smallvec::SmallVec;
 use std::fmt;
 use std::sync::Arc;
 
+const SALVAGE_MAX_TRIES: usize = 1 << 16;
+
+type ColumnOptions = Vec<SmallVec<[[u8; 1]; 32]>>;
+
 pub struct RSRepairer {
     index: usize,
     rs_codec: ReedSolomon,
@@ -18,6 +22,8 @@ pub struct RSRepairer {
     version: Version,
     buf: SmallVec<[SmallVec<[u8; SBX_LARGEST_BLOCK_SIZE]>; 32]>,
     buf_present: SmallVec<[bool; 32]>,
+    buf_candidate: SmallVec<[bool; 32]>,
+    buf_corrected: SmallVec<[usize; 32]>,
     ref_block: Block,
     active: bool,
     json_printer: Arc<JSONPrinter>,
@@ -27,8 +33,11 @@ pub struct RSRepairStats<'a> {
     pub version: Version,
     pub data_par_burst: (usize, usize, usize),
     pub successful: bool,
+    pub salvaged: bool,
     pub start_seq_num: u32,
     pub present: &'a SmallVec<[bool; 32]>,
+    pub candidate: &'a SmallVec<[bool; 32]>,
+    pub corrected: &'a SmallVec<[usize; 32]>,
     pub missing_count: usize,
     pub present_count: usize,
     json_printer: Arc<JSONPrinter>,
@@ -52,6 +61,12 @@ impl<'a> fmt::Display for RSRepairStats<'a> {
                     write_maybe_json!(f, json_printer, "success : false")?;
                 }
 
+                if self.salvaged {
+                    write_maybe_json!(f, json_printer, "salvaged : true")?;
+                } else {
+                    write_maybe_json!(f, json_printer, "salvaged : false")?;
+                }
+
                 write_maybe_json!(f, json_printer, "block set start : {}", self.start_seq_num)?;
                 write_maybe_json!(
                     f,
@@ -79,6 +94,24 @@ impl<'a> fmt::Display for RSRepairStats<'a> {
                             write_maybe_json!(f, json_printer, "seq num : {}", seq_num)?;
                             write_maybe_json!(f, json_printer, "pos : {}", block_pos)?;
 
+                            if self.salvaged {
+                                if self.candidate[i] {
+                                    write_maybe_json!(
+                                        f,
+                                        json_printer,
+                                        "corrected bytes : {}",
+                                        self.corrected[i]
+                                    )?;
+                                } else {
+                                    write_maybe_json!(
+                                        f,
+                                        json_printer,
+                                        "corrected bytes : {}",
+                                        null_if_json_else_NA!(json_printer)
+                                    )?;
+                                }
+                            }
+
                             json_printer.write_close_bracket(f)?;
                         }
                     }
@@ -94,7 +127,9 @@ impl<'a> fmt::Display for RSRepairStats<'a> {
             }
         } else {
             if self.missing_count > 0 {
-                if self.successful {
+                if self.salvaged {
+                    write!(f, "Salvage successful for ")?;
+                } else if self.successful {
                     write!(f, "Repair successful for ")?;
                 } else {
                     write!(f, "Repair failed     for ")?;
@@ -130,6 +165,14 @@ impl<'a> fmt::Display for RSRepairStats<'a> {
 
                         write!(f, "{} at byte {} (0x{:X})", seq_num, block_pos, block_pos)?;
 
+                        if self.salvaged {
+                            if self.candidate[i] {
+                                write!(f, ", {} byte(s) corrected", self.corrected[i])?;
+                            } else {
+                                write!(f, ", reconstructed")?;
+                            }
+                        }
+
                         first_num = false;
                     }
                 }
@@ -195,6 +238,8 @@ impl RSRepairer {
         let buf: SmallVec<[SmallVec<[u8; SBX_LARGEST_BLOCK_SIZE]>; 32]> =
             smallvec![smallvec![0; block_size]; data_shards + parity_shards];
         let buf_present: SmallVec<[bool; 32]> = smallvec![false; data_shards + parity_shards];
+        let buf_candidate: SmallVec<[bool; 32]> = smallvec![false; data_shards + parity_shards];
+        let buf_corrected: SmallVec<[usize; 32]> = smallvec![0; data_shards + parity_shards];
 
         RSRepairer {
             index: 0,
@@ -203,6 +248,8 @@ impl RSRepairer {
             version,
             buf,
             buf_present,
+            buf_candidate,
+            buf_corrected,
             ref_block: ref_block.clone(),
             active: false,
             json_printer: Arc::clone(json_printer),
@@ -231,6 +278,7 @@ impl RSRepairer {
         assert_not_ready!(self);
 
         self.buf_present[self.index] = true;
+        self.buf_candidate[self.index] = false;
 
         incre_index!(self);
 
@@ -247,6 +295,7 @@ impl RSRepairer {
         assert_not_ready!(self);
 
         self.buf_present[self.index] = false;
+        self.buf_candidate[self.index] = false;
 
         incre_index!(self);
 
@@ -259,6 +308,172 @@ impl RSRepairer {
         }
     }
 
+    // for blocks which have an intact header but fail the CRC check,
+    // the block is only used if the block set cannot be repaired otherwise
+    pub fn mark_candidate(&mut self) -> RSCodecState {
+        assert_not_ready!(self);
+
+        self.buf_present[self.index] = false;
+        self.buf_candidate[self.index] = true;
+
+        incre_index!(self);
+
+        mark_active!(self);
+
+        if codec_ready!(self) {
+            RSCodecState::Ready
+        } else {
+            RSCodecState::NotReady
+        }
+    }
+
+    // Reed-Solomon works on each byte column independently, so each column is
+    // reconstructed separately while dropping as few candidate blocks as possible.
+    //
+    // A column which can only be reconstructed by dropping the maximum number of
+    // candidates cannot be checked on its own, so all such options are collected,
+    // and the candidate CRCs decide which combination of options is accepted
+    fn salvage(&mut self) -> bool {
+        let data_shards = self.rs_codec.data_shard_count();
+        let parity_shards = self.rs_codec.parity_shard_count();
+        let total_shards = self.rs_codec.total_shard_count();
+
+        let candidates: SmallVec<[usize; 32]> = (0..total_shards)
+            .filter(|&i| self.buf_candidate[i])
+            .collect();
+
+        let missing_count = self.missing_count() - candidates.len();
+
+        if candidates.is_empty() || missing_count > parity_shards {
+            return false;
+        }
+
+        let max_drop_count = std::cmp::min(parity_shards - missing_count, candidates.len());
+
+        let orig_candidates: SmallVec<[SmallVec<[u8; SBX_LARGEST_BLOCK_SIZE]>; 32]> =
+            candidates.iter().map(|&i| self.buf[i].clone()).collect();
+
+        let mut ambiguous_columns: Vec<(usize, ColumnOptions)> = Vec::new();
+
+        let block_size = ver_to_block_size(self.version);
+
+        for col in SBX_HEADER_SIZE..block_size {
+            let mut options: ColumnOptions = Vec::new();
+
+            for drop_count in 0..=max_drop_count {
+                let checkable = total_shards - missing_count - drop_count > data_shards;
+
+                // indices into candidates of the candidates treated as missing
+                let mut dropped: SmallVec<[usize; 32]> = (0..drop_count).collect();
+
+                loop {
+                    let mut column: SmallVec<[[u8; 1]; 32]> =
+                        self.buf.iter().map(|b| [b[col]]).collect();
+                    let mut usable: SmallVec<[bool; 32]> = self.buf_present.clone();
+                    for (k, &i) in candidates.iter().enumerate() {
+                        usable[i] = !dropped.contains(&k);
+                    }
+
+                    let reconstructed = {
+                        let mut shards: SmallVec<[(&mut [u8], bool); 32]> = column
+                            .iter_mut()
+                            .zip(usable.iter())
+                            .map(|(b, &u)| (&mut b[..], u))
+                            .collect();
+
+                        self.rs_codec.reconstruct(&mut shards).is_ok()
+                    };
+
+                    if reconstructed {
+                        if !checkable {
+                            // different candidates dropped can lead to the same column,
+                            // which would only multiply the combinations tried below
+                            if !options.contains(&column) {
+                                options.push(column);
+                            }
+                        } else if self.rs_codec.verify(&column).unwrap_or(false) {
+                            options.push(column);
+                            break;
+                        }
+                    }
+
+                    if !next_combination(&mut dropped, candidates.len()) {
+                        break;
+                    }
+                }
+
+                if !options.is_empty() {
+                    break;
+                }
+            }
+
+            match options.len() {
+                0 => return false,
+                1 => self.write_column(col, &options[0]),
+                _ => ambiguous_columns.push((col, options)),
+            }
+        }
+
+        // go through all combinations of the options of ambiguous columns
+        let mut tries: usize = 1;
+        for (_, options) in ambiguous_columns.iter() {
+            tries = match tries.checked_mul(options.len()) {
+                Some(x) if x <= SALVAGE_MAX_TRIES => x,
+                _ => return false,
+            };
+        }
+
+        let mut choices: SmallVec<[usize; 32]> = smallvec![0; ambiguous_columns.len()];
+
+        let mut accepted = false;
+
+        for _ in 0..tries {
+            for (&choice, (col, options)) in choices.iter().zip(ambiguous_columns.iter()) {
+                self.write_column(*col, &options[choice]);
+            }
+
+            // accept the result only if all candidate blocks now pass their own CRC check
+            let mut block = Block::dummy();
+            if candidates.iter().all(|&i| {
+                block
+                    .sync_from_buffer(sbx_block::slice_buf(self.version, &self.buf[i]), None, None)
+                    .is_ok()
+            }) {
+                accepted = true;
+                break;
+            }
+
+            for (choice, (_, options)) in choices.iter_mut().zip(ambiguous_columns.iter()) {
+                *choice += 1;
+                if *choice < options.len() {
+                    break;
+                }
+                *choice = 0;
+            }
+        }
+
+        for i in 0..total_shards {
+            self.buf_corrected[i] = 0;
+        }
+        for (&i, orig) in candidates.iter().zip(orig_candidates.iter()) {
+            self.buf_corrected[i] = self.buf[i]
+                .iter()
+                .zip(orig.iter())
+                .filter(|(x, y)| x != y)
+                .count();
+        }
+
+        accepted
+    }
+
+    fn write_column(&mut self, col: usize, column: &[[u8; 1]]) {
+        for (i, b) in column.iter().enumerate() {
+            if !self.buf_present[i] {
+                self.buf[i][col] = b[0];
+            }
+        }
+    }
+
     fn missing_count(&self) -> usize {
         self.rs_codec.total_shard_count() - self.present_count()
     }
@@ -299,6 +514,10 @@ impl RSRepairer {
             }
         };
 
+        let salvaged = !successful && self.salvage();
+
+        let successful = successful || salvaged;
+
         let block_set_size = self.rs_codec.total_shard_count() as u32;
 
         let data_index = seq_num - SBX_FIRST_DATA_SEQ_NUM;
@@ -343,9 +562,12 @@ impl RSRepairer {
                 version: self.version,
                 data_par_burst: self.data_par_burst,
                 successful,
+                salvaged,
                 json_printer: Arc::clone(&self.json_printer),
                 start_seq_num: first_seq_num_in_cur_set,
                 present: &self.buf_present,
+                candidate: &self.buf_candidate,
+                corrected: &self.buf_corrected,
                 missing_count: self.missing_count(),
                 present_count: self.present_count(),
             },
@@ -353,3 +575,20 @@ impl RSRepairer {
         )
     }
 }
+
+// advances the sorted indices to the next combination of the same size
+fn next_combination(indices: &mut [usize], n: usize) -> bool {
+    let k = indices.len();
+
+    for i in (0..k).rev() {
+        if indices[i] < n - k + i {
+            indices[i] += 1;
+            for j in i + 1..k {
+                indices[j] = indices[j - 1] + 1;
+            }
+            return true;
+        }
+    }
+
+    false
+}
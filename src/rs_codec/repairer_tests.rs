@@ -509,3 +509,149 @@ quickcheck! {
         true
     }
 }
+
+macro_rules! make_encoded_block_set {
+    ($version:expr, $data:expr, $parity:expr) => {{
+        let r = ReedSolomon::new($data, $parity).unwrap();
+
+        let mut buffer = make_random_block_buffers!(512, $data + $parity);
+
+        {
+            let mut refs = Vec::new();
+            for b in buffer.iter_mut() {
+                refs.push(sbx_block::slice_data_buf_mut($version, b));
+            }
+
+            r.encode(&mut refs).unwrap();
+        }
+
+        let mut block = Block::new($version, &[0; 6], BlockType::Data);
+        for (i, b) in buffer.iter_mut().enumerate() {
+            block.set_seq_num(1 + i as u32);
+            block.sync_to_buffer(None, b).unwrap();
+        }
+
+        buffer
+    }};
+}
+
+#[test]
+fn test_repairer_salvage_simple_cases() {
+    let json_printer = Arc::new(JSONPrinter::new(false, OutputChannel::Stdout));
+
+    let version = Version::V17;
+
+    let ref_block = Block::new(version, &[0; 6], BlockType::Data);
+    let mut repairer = RSRepairer::new(&json_printer, &ref_block, 10, 2, 0);
+
+    let orig = make_encoded_block_set!(version, 10, 2);
+
+    let mut corrupted = orig.clone();
+    // flip bytes in data portion of 2 and 7 in different columns
+    corrupted[2][100] ^= 0xFF;
+    corrupted[2][200] ^= 0x01;
+    corrupted[7][300] ^= 0x10;
+
+    // 0 is missing, 2 and 7 fail CRC check, so plain repair would fail
+    for (i, b) in corrupted.iter().enumerate() {
+        repairer.get_block_buffer().copy_from_slice(b);
+        let codec_state = if i == 0 {
+            repairer.mark_missing()
+        } else if i == 2 || i == 7 {
+            repairer.mark_candidate()
+        } else {
+            repairer.mark_present()
+        };
+
+        if i == 11 {
+            assert_eq!(RSCodecState::Ready, codec_state);
+        }
+    }
+
+    let (stats, blocks) = repairer.repair_with_block_sync(1);
+
+    assert!(stats.successful);
+    assert!(stats.salvaged);
+    assert_eq!(3, stats.missing_count);
+    assert_eq!(2, stats.corrected[2]);
+    assert_eq!(1, stats.corrected[7]);
+
+    assert_eq!(3, blocks.len());
+    assert_eq!(&orig[0][..], blocks[0].1);
+    assert_eq!(&orig[2][..], blocks[1].1);
+    assert_eq!(&orig[7][..], blocks[2].1);
+}
+
+#[test]
+fn test_repairer_salvage_fails_when_column_is_beyond_repair() {
+    let json_printer = Arc::new(JSONPrinter::new(false, OutputChannel::Stdout));
+
+    let version = Version::V17;
+
+    let ref_block = Block::new(version, &[0; 6], BlockType::Data);
+    let mut repairer = RSRepairer::new(&json_printer, &ref_block, 10, 2, 0);
+
+    let orig = make_encoded_block_set!(version, 10, 2);
+
+    let mut corrupted = orig.clone();
+    // both candidates are damaged in the same column,
+    // which leaves more unknown bytes than parity in that column
+    corrupted[2][100] ^= 0xFF;
+    corrupted[7][100] ^= 0x10;
+
+    for (i, b) in corrupted.iter().enumerate() {
+        repairer.get_block_buffer().copy_from_slice(b);
+        let codec_state = if i == 0 {
+            repairer.mark_missing()
+        } else if i == 2 || i == 7 {
+            repairer.mark_candidate()
+        } else {
+            repairer.mark_present()
+        };
+
+        if i == 11 {
+            assert_eq!(RSCodecState::Ready, codec_state);
+        }
+    }
+
+    let (stats, blocks) = repairer.repair_with_block_sync(1);
+
+    assert!(!stats.successful);
+    assert!(!stats.salvaged);
+    assert_eq!(0, blocks.len());
+}
+
+#[test]
+fn test_repairer_salvage_not_used_when_repair_succeeds() {
+    let json_printer = Arc::new(JSONPrinter::new(false, OutputChannel::Stdout));
+
+    let version = Version::V17;
+
+    let ref_block = Block::new(version, &[0; 6], BlockType::Data);
+    let mut repairer = RSRepairer::new(&json_printer, &ref_block, 10, 2, 0);
+
+    let orig = make_encoded_block_set!(version, 10, 2);
+
+    let mut corrupted = orig.clone();
+    corrupted[2][100] ^= 0xFF;
+
+    for (i, b) in corrupted.iter().enumerate() {
+        repairer.get_block_buffer().copy_from_slice(b);
+        let codec_state = if i == 2 {
+            repairer.mark_candidate()
+        } else {
+            repairer.mark_present()
+        };
+
+        if i == 11 {
+            assert_eq!(RSCodecState::Ready, codec_state);
+        }
+    }
+
+    let (stats, blocks) = repairer.repair_with_block_sync(1);
+
+    assert!(stats.successful);
+    assert!(!stats.salvaged);
+    assert_eq!(1, blocks.len());
+    assert_eq!(&orig[2][..], blocks[0].1);
+}
@@ -6,6 +6,7 @@ use crate::time_utils;
 use std;
 use std::fmt;
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Debug, PartialEq)]
 pub enum Metadata {
     FNM(String),
@@ -16,6 +17,8 @@ pub enum Metadata {
     HSH(multihash::HashBytes),
     RSD(u8),
     RSP(u8),
+    SPR(u64),
+    TRL(u8),
 }
 
 impl fmt::Display for Metadata {
@@ -43,10 +46,13 @@ impl fmt::Display for Metadata {
             ),
             RSD(x) => write!(f, "{}", *x),
             RSP(x) => write!(f, "{}", *x),
+            SPR(x) => write!(f, "{}", *x),
+            TRL(x) => write!(f, "{}", *x),
         }
     }
 }
 
+#[allow(clippy::upper_case_acronyms)]
 pub enum UncheckedMetadata {
     FNM(Vec<u8>),
     SNM(Vec<u8>),
@@ -56,8 +62,11 @@ pub enum UncheckedMetadata {
     HSH(multihash::HashBytes),
     RSD(u8),
     RSP(u8),
+    SPR(u64),
+    TRL(u8),
 }
 
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Clone, Copy, Debug, PartialEq)]
 pub enum MetadataID {
     FNM,
@@ -68,6 +77,8 @@ pub enum MetadataID {
     HSH,
     RSD,
     RSP,
+    SPR,
+    TRL,
 }
 
 static PREAMBLE_LEN: usize = 3 + 1;
@@ -77,9 +88,9 @@ fn single_info_size(meta: &Metadata) -> usize {
     use std::mem;
     match *meta {
         FNM(ref x) | SNM(ref x) => x.len(),
-        FSZ(_) | FDT(_) | SDT(_) => mem::size_of::<u64>(),
+        FSZ(_) | FDT(_) | SDT(_) | SPR(_) => mem::size_of::<u64>(),
         HSH(ref x) => multihash::specs::Param::new(x.0).total_length(),
-        RSD(_) | RSP(_) => mem::size_of::<u8>(),
+        RSD(_) | RSP(_) | TRL(_) => mem::size_of::<u8>(),
     }
 }
 
@@ -98,6 +109,8 @@ pub fn id_to_bytes(id: MetadataID) -> [u8; 3] {
         HSH => [b'H', b'S', b'H'],
         RSD => [b'R', b'S', b'D'],
         RSP => [b'R', b'S', b'P'],
+        SPR => [b'S', b'P', b'R'],
+        TRL => [b'T', b'R', b'L'],
     }
 }
 
@@ -112,6 +125,25 @@ pub fn id_to_str(id: MetadataID) -> &'static str {
         HSH => "HSH",
         RSD => "RSD",
         RSP => "RSP",
+        SPR => "SPR",
+        TRL => "TRL",
+    }
+}
+
+pub fn str_to_id(string: &str) -> Option<MetadataID> {
+    use self::MetadataID::*;
+    match string.to_uppercase().as_str() {
+        "FNM" => Some(FNM),
+        "SNM" => Some(SNM),
+        "FSZ" => Some(FSZ),
+        "FDT" => Some(FDT),
+        "SDT" => Some(SDT),
+        "HSH" => Some(HSH),
+        "RSD" => Some(RSD),
+        "RSP" => Some(RSP),
+        "SPR" => Some(SPR),
+        "TRL" => Some(TRL),
+        _ => None,
     }
 }
 
@@ -125,6 +157,8 @@ pub fn meta_to_id(meta: &Metadata) -> MetadataID {
         Metadata::HSH(_) => MetadataID::HSH,
         Metadata::RSD(_) => MetadataID::RSD,
         Metadata::RSP(_) => MetadataID::RSP,
+        Metadata::SPR(_) => MetadataID::SPR,
+        Metadata::TRL(_) => MetadataID::TRL,
     }
 }
 
@@ -154,7 +188,7 @@ fn single_to_bytes(meta: &Metadata, buffer: &mut [u8]) -> Result<usize, ()> {
         FNM(ref x) | SNM(ref x) => {
             dst.copy_from_slice(x.as_bytes());
         }
-        FSZ(x) => {
+        FSZ(x) | SPR(x) => {
             let be_bytes: [u8; 8] = unsafe { std::mem::transmute::<u64, [u8; 8]>(x.to_be()) };
             dst.copy_from_slice(&be_bytes);
         }
@@ -165,7 +199,7 @@ fn single_to_bytes(meta: &Metadata, buffer: &mut [u8]) -> Result<usize, ()> {
         HSH(ref x) => {
             multihash::hash_bytes_to_bytes(x, dst);
         }
-        RSD(x) | RSP(x) => {
+        RSD(x) | RSP(x) | TRL(x) => {
             dst[0] = x;
         }
     }
@@ -272,6 +306,8 @@ mod parsers {
     make_meta_parser!(sdt_p, b"SDT", SDT => num, 8, be_i64);
     make_meta_parser!(rsd_p, b"RSD", RSD => num, 1, be_u8);
     make_meta_parser!(rsp_p, b"RSP", RSP => num, 1, be_u8);
+    make_meta_parser!(spr_p, b"SPR", SPR => num, 8, be_u64);
+    make_meta_parser!(trl_p, b"TRL", TRL => num, 1, be_u8);
 
     named!(
         hsh_p<UncheckedMetadata>,
@@ -289,6 +325,8 @@ mod parsers {
                        | complete!(hsh_p)
                        | complete!(rsd_p)
                        | complete!(rsp_p)
+                       | complete!(spr_p)
+                       | complete!(trl_p)
                )
            )
     );
@@ -314,6 +352,8 @@ pub fn filter_invalid_metadata(input: Vec<UncheckedMetadata>) -> Vec<Metadata> {
             FSZ(x) => Some(Metadata::FSZ(x)),
             FDT(x) => Some(Metadata::FDT(x)),
             SDT(x) => Some(Metadata::SDT(x)),
+            SPR(x) => Some(Metadata::SPR(x)),
+            TRL(x) => Some(Metadata::TRL(x)),
             HSH(h) => Some(Metadata::HSH(h)),
             RSD(d) => {
                 if 1 <= d {
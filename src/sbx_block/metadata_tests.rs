@@ -114,6 +114,32 @@ fn test_to_bytes_simple_cases() {
 
         assert_eq!(*expect, *&buffer[0..expect.len()]);
 
+        for i in expect.len()..buffer.len() {
+            assert_eq!(buffer[i], 0x1A);
+        }
+    }
+    {
+        let expect = b"SPR\x08\x01\x23\x45\x67\x89\xAB\xCD\xEF";
+        let meta = [Metadata::SPR(0x01234567_89ABCDEF)];
+
+        let mut buffer: [u8; 100] = [0; 100];
+        metadata::to_bytes(&meta, &mut buffer).unwrap();
+
+        assert_eq!(*expect, *&buffer[0..expect.len()]);
+
+        for i in expect.len()..buffer.len() {
+            assert_eq!(buffer[i], 0x1A);
+        }
+    }
+    {
+        let expect = b"TRL\x01\x01";
+        let meta = [Metadata::TRL(1)];
+
+        let mut buffer: [u8; 100] = [0; 100];
+        metadata::to_bytes(&meta, &mut buffer).unwrap();
+
+        assert_eq!(*expect, *&buffer[0..expect.len()]);
+
         for i in expect.len()..buffer.len() {
             assert_eq!(buffer[i], 0x1A);
         }
@@ -202,6 +228,24 @@ fn test_from_bytes_simple_cases() {
         let metas = metadata::from_bytes(input).unwrap();
         assert_eq!(1, metas.len());
 
+        assert_eq!(expect, metas[0]);
+    }
+    {
+        let input = b"SPR\x08\x01\x23\x45\x67\x89\xAB\xCD\xEF";
+        let expect = Metadata::SPR(0x01234567_89ABCDEF);
+
+        let metas = metadata::from_bytes(input).unwrap();
+        assert_eq!(1, metas.len());
+
+        assert_eq!(expect, metas[0]);
+    }
+    {
+        let input = b"TRL\x01\x01";
+        let expect = Metadata::TRL(1);
+
+        let metas = metadata::from_bytes(input).unwrap();
+        assert_eq!(1, metas.len());
+
         assert_eq!(expect, metas[0]);
     }
 }
@@ -218,6 +262,21 @@ fn test_id_to_str() {
     assert_eq!(metadata::id_to_str(HSH), "HSH");
     assert_eq!(metadata::id_to_str(RSD), "RSD");
     assert_eq!(metadata::id_to_str(RSP), "RSP");
+    assert_eq!(metadata::id_to_str(SPR), "SPR");
+    assert_eq!(metadata::id_to_str(TRL), "TRL");
+}
+
+#[test]
+fn test_str_to_id() {
+    use super::metadata::MetadataID::*;
+
+    for &id in [FNM, SNM, FSZ, FDT, SDT, HSH, RSD, RSP, SPR, TRL].iter() {
+        assert_eq!(Some(id), metadata::str_to_id(metadata::id_to_str(id)));
+    }
+
+    assert_eq!(Some(FDT), metadata::str_to_id("fdt"));
+    assert_eq!(None, metadata::str_to_id("ABC"));
+    assert_eq!(None, metadata::str_to_id(""));
 }
 
 #[test]
@@ -239,6 +298,8 @@ fn test_meta_to_id() {
     );
     assert_eq!(metadata::meta_to_id(&Metadata::RSD(0)), MetadataID::RSD);
     assert_eq!(metadata::meta_to_id(&Metadata::RSP(0)), MetadataID::RSP);
+    assert_eq!(metadata::meta_to_id(&Metadata::SPR(0)), MetadataID::SPR);
+    assert_eq!(metadata::meta_to_id(&Metadata::TRL(0)), MetadataID::TRL);
 }
 
 #[test]
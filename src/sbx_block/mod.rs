@@ -22,6 +22,10 @@ pub fn meta_id_to_str(id: MetadataID) -> &'static str {
     self::metadata::id_to_str(id)
 }
 
+pub fn str_to_meta_id(string: &str) -> Option<MetadataID> {
+    self::metadata::str_to_id(string)
+}
+
 pub fn get_meta_ref_by_meta_id(metas: &[Metadata], id: MetadataID) -> Option<&Metadata> {
     self::metadata::get_meta_ref_by_id(metas, id)
 }
@@ -679,6 +683,8 @@ impl Block {
     make_meta_getter!(get_HSH => HSH => ret_ref multihash::HashBytes);
     make_meta_getter!(get_RSD => RSD => ret_val u8);
     make_meta_getter!(get_RSP => RSP => ret_val u8);
+    make_meta_getter!(get_SPR => SPR => ret_val u64);
+    make_meta_getter!(get_TRL => TRL => ret_val u8);
 
     pub fn metas(&self) -> Result<&Vec<Metadata>, Error> {
         match self.data {
@@ -794,6 +800,14 @@ impl Block {
         }
     }
 
+    pub fn sync_to_buffer_header_only(&self, buffer: &mut [u8]) -> Result<(), Error> {
+        check_buffer!(self, buffer);
+
+        self.header.to_bytes(slice_buf!(header_mut => self, buffer));
+
+        Ok(())
+    }
+
     pub fn sync_from_buffer_header_only(&mut self, buffer: &[u8]) -> Result<(), Error> {
         self.header.from_bytes(slice_buf!(header => self, buffer))?;
 
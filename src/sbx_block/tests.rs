@@ -547,6 +547,30 @@ fn test_sync_from_buffer_simple_cases() {
     }
 }
 
+#[test]
+fn test_sync_to_buffer_header_only_changes_uid() {
+    let mut block = Block::new(sbx_specs::Version::V1, &[3; 6], BlockType::Data);
+
+    let mut buffer: [u8; 512] = [0; 512];
+    rand_utils::fill_random_bytes(&mut buffer[16..]);
+
+    block.set_seq_num(5);
+    block.sync_to_buffer(None, &mut buffer).unwrap();
+
+    let data = buffer[16..].to_vec();
+
+    block.set_uid([7; 6]);
+    block.update_crc(&buffer);
+    block.sync_to_buffer_header_only(&mut buffer).unwrap();
+
+    let mut new_block = Block::dummy();
+    new_block.sync_from_buffer(&buffer, None, None).unwrap();
+
+    assert_eq!([7; 6], new_block.get_uid());
+    assert_eq!(5, new_block.get_seq_num());
+    assert_eq!(&data[..], &buffer[16..]);
+}
+
 #[test]
 fn test_seq_num_is_parity_simple_cases() {
     assert_eq!(false, seq_num_is_parity(0, 0, 0));
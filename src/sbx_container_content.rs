@@ -8,6 +8,8 @@ use crate::progress_report::{PRVerbosityLevel, ProgressReporter};
 use crate::sbx_block;
 use crate::sbx_block::Block;
 use crate::sbx_specs::ver_to_data_size;
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
 use std::io::SeekFrom;
 use std::sync::atomic::AtomicBool;
 use std::sync::mpsc::channel;
@@ -26,6 +28,7 @@ pub fn hash(
     in_file: &str,
     orig_file_size: u64,
     ref_block: &Block,
+    sparse_map: &Option<SparseMap>,
     mut hash_ctx: hash::Ctx,
 ) -> Result<(HashStats, HashBytes), Error> {
     let stats = Arc::new(Mutex::new(HashStats::new(orig_file_size)));
@@ -52,6 +55,8 @@ pub fn hash(
 
     let header_pred = header_pred_same_ver_uid!(ref_block);
 
+    let uid = ref_block.get_uid();
+
     let (to_hasher, from_reader) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
     let (to_reader, from_hasher) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
     let (error_tx_reader, error_rx) = channel::<Error>();
@@ -81,6 +86,7 @@ pub fn hash(
         let ctrlc_stop_flag = Arc::clone(ctrlc_stop_flag);
         let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
         let stats = Arc::clone(&stats);
+        let sparse_map = sparse_map.clone();
 
         thread::spawn(move || {
             let mut run = true;
@@ -114,12 +120,23 @@ pub fn hash(
                     } = buffer.get_slot().unwrap();
                     match reader.read(slot) {
                         Ok(read_res) => {
-                            let decode_successful = !read_res.eof_seen
+                            let mut decode_successful = !read_res.eof_seen
                                 && match block.sync_from_buffer(slot, Some(&header_pred), None) {
                                     Ok(_) => block.get_seq_num() == seq_num,
                                     _ => false,
                                 };
 
+                            // omitted block of a sparse container
+                            if !decode_successful
+                                && !read_res.eof_seen
+                                && sparse_map::block_is_omitted(&sparse_map, seq_num, slot)
+                            {
+                                sparse_map::fill_omitted_block(version, &uid, seq_num, slot);
+                                block.sync_from_buffer(slot, None, None).unwrap();
+
+                                decode_successful = true;
+                            }
+
                             let bytes_remaining = total_bytes - bytes_processed;
 
                             if sbx_block::seq_num_is_parity_w_data_par_burst(
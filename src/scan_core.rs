@@ -0,0 +1,605 @@
+use crate::block_utils;
+use crate::cli_utils::setup_ctrlc_handler;
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::general_error::Error;
+use crate::json_printer::{BracketType, JSONPrinter};
+use crate::misc_utils;
+use crate::misc_utils::RequiredLenAndSeekTo;
+use crate::misc_utils::{PositionOrLength, RangeEnd};
+use crate::multihash::*;
+use crate::progress_report::*;
+use crate::sbx_block;
+use crate::sbx_block::Block;
+use crate::sbx_specs::{ver_to_usize, ver_uses_rs, Version};
+use crate::sbx_specs::{SBX_FILE_UID_LEN, SBX_FIRST_DATA_SEQ_NUM, SBX_LARGEST_BLOCK_SIZE};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::io::SeekFrom;
+use std::sync::{Arc, Mutex};
+
+pub struct Param {
+    force_misalign: bool,
+    json_printer: Arc<JSONPrinter>,
+    from_pos: Option<u64>,
+    to_pos: Option<RangeEnd<u64>>,
+    in_file: String,
+    only_pick_uid: Option<[u8; SBX_FILE_UID_LEN]>,
+    pr_verbosity_level: PRVerbosityLevel,
+}
+
+impl Param {
+    pub fn new(
+        force_misalign: bool,
+        json_printer: &Arc<JSONPrinter>,
+        from_pos: Option<u64>,
+        to_pos: Option<RangeEnd<u64>>,
+        in_file: &str,
+        only_pick_uid: Option<&[u8; SBX_FILE_UID_LEN]>,
+        pr_verbosity_level: PRVerbosityLevel,
+    ) -> Param {
+        Param {
+            force_misalign,
+            json_printer: Arc::clone(json_printer),
+            from_pos,
+            to_pos,
+            in_file: String::from(in_file),
+            only_pick_uid: only_pick_uid.copied(),
+            pr_verbosity_level,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Recoverability {
+    Unknown,
+    DataBlocksMissing(u64),
+    BlockSetsUnrecoverable(u64),
+}
+
+#[derive(Clone, Debug)]
+pub struct UidInventory {
+    uid: [u8; SBX_FILE_UID_LEN],
+    version: Version,
+    first_found_at: u64,
+    meta_blocks: u64,
+    data_or_par_blocks: u64,
+    seq_nums: BTreeSet<u32>,
+    file_name: Option<String>,
+    file_size: Option<u64>,
+    hash: Option<HashBytes>,
+    rs_data: Option<usize>,
+    rs_parity: Option<usize>,
+}
+
+impl UidInventory {
+    pub fn new(block: &Block, found_at: u64) -> UidInventory {
+        UidInventory {
+            uid: block.get_uid(),
+            version: block.get_version(),
+            first_found_at: found_at,
+            meta_blocks: 0,
+            data_or_par_blocks: 0,
+            seq_nums: BTreeSet::new(),
+            file_name: None,
+            file_size: None,
+            hash: None,
+            rs_data: None,
+            rs_parity: None,
+        }
+    }
+
+    pub fn add_block(&mut self, block: &Block) {
+        if block.is_meta() {
+            self.meta_blocks += 1;
+
+            // only record the first metadata found for each field
+            if self.file_name.is_none() {
+                self.file_name = block.get_FNM().unwrap().map(String::from);
+            }
+            if self.file_size.is_none() {
+                self.file_size = block.get_FSZ().unwrap();
+            }
+            if self.hash.is_none() {
+                self.hash = block.get_HSH().unwrap().cloned();
+            }
+            if self.rs_data.is_none() {
+                self.rs_data = block.get_RSD().unwrap().map(|x| x as usize);
+            }
+            if self.rs_parity.is_none() {
+                self.rs_parity = block.get_RSP().unwrap().map(|x| x as usize);
+            }
+        } else {
+            self.data_or_par_blocks += 1;
+            self.seq_nums.insert(block.get_seq_num());
+        }
+    }
+
+    fn data_par(&self) -> Option<(usize, usize)> {
+        match (self.rs_data, self.rs_parity) {
+            (Some(d), Some(p)) => Some((d, p)),
+            _ => None,
+        }
+    }
+
+    pub fn data_and_parity_block_counts(&self) -> (u64, Option<u64>) {
+        if !ver_uses_rs(self.version) {
+            return (self.seq_nums.len() as u64, Some(0));
+        }
+
+        match self.data_par() {
+            None => (self.seq_nums.len() as u64, None),
+            Some((data, parity)) => {
+                let parity_blocks = self
+                    .seq_nums
+                    .iter()
+                    .filter(|&&x| sbx_block::seq_num_is_parity(x, data, parity))
+                    .count() as u64;
+
+                (
+                    self.seq_nums.len() as u64 - parity_blocks,
+                    Some(parity_blocks),
+                )
+            }
+        }
+    }
+
+    pub fn duplicate_block_count(&self) -> u64 {
+        self.data_or_par_blocks - self.seq_nums.len() as u64
+    }
+
+    pub fn seq_num_ranges(&self) -> Vec<(u32, u32)> {
+        let mut ranges: Vec<(u32, u32)> = Vec::new();
+
+        for &seq_num in self.seq_nums.iter() {
+            match ranges.last_mut() {
+                Some(last) if last.1 + 1 == seq_num => last.1 = seq_num,
+                _ => ranges.push((seq_num, seq_num)),
+            }
+        }
+
+        ranges
+    }
+
+    pub fn recoverability(&self) -> Recoverability {
+        use crate::file_utils::from_orig_file_size::*;
+
+        let file_size = match self.file_size {
+            None => return Recoverability::Unknown,
+            Some(x) => x,
+        };
+
+        if ver_uses_rs(self.version) {
+            let (data, parity) = match self.data_par() {
+                None => return Recoverability::Unknown,
+                Some(x) => x,
+            };
+
+            let (data_block_count, _) = calc_data_only_and_parity_block_count_exc_burst_gaps(
+                self.version,
+                Some((data, parity, 0)),
+                file_size,
+            );
+
+            let set_size = (data + parity) as u32;
+            let set_count = data_block_count / data as u64;
+
+            let mut unrecoverable_sets = 0;
+
+            for set_index in 0..set_count {
+                let start = SBX_FIRST_DATA_SEQ_NUM + set_index as u32 * set_size;
+                let end_exc = start + set_size;
+
+                let present = self.seq_nums.range(start..end_exc).count();
+
+                if present < data {
+                    unrecoverable_sets += 1;
+                }
+            }
+
+            Recoverability::BlockSetsUnrecoverable(unrecoverable_sets)
+        } else {
+            let data_block_count = calc_data_chunk_count(self.version, file_size);
+
+            let first = SBX_FIRST_DATA_SEQ_NUM;
+            let last_inc = first as u64 + data_block_count - 1;
+
+            let present = if data_block_count == 0 {
+                0
+            } else {
+                self.seq_nums.range(first..=last_inc as u32).count() as u64
+            };
+
+            Recoverability::DataBlocksMissing(data_block_count - present)
+        }
+    }
+
+    fn write(&self, f: &mut fmt::Formatter, json_printer: &JSONPrinter) -> fmt::Result {
+        let (data_blocks, parity_blocks) = self.data_and_parity_block_counts();
+
+        json_printer.write_open_bracket(f, None, BracketType::Curly)?;
+
+        write_maybe_json!(
+            f,
+            json_printer,
+            "File UID                     : {}",
+            misc_utils::bytes_to_upper_hex_string(&self.uid)
+                => force_quotes
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX container version        : {}",
+            if ver_uses_rs(self.version) && !json_printer.json_enabled() {
+                format!(
+                    "{} (0x{:X})",
+                    ver_to_usize(self.version),
+                    ver_to_usize(self.version)
+                )
+            } else {
+                ver_to_usize(self.version).to_string()
+            }
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "First block found at byte    : {}",
+            self.first_found_at
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Metadata blocks found        : {}",
+            self.meta_blocks
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Data blocks found            : {}",
+            data_blocks
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Parity blocks found          : {}",
+            match parity_blocks {
+                None => null_if_json_else!(json_printer, "N/A (RS parameters unknown)").to_string(),
+                Some(x) => x.to_string(),
+            }
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Duplicate blocks found       : {}",
+            self.duplicate_block_count()
+        )?;
+
+        let ranges = self.seq_num_ranges();
+
+        if json_printer.json_enabled() {
+            json_printer.write_open_bracket(f, Some("seq num ranges"), BracketType::Square)?;
+
+            for &(start, end_inc) in ranges.iter() {
+                json_printer.write_open_bracket(f, None, BracketType::Curly)?;
+                write_maybe_json!(f, json_printer, "from : {}", start)?;
+                write_maybe_json!(f, json_printer, "to   : {}", end_inc)?;
+                json_printer.write_close_bracket(f)?;
+            }
+
+            json_printer.write_close_bracket(f)?;
+        } else {
+            let ranges_str: Vec<String> = ranges
+                .iter()
+                .map(|&(start, end_inc)| {
+                    if start == end_inc {
+                        start.to_string()
+                    } else {
+                        format!("{}-{}", start, end_inc)
+                    }
+                })
+                .collect();
+
+            write_if!(not_json => f, json_printer =>
+                      "Seq num coverage             : {}",
+                      if ranges_str.is_empty() {
+                          String::from("N/A")
+                      } else {
+                          ranges_str.join(", ")
+                      };)?;
+        }
+
+        match self.file_name {
+            None => write_maybe_json!(
+                f,
+                json_printer,
+                "File name                    : {}",
+                null_if_json_else_NA!(json_printer)
+            )?,
+            Some(ref x) => write_maybe_json!(
+                f,
+                json_printer,
+                "File name                    : {}",
+                x
+                    => force_quotes
+            )?,
+        }
+        write_maybe_json!(
+            f,
+            json_printer,
+            "File size                    : {}",
+            match self.file_size {
+                None => null_if_json_else_NA!(json_printer).to_string(),
+                Some(x) => x.to_string(),
+            }
+        )?;
+        match self.hash {
+            None => write_maybe_json!(
+                f,
+                json_printer,
+                "Hash                         : {}",
+                null_if_json_else_NA!(json_printer)
+            )?,
+            Some(ref h) => write_maybe_json!(
+                f,
+                json_printer,
+                "Hash                         : {} - {}",
+                hash_type_to_string(h.0),
+                misc_utils::bytes_to_lower_hex_string(&h.1)
+                    => force_quotes
+            )?,
+        }
+        write_maybe_json!(
+            f,
+            json_printer,
+            "RS data shard count          : {}",
+            if ver_uses_rs(self.version) {
+                match self.rs_data {
+                    None => null_if_json_else_NA!(json_printer).to_string(),
+                    Some(x) => x.to_string(),
+                }
+            } else {
+                null_if_json_else!(json_printer, "version does not use RS").to_string()
+            }
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "RS parity shard count        : {}",
+            if ver_uses_rs(self.version) {
+                match self.rs_parity {
+                    None => null_if_json_else_NA!(json_printer).to_string(),
+                    Some(x) => x.to_string(),
+                }
+            } else {
+                null_if_json_else!(json_printer, "version does not use RS").to_string()
+            }
+        )?;
+
+        let recoverability = self.recoverability();
+
+        if json_printer.json_enabled() {
+            let null = null_if_json_else_NA!(json_printer).to_string();
+
+            let (fully_recoverable, missing_data_blocks, unrecoverable_sets) = match recoverability
+            {
+                Recoverability::Unknown => (null.clone(), null.clone(), null),
+                Recoverability::DataBlocksMissing(x) => ((x == 0).to_string(), x.to_string(), null),
+                Recoverability::BlockSetsUnrecoverable(x) => {
+                    ((x == 0).to_string(), null, x.to_string())
+                }
+            };
+
+            write_maybe_json!(f, json_printer, "Fully recoverable : {}", fully_recoverable)?;
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Missing data blocks : {}",
+                missing_data_blocks
+            )?;
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Unrecoverable block sets : {}",
+                unrecoverable_sets
+            )?;
+        } else {
+            write_if!(not_json => f, json_printer =>
+                      "Fully recoverable            : {}",
+                      match recoverability {
+                          Recoverability::Unknown => String::from("Unknown (no usable metadata found)"),
+                          Recoverability::DataBlocksMissing(0)
+                              | Recoverability::BlockSetsUnrecoverable(0) => String::from("Yes"),
+                          Recoverability::DataBlocksMissing(x) => format!("No ({} data blocks missing)", x),
+                          Recoverability::BlockSetsUnrecoverable(x) => format!("No ({} block sets unrecoverable)", x),
+                      };)?;
+        }
+
+        json_printer.write_close_bracket(f)?;
+
+        Ok(())
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    pub bytes_processed: u64,
+    total_bytes: u64,
+    blocks_processed: u64,
+    inventories: Vec<UidInventory>,
+    start_time: f64,
+    end_time: f64,
+    json_printer: Arc<JSONPrinter>,
+}
+
+impl Stats {
+    pub fn new(required_len: u64, json_printer: &Arc<JSONPrinter>) -> Stats {
+        Stats {
+            bytes_processed: 0,
+            total_bytes: required_len,
+            blocks_processed: 0,
+            inventories: Vec::new(),
+            start_time: 0.,
+            end_time: 0.,
+            json_printer: Arc::clone(json_printer),
+        }
+    }
+}
+
+impl ProgressReport for Stats {
+    fn start_time_mut(&mut self) -> &mut f64 {
+        &mut self.start_time
+    }
+
+    fn end_time_mut(&mut self) -> &mut f64 {
+        &mut self.end_time
+    }
+
+    fn units_so_far(&self) -> u64 {
+        self.bytes_processed
+    }
+
+    fn total_units(&self) -> Option<u64> {
+        Some(self.total_bytes)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let json_printer = &self.json_printer;
+
+        json_printer.write_open_bracket(f, Some("stats"), BracketType::Curly)?;
+
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of bytes processed  : {}",
+            self.bytes_processed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks processed : {}",
+            self.blocks_processed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of UIDs found       : {}",
+            self.inventories.len()
+        )?;
+
+        json_printer.write_close_bracket(f)?;
+
+        json_printer.write_open_bracket(f, Some("containers"), BracketType::Square)?;
+
+        for inventory in self.inventories.iter() {
+            write_if!(not_json => f, json_printer => "";)?;
+            write_if!(not_json => f, json_printer => "========================================";)?;
+            inventory.write(f, json_printer)?;
+        }
+
+        json_printer.write_close_bracket(f)?;
+
+        Ok(())
+    }
+}
+
+pub fn scan_file(param: &Param) -> Result<Stats, Error> {
+    let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
+
+    let file_size = file_utils::get_file_size(&param.in_file)?;
+
+    // calulate length to read and position to seek to
+    let RequiredLenAndSeekTo {
+        required_len,
+        seek_to,
+    } = misc_utils::calc_required_len_and_seek_to_from_byte_range(
+        param.from_pos,
+        param.to_pos,
+        param.force_misalign,
+        0,
+        PositionOrLength::Len(file_size),
+        None,
+    );
+
+    let stats = Arc::new(Mutex::new(Stats::new(required_len, &param.json_printer)));
+
+    let reporter = ProgressReporter::new(
+        &stats,
+        "Block scanning progress",
+        "bytes",
+        param.pr_verbosity_level,
+        param.json_printer.json_enabled(),
+    );
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let mut reader = FileReader::new(
+        &param.in_file,
+        FileReaderParam {
+            write: false,
+            buffered: true,
+        },
+    )?;
+
+    // seek to calculated position
+    reader.seek(SeekFrom::Start(seek_to))?;
+
+    let mut uid_to_index: HashMap<[u8; SBX_FILE_UID_LEN], usize> = HashMap::new();
+
+    let mut block_pos: u64;
+    let mut bytes_processed: u64 = 0;
+
+    reporter.start();
+
+    loop {
+        break_if_atomic_bool!(ctrlc_stop_flag);
+
+        break_if_reached_required_len!(bytes_processed, required_len);
+
+        let lazy_read_res = block_utils::read_block_lazily(&mut block, &mut buffer, &mut reader)?;
+
+        block_pos = bytes_processed;
+        bytes_processed += lazy_read_res.len_read as u64;
+
+        let mut stats = stats.lock().unwrap();
+
+        stats.bytes_processed = bytes_processed;
+
+        break_if_eof_seen!(lazy_read_res);
+
+        if !lazy_read_res.usable {
+            continue;
+        }
+
+        // check if block has the required UID
+        if let Some(x) = param.only_pick_uid {
+            if block.get_uid() != x {
+                continue;
+            }
+        }
+
+        stats.blocks_processed += 1;
+
+        let index = match uid_to_index.get(&block.get_uid()) {
+            Some(&i) => i,
+            None => {
+                let i = stats.inventories.len();
+                stats
+                    .inventories
+                    .push(UidInventory::new(&block, block_pos + seek_to));
+                uid_to_index.insert(block.get_uid(), i);
+                i
+            }
+        };
+
+        stats.inventories[index].add_block(&block);
+    }
+
+    reporter.stop();
+
+    let stats = stats.lock().unwrap().clone();
+
+    Ok(stats)
+}
@@ -264,6 +264,19 @@ pub fn show_file(param: &Param) -> Result<Stats, Error> {
 
             reporter.pause();
 
+            // prefer the final metadata block of a streamed container
+            if !param.show_all && block.get_TRL().unwrap().is_some() {
+                if let Some((pos, trailing_block)) = block_utils::get_trailing_meta_block(
+                    &param.in_file,
+                    seek_to + block_pos,
+                    &block,
+                    seek_to + required_len,
+                )? {
+                    block_pos = pos - seek_to;
+                    block = trailing_block;
+                }
+            }
+
             json_printer.print_open_bracket(None, BracketType::Curly);
 
             if param.show_all {
@@ -394,6 +407,14 @@ pub fn show_file(param: &Param) -> Result<Stats, Error> {
                     ),
                 }
             );
+            print_maybe_json!(
+                json_printer,
+                "Blank blocks omitted   : {}",
+                match block.get_SPR().unwrap() {
+                    None => null_if_json_else!(json_printer, "container is not sparse").to_string(),
+                    Some(x) => x.to_string(),
+                }
+            );
 
             meta_block_count += 1;
 
@@ -0,0 +1,321 @@
+use crate::file_utils;
+use crate::file_utils::from_orig_file_size;
+use crate::integer_utils::IntegerUtils;
+use crate::protect::{parse_percent, parse_size};
+use crate::sbx_block;
+use crate::sbx_specs::{ver_to_block_size, ver_to_data_size, Version};
+use rand::rngs::StdRng;
+use rand::Rng;
+use std::str::FromStr;
+
+pub const DEFAULT_TRIAL_COUNT: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DamageModel {
+    pub random_loss_percent: Option<f64>,
+    pub burst_len_range: Option<(u64, u64)>,
+    pub burst_count: u64,
+    pub wipe_percent: Option<f64>,
+}
+
+impl Default for DamageModel {
+    fn default() -> Self {
+        DamageModel {
+            random_loss_percent: None,
+            burst_len_range: None,
+            burst_count: 1,
+            wipe_percent: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Stats {
+    pub trials: u64,
+    pub fully_recovered_trials: u64,
+    pub all_meta_lost_trials: u64,
+    pub total_bytes_lost: u64,
+    pub max_bytes_lost: u64,
+}
+
+impl Stats {
+    pub fn full_recovery_prob(&self) -> f64 {
+        self.fully_recovered_trials as f64 / self.trials as f64
+    }
+
+    pub fn all_meta_lost_prob(&self) -> f64 {
+        self.all_meta_lost_trials as f64 / self.trials as f64
+    }
+
+    pub fn expected_bytes_lost(&self) -> f64 {
+        self.total_bytes_lost as f64 / self.trials as f64
+    }
+}
+
+fn parse_burst_len_range(string: &str) -> Result<(u64, u64), ()> {
+    let mut split = string.splitn(2, '-');
+
+    let min = parse_size(split.next().unwrap().trim())?;
+    let max = match split.next() {
+        None => min,
+        Some(x) => parse_size(x.trim())?,
+    };
+
+    if min == 0 || min > max {
+        Err(())
+    } else {
+        Ok((min, max))
+    }
+}
+
+pub fn string_to_damage_model(string: &str) -> Result<DamageModel, ()> {
+    let mut model = DamageModel::default();
+
+    for item in string.split(',') {
+        let mut split = item.trim().splitn(2, '=');
+
+        let key = split.next().unwrap().trim().to_lowercase();
+        let value = match split.next() {
+            None => return Err(()),
+            Some(x) => x.trim(),
+        };
+
+        match key.as_str() {
+            "random" => model.random_loss_percent = Some(parse_percent(value, 100.)?),
+            "burst" => model.burst_len_range = Some(parse_burst_len_range(value)?),
+            "bursts" => match u64::from_str(value) {
+                Ok(x) if x > 0 => model.burst_count = x,
+                Ok(_) | Err(_) => return Err(()),
+            },
+            "wipe" => model.wipe_percent = Some(parse_percent(value, 100.)?),
+            _ => return Err(()),
+        }
+    }
+
+    if model.random_loss_percent.is_none()
+        && model.burst_len_range.is_none()
+        && model.wipe_percent.is_none()
+    {
+        return Err(());
+    }
+
+    Ok(model)
+}
+
+pub struct Layout {
+    version: Version,
+    meta_enabled: Option<bool>,
+    data_par_burst: Option<(usize, usize, usize)>,
+    file_size: u64,
+    container_size: u64,
+    block_count: u64,
+    last_seq_num: u64,
+    meta_block_count: u64,
+}
+
+impl Layout {
+    pub fn new(
+        version: Version,
+        meta_enabled: Option<bool>,
+        data_par_burst: Option<(usize, usize, usize)>,
+        file_size: u64,
+    ) -> Layout {
+        let container_size = from_orig_file_size::calc_container_size(
+            version,
+            meta_enabled,
+            data_par_burst,
+            file_size,
+        );
+
+        Layout {
+            version,
+            meta_enabled,
+            data_par_burst,
+            file_size,
+            container_size,
+            block_count: container_size / ver_to_block_size(version) as u64,
+            last_seq_num: from_orig_file_size::calc_data_block_count_exc_burst_gaps(
+                version,
+                data_par_burst,
+                file_size,
+            ),
+            meta_block_count: file_utils::calc_meta_block_count_exc_burst_gaps(
+                version,
+                meta_enabled,
+                data_par_burst,
+            ),
+        }
+    }
+
+    fn chunk_size(&self, chunk_index: u64) -> u64 {
+        let data_size = ver_to_data_size(self.version) as u64;
+
+        let start = chunk_index * data_size;
+
+        if start >= self.file_size {
+            0
+        } else {
+            std::cmp::min(data_size, self.file_size - start)
+        }
+    }
+
+    fn add_region(&self, start: u64, len: u64, lost: &mut Vec<u64>) {
+        let block_size = ver_to_block_size(self.version) as u64;
+
+        let end = std::cmp::min(start + len, self.container_size);
+
+        for index in start / block_size..u64::round_up_to_multiple(end, block_size) / block_size {
+            lost.push(index);
+        }
+    }
+
+    fn add_random_region(&self, rng: &mut StdRng, len: u64, lost: &mut Vec<u64>) {
+        if len >= self.container_size {
+            self.add_region(0, self.container_size, lost);
+        } else {
+            let start = rng.gen_range(0, self.container_size - len + 1);
+
+            self.add_region(start, len, lost);
+        }
+    }
+
+    // returns the indices of blocks damaged in a single trial
+    fn damage(&self, model: &DamageModel, rng: &mut StdRng) -> Vec<u64> {
+        let mut lost = Vec::new();
+
+        if let Some(loss_percent) = model.random_loss_percent {
+            // skip over intact blocks by sampling the gaps between lost
+            // blocks, which follow geometric distribution
+            let ln_keep = (-loss_percent / 100.).ln_1p();
+
+            let mut index = 0;
+            loop {
+                let u: f64 = 1. - rng.gen::<f64>();
+                let skip = (u.ln() / ln_keep).floor();
+
+                if skip >= (self.block_count - index) as f64 {
+                    break;
+                }
+
+                index += skip as u64;
+
+                lost.push(index);
+
+                index += 1;
+            }
+        }
+
+        if let Some((min, max)) = model.burst_len_range {
+            for _ in 0..model.burst_count {
+                let len = rng.gen_range(min, max + 1);
+
+                self.add_random_region(rng, len, &mut lost);
+            }
+        }
+
+        if let Some(wipe_percent) = model.wipe_percent {
+            let len = (self.container_size as f64 * wipe_percent / 100.).ceil() as u64;
+
+            self.add_random_region(rng, len, &mut lost);
+        }
+
+        lost.sort_unstable();
+        lost.dedup();
+
+        lost
+    }
+
+    // returns the number of file bytes which cannot be recovered, and the
+    // number of metadata blocks lost
+    pub fn evaluate(&self, lost: &[u64]) -> (u64, u64) {
+        let mut meta_lost = 0;
+
+        // (block set index, data chunk index if data block)
+        let mut lost_blocks: Vec<(u64, Option<u64>)> = Vec::with_capacity(lost.len());
+
+        for &index in lost.iter() {
+            let seq_num =
+                sbx_block::calc_seq_num_at_index(index, self.meta_enabled, self.data_par_burst);
+
+            if sbx_block::seq_num_is_meta(seq_num) {
+                meta_lost += 1;
+                continue;
+            }
+
+            // gaps left by burst error resistance in the last super block set
+            if seq_num as u64 > self.last_seq_num {
+                continue;
+            }
+
+            match self.data_par_burst {
+                None => lost_blocks.push((seq_num as u64, Some(seq_num as u64 - 1))),
+                Some((data, parity, _)) => {
+                    let block_set_index = (seq_num as u64 - 1) / (data + parity) as u64;
+
+                    lost_blocks.push((
+                        block_set_index,
+                        sbx_block::calc_data_chunk_write_index(seq_num, Some((data, parity))),
+                    ));
+                }
+            }
+        }
+
+        let parity = match self.data_par_burst {
+            None => 0,
+            Some((_, parity, _)) => parity,
+        };
+
+        lost_blocks.sort_unstable();
+
+        let mut bytes_lost = 0;
+
+        // lost blocks of the same block set are next to each other after sorting
+        let mut set_start = 0;
+
+        while set_start < lost_blocks.len() {
+            let block_set_index = lost_blocks[set_start].0;
+
+            let mut set_end = set_start;
+            while set_end < lost_blocks.len() && lost_blocks[set_end].0 == block_set_index {
+                set_end += 1;
+            }
+
+            if set_end - set_start > parity {
+                for &(_, chunk_index) in lost_blocks[set_start..set_end].iter() {
+                    if let Some(chunk_index) = chunk_index {
+                        bytes_lost += self.chunk_size(chunk_index);
+                    }
+                }
+            }
+
+            set_start = set_end;
+        }
+
+        (bytes_lost, meta_lost)
+    }
+}
+
+pub fn simulate(layout: &Layout, model: &DamageModel, trials: u64, rng: &mut StdRng) -> Stats {
+    let mut stats = Stats::default();
+
+    for _ in 0..trials {
+        let lost = layout.damage(model, rng);
+
+        let (bytes_lost, meta_lost) = layout.evaluate(&lost);
+
+        stats.trials += 1;
+
+        if bytes_lost == 0 {
+            stats.fully_recovered_trials += 1;
+        }
+
+        if layout.meta_block_count > 0 && meta_lost == layout.meta_block_count {
+            stats.all_meta_lost_trials += 1;
+        }
+
+        stats.total_bytes_lost += bytes_lost;
+        stats.max_bytes_lost = std::cmp::max(stats.max_bytes_lost, bytes_lost);
+    }
+
+    stats
+}
@@ -0,0 +1,138 @@
+#![cfg(test)]
+
+use crate::sbx_specs::Version;
+use crate::simulate::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+#[test]
+fn test_string_to_damage_model() {
+    assert_eq!(
+        Ok(DamageModel {
+            random_loss_percent: Some(1.),
+            burst_len_range: None,
+            burst_count: 1,
+            wipe_percent: None,
+        }),
+        string_to_damage_model("random=1%")
+    );
+    assert_eq!(
+        Ok(DamageModel {
+            random_loss_percent: None,
+            burst_len_range: Some((4096, 1 << 20)),
+            burst_count: 3,
+            wipe_percent: Some(5.),
+        }),
+        string_to_damage_model("burst=4K-1M, bursts=3, wipe=5")
+    );
+    assert_eq!(
+        Ok(DamageModel {
+            random_loss_percent: None,
+            burst_len_range: Some((100, 100)),
+            burst_count: 1,
+            wipe_percent: None,
+        }),
+        string_to_damage_model("burst=100")
+    );
+}
+
+#[test]
+fn test_string_to_damage_model_invalid() {
+    assert_eq!(Err(()), string_to_damage_model(""));
+    assert_eq!(Err(()), string_to_damage_model("bursts=2"));
+    assert_eq!(Err(()), string_to_damage_model("burst=1M-4K"));
+    assert_eq!(Err(()), string_to_damage_model("burst=0"));
+    assert_eq!(Err(()), string_to_damage_model("burst=4K,bursts=0"));
+    assert_eq!(Err(()), string_to_damage_model("wipe=100"));
+    assert_eq!(Err(()), string_to_damage_model("random"));
+    assert_eq!(Err(()), string_to_damage_model("sector=1"));
+}
+
+#[test]
+fn test_evaluate_rs_block_sets() {
+    // 10 data chunks in block sets of 3 data and 2 parity shards
+    let layout = Layout::new(Version::V17, None, Some((3, 2, 0)), 496 * 9 + 100);
+
+    // metadata blocks are at indices 0 to 2, followed by data and parity blocks
+    assert_eq!((0, 2), layout.evaluate(&[0, 1]));
+    assert_eq!((0, 0), layout.evaluate(&[3, 4]));
+    assert_eq!((496 * 2, 0), layout.evaluate(&[3, 5, 7]));
+    assert_eq!((496, 0), layout.evaluate(&[3, 6, 7]));
+    // last block set only carries 100 bytes in its first data block
+    assert_eq!((100, 0), layout.evaluate(&[18, 19, 20]));
+}
+
+#[test]
+fn test_evaluate_rs_burst_interleaving() {
+    let layout = Layout::new(Version::V17, None, Some((3, 2, 4)), 496 * 48);
+
+    // second super block set starts at index 23, 8 consecutive blocks in it
+    // are spread over 4 block sets
+    let lost: Vec<u64> = (24..32).collect();
+
+    assert_eq!((0, 0), layout.evaluate(&lost));
+
+    let lost: Vec<u64> = (24..33).collect();
+
+    assert!(layout.evaluate(&lost).0 > 0);
+}
+
+#[test]
+fn test_evaluate_non_rs() {
+    let layout = Layout::new(Version::V1, Some(true), None, 496 * 2 + 10);
+
+    assert_eq!((0, 1), layout.evaluate(&[0]));
+    assert_eq!((496, 0), layout.evaluate(&[1]));
+    assert_eq!((10, 0), layout.evaluate(&[3]));
+
+    let layout = Layout::new(Version::V1, Some(false), None, 496 * 2 + 10);
+
+    assert_eq!((496 + 10, 0), layout.evaluate(&[0, 2]));
+}
+
+#[test]
+fn test_simulate_within_tolerance() {
+    let layout = Layout::new(Version::V17, None, Some((10, 2, 12)), 1 << 20);
+    // 24 blocks of tolerance covers any unaligned loss of 23 blocks + 1 byte
+    let model = string_to_damage_model("burst=11K").unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let stats = simulate(&layout, &model, 200, &mut rng);
+
+    assert_eq!(200, stats.trials);
+    assert_eq!(200, stats.fully_recovered_trials);
+    assert_eq!(0, stats.total_bytes_lost);
+    assert_eq!(1., stats.full_recovery_prob());
+}
+
+#[test]
+fn test_simulate_beyond_tolerance() {
+    let layout = Layout::new(Version::V17, None, Some((10, 2, 0)), 1 << 20);
+    let model = string_to_damage_model("wipe=50").unwrap();
+    let mut rng = StdRng::seed_from_u64(0);
+
+    let stats = simulate(&layout, &model, 50, &mut rng);
+
+    assert_eq!(0, stats.fully_recovered_trials);
+    assert!(stats.expected_bytes_lost() > (1 << 18) as f64);
+    assert!(stats.max_bytes_lost <= 1 << 20);
+}
+
+#[test]
+fn test_simulate_random_loss() {
+    let layout = Layout::new(Version::V17, None, Some((10, 4, 0)), 1 << 20);
+    let model = string_to_damage_model("random=1").unwrap();
+
+    let stats_a = simulate(&layout, &model, 100, &mut StdRng::seed_from_u64(1));
+    let stats_b = simulate(&layout, &model, 100, &mut StdRng::seed_from_u64(1));
+
+    // same seed gives same results
+    assert_eq!(stats_a, stats_b);
+    assert!(stats_a.full_recovery_prob() > 0.9);
+
+    let model = string_to_damage_model("random=30").unwrap();
+
+    let stats = simulate(&layout, &model, 100, &mut StdRng::seed_from_u64(1));
+
+    assert!(stats.full_recovery_prob() < 0.1);
+}
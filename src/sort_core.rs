@@ -1,5 +1,6 @@
+use crate::block_utils;
 use crate::block_utils::RefBlockChoice;
-use crate::cli_utils::setup_ctrlc_handler;
+use crate::cli_utils::{report_ref_block_info, setup_ctrlc_handler};
 use crate::data_block_buffer::{
     BlockArrangement, DataBlockBuffer, InputType, OutputType, Slot, SlotView,
 };
@@ -7,7 +8,9 @@ use crate::file_reader::{FileReader, FileReaderParam};
 use crate::file_utils;
 use crate::file_writer::{FileWriter, FileWriterParam};
 use crate::general_error::Error;
+use crate::integer_utils::IntegerUtils;
 use crate::json_printer::{BracketType, JSONPrinter};
+use crate::log::*;
 use crate::misc_utils;
 use crate::misc_utils::MultiPassType;
 use crate::misc_utils::RequiredLenAndSeekTo;
@@ -16,12 +19,14 @@ use crate::progress_report::*;
 use crate::sbx_block;
 use crate::sbx_block::{Block, Header};
 use crate::sbx_specs::Version;
-use crate::sbx_specs::SBX_LARGEST_BLOCK_SIZE;
 use crate::sbx_specs::{ver_to_block_size, ver_to_usize, ver_uses_rs};
+use crate::sbx_specs::{SBX_LARGEST_BLOCK_SIZE, SBX_SCAN_BLOCK_SIZE};
+use crate::sparse_map;
 use crate::time_utils;
 use crate::writer::{Writer, WriterType};
 use std::fmt;
 use std::io::SeekFrom;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::mpsc::channel;
 use std::sync::mpsc::sync_channel;
 use std::sync::Barrier;
@@ -30,9 +35,20 @@ use std::thread;
 
 const PIPELINE_BUFFER_IN_ROTATION: usize = 9;
 
+struct ReaderStats {
+    bytes_processed: u64,
+    meta_blocks_decoded: u64,
+    meta_blocks_same_order: u64,
+    meta_blocks_diff_order: u64,
+    data_blocks_decoded: u64,
+    parity_blocks_decoded: u64,
+    blocks_decode_failed: u64,
+    okay_blank_blocks: u64,
+}
+
 enum SendToWriter {
     Meta(Vec<u8>),
-    Data(DataBlockBuffer),
+    Data(ReaderStats, DataBlockBuffer),
 }
 
 pub struct Param {
@@ -47,7 +63,10 @@ pub struct Param {
     to_pos: Option<RangeEnd<u64>>,
     force_misalign: bool,
     in_file: String,
+    merge_files: Vec<String>,
     out_file: Option<String>,
+    log_file: Option<String>,
+    sparse_map_file: Option<String>,
     verbose: bool,
     pr_verbosity_level: PRVerbosityLevel,
     burst: Option<usize>,
@@ -66,7 +85,10 @@ impl Param {
         to_pos: Option<RangeEnd<u64>>,
         force_misalign: bool,
         in_file: &str,
+        merge_files: &[&str],
         out_file: Option<&str>,
+        log_file: Option<&str>,
+        sparse_map_file: Option<&str>,
         verbose: bool,
         pr_verbosity_level: PRVerbosityLevel,
         burst: Option<usize>,
@@ -83,10 +105,19 @@ impl Param {
             to_pos,
             force_misalign,
             in_file: String::from(in_file),
+            merge_files: merge_files.iter().map(|x| String::from(*x)).collect(),
             out_file: match out_file {
                 Some(x) => Some(String::from(x)),
                 None => None,
             },
+            log_file: match log_file {
+                Some(x) => Some(String::from(x)),
+                None => None,
+            },
+            sparse_map_file: match sparse_map_file {
+                Some(x) => Some(String::from(x)),
+                None => None,
+            },
             verbose,
             pr_verbosity_level,
             burst,
@@ -94,10 +125,20 @@ impl Param {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct InputStats {
+    file: String,
+    pub meta_blocks_decoded: u64,
+    pub data_blocks_decoded: u64,
+    pub parity_blocks_decoded: u64,
+    pub blocks_decode_failed: u64,
+}
+
 #[derive(Clone, Debug)]
 pub struct Stats {
     version: Version,
     block_size: u64,
+    bytes_processed: u64,
     pub meta_blocks_decoded: u64,
     pub data_blocks_decoded: u64,
     pub parity_blocks_decoded: u64,
@@ -110,6 +151,7 @@ pub struct Stats {
     pub parity_blocks_same_order: u64,
     pub parity_blocks_diff_order: u64,
     total_blocks: u64,
+    inputs: Vec<InputStats>,
     start_time: f64,
     end_time: f64,
     json_printer: Arc<JSONPrinter>,
@@ -123,6 +165,7 @@ impl Stats {
         Stats {
             version,
             block_size: ver_to_block_size(version) as u64,
+            bytes_processed: 0,
             meta_blocks_decoded: 0,
             data_blocks_decoded: 0,
             parity_blocks_decoded: 0,
@@ -135,6 +178,7 @@ impl Stats {
             data_blocks_diff_order: 0,
             parity_blocks_same_order: 0,
             parity_blocks_diff_order: 0,
+            inputs: Vec::new(),
             start_time: 0.,
             end_time: 0.,
             json_printer: Arc::clone(json_printer),
@@ -150,6 +194,87 @@ impl Stats {
     }
 }
 
+impl Log for Stats {
+    fn serialize(&self) -> String {
+        let mut string = String::with_capacity(500);
+        string.push_str(&format!("bytes_processed={}\n", self.bytes_processed));
+        string.push_str(&format!(
+            "meta_blocks_decoded={}\n",
+            self.meta_blocks_decoded
+        ));
+        string.push_str(&format!(
+            "data_blocks_decoded={}\n",
+            self.data_blocks_decoded
+        ));
+        string.push_str(&format!(
+            "parity_blocks_decoded={}\n",
+            self.parity_blocks_decoded
+        ));
+        string.push_str(&format!("blocks_failed={}\n", self.blocks_decode_failed));
+        string.push_str(&format!("okay_blank_blocks={}\n", self.okay_blank_blocks));
+        string.push_str(&format!(
+            "meta_blocks_same_order={}\n",
+            self.meta_blocks_same_order
+        ));
+        string.push_str(&format!(
+            "meta_blocks_diff_order={}\n",
+            self.meta_blocks_diff_order
+        ));
+        string.push_str(&format!(
+            "data_blocks_same_order={}\n",
+            self.data_blocks_same_order
+        ));
+        string.push_str(&format!(
+            "data_blocks_diff_order={}\n",
+            self.data_blocks_diff_order
+        ));
+        string.push_str(&format!(
+            "parity_blocks_same_order={}\n",
+            self.parity_blocks_same_order
+        ));
+        string.push_str(&format!(
+            "parity_blocks_diff_order={}\n",
+            self.parity_blocks_diff_order
+        ));
+
+        string
+    }
+
+    fn deserialize(&mut self, input: &[u8]) -> Result<(), ()> {
+        let fields = parse_u64_fields(
+            input,
+            &[
+                "bytes_processed",
+                "meta_blocks_decoded",
+                "data_blocks_decoded",
+                "parity_blocks_decoded",
+                "blocks_failed",
+                "okay_blank_blocks",
+                "meta_blocks_same_order",
+                "meta_blocks_diff_order",
+                "data_blocks_same_order",
+                "data_blocks_diff_order",
+                "parity_blocks_same_order",
+                "parity_blocks_diff_order",
+            ],
+        )?;
+
+        self.bytes_processed = u64::round_down_to_multiple(fields[0], self.block_size);
+        self.meta_blocks_decoded = fields[1];
+        self.data_blocks_decoded = fields[2];
+        self.parity_blocks_decoded = fields[3];
+        self.blocks_decode_failed = fields[4];
+        self.okay_blank_blocks = fields[5];
+        self.meta_blocks_same_order = fields[6];
+        self.meta_blocks_diff_order = fields[7];
+        self.data_blocks_same_order = fields[8];
+        self.data_blocks_diff_order = fields[9];
+        self.parity_blocks_same_order = fields[10];
+        self.parity_blocks_diff_order = fields[11];
+        Ok(())
+    }
+}
+
 impl ProgressReport for Stats {
     fn start_time_mut(&mut self) -> &mut f64 {
         &mut self.start_time
@@ -269,6 +394,54 @@ impl fmt::Display for Stats {
             second
         )?;
 
+        // contribution of each input is only of interest when merging
+        if self.inputs.len() > 1 {
+            json_printer.write_open_bracket(f, Some("inputs"), BracketType::Square)?;
+
+            for input in self.inputs.iter() {
+                write_if!(not_json => f, json_printer => "";)?;
+
+                json_printer.write_open_bracket(f, None, BracketType::Curly)?;
+
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "File                                      : {}",
+                    input.file
+                )?;
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blocks sorted (metadata)        : {}",
+                    input.meta_blocks_decoded
+                )?;
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blocks sorted (data)            : {}",
+                    input.data_blocks_decoded
+                )?;
+                if ver_uses_rs(self.version) {
+                    write_maybe_json!(
+                        f,
+                        json_printer,
+                        "Number of blocks sorted (parity)          : {}",
+                        input.parity_blocks_decoded
+                    )?;
+                }
+                write_maybe_json!(
+                    f,
+                    json_printer,
+                    "Number of blocks failed to sort           : {}",
+                    input.blocks_decode_failed
+                )?;
+
+                json_printer.write_close_bracket(f)?;
+            }
+
+            json_printer.write_close_bracket(f)?;
+        }
+
         json_printer.write_close_bracket(f)?;
 
         Ok(())
@@ -368,35 +541,146 @@ fn write_meta_blocks_writer(
     Ok(())
 }
 
+fn get_ref_block_from_inputs(
+    param: &Param,
+    in_files: &[&str],
+    stop_flag: &AtomicBool,
+) -> Result<Option<(usize, u64, Block)>, Error> {
+    let from_pos = match param.ref_block_from_pos {
+        Some(x) => Some(x),
+        None => match param.from_pos {
+            Some(x) => Some(x % SBX_SCAN_BLOCK_SIZE as u64),
+            None => None,
+        },
+    };
+
+    let mut candidate: Option<(usize, u64, Block)> = None;
+
+    // a final metadata block from any input is preferred, otherwise the first
+    // metadata block found, otherwise the first block found
+    for (i, in_file) in in_files.iter().enumerate() {
+        let res = block_utils::get_ref_block(
+            in_file,
+            from_pos,
+            param.ref_block_to_pos,
+            param.force_misalign,
+            param.ref_block_choice,
+            param.pr_verbosity_level,
+            param.json_printer.json_enabled(),
+            stop_flag,
+        )?;
+
+        if stop_flag.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+
+        if let Some((pos, block)) = res {
+            let replace = match candidate {
+                None => true,
+                Some((_, _, ref x)) => !x.is_meta() && block.is_meta(),
+            };
+
+            let final_meta = block.is_meta() && block.get_TRL().unwrap().is_none();
+
+            if replace {
+                candidate = Some((i, pos, block));
+            }
+
+            if final_meta {
+                break;
+            }
+        }
+    }
+
+    match candidate {
+        None => Err(Error::with_msg("Failed to find reference block")),
+        Some(x) => Ok(Some(x)),
+    }
+}
+
 pub fn sort_file(param: &Param) -> Result<Option<Stats>, Error> {
     let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
 
     let json_printer = &param.json_printer;
 
-    let (ref_block_pos, ref_block) = get_ref_block!(param, json_printer, ctrlc_stop_flag);
+    let mut in_files: Vec<&str> = vec![&param.in_file];
+    for f in param.merge_files.iter() {
+        in_files.push(f);
+    }
 
-    let file_size = file_utils::get_file_size(&param.in_file)?;
-    let stats = Arc::new(Mutex::new(Stats::new(&ref_block, file_size, json_printer)));
+    let (ref_file_index, ref_block_pos, ref_block) =
+        match get_ref_block_from_inputs(param, &in_files, &ctrlc_stop_flag)? {
+            None => return Ok(None),
+            Some(x) => x,
+        };
 
-    let version = ref_block.get_version();
+    let ref_file = in_files[ref_file_index];
 
-    let data_par_burst = get_data_par_burst!(param, ref_block_pos, ref_block, "sort");
+    if param.verbose {
+        if in_files.len() > 1 {
+            print_if!(not_json => json_printer => "";
+                      "Using reference block from \"{}\"", ref_file;);
+        }
+        print_if!(not_json => json_printer => "";);
+        report_ref_block_info(json_printer, ref_block_pos, &ref_block);
+        print_if!(not_json => json_printer => "";);
+    }
 
-    let mut reader = FileReader::new(
-        &param.in_file,
-        FileReaderParam {
-            write: false,
-            buffered: true,
+    let mut file_sizes = Vec::with_capacity(in_files.len());
+    for f in in_files.iter() {
+        file_sizes.push(file_utils::get_file_size(f)?);
+    }
+
+    let stats = Arc::new(Mutex::new(Stats::new(
+        &ref_block,
+        file_sizes.iter().sum(),
+        json_printer,
+    )));
+
+    let version = ref_block.get_version();
+
+    let data_par_burst =
+        get_data_par_burst!(in_file => ref_file, param, ref_block_pos, ref_block, "sort");
+
+    // holes left by the blocks recorded as omitted are not failures,
+    // and are left as holes in the output
+    let sparse_map = Arc::new(sparse_map::load(
+        json_printer,
+        &ref_block,
+        Some(ref_file),
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
         },
-    )?;
+    )?);
+
+    let log_handler = Arc::new(match param.log_file {
+        None => LogHandler::new(None, &stats),
+        Some(ref f) => LogHandler::new(Some(f), &stats),
+    });
+
+    // read from log file and update stats if the log file exists
+    log_handler.read_from_file()?;
+
+    let bytes_processed = stats.lock().unwrap().bytes_processed;
+
+    // metadata blocks are sent to writer before the first buffer that is
+    // recorded in log file, so they are already written if any was counted
+    let mut meta_written = {
+        let stats = stats.lock().unwrap();
+        stats.meta_blocks_same_order + stats.meta_blocks_diff_order > 0
+    };
+    let mut meta_written_is_provisional = false;
 
+    // do not truncate when continuing from log file,
+    // otherwise the output sorted so far would be lost
     let mut writer = match param.out_file {
         Some(ref f) => Some(Writer::new(WriterType::File(FileWriter::new(
             f,
             FileWriterParam {
                 read: param.multi_pass == Some(MultiPassType::SkipGood),
                 append: false,
-                truncate: param.multi_pass == None,
+                truncate: param.multi_pass == None && bytes_processed == 0,
                 buffered: true,
             },
         )?))),
@@ -413,296 +697,419 @@ pub fn sort_file(param: &Param) -> Result<Option<Stats>, Error> {
 
     let header_pred = header_pred_same_ver_uid!(ref_block);
 
-    // calulate length to read and position to seek to
-    let RequiredLenAndSeekTo {
-        required_len,
-        seek_to,
-    } = misc_utils::calc_required_len_and_seek_to_from_byte_range(
-        param.from_pos,
-        param.to_pos,
-        param.force_misalign,
-        0,
-        PositionOrLength::Len(file_size),
-        Some(ver_to_block_size(version) as u64),
-    );
-
-    // seek to calculated position
-    reader.seek(SeekFrom::Start(seek_to))?;
-
-    let read_offset = seek_to % ver_to_block_size(version) as u64;
-
-    reporter.start();
-
-    let (to_writer, from_reader) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 2); // one extra space for the case of metadata block
-    let (to_counter, from_writer) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
-    let (to_reader, from_counter) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
-    let (error_tx_reader, error_rx) = channel::<Error>();
-    let error_tx_writer = error_tx_reader.clone();
-
-    let worker_shutdown_barrier = Arc::new(Barrier::new(3));
-
     let skip_good = match param.multi_pass {
         None | Some(MultiPassType::OverwriteAll) => false,
         Some(MultiPassType::SkipGood) => true,
     };
 
-    // push buffers into pipeline
-    let buffers = DataBlockBuffer::new_multi(
-        ref_block.get_version(),
-        Some(&ref_block.get_uid()),
-        InputType::Block(BlockArrangement::Unordered),
-        OutputType::Block,
-        data_par_burst,
-        true,
-        skip_good,
-        PIPELINE_BUFFER_IN_ROTATION,
-    );
-
-    for buffer in buffers.into_iter() {
-        to_reader.send(Some(buffer)).unwrap();
-    }
+    log_handler.start();
+    reporter.start();
 
-    let reader_thread = {
-        let version = ref_block.get_version();
-        let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
-        let report_blank = param.report_blank;
-        let block_size = ver_to_block_size(version);
-        let stats = Arc::clone(&stats);
-        let mut bytes_processed: u64 = 0;
-
-        thread::spawn(move || {
-            let mut run = true;
-            let mut meta_written = false;
-
-            while let Some(mut buffer) = from_counter.recv().unwrap() {
-                if !run {
-                    break;
-                }
+    // every input is sorted into the same output in turn,
+    // so a later input overwrites blocks written by an earlier one
+    for (in_file_index, in_file) in in_files.iter().enumerate() {
+        let file_size = file_sizes[in_file_index];
 
-                let mut meta_blocks_same_order = 0;
-                let mut meta_blocks_diff_order = 0;
-                let mut meta_blocks_decoded = 0;
-                let mut parity_blocks_decoded = 0;
-                let mut data_blocks_decoded = 0;
-                let mut blocks_decode_failed = 0;
-                let mut okay_blank_blocks = 0;
+        let mut reader = FileReader::new(
+            in_file,
+            FileReaderParam {
+                write: false,
+                buffered: true,
+            },
+        )?;
 
-                while !buffer.is_full() {
-                    stop_run_if_atomic_bool!(run => ctrlc_stop_flag);
+        // calulate length to read and position to seek to
+        let RequiredLenAndSeekTo {
+            required_len,
+            seek_to,
+        } = misc_utils::calc_required_len_and_seek_to_from_byte_range(
+            param.from_pos,
+            param.to_pos,
+            param.force_misalign,
+            0,
+            PositionOrLength::Len(file_size),
+            Some(ver_to_block_size(version) as u64),
+        );
+
+        // seek to calculated position, skipping the part already sorted
+        reader.seek(SeekFrom::Start(seek_to + bytes_processed))?;
+
+        let read_offset = seek_to % ver_to_block_size(version) as u64;
+
+        let stats_before = stats.lock().unwrap().clone();
+
+        let (to_writer, from_reader) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 2); // one extra space for the case of metadata block
+        let (to_counter, from_writer) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
+        let (to_reader, from_counter) = sync_channel(PIPELINE_BUFFER_IN_ROTATION + 1);
+        let (error_tx_reader, error_rx) = channel::<Error>();
+        let error_tx_writer = error_tx_reader.clone();
+
+        let worker_shutdown_barrier = Arc::new(Barrier::new(3));
+
+        // push buffers into pipeline
+        let buffers = DataBlockBuffer::new_multi(
+            ref_block.get_version(),
+            Some(&ref_block.get_uid()),
+            InputType::Block(BlockArrangement::Unordered),
+            OutputType::Block,
+            data_par_burst,
+            true,
+            skip_good,
+            PIPELINE_BUFFER_IN_ROTATION,
+        );
+
+        for buffer in buffers.into_iter() {
+            to_reader.send(Some(buffer)).unwrap();
+        }
 
-                    stop_run_if_reached_required_len!(run => bytes_processed, required_len);
+        let reader_thread = {
+            let version = ref_block.get_version();
+            let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+            let report_blank = param.report_blank;
+            let block_size = ver_to_block_size(version);
+            let mut bytes_processed = bytes_processed;
+            let ctrlc_stop_flag = Arc::clone(&ctrlc_stop_flag);
+            let sparse_map = Arc::clone(&sparse_map);
+
+            thread::spawn(move || {
+                let mut run = true;
+
+                while let Some(mut buffer) = from_counter.recv().unwrap() {
+                    if !run {
+                        break;
+                    }
 
-                    let read_pos = seek_to + bytes_processed;
+                    let mut meta_blocks_same_order = 0;
+                    let mut meta_blocks_diff_order = 0;
+                    let mut meta_blocks_decoded = 0;
+                    let mut parity_blocks_decoded = 0;
+                    let mut data_blocks_decoded = 0;
+                    let mut blocks_decode_failed = 0;
+                    let mut okay_blank_blocks = 0;
 
-                    let Slot {
-                        block,
-                        slot,
-                        read_pos: slot_read_pos,
-                        content_len_exc_header: _,
-                    } = buffer.get_slot().unwrap();
-                    match reader.read(slot) {
-                        Ok(read_res) => {
-                            bytes_processed += read_res.len_read as u64;
+                    while !buffer.is_full() {
+                        stop_run_if_atomic_bool!(run => ctrlc_stop_flag);
 
-                            if read_res.eof_seen {
-                                buffer.cancel_slot();
-                                run = false;
-                                break;
-                            }
+                        stop_run_if_reached_required_len!(run => bytes_processed, required_len);
 
-                            match block.sync_from_buffer(slot, Some(&header_pred), None) {
-                                Ok(()) => {
-                                    if block.is_meta() {
-                                        if !meta_written {
-                                            let (same_order, diff_order) =
-                                                match check_meta_blocks_reader(
-                                                    version,
-                                                    data_par_burst,
-                                                    seek_to,
-                                                    slot,
-                                                    &mut reader,
-                                                ) {
-                                                    Ok(x) => x,
-                                                    Err(e) => {
-                                                        stop_run_forward_error!(run => error_tx_reader => e);
-                                                    }
-                                                };
-
-                                            meta_blocks_same_order = same_order;
-                                            meta_blocks_diff_order = diff_order;
-
-                                            // copy current metadata block to send to writer
-                                            let mut meta_buffer = vec![0u8; block_size];
-                                            meta_buffer.clone_from_slice(slot);
-
-                                            to_writer
-                                                .send(Some(SendToWriter::Meta(meta_buffer)))
-                                                .unwrap();
-
-                                            meta_written = true;
-                                        }
+                        let read_pos = seek_to + bytes_processed;
 
-                                        buffer.cancel_slot();
+                        let Slot {
+                            block,
+                            slot,
+                            read_pos: slot_read_pos,
+                            content_len_exc_header: _,
+                        } = buffer.get_slot().unwrap();
+                        match reader.read(slot) {
+                            Ok(read_res) => {
+                                bytes_processed += read_res.len_read as u64;
+
+                                if read_res.eof_seen {
+                                    buffer.cancel_slot();
+                                    run = false;
+                                    break;
+                                }
 
-                                        meta_blocks_decoded += 1;
-                                    } else {
-                                        if block.is_parity_w_data_par_burst(data_par_burst) {
-                                            parity_blocks_decoded += 1;
+                                match block.sync_from_buffer(slot, Some(&header_pred), None) {
+                                    Ok(()) => {
+                                        if block.is_meta() {
+                                            let provisional = block.get_TRL().unwrap().is_some();
+
+                                            if !meta_written {
+                                                let (same_order, diff_order) =
+                                                    match check_meta_blocks_reader(
+                                                        version,
+                                                        data_par_burst,
+                                                        seek_to,
+                                                        slot,
+                                                        &mut reader,
+                                                    ) {
+                                                        Ok(x) => x,
+                                                        Err(e) => {
+                                                            stop_run_forward_error!(run => error_tx_reader => e);
+                                                        }
+                                                    };
+
+                                                meta_blocks_same_order = same_order;
+                                                meta_blocks_diff_order = diff_order;
+
+                                                // copy current metadata block to send to writer
+                                                let mut meta_buffer = vec![0u8; block_size];
+                                                meta_buffer.clone_from_slice(slot);
+
+                                                to_writer
+                                                    .send(Some(SendToWriter::Meta(meta_buffer)))
+                                                    .unwrap();
+
+                                                meta_written = true;
+                                                meta_written_is_provisional = provisional;
+                                            } else if meta_written_is_provisional && !provisional {
+                                                // replace the provisional metadata block of a
+                                                // streamed container with the final one
+                                                let mut meta_buffer = vec![0u8; block_size];
+                                                meta_buffer.clone_from_slice(slot);
+
+                                                to_writer
+                                                    .send(Some(SendToWriter::Meta(meta_buffer)))
+                                                    .unwrap();
+
+                                                meta_written_is_provisional = false;
+                                            }
+
+                                            buffer.cancel_slot();
+
+                                            meta_blocks_decoded += 1;
                                         } else {
-                                            data_blocks_decoded += 1;
-                                        }
+                                            if block.is_parity_w_data_par_burst(data_par_burst) {
+                                                parity_blocks_decoded += 1;
+                                            } else {
+                                                data_blocks_decoded += 1;
+                                            }
 
-                                        *slot_read_pos = Some(read_pos);
+                                            *slot_read_pos = Some(read_pos);
+                                        }
                                     }
-                                }
-                                Err(_) => {
-                                    // only consider it failed if the buffer is not completely blank
-                                    // unless report blank is true
-                                    if misc_utils::buffer_is_blank(sbx_block::slice_buf(
-                                        version, slot,
-                                    )) {
-                                        if report_blank {
-                                            blocks_decode_failed += 1;
+                                    Err(_) => {
+                                        // only consider it failed if the buffer is not completely blank
+                                        // unless report blank is true
+                                        if misc_utils::buffer_is_blank(sbx_block::slice_buf(
+                                            version, slot,
+                                        )) {
+                                            let omitted = match *sparse_map {
+                                                None => false,
+                                                Some(ref sparse_map) => sparse_map.contains(
+                                                    sbx_block::calc_seq_num_at_index(
+                                                        (read_pos - read_offset)
+                                                            / block_size as u64,
+                                                        Some(true),
+                                                        data_par_burst,
+                                                    ),
+                                                ),
+                                            };
+
+                                            if report_blank && !omitted {
+                                                blocks_decode_failed += 1;
+                                            } else {
+                                                okay_blank_blocks += 1;
+                                            }
                                         } else {
-                                            okay_blank_blocks += 1;
+                                            blocks_decode_failed += 1;
                                         }
-                                    } else {
-                                        blocks_decode_failed += 1;
-                                    }
 
-                                    buffer.cancel_slot();
+                                        buffer.cancel_slot();
+                                    }
                                 }
                             }
-                        }
-                        Err(e) => {
-                            buffer.cancel_slot();
-                            stop_run_forward_error!(run => error_tx_reader => e);
+                            Err(e) => {
+                                buffer.cancel_slot();
+                                stop_run_forward_error!(run => error_tx_reader => e);
+                            }
                         }
                     }
-                }
-
-                {
-                    let mut stats = stats.lock().unwrap();
 
-                    stats.meta_blocks_decoded += meta_blocks_decoded;
-                    stats.meta_blocks_same_order += meta_blocks_same_order;
-                    stats.meta_blocks_diff_order += meta_blocks_diff_order;
-                    stats.parity_blocks_decoded += parity_blocks_decoded;
-                    stats.data_blocks_decoded += data_blocks_decoded;
-                    stats.blocks_decode_failed += blocks_decode_failed;
-                    stats.okay_blank_blocks += okay_blank_blocks;
+                    let reader_stats = ReaderStats {
+                        bytes_processed,
+                        meta_blocks_decoded,
+                        meta_blocks_same_order,
+                        meta_blocks_diff_order,
+                        data_blocks_decoded,
+                        parity_blocks_decoded,
+                        blocks_decode_failed,
+                        okay_blank_blocks,
+                    };
+
+                    to_writer
+                        .send(Some(SendToWriter::Data(reader_stats, buffer)))
+                        .unwrap();
                 }
 
-                to_writer.send(Some(SendToWriter::Data(buffer))).unwrap();
-            }
+                worker_shutdown!(to_writer, shutdown_barrier);
 
-            worker_shutdown!(to_writer, shutdown_barrier);
-        })
-    };
+                (meta_written, meta_written_is_provisional)
+            })
+        };
 
-    let writer_thread = {
-        let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
-        let multi_pass = param.multi_pass;
-
-        thread::spawn(move || {
-            while let Some(data) = from_reader.recv().unwrap() {
-                match data {
-                    SendToWriter::Meta(meta_block) => {
-                        if let Err(e) = write_meta_blocks_writer(
-                            version,
-                            data_par_burst,
-                            multi_pass,
-                            &header_pred,
-                            &meta_block,
-                            &mut writer,
-                        ) {
-                            error_tx_writer.send(e).unwrap();
-                            break;
-                        }
+        let writer_thread = {
+            let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+            let multi_pass = param.multi_pass;
+            let log_handler = Arc::clone(&log_handler);
+
+            thread::spawn(move || {
+                while let Some(data) = from_reader.recv().unwrap() {
+                    // check if there's any error in log handling
+                    if let Err(e) = log_handler.pop_error() {
+                        error_tx_writer.send(e).unwrap();
+                        break;
                     }
-                    SendToWriter::Data(mut buffer) => {
-                        match writer {
-                            Some(ref mut writer) => {
-                                if let Err(e) = buffer.write(writer) {
-                                    error_tx_writer.send(e).unwrap();
-                                    break;
-                                }
-                            }
-                            None => {
-                                buffer.calc_slot_write_pos();
+
+                    match data {
+                        SendToWriter::Meta(meta_block) => {
+                            if let Err(e) = write_meta_blocks_writer(
+                                version,
+                                data_par_burst,
+                                multi_pass,
+                                &header_pred,
+                                &meta_block,
+                                &mut writer,
+                            ) {
+                                error_tx_writer.send(e).unwrap();
+                                break;
                             }
                         }
+                        SendToWriter::Data(reader_stats, mut buffer) => {
+                            match writer {
+                                Some(ref mut writer) => {
+                                    if let Err(e) = buffer.write(writer) {
+                                        error_tx_writer.send(e).unwrap();
+                                        break;
+                                    }
+                                }
+                                None => {
+                                    buffer.calc_slot_write_pos();
+                                }
+                            }
 
-                        to_counter.send(Some(buffer)).unwrap();
+                            to_counter.send(Some((reader_stats, buffer))).unwrap();
+                        }
                     }
                 }
-            }
 
-            worker_shutdown!(to_counter, shutdown_barrier);
-        })
-    };
+                worker_shutdown!(to_counter, shutdown_barrier);
 
-    let counter_thread = {
-        let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
-        let stats = Arc::clone(&stats);
-
-        thread::spawn(move || {
-            while let Some(mut buffer) = from_writer.recv().unwrap() {
-                let mut data_blocks_same_order = 0;
-                let mut data_blocks_diff_order = 0;
-                let mut parity_blocks_same_order = 0;
-                let mut parity_blocks_diff_order = 0;
-
-                for SlotView {
-                    block,
-                    slot: _,
-                    read_pos,
-                    write_pos,
-                    content_len_exc_header: _,
-                } in buffer.view_slots()
-                {
-                    if read_pos.unwrap() - read_offset == write_pos.unwrap() {
-                        if block.is_parity_w_data_par_burst(data_par_burst) {
-                            parity_blocks_same_order += 1;
-                        } else {
-                            data_blocks_same_order += 1;
-                        }
-                    } else {
-                        if block.is_parity_w_data_par_burst(data_par_burst) {
-                            parity_blocks_diff_order += 1;
+                writer
+            })
+        };
+
+        let counter_thread = {
+            let shutdown_barrier = Arc::clone(&worker_shutdown_barrier);
+            let stats = Arc::clone(&stats);
+
+            thread::spawn(move || {
+                while let Some((reader_stats, mut buffer)) = from_writer.recv().unwrap() {
+                    let mut data_blocks_same_order = 0;
+                    let mut data_blocks_diff_order = 0;
+                    let mut parity_blocks_same_order = 0;
+                    let mut parity_blocks_diff_order = 0;
+
+                    for SlotView {
+                        block,
+                        slot: _,
+                        read_pos,
+                        write_pos,
+                        content_len_exc_header: _,
+                    } in buffer.view_slots()
+                    {
+                        if read_pos.unwrap() - read_offset == write_pos.unwrap() {
+                            if block.is_parity_w_data_par_burst(data_par_burst) {
+                                parity_blocks_same_order += 1;
+                            } else {
+                                data_blocks_same_order += 1;
+                            }
                         } else {
-                            data_blocks_diff_order += 1;
+                            if block.is_parity_w_data_par_burst(data_par_burst) {
+                                parity_blocks_diff_order += 1;
+                            } else {
+                                data_blocks_diff_order += 1;
+                            }
                         }
                     }
-                }
 
-                {
-                    let mut stats = stats.lock().unwrap();
+                    // only update stats after the blocks are written,
+                    // so the log file never runs ahead of the output
+                    {
+                        let ReaderStats {
+                            bytes_processed,
+                            meta_blocks_decoded,
+                            meta_blocks_same_order,
+                            meta_blocks_diff_order,
+                            data_blocks_decoded,
+                            parity_blocks_decoded,
+                            blocks_decode_failed,
+                            okay_blank_blocks,
+                        } = reader_stats;
+
+                        let mut stats = stats.lock().unwrap();
+
+                        stats.bytes_processed = bytes_processed;
+                        stats.meta_blocks_decoded += meta_blocks_decoded;
+                        stats.meta_blocks_same_order += meta_blocks_same_order;
+                        stats.meta_blocks_diff_order += meta_blocks_diff_order;
+                        stats.parity_blocks_decoded += parity_blocks_decoded;
+                        stats.data_blocks_decoded += data_blocks_decoded;
+                        stats.blocks_decode_failed += blocks_decode_failed;
+                        stats.okay_blank_blocks += okay_blank_blocks;
+                        stats.data_blocks_same_order += data_blocks_same_order;
+                        stats.data_blocks_diff_order += data_blocks_diff_order;
+                        stats.parity_blocks_same_order += parity_blocks_same_order;
+                        stats.parity_blocks_diff_order += parity_blocks_diff_order;
+                    }
+
+                    buffer.reset();
 
-                    stats.data_blocks_same_order += data_blocks_same_order;
-                    stats.data_blocks_diff_order += data_blocks_diff_order;
-                    stats.parity_blocks_same_order += parity_blocks_same_order;
-                    stats.parity_blocks_diff_order += parity_blocks_diff_order;
+                    to_reader.send(Some(buffer)).unwrap();
                 }
 
-                buffer.reset();
+                worker_shutdown!(to_reader, shutdown_barrier);
+            })
+        };
 
-                to_reader.send(Some(buffer)).unwrap();
-            }
+        let meta_state = reader_thread.join().unwrap();
+        meta_written = meta_state.0;
+        meta_written_is_provisional = meta_state.1;
+        writer = writer_thread.join().unwrap();
+        counter_thread.join().unwrap();
 
-            worker_shutdown!(to_reader, shutdown_barrier);
-        })
-    };
+        if let Ok(err) = error_rx.try_recv() {
+            return Err(err);
+        }
+
+        {
+            let mut stats = stats.lock().unwrap();
 
-    reader_thread.join().unwrap();
-    writer_thread.join().unwrap();
-    counter_thread.join().unwrap();
+            let input_stats = InputStats {
+                file: String::from(*in_file),
+                meta_blocks_decoded: stats.meta_blocks_decoded - stats_before.meta_blocks_decoded,
+                data_blocks_decoded: stats.data_blocks_decoded - stats_before.data_blocks_decoded,
+                parity_blocks_decoded: stats.parity_blocks_decoded
+                    - stats_before.parity_blocks_decoded,
+                blocks_decode_failed: stats.blocks_decode_failed
+                    - stats_before.blocks_decode_failed,
+            };
+
+            stats.inputs.push(input_stats);
+        }
+
+        break_if_atomic_bool!(ctrlc_stop_flag);
+    }
+
+    // the output container has the same holes as the input container
+    if let (Some(ref mut writer), Some(ref sparse_map), Some(ref out_file)) =
+        (&mut writer, &*sparse_map, &param.out_file)
+    {
+        let orig_file_size = if ref_block.is_meta() {
+            ref_block.get_FSZ().unwrap()
+        } else {
+            None
+        };
+
+        if let Some(orig_file_size) = orig_file_size {
+            let container_size = file_utils::from_orig_file_size::calc_container_size(
+                version,
+                Some(true),
+                data_par_burst,
+                orig_file_size,
+            );
+
+            if writer.get_file_size().unwrap()? < container_size {
+                writer.set_len(container_size).unwrap()?;
+            }
+        }
 
-    if let Ok(err) = error_rx.try_recv() {
-        return Err(err);
+        sparse_map.write_to_file(&sparse_map::default_file(out_file))?;
     }
 
     reporter.stop();
+    log_handler.stop();
 
     let stats = stats.lock().unwrap().clone();
 
@@ -0,0 +1,613 @@
+use crate::block_utils::{GuessBurstFromPos, RefBlockChoice};
+use crate::cli_utils::setup_ctrlc_handler;
+use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::journal::canonical_path;
+use crate::json_printer::{BracketType, JSONPrinter};
+use crate::misc_utils;
+use crate::misc_utils::RangeEnd;
+use crate::progress_report::*;
+use crate::sbx_block;
+use crate::sbx_block::{Block, Header};
+use crate::sbx_specs::SBX_LARGEST_BLOCK_SIZE;
+use crate::sbx_specs::{ver_to_block_size, ver_to_usize, ver_uses_rs, Version};
+use crate::sort_journal::MAX_WRITES_PER_RECORD;
+use crate::sort_journal::{SortJournal, SortJournalHeader, SortJournalRecord};
+use crate::sparse_map;
+use crate::time_utils;
+use std::fmt;
+use std::io::SeekFrom;
+use std::sync::atomic::Ordering;
+use std::sync::{Arc, Mutex};
+
+pub struct Param {
+    ref_block_choice: RefBlockChoice,
+    ref_block_from_pos: Option<u64>,
+    ref_block_to_pos: Option<RangeEnd<u64>>,
+    report_blank: bool,
+    guess_burst_from_pos: Option<u64>,
+    json_printer: Arc<JSONPrinter>,
+    in_file: String,
+    journal_file: String,
+    sparse_map_file: Option<String>,
+    verbose: bool,
+    pr_verbosity_level: PRVerbosityLevel,
+    burst: Option<usize>,
+}
+
+impl Param {
+    pub fn new(
+        ref_block_choice: RefBlockChoice,
+        ref_block_from_pos: Option<u64>,
+        ref_block_to_pos: Option<RangeEnd<u64>>,
+        report_blank: bool,
+        guess_burst_from_pos: Option<u64>,
+        json_printer: &Arc<JSONPrinter>,
+        in_file: &str,
+        journal_file: &str,
+        sparse_map_file: Option<&str>,
+        verbose: bool,
+        pr_verbosity_level: PRVerbosityLevel,
+        burst: Option<usize>,
+    ) -> Param {
+        Param {
+            ref_block_choice,
+            ref_block_from_pos,
+            ref_block_to_pos,
+            report_blank,
+            guess_burst_from_pos,
+            json_printer: Arc::clone(json_printer),
+            in_file: String::from(in_file),
+            journal_file: String::from(journal_file),
+            sparse_map_file: sparse_map_file.map(String::from),
+            verbose,
+            pr_verbosity_level,
+            burst,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    version: Version,
+    bytes_processed: u64,
+    container_size: u64,
+    pub resumed: bool,
+    pub blocks_moved: u64,
+    pub duplicate_blocks_removed: u64,
+    pub meta_blocks_written: u64,
+    pub blocks_decode_failed: u64,
+    pub final_container_size: u64,
+    start_time: f64,
+    end_time: f64,
+    json_printer: Arc<JSONPrinter>,
+}
+
+impl Stats {
+    pub fn new(
+        version: Version,
+        container_size: u64,
+        bytes_processed: u64,
+        resumed: bool,
+        json_printer: &Arc<JSONPrinter>,
+    ) -> Stats {
+        Stats {
+            version,
+            bytes_processed,
+            container_size,
+            resumed,
+            blocks_moved: 0,
+            duplicate_blocks_removed: 0,
+            meta_blocks_written: 0,
+            blocks_decode_failed: 0,
+            final_container_size: container_size,
+            start_time: 0.,
+            end_time: 0.,
+            json_printer: Arc::clone(json_printer),
+        }
+    }
+}
+
+impl ProgressReport for Stats {
+    fn start_time_mut(&mut self) -> &mut f64 {
+        &mut self.start_time
+    }
+
+    fn end_time_mut(&mut self) -> &mut f64 {
+        &mut self.end_time
+    }
+
+    fn units_so_far(&self) -> u64 {
+        self.bytes_processed
+    }
+
+    fn total_units(&self) -> Option<u64> {
+        Some(self.container_size)
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let block_size = ver_to_block_size(self.version);
+        let time_elapsed = (self.end_time - self.start_time) as i64;
+        let (hour, minute, second) = time_utils::seconds_to_hms(time_elapsed);
+
+        let json_printer = &self.json_printer;
+
+        json_printer.write_open_bracket(f, Some("stats"), BracketType::Curly)?;
+
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX version                               : {}",
+            ver_to_usize(self.version)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Block size used in sorting                : {}",
+            block_size
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Resumed from journal                      : {}",
+            self.resumed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of bytes processed                 : {}",
+            self.bytes_processed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks moved                    : {}",
+            self.blocks_moved
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of duplicate blocks removed        : {}",
+            self.duplicate_blocks_removed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks written (metadata)       : {}",
+            self.meta_blocks_written
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks failed to sort           : {}",
+            self.blocks_decode_failed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Container size after sorting              : {}",
+            self.final_container_size
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Time elapsed                              : {:02}:{:02}:{:02}",
+            hour,
+            minute,
+            second
+        )?;
+
+        json_printer.write_close_bracket(f)?;
+
+        Ok(())
+    }
+}
+
+enum BlockKind {
+    Meta,
+    Data(u64),
+    Invalid,
+}
+
+enum Displaced {
+    CopyInPlace,
+    Block(Vec<u8>),
+    Nothing,
+}
+
+fn read_block(container: &mut FileWriter, pos: u64, buffer: &mut [u8]) -> Result<bool, Error> {
+    for x in buffer.iter_mut() {
+        *x = 0;
+    }
+
+    container.seek(SeekFrom::Start(pos))?;
+    let read_res = container.read(buffer)?;
+
+    Ok(!read_res.eof_seen)
+}
+
+fn classify_block(
+    block: &mut Block,
+    buffer: &[u8],
+    header_pred: &dyn Fn(&Header) -> bool,
+    data_par_burst: Option<(usize, usize, usize)>,
+) -> BlockKind {
+    match block.sync_from_buffer(buffer, Some(header_pred), None) {
+        Ok(()) => {
+            if block.is_meta() {
+                BlockKind::Meta
+            } else {
+                BlockKind::Data(sbx_block::calc_data_block_write_pos(
+                    block.get_version(),
+                    block.get_seq_num(),
+                    Some(true),
+                    data_par_burst,
+                ))
+            }
+        }
+        Err(_) => BlockKind::Invalid,
+    }
+}
+
+fn is_provisional_meta(buffer: &[u8]) -> bool {
+    let mut block = Block::dummy();
+
+    match block.sync_from_buffer(buffer, None, None) {
+        Ok(()) => match block.get_TRL() {
+            Ok(x) => x.is_some(),
+            Err(_) => false,
+        },
+        Err(_) => false,
+    }
+}
+
+fn keep_meta_block(meta_block: &mut Option<Vec<u8>>, buffer: &[u8]) {
+    // the first metadata block is used, unless it is the provisional metadata
+    // block of a streamed container and a final one shows up later
+    let replace = match meta_block {
+        None => true,
+        Some(ref x) => is_provisional_meta(x) && !is_provisional_meta(buffer),
+    };
+
+    if replace {
+        *meta_block = Some(buffer.to_vec());
+    }
+}
+
+fn apply_writes(container: &mut FileWriter, writes: &[(u64, Vec<u8>)]) -> Result<(), Error> {
+    for &(pos, ref block) in writes.iter() {
+        container.seek(SeekFrom::Start(pos))?;
+        container.write(block)?;
+    }
+
+    container.sync()
+}
+
+pub fn sort_file_in_place(param: &Param) -> Result<Option<Stats>, Error> {
+    let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
+
+    let json_printer = &param.json_printer;
+
+    let resumed = file_utils::check_if_file_exists(&param.journal_file)
+        && file_utils::get_file_size(&param.journal_file)? > 0;
+
+    // the layout is taken from the journal when resuming, as guessing the burst
+    // error resistance level of a partially sorted container is unreliable
+    let (mut journal, header, record) = if resumed {
+        let (journal, header, record) = SortJournal::open(&param.journal_file, &param.in_file)?;
+
+        if let (Some(burst), Some((_, _, journal_burst))) = (param.burst, header.data_par_burst) {
+            if burst != journal_burst {
+                return Err(Error::with_msg(&format!(
+                    "Sort journal \"{}\" was made for burst error resistance level {}",
+                    param.journal_file, journal_burst
+                )));
+            }
+        }
+
+        print_if!(verbose not_json => param, json_printer =>
+                  "Resuming from sort journal \"{}\"", param.journal_file;
+                  "";);
+
+        (journal, header, record)
+    } else {
+        let (ref_block_pos, ref_block) = get_ref_block!(no_force_misalign => param,
+                                                        json_printer,
+                                                        param.ref_block_choice,
+                                                        ctrlc_stop_flag);
+
+        let version = ref_block.get_version();
+
+        let data_par_burst = if ver_uses_rs(version) {
+            let from_pos = match param.guess_burst_from_pos {
+                None => None,
+                Some(x) => Some(GuessBurstFromPos::NoShift(x)),
+            };
+
+            Some((
+                get_RSD_from_ref_block!(ref_block_pos, ref_block, "sort"),
+                get_RSP_from_ref_block!(ref_block_pos, ref_block, "sort"),
+                get_burst_or_guess!(param, from_pos, false, ref_block_pos, ref_block),
+            ))
+        } else {
+            None
+        };
+
+        let header = SortJournalHeader {
+            container: canonical_path(&param.in_file),
+            container_size: file_utils::get_file_size(&param.in_file)?,
+            version,
+            uid: ref_block.get_uid(),
+            data_par_burst,
+        };
+
+        let journal = SortJournal::create(&param.journal_file, &header)?;
+
+        let record = SortJournalRecord {
+            counter: 0,
+            scan_pos: 0,
+            meta_block: None,
+            held_block: None,
+            writes: Vec::new(),
+        };
+
+        (journal, header, record)
+    };
+
+    let version = header.version;
+    let uid = header.uid;
+    let data_par_burst = header.data_par_burst;
+    let block_size = ver_to_block_size(version);
+    let container_size = header.container_size;
+
+    // holes left by the blocks recorded as omitted are not failures
+    let sparse_map = sparse_map::load_by_uid(
+        &uid,
+        &param.in_file,
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+    )?;
+
+    let header_pred =
+        move |header: &Header| -> bool { header.version == version && header.uid == uid };
+
+    let stats = Arc::new(Mutex::new(Stats::new(
+        version,
+        container_size,
+        record.scan_pos,
+        resumed,
+        json_printer,
+    )));
+
+    let mut container = FileWriter::new(
+        &param.in_file,
+        FileWriterParam {
+            read: true,
+            append: false,
+            truncate: false,
+            buffered: false,
+        },
+    )?;
+
+    let reporter = ProgressReporter::new(
+        &stats,
+        "SBX block sorting progress",
+        "bytes",
+        param.pr_verbosity_level,
+        param.json_printer.json_enabled(),
+    );
+
+    // redo the writes of the last record, which may not have completed
+    apply_writes(&mut container, &record.writes)?;
+
+    let mut counter = record.counter;
+    let mut scan_pos = record.scan_pos;
+    let mut meta_block = record.meta_block;
+    let mut held_block = record.held_block;
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+    let blank_block = vec![0u8; block_size];
+
+    reporter.start();
+
+    loop {
+        break_if_atomic_bool!(ctrlc_stop_flag);
+
+        stats.lock().unwrap().bytes_processed = u64::min(scan_pos, container_size);
+
+        let mut cur_block = match held_block.take() {
+            Some(x) => x,
+            None => {
+                if scan_pos >= container_size {
+                    break;
+                }
+
+                let buffer = sbx_block::slice_buf_mut(version, &mut buffer);
+
+                if !read_block(&mut container, scan_pos, buffer)? {
+                    break;
+                }
+
+                match classify_block(&mut block, buffer, &header_pred, data_par_burst) {
+                    BlockKind::Data(dest) if dest != scan_pos => buffer.to_vec(),
+                    BlockKind::Data(_) => {
+                        scan_pos += block_size as u64;
+                        continue;
+                    }
+                    BlockKind::Meta => {
+                        keep_meta_block(&mut meta_block, buffer);
+                        scan_pos += block_size as u64;
+                        continue;
+                    }
+                    BlockKind::Invalid => {
+                        let omitted = match sparse_map {
+                            None => false,
+                            Some(ref sparse_map) => {
+                                sparse_map.contains(sbx_block::calc_seq_num_at_index(
+                                    scan_pos / block_size as u64,
+                                    Some(true),
+                                    data_par_burst,
+                                ))
+                            }
+                        };
+
+                        if (param.report_blank && !omitted) || !misc_utils::buffer_is_blank(buffer)
+                        {
+                            stats.lock().unwrap().blocks_decode_failed += 1;
+                        }
+                        scan_pos += block_size as u64;
+                        continue;
+                    }
+                }
+            }
+        };
+
+        // follow the cycle starting at the hole at scan position,
+        // every block displaced is carried to its own destination in turn
+        let hole = scan_pos;
+        let mut writes: Vec<(u64, Vec<u8>)> = Vec::with_capacity(MAX_WRITES_PER_RECORD);
+
+        loop {
+            // one write is reserved for filling the hole, the rest of
+            // the cycle is continued in the next record
+            if writes.len() + 1 >= MAX_WRITES_PER_RECORD {
+                held_block = Some(cur_block);
+                break;
+            }
+
+            let dest = match classify_block(&mut block, &cur_block, &header_pred, data_par_burst) {
+                BlockKind::Data(dest) => dest,
+                _ => unreachable!(),
+            };
+
+            if dest == hole {
+                writes.push((hole, cur_block));
+                stats.lock().unwrap().blocks_moved += 1;
+                break;
+            }
+
+            let buffer = sbx_block::slice_buf_mut(version, &mut buffer);
+
+            let displaced = if writes.iter().any(|&(p, _)| p == dest) {
+                Displaced::CopyInPlace
+            } else {
+                let _ = read_block(&mut container, dest, buffer)?;
+
+                match classify_block(&mut block, buffer, &header_pred, data_par_burst) {
+                    BlockKind::Data(x) if x == dest => Displaced::CopyInPlace,
+                    BlockKind::Data(_) => Displaced::Block(buffer.to_vec()),
+                    BlockKind::Meta => {
+                        keep_meta_block(&mut meta_block, buffer);
+                        Displaced::Nothing
+                    }
+                    BlockKind::Invalid => Displaced::Nothing,
+                }
+            };
+
+            if let Displaced::CopyInPlace = displaced {
+                writes.push((hole, blank_block.clone()));
+                stats.lock().unwrap().duplicate_blocks_removed += 1;
+                break;
+            }
+
+            writes.push((dest, cur_block));
+            stats.lock().unwrap().blocks_moved += 1;
+
+            match displaced {
+                Displaced::Block(x) => cur_block = x,
+                _ => {
+                    // the chain ends at a position without a valid block,
+                    // so nothing is moved into the hole
+                    writes.push((hole, blank_block.clone()));
+                    break;
+                }
+            }
+        }
+
+        if held_block.is_none() {
+            scan_pos += block_size as u64;
+        }
+
+        counter += 1;
+
+        // the record must be on disk before the container is touched
+        journal.write_record(&SortJournalRecord {
+            counter,
+            scan_pos,
+            meta_block: meta_block.clone(),
+            held_block: held_block.clone(),
+            writes: writes.clone(),
+        })?;
+
+        apply_writes(&mut container, &writes)?;
+    }
+
+    if !ctrlc_stop_flag.load(Ordering::SeqCst) {
+        stats.lock().unwrap().bytes_processed = container_size;
+
+        if let Some(ref meta_block) = meta_block {
+            counter += 1;
+
+            journal.write_record(&SortJournalRecord {
+                counter,
+                scan_pos,
+                meta_block: Some(meta_block.clone()),
+                held_block: None,
+                writes: Vec::new(),
+            })?;
+
+            let write_pos_s = sbx_block::calc_meta_block_all_write_pos_s(version, data_par_burst);
+
+            for &p in write_pos_s.iter() {
+                container.seek(SeekFrom::Start(p))?;
+                container.write(meta_block)?;
+
+                stats.lock().unwrap().meta_blocks_written += 1;
+            }
+
+            container.sync()?;
+        }
+
+        // drop the trailing blocks left over from the previous layout
+        let file_size = container.get_file_size()?;
+        stats.lock().unwrap().final_container_size = file_size;
+
+        let mut pos = file_size - file_size % block_size as u64;
+        while pos > 0 {
+            pos -= block_size as u64;
+
+            let buffer = sbx_block::slice_buf_mut(version, &mut buffer);
+            let _ = read_block(&mut container, pos, buffer)?;
+
+            match classify_block(&mut block, buffer, &header_pred, data_par_burst) {
+                BlockKind::Invalid => {}
+                _ => {
+                    let new_size = pos + block_size as u64;
+
+                    if new_size < file_size {
+                        container.set_len(new_size)?;
+                        container.sync()?;
+                    }
+
+                    stats.lock().unwrap().final_container_size = new_size;
+
+                    break;
+                }
+            }
+        }
+
+        file_utils::remove_file(&param.journal_file)?;
+    }
+
+    reporter.stop();
+
+    let stats = stats.lock().unwrap().clone();
+
+    Ok(Some(stats))
+}
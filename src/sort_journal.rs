@@ -0,0 +1,377 @@
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::journal::{calc_crc, canonical_path};
+use crate::sbx_specs::SBX_FILE_UID_LEN;
+use crate::sbx_specs::{string_to_ver, ver_to_block_size, ver_to_usize, Version};
+use std::io::SeekFrom;
+
+const SORT_JOURNAL_SIGNATURE: &[u8; 8] = b"BLKARSRT";
+const SORT_JOURNAL_VERSION: u8 = 1;
+
+pub const MAX_WRITES_PER_RECORD: usize = 256;
+
+const RECORD_FLAG_META: u8 = 0x1;
+const RECORD_FLAG_HELD: u8 = 0x2;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortJournalHeader {
+    pub container: String,
+    pub container_size: u64,
+    pub version: Version,
+    pub uid: [u8; SBX_FILE_UID_LEN],
+    pub data_par_burst: Option<(usize, usize, usize)>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SortJournalRecord {
+    pub counter: u64,
+    pub scan_pos: u64,
+    pub meta_block: Option<Vec<u8>>,
+    pub held_block: Option<Vec<u8>>,
+    pub writes: Vec<(u64, Vec<u8>)>,
+}
+
+pub struct SortJournal {
+    writer: FileWriter,
+    header_len: u64,
+    slot_len: u64,
+}
+
+mod parsers {
+    use super::SORT_JOURNAL_SIGNATURE;
+    use nom::number::complete::{be_u32, be_u64, be_u8};
+
+    type HeaderParseResult<'a> = (u8, u8, &'a [u8], u8, u32, u32, u32, u64, &'a [u8]);
+
+    named!(pub header_p <HeaderParseResult>,
+           do_parse!(
+               _sig : tag!(SORT_JOURNAL_SIGNATURE) >>
+                   ver            : be_u8 >>
+                   sbx_ver        : be_u8 >>
+                   uid            : take!(6) >>
+                   uses_rs        : be_u8 >>
+                   data           : be_u32 >>
+                   parity         : be_u32 >>
+                   burst          : be_u32 >>
+                   container_size : be_u64 >>
+                   path_len       : be_u32 >>
+                   path           : take!(path_len) >>
+                   ((ver, sbx_ver, uid, uses_rs, data, parity, burst, container_size, path))
+           )
+    );
+
+    named!(pub record_fixed_p <(u64, u64, u8, u32)>,
+           do_parse!(
+               counter         : be_u64 >>
+                   scan_pos    : be_u64 >>
+                   flags       : be_u8 >>
+                   write_count : be_u32 >>
+                   ((counter, scan_pos, flags, write_count))
+           )
+    );
+}
+
+fn take_bytes(input: &[u8], len: usize) -> Option<(&[u8], &[u8])> {
+    if input.len() < len {
+        None
+    } else {
+        Some((&input[len..], &input[..len]))
+    }
+}
+
+pub fn calc_slot_len(version: Version) -> u64 {
+    let block_size = ver_to_block_size(version) as u64;
+    let max_writes = MAX_WRITES_PER_RECORD as u64;
+
+    // fixed fields, metadata block, held block, writes, and CRC
+    8 + 8 + 1 + 4 + 2 * block_size + max_writes * (8 + block_size) + 2
+}
+
+impl SortJournalHeader {
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(100);
+
+        let (uses_rs, (data, parity, burst)) = match self.data_par_burst {
+            None => (0u8, (0, 0, 0)),
+            Some(x) => (1u8, x),
+        };
+
+        bytes.extend_from_slice(SORT_JOURNAL_SIGNATURE);
+        bytes.push(SORT_JOURNAL_VERSION);
+        bytes.push(ver_to_usize(self.version) as u8);
+        bytes.extend_from_slice(&self.uid);
+        bytes.push(uses_rs);
+        bytes.extend_from_slice(&(data as u32).to_be_bytes());
+        bytes.extend_from_slice(&(parity as u32).to_be_bytes());
+        bytes.extend_from_slice(&(burst as u32).to_be_bytes());
+        bytes.extend_from_slice(&self.container_size.to_be_bytes());
+        bytes.extend_from_slice(&(self.container.len() as u32).to_be_bytes());
+        bytes.extend_from_slice(self.container.as_bytes());
+
+        bytes
+    }
+}
+
+impl SortJournalRecord {
+    fn to_bytes_exc_crc(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(100);
+
+        let mut flags = 0;
+        if self.meta_block.is_some() {
+            flags |= RECORD_FLAG_META;
+        }
+        if self.held_block.is_some() {
+            flags |= RECORD_FLAG_HELD;
+        }
+
+        bytes.extend_from_slice(&self.counter.to_be_bytes());
+        bytes.extend_from_slice(&self.scan_pos.to_be_bytes());
+        bytes.push(flags);
+        bytes.extend_from_slice(&(self.writes.len() as u32).to_be_bytes());
+        if let Some(ref x) = self.meta_block {
+            bytes.extend_from_slice(x);
+        }
+        if let Some(ref x) = self.held_block {
+            bytes.extend_from_slice(x);
+        }
+        for &(pos, ref block) in self.writes.iter() {
+            bytes.extend_from_slice(&pos.to_be_bytes());
+            bytes.extend_from_slice(block);
+        }
+
+        bytes
+    }
+
+    pub fn calc_crc(&self) -> u16 {
+        calc_crc(&self.to_bytes_exc_crc())
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = self.to_bytes_exc_crc();
+
+        let crc = calc_crc(&bytes);
+
+        bytes.extend_from_slice(&crc.to_be_bytes());
+
+        bytes
+    }
+
+    pub fn parse(input: &[u8], block_size: usize) -> Option<SortJournalRecord> {
+        let (rest, (counter, scan_pos, flags, write_count)) =
+            parsers::record_fixed_p(input).ok()?;
+
+        if write_count as usize > MAX_WRITES_PER_RECORD {
+            return None;
+        }
+
+        let (rest, meta_block) = if flags & RECORD_FLAG_META != 0 {
+            let (rest, x) = take_bytes(rest, block_size)?;
+            (rest, Some(x.to_vec()))
+        } else {
+            (rest, None)
+        };
+
+        let (mut rest, held_block) = if flags & RECORD_FLAG_HELD != 0 {
+            let (rest, x) = take_bytes(rest, block_size)?;
+            (rest, Some(x.to_vec()))
+        } else {
+            (rest, None)
+        };
+
+        let mut writes = Vec::with_capacity(write_count as usize);
+        for _ in 0..write_count {
+            let (r, pos) = take_bytes(rest, 8)?;
+            let (r, block) = take_bytes(r, block_size)?;
+
+            let mut pos_bytes = [0u8; 8];
+            pos_bytes.copy_from_slice(pos);
+
+            writes.push((u64::from_be_bytes(pos_bytes), block.to_vec()));
+            rest = r;
+        }
+
+        let (_, crc) = take_bytes(rest, 2)?;
+        let crc = ((crc[0] as u16) << 8) | crc[1] as u16;
+
+        let record = SortJournalRecord {
+            counter,
+            scan_pos,
+            meta_block,
+            held_block,
+            writes,
+        };
+
+        if record.calc_crc() == crc {
+            Some(record)
+        } else {
+            None
+        }
+    }
+}
+
+pub fn parse(input: &[u8], path: &str) -> Result<(SortJournalHeader, SortJournalRecord), Error> {
+    let parse_err = || Error::with_msg(&format!("Failed to parse sort journal \"{}\"", path));
+
+    let (rest, header) = match parsers::header_p(input) {
+        Ok((
+            rest,
+            (ver, sbx_ver, uid, uses_rs, data, parity, burst, container_size, container),
+        )) => {
+            if ver != SORT_JOURNAL_VERSION {
+                return Err(parse_err());
+            }
+
+            let version = match string_to_ver(&format!("{}", sbx_ver)) {
+                Ok(x) => x,
+                Err(()) => return Err(parse_err()),
+            };
+
+            let container = match std::str::from_utf8(container) {
+                Ok(x) => String::from(x),
+                Err(_) => return Err(parse_err()),
+            };
+
+            let mut uid_buf = [0u8; SBX_FILE_UID_LEN];
+            uid_buf.copy_from_slice(uid);
+
+            (
+                rest,
+                SortJournalHeader {
+                    container,
+                    container_size,
+                    version,
+                    uid: uid_buf,
+                    data_par_burst: if uses_rs == 0 {
+                        None
+                    } else {
+                        Some((data as usize, parity as usize, burst as usize))
+                    },
+                },
+            )
+        }
+        Err(_) => return Err(parse_err()),
+    };
+
+    let block_size = ver_to_block_size(header.version);
+    let slot_len = calc_slot_len(header.version) as usize;
+
+    // a slot is only partially written if the run was interrupted while
+    // recording, in which case the other slot holds the last complete record
+    let mut latest: Option<SortJournalRecord> = None;
+    for i in 0..2 {
+        let start = usize::min(i * slot_len, rest.len());
+        let end = usize::min(start + slot_len, rest.len());
+
+        if let Some(record) = SortJournalRecord::parse(&rest[start..end], block_size) {
+            let newer = match latest {
+                None => true,
+                Some(ref x) => record.counter > x.counter,
+            };
+            if newer {
+                latest = Some(record);
+            }
+        }
+    }
+
+    match latest {
+        Some(record) => Ok((header, record)),
+        None => Err(Error::with_msg(&format!(
+            "Sort journal \"{}\" does not contain a complete record",
+            path
+        ))),
+    }
+}
+
+impl SortJournal {
+    pub fn create(path: &str, header: &SortJournalHeader) -> Result<SortJournal, Error> {
+        let mut writer = FileWriter::new(
+            path,
+            FileWriterParam {
+                read: false,
+                append: false,
+                truncate: true,
+                buffered: false,
+            },
+        )?;
+
+        let header_bytes = header.to_bytes();
+
+        writer.write(&header_bytes)?;
+        writer.sync()?;
+
+        let mut journal = SortJournal {
+            writer,
+            header_len: header_bytes.len() as u64,
+            slot_len: calc_slot_len(header.version),
+        };
+
+        journal.write_record(&SortJournalRecord {
+            counter: 0,
+            scan_pos: 0,
+            meta_block: None,
+            held_block: None,
+            writes: Vec::new(),
+        })?;
+
+        Ok(journal)
+    }
+
+    pub fn open(
+        path: &str,
+        container: &str,
+    ) -> Result<(SortJournal, SortJournalHeader, SortJournalRecord), Error> {
+        let file_size = file_utils::get_file_size(path)?;
+
+        let mut reader = FileReader::new(
+            path,
+            FileReaderParam {
+                write: false,
+                buffered: false,
+            },
+        )?;
+
+        let mut buffer = vec![0; file_size as usize];
+        let _len_read = reader.read(&mut buffer)?;
+
+        let (header, record) = parse(&buffer, path)?;
+
+        if header.container != canonical_path(container) {
+            return Err(Error::with_msg(&format!(
+                "Sort journal \"{}\" was made for container \"{}\"",
+                path, header.container
+            )));
+        }
+
+        let writer = FileWriter::new(
+            path,
+            FileWriterParam {
+                read: false,
+                append: false,
+                truncate: false,
+                buffered: false,
+            },
+        )?;
+
+        let journal = SortJournal {
+            writer,
+            header_len: header.to_bytes().len() as u64,
+            slot_len: calc_slot_len(header.version),
+        };
+
+        Ok((journal, header, record))
+    }
+
+    pub fn write_record(&mut self, record: &SortJournalRecord) -> Result<(), Error> {
+        assert!(record.writes.len() <= MAX_WRITES_PER_RECORD);
+
+        // alternate between two slots so the previous record stays intact
+        // until the current one is fully on disk
+        let slot_pos = self.header_len + (record.counter % 2) * self.slot_len;
+
+        self.writer.seek(SeekFrom::Start(slot_pos))?;
+        self.writer.write(&record.to_bytes())?;
+        self.writer.sync()
+    }
+}
@@ -0,0 +1,162 @@
+#![cfg(test)]
+
+use crate::sbx_specs::{ver_to_block_size, Version};
+use crate::sort_journal::*;
+
+fn make_header(version: Version) -> SortJournalHeader {
+    SortJournalHeader {
+        container: String::from("/tmp/test.sbx"),
+        container_size: 1 << 20,
+        version,
+        uid: [1, 2, 3, 4, 5, 6],
+        data_par_burst: Some((10, 2, 3)),
+    }
+}
+
+fn make_record(version: Version, counter: u64) -> SortJournalRecord {
+    let block_size = ver_to_block_size(version);
+
+    SortJournalRecord {
+        counter,
+        scan_pos: counter * block_size as u64,
+        meta_block: Some(vec![0xAA; block_size]),
+        held_block: Some(vec![counter as u8; block_size]),
+        writes: vec![
+            (0, vec![1; block_size]),
+            (7 * block_size as u64, vec![2; block_size]),
+        ],
+    }
+}
+
+fn make_journal(header: &SortJournalHeader, slots: &[Option<Vec<u8>>]) -> Vec<u8> {
+    let slot_len = calc_slot_len(header.version) as usize;
+
+    let mut bytes = header.to_bytes();
+    for slot in slots.iter() {
+        let mut slot_bytes = match slot {
+            None => vec![],
+            Some(x) => x.clone(),
+        };
+        slot_bytes.resize(slot_len, 0);
+
+        bytes.extend_from_slice(&slot_bytes);
+    }
+
+    bytes
+}
+
+#[test]
+fn test_record_to_bytes_parse_round_trip() {
+    let record = make_record(Version::V17, 3);
+
+    let block_size = ver_to_block_size(Version::V17);
+
+    assert_eq!(
+        Some(record.clone()),
+        SortJournalRecord::parse(&record.to_bytes(), block_size)
+    );
+}
+
+#[test]
+fn test_record_without_optional_parts() {
+    let record = SortJournalRecord {
+        counter: 0,
+        scan_pos: 0,
+        meta_block: None,
+        held_block: None,
+        writes: vec![],
+    };
+
+    assert_eq!(
+        Some(record.clone()),
+        SortJournalRecord::parse(&record.to_bytes(), 512)
+    );
+}
+
+#[test]
+fn test_record_corrupted() {
+    let record = make_record(Version::V18, 5);
+
+    let mut bytes = record.to_bytes();
+    bytes[20] ^= 0xFF;
+
+    assert_eq!(None, SortJournalRecord::parse(&bytes, 128));
+}
+
+#[test]
+fn test_record_truncated() {
+    let record = make_record(Version::V18, 5);
+
+    let bytes = record.to_bytes();
+
+    assert_eq!(
+        None,
+        SortJournalRecord::parse(&bytes[..bytes.len() - 1], 128)
+    );
+}
+
+#[test]
+fn test_parse_picks_latest_record() {
+    let header = make_header(Version::V17);
+
+    let bytes = make_journal(
+        &header,
+        &[
+            Some(make_record(Version::V17, 4).to_bytes()),
+            Some(make_record(Version::V17, 3).to_bytes()),
+        ],
+    );
+
+    let (parsed_header, record) = parse(&bytes, "test").unwrap();
+
+    assert_eq!(header, parsed_header);
+    assert_eq!(make_record(Version::V17, 4), record);
+}
+
+#[test]
+fn test_parse_skips_partially_written_record() {
+    let header = make_header(Version::V19);
+
+    let mut latest = make_record(Version::V19, 6).to_bytes();
+    latest.truncate(100);
+
+    let bytes = make_journal(
+        &header,
+        &[Some(latest), Some(make_record(Version::V19, 5).to_bytes())],
+    );
+
+    let (_, record) = parse(&bytes, "test").unwrap();
+
+    assert_eq!(make_record(Version::V19, 5), record);
+}
+
+#[test]
+fn test_parse_no_complete_record() {
+    let header = make_header(Version::V1);
+
+    let bytes = make_journal(&header, &[None, None]);
+
+    assert!(parse(&bytes, "test").is_err());
+}
+
+#[test]
+fn test_parse_non_rs_header() {
+    let mut header = make_header(Version::V1);
+    header.data_par_burst = None;
+
+    let bytes = make_journal(&header, &[Some(make_record(Version::V1, 0).to_bytes())]);
+
+    let (parsed_header, _) = parse(&bytes, "test").unwrap();
+
+    assert_eq!(header, parsed_header);
+}
+
+#[test]
+fn test_parse_bad_signature() {
+    let header = make_header(Version::V17);
+
+    let mut bytes = make_journal(&header, &[Some(make_record(Version::V17, 0).to_bytes())]);
+    bytes[0] = b'X';
+
+    assert!(parse(&bytes, "test").is_err());
+}
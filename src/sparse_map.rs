@@ -0,0 +1,330 @@
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::file_writer::{FileWriter, FileWriterParam};
+use crate::general_error::Error;
+use crate::json_printer::JSONPrinter;
+use crate::misc_utils;
+use crate::range_set::RangeSet;
+use crate::sbx_block::{Block, BlockType};
+use crate::sbx_specs::{Version, SBX_FILE_UID_LEN, SBX_FIRST_DATA_SEQ_NUM};
+
+// the blocks omitted when encoding a sparse container, only the holes
+// recorded here are taken as chunks of zeros, any other blank block
+// is still a missing block
+#[derive(Clone, Debug, PartialEq)]
+pub struct SparseMap {
+    uid: [u8; SBX_FILE_UID_LEN],
+    seq_nums: RangeSet,
+}
+
+mod parsers {
+    use crate::sbx_specs::SBX_FILE_UID_LEN;
+    use nom::character::complete::digit1;
+    use nom::character::complete::newline;
+
+    type Range<'a> = (&'a [u8], &'a [u8]);
+
+    named!(
+        uid_p<&[u8]>,
+        do_parse!(
+            _id : tag!(b"uid=") >>
+                uid : take!(SBX_FILE_UID_LEN * 2) >> _n : newline >>
+                (uid)
+        )
+    );
+
+    named!(
+        seq_nums_p<Range>,
+        do_parse!(
+            _id : tag!(b"seq_nums=") >>
+                start   : digit1 >> _d : char!('-') >>
+                end_exc : digit1 >> _n : newline >>
+                ((start, end_exc))
+        )
+    );
+
+    named!(pub seq_nums_list_p <Vec<Range>>,
+           many0!(seq_nums_p)
+    );
+
+    named!(pub sparse_map_p <(&[u8], Vec<Range>)>,
+           do_parse!(
+               uid      : uid_p >>
+                   seq_nums : seq_nums_list_p >>
+                   ((uid, seq_nums))
+           )
+    );
+}
+
+fn parse_num(bytes: &[u8]) -> Result<u64, ()> {
+    match std::str::from_utf8(bytes) {
+        Ok(x) => match x.parse::<u64>() {
+            Ok(x) => Ok(x),
+            Err(_) => Err(()),
+        },
+        Err(_) => Err(()),
+    }
+}
+
+pub fn default_file(container: &str) -> String {
+    format!("{}.sparse", container)
+}
+
+// returns whether the buffer read at the position of the block with
+// the seq num is the hole left by a block omitted during encoding
+pub fn block_is_omitted(sparse_map: &Option<SparseMap>, seq_num: u32, buffer: &[u8]) -> bool {
+    match sparse_map {
+        Some(map) => map.contains(seq_num) && misc_utils::buffer_is_blank(buffer),
+        None => false,
+    }
+}
+
+// fills the buffer with the block omitted during encoding,
+// which only carries zeros in the data section
+pub fn fill_omitted_block(
+    version: Version,
+    uid: &[u8; SBX_FILE_UID_LEN],
+    seq_num: u32,
+    buffer: &mut [u8],
+) {
+    misc_utils::fill_zeros(buffer);
+
+    let mut block = Block::new(version, uid, BlockType::Data);
+    block.set_seq_num(seq_num);
+    block.sync_to_buffer(None, buffer).unwrap();
+}
+
+// loads the record of omitted blocks of a sparse container, which is
+// the file specified or the default file next to the container,
+// omitted blocks are treated as missing blocks if neither is available
+pub fn load(
+    json_printer: &JSONPrinter,
+    ref_block: &Block,
+    container: Option<&str>,
+    file: Option<&str>,
+) -> Result<Option<SparseMap>, Error> {
+    let recorded_count = if ref_block.is_meta() {
+        ref_block.get_SPR().unwrap()
+    } else {
+        None
+    };
+
+    let path = match (file, container, recorded_count) {
+        (Some(f), _, _) => String::from(f),
+        (None, Some(c), Some(_)) if file_utils::check_if_file_exists(&default_file(c)) => {
+            default_file(c)
+        }
+        (None, _, Some(_)) => {
+            if !json_printer.json_enabled() {
+                print_block!(json_printer.output_channel() =>
+                    "";
+                    "Warning :";
+                    "";
+                    "    Container is sparse but no sparse map was found, blocks";
+                    "    omitted during encoding are treated as missing blocks.";
+                    "";)
+            }
+
+            return Ok(None);
+        }
+        (None, _, None) => return Ok(None),
+    };
+
+    let map = SparseMap::read_from_file(&path)?;
+
+    if map.uid != ref_block.get_uid() {
+        return Err(Error::with_msg(&format!(
+            "Sparse map \"{}\" does not match the container",
+            path
+        )));
+    }
+
+    if let Some(count) = recorded_count {
+        if count != map.block_count() {
+            return Err(Error::with_msg(&format!(
+                "Sparse map \"{}\" records {} omitted blocks, but the container records {}",
+                path,
+                map.block_count(),
+                count
+            )));
+        }
+    }
+
+    Ok(Some(map))
+}
+
+// loads the sparse map when no reference block is available, which is the
+// file specified or the default file next to the container if it exists
+pub fn load_by_uid(
+    uid: &[u8; SBX_FILE_UID_LEN],
+    container: &str,
+    file: Option<&str>,
+) -> Result<Option<SparseMap>, Error> {
+    let path = match file {
+        Some(f) => String::from(f),
+        None => {
+            let f = default_file(container);
+
+            if !file_utils::check_if_file_exists(&f) {
+                return Ok(None);
+            }
+
+            f
+        }
+    };
+
+    let map = SparseMap::read_from_file(&path)?;
+
+    if &map.uid != uid {
+        return Err(Error::with_msg(&format!(
+            "Sparse map \"{}\" does not match the container",
+            path
+        )));
+    }
+
+    Ok(Some(map))
+}
+
+impl SparseMap {
+    pub fn new(uid: &[u8; SBX_FILE_UID_LEN]) -> SparseMap {
+        SparseMap {
+            uid: *uid,
+            seq_nums: RangeSet::new(),
+        }
+    }
+
+    pub fn uid(&self) -> &[u8; SBX_FILE_UID_LEN] {
+        &self.uid
+    }
+
+    pub fn set_uid(&mut self, uid: &[u8; SBX_FILE_UID_LEN]) {
+        self.uid = *uid;
+    }
+
+    pub fn add_seq_num(&mut self, seq_num: u32) {
+        self.seq_nums.insert(u64::from(seq_num));
+    }
+
+    pub fn contains(&self, seq_num: u32) -> bool {
+        self.seq_nums.contains(u64::from(seq_num))
+    }
+
+    pub fn block_count(&self) -> u64 {
+        self.seq_nums.len()
+    }
+
+    // only the seq nums before end_exc are included
+    pub fn seq_nums_to_string(&self, end_exc: u64) -> String {
+        let mut string = String::with_capacity(200);
+
+        for (start, end) in self.seq_nums.ranges().into_iter() {
+            let end = std::cmp::min(end, end_exc);
+            if start < end {
+                string.push_str(&format!("seq_nums={}-{}\n", start, end));
+            }
+        }
+
+        string
+    }
+
+    fn add_parsed_ranges(&mut self, ranges: Vec<(&[u8], &[u8])>) -> Result<(), ()> {
+        for (start, end_exc) in ranges.into_iter() {
+            let start = parse_num(start)?;
+            let end_exc = parse_num(end_exc)?;
+
+            if start < u64::from(SBX_FIRST_DATA_SEQ_NUM)
+                || start >= end_exc
+                || end_exc > u64::from(u32::MAX) + 1
+            {
+                return Err(());
+            }
+
+            self.seq_nums.insert_range(start, end_exc);
+        }
+
+        Ok(())
+    }
+
+    // adds the seq nums of the seq_nums=FROM-TO lines at the start of input
+    pub fn add_seq_nums_from_bytes(&mut self, input: &[u8]) -> Result<(), ()> {
+        match parsers::seq_nums_list_p(input) {
+            Ok((_, ranges)) => self.add_parsed_ranges(ranges),
+            Err(_) => Err(()),
+        }
+    }
+
+    pub fn write_to_file(&self, path: &str) -> Result<(), Error> {
+        let mut string = String::with_capacity(200);
+        string.push_str(&format!(
+            "uid={}\n",
+            misc_utils::bytes_to_upper_hex_string(&self.uid)
+        ));
+        string.push_str(&self.seq_nums_to_string(u64::MAX));
+
+        let mut writer = FileWriter::new(
+            path,
+            FileWriterParam {
+                read: false,
+                append: false,
+                truncate: true,
+                buffered: true,
+            },
+        )?;
+
+        writer.write(string.as_bytes())?;
+
+        Ok(())
+    }
+
+    pub fn read_from_file(path: &str) -> Result<SparseMap, Error> {
+        let file_size = file_utils::get_file_size(path)?;
+
+        let mut reader = FileReader::new(
+            path,
+            FileReaderParam {
+                write: false,
+                buffered: false,
+            },
+        )?;
+
+        // leave a trailing zero byte so the parsers never run out of input
+        let mut buffer = vec![0; file_size as usize + 1];
+        let _len_read = reader.read(&mut buffer[..file_size as usize])?;
+
+        match SparseMap::parse(&buffer) {
+            Ok(map) => Ok(map),
+            Err(()) => Err(Error::with_msg(&format!(
+                "Failed to parse sparse map \"{}\"",
+                path
+            ))),
+        }
+    }
+
+    pub fn parse(input: &[u8]) -> Result<SparseMap, ()> {
+        let (uid_str, ranges) = match parsers::sparse_map_p(input) {
+            Ok((rest, x)) => {
+                // only the zero padding may be left over
+                if !misc_utils::buffer_is_blank(rest) {
+                    return Err(());
+                }
+                x
+            }
+            Err(_) => return Err(()),
+        };
+
+        let mut uid = [0; SBX_FILE_UID_LEN];
+        match std::str::from_utf8(uid_str) {
+            Ok(x) => match misc_utils::hex_string_to_bytes(x) {
+                Ok(x) => uid.copy_from_slice(&x),
+                Err(_) => return Err(()),
+            },
+            Err(_) => return Err(()),
+        }
+
+        let mut map = SparseMap::new(&uid);
+
+        map.add_parsed_ranges(ranges)?;
+
+        Ok(map)
+    }
+}
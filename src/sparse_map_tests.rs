@@ -0,0 +1,87 @@
+#![cfg(test)]
+
+use crate::sbx_block::Block;
+use crate::sbx_specs::{ver_to_block_size, Version};
+use crate::sparse_map::*;
+
+#[test]
+fn test_parse_simple_cases() {
+    let map = SparseMap::parse(b"uid=0102030405A6\nseq_nums=3-5\nseq_nums=9-10\n\0").unwrap();
+
+    assert_eq!(&[1, 2, 3, 4, 5, 0xA6], map.uid());
+    assert_eq!(3, map.block_count());
+    assert!(!map.contains(2));
+    assert!(map.contains(3));
+    assert!(map.contains(4));
+    assert!(!map.contains(5));
+    assert!(map.contains(9));
+}
+
+#[test]
+fn test_parse_rejects_invalid_ranges() {
+    assert!(SparseMap::parse(b"uid=0102030405A6\nseq_nums=0-5\n\0").is_err());
+    assert!(SparseMap::parse(b"uid=0102030405A6\nseq_nums=5-5\n\0").is_err());
+    assert!(SparseMap::parse(b"uid=0102030405A6\nseq_nums=1-4294967297\n\0").is_err());
+    assert!(SparseMap::parse(b"uid=0102030405A6\nseq_nums=1-2\nabc\n\0").is_err());
+    assert!(SparseMap::parse(b"uid=01020304\n\0").is_err());
+}
+
+#[test]
+fn test_seq_nums_to_string_merges_and_prunes() {
+    let mut map = SparseMap::new(&[0; 6]);
+
+    map.add_seq_num(4);
+    map.add_seq_num(2);
+    map.add_seq_num(3);
+    map.add_seq_num(10);
+
+    assert_eq!(
+        "seq_nums=2-5\nseq_nums=10-11\n",
+        map.seq_nums_to_string(100)
+    );
+    assert_eq!("seq_nums=2-4\n", map.seq_nums_to_string(4));
+    assert_eq!(4, map.block_count());
+}
+
+#[test]
+fn test_add_seq_nums_from_bytes() {
+    let mut map = SparseMap::new(&[0; 6]);
+
+    map.add_seq_nums_from_bytes(b"seq_nums=2-4\nseq_nums=7-8\n\0")
+        .unwrap();
+
+    assert_eq!(3, map.block_count());
+    assert!(map.contains(7));
+}
+
+#[test]
+fn test_block_is_omitted() {
+    let mut map = SparseMap::new(&[0; 6]);
+    map.add_seq_num(2);
+    let map = Some(map);
+
+    let blank = [0; 512];
+    let mut non_blank = [0; 512];
+    non_blank[100] = 1;
+
+    assert!(block_is_omitted(&map, 2, &blank));
+    assert!(!block_is_omitted(&map, 2, &non_blank));
+    assert!(!block_is_omitted(&map, 3, &blank));
+    assert!(!block_is_omitted(&None, 2, &blank));
+}
+
+#[test]
+fn test_fill_omitted_block() {
+    let uid = [1, 2, 3, 4, 5, 6];
+    let block_size = ver_to_block_size(Version::V1);
+    let mut buffer = vec![0xFF; block_size];
+
+    fill_omitted_block(Version::V1, &uid, 7, &mut buffer);
+
+    let mut block = Block::dummy();
+    block.sync_from_buffer(&buffer, None, None).unwrap();
+
+    assert_eq!(7, block.get_seq_num());
+    assert_eq!(uid, block.get_uid());
+    assert!(buffer[16..].iter().all(|&x| x == 0));
+}
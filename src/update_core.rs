@@ -1,30 +1,45 @@
 use crate::block_utils::RefBlockChoice;
 use crate::cli_utils::setup_ctrlc_handler;
 use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
 use crate::general_error::Error;
+use crate::journal;
+use crate::journal::Journal;
 use crate::json_printer::{BracketType, JSONPrinter};
+use crate::metadata_edit;
+use crate::metadata_edit::MetadataEdit;
+use crate::misc_utils;
 use crate::multihash;
 use crate::progress_report::*;
 use crate::sbx_block;
 use crate::sbx_block::Block;
 use crate::sbx_block::BlockType;
+use crate::sbx_block::Header;
 use crate::sbx_block::{Metadata, MetadataID};
 use crate::sbx_container_content;
-use crate::sbx_specs::{ver_to_block_size, ver_to_usize, Version, SBX_LARGEST_BLOCK_SIZE};
+use crate::sbx_specs::{
+    ver_to_block_size, ver_to_data_size, ver_to_usize, ver_uses_rs, Version, SBX_FILE_UID_LEN,
+    SBX_LARGEST_BLOCK_SIZE,
+};
+use crate::sparse_map;
+use crate::sparse_map::SparseMap;
 use crate::time_utils;
 use smallvec::SmallVec;
 use std::fmt;
 use std::io::SeekFrom;
-use std::sync::atomic::AtomicBool;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 
 pub struct Param {
     in_file: String,
+    journal_file: Option<String>,
     dry_run: bool,
     metas_to_update: SmallVec<[Metadata; 8]>,
     metas_to_remove: SmallVec<[MetadataID; 8]>,
     json_printer: Arc<JSONPrinter>,
     hash_type: Option<multihash::HashType>,
+    new_uid: Option<[u8; SBX_FILE_UID_LEN]>,
+    add_meta: bool,
     verbose: bool,
     pr_verbosity_level: PRVerbosityLevel,
     burst: Option<usize>,
@@ -33,22 +48,31 @@ pub struct Param {
 impl Param {
     pub fn new(
         in_file: &str,
+        journal_file: Option<&str>,
         dry_run: bool,
         metas_to_update: SmallVec<[Metadata; 8]>,
         metas_to_remove: SmallVec<[MetadataID; 8]>,
         json_printer: &Arc<JSONPrinter>,
         hash_type: Option<multihash::HashType>,
+        new_uid: Option<&[u8; SBX_FILE_UID_LEN]>,
+        add_meta: bool,
         verbose: bool,
         pr_verbosity_level: PRVerbosityLevel,
         burst: Option<usize>,
     ) -> Param {
         Param {
             in_file: String::from(in_file),
+            journal_file: match journal_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
             dry_run,
             metas_to_update,
             metas_to_remove,
             json_printer: Arc::clone(json_printer),
             hash_type,
+            new_uid: new_uid.copied(),
+            add_meta,
             verbose,
             pr_verbosity_level,
             burst,
@@ -62,6 +86,11 @@ pub struct Stats {
     pub meta_blocks_updated: u64,
     pub meta_blocks_decode_failed: u64,
     total_meta_blocks: u64,
+    new_uid: Option<[u8; SBX_FILE_UID_LEN]>,
+    pub blocks_uid_changed: u64,
+    pub blocks_uid_decode_failed: u64,
+    add_meta: bool,
+    pub meta_blocks_rebuilt: u64,
     start_time: f64,
     end_time: f64,
     json_printer: Arc<JSONPrinter>,
@@ -82,6 +111,11 @@ impl Stats {
             meta_blocks_updated: 0,
             meta_blocks_decode_failed: 0,
             total_meta_blocks,
+            new_uid: None,
+            blocks_uid_changed: 0,
+            blocks_uid_decode_failed: 0,
+            add_meta: false,
+            meta_blocks_rebuilt: 0,
             start_time: 0.,
             end_time: 0.,
             json_printer: Arc::clone(json_printer),
@@ -147,6 +181,34 @@ impl fmt::Display for Stats {
             "Number of metadata blocks failed to decode : {}",
             self.meta_blocks_decode_failed
         )?;
+        if self.add_meta {
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Number of metadata blocks rebuilt          : {}",
+                self.meta_blocks_rebuilt
+            )?;
+        }
+        if let Some(uid) = self.new_uid {
+            write_maybe_json!(
+                f,
+                json_printer,
+                "New UID                                    : {}",
+                misc_utils::bytes_to_upper_hex_string(&uid)
+            )?;
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Number of blocks with UID changed          : {}",
+                self.blocks_uid_changed
+            )?;
+            write_maybe_json!(
+                f,
+                json_printer,
+                "Number of blocks failed to decode          : {}",
+                self.blocks_uid_decode_failed
+            )?;
+        }
         write_maybe_json!(
             f,
             json_printer,
@@ -161,6 +223,42 @@ impl fmt::Display for Stats {
     }
 }
 
+struct PassStats {
+    units_processed: u64,
+    total_units: u64,
+    start_time: f64,
+    end_time: f64,
+}
+
+impl PassStats {
+    fn new(total_units: u64) -> PassStats {
+        PassStats {
+            units_processed: 0,
+            total_units,
+            start_time: 0.,
+            end_time: 0.,
+        }
+    }
+}
+
+impl ProgressReport for PassStats {
+    fn start_time_mut(&mut self) -> &mut f64 {
+        &mut self.start_time
+    }
+
+    fn end_time_mut(&mut self) -> &mut f64 {
+        &mut self.end_time
+    }
+
+    fn units_so_far(&self) -> u64 {
+        self.units_processed
+    }
+
+    fn total_units(&self) -> Option<u64> {
+        Some(self.total_units)
+    }
+}
+
 fn update_metas(block: &mut Block, metas: &[Metadata]) {
     block.update_metas(metas).unwrap();
 }
@@ -292,6 +390,12 @@ fn update_metadata_blocks(
         },
     )?;
 
+    // record original metadata blocks before any in-place modification
+    let mut journal = match param.journal_file {
+        Some(ref f) if !param.dry_run && !test_run => Some(Journal::new(f, &param.in_file)?),
+        _ => None,
+    };
+
     let mut err = None;
 
     reporter.start();
@@ -331,8 +435,12 @@ fn update_metadata_blocks(
                         }
 
                         if !param.dry_run {
-                            reader.seek(SeekFrom::Start(p))?;
-                            reader.write(sbx_block::slice_buf(version, &buffer))?;
+                            journal::write_with_journal(
+                                &mut journal,
+                                &mut reader,
+                                p,
+                                sbx_block::slice_buf(version, &buffer),
+                            )?;
                         }
                     }
 
@@ -367,6 +475,521 @@ fn update_metadata_blocks(
     }
 }
 
+fn update_uid(
+    ctrlc_stop_flag: &AtomicBool,
+    param: &Param,
+    ref_block: &Block,
+    ref_block_pos: u64,
+    new_uid: [u8; SBX_FILE_UID_LEN],
+    stats: &mut Stats,
+) -> Result<(), Error> {
+    let version = ref_block.get_version();
+    let block_size = ver_to_block_size(version) as u64;
+
+    let old_uid = ref_block.get_uid();
+
+    // blocks already carrying the new UID are accepted as well,
+    // so an interrupted run can be completed by running it again
+    let header_pred = move |header: &Header| -> bool {
+        header.version == version && (header.uid == old_uid || header.uid == new_uid)
+    };
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let file_size = file_utils::get_file_size(&param.in_file)?;
+
+    let uid_stats = Arc::new(Mutex::new(PassStats::new(file_size)));
+
+    let reporter = ProgressReporter::new(
+        &uid_stats,
+        if param.dry_run {
+            "SBX block UID update testing progress"
+        } else {
+            "SBX block UID update progress"
+        },
+        "bytes",
+        param.pr_verbosity_level,
+        param.json_printer.json_enabled(),
+    );
+
+    let mut reader = FileReader::new(
+        &param.in_file,
+        FileReaderParam {
+            write: !param.dry_run,
+            buffered: true,
+        },
+    )?;
+
+    let mut journal = match param.journal_file {
+        Some(ref f) if !param.dry_run => Some(Journal::new(f, &param.in_file)?),
+        _ => None,
+    };
+
+    // metadata blocks are written last, see below
+    let mut meta_blocks: Vec<(u64, Vec<u8>)> = Vec::new();
+
+    let mut pos = ref_block_pos % block_size;
+
+    stats.new_uid = Some(new_uid);
+
+    reporter.start();
+
+    reader.seek(SeekFrom::Start(pos))?;
+
+    let mut interrupted = false;
+
+    loop {
+        if ctrlc_stop_flag.load(Ordering::SeqCst) {
+            interrupted = true;
+            break;
+        }
+
+        let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
+
+        break_if_eof_seen!(read_res);
+
+        match block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+            Ok(()) => {
+                if block.get_uid() == old_uid {
+                    block.set_uid(new_uid);
+                    block.update_crc(&buffer);
+                    block.sync_to_buffer_header_only(&mut buffer).unwrap();
+
+                    if block.is_meta() {
+                        meta_blocks.push((pos, sbx_block::slice_buf(version, &buffer).to_vec()));
+                    } else {
+                        if !param.dry_run {
+                            journal::write_with_journal(
+                                &mut journal,
+                                &mut reader,
+                                pos,
+                                sbx_block::slice_buf(version, &buffer),
+                            )?;
+                        }
+
+                        stats.blocks_uid_changed += 1;
+                    }
+                }
+            }
+            Err(_) => {
+                let blank = misc_utils::buffer_is_blank(sbx_block::slice_buf(version, &buffer));
+
+                // blocks of other containers are left as is
+                let other_uid = match block.sync_from_buffer(&buffer, None, None) {
+                    Ok(()) => true,
+                    Err(_) => false,
+                };
+
+                if !blank && !other_uid {
+                    stats.blocks_uid_decode_failed += 1;
+                }
+            }
+        }
+
+        pos += block_size;
+
+        uid_stats.lock().unwrap().units_processed = pos;
+    }
+
+    // the metadata blocks are written at the end and in reverse order, so the
+    // first one, which is picked as the reference block, keeps the old UID
+    // until all other blocks are done
+    if !interrupted {
+        for &(p, ref b) in meta_blocks.iter().rev() {
+            if !param.dry_run {
+                journal::write_with_journal(&mut journal, &mut reader, p, b)?;
+            }
+
+            stats.blocks_uid_changed += 1;
+        }
+    }
+
+    reporter.stop();
+
+    copy_pass_times(stats, &uid_stats);
+
+    if interrupted && !param.dry_run {
+        return Err(Error::with_msg(&format!(
+            "UID update interrupted, run update again with --new-uid {} to finish",
+            misc_utils::bytes_to_upper_hex_string(&new_uid)
+        )));
+    }
+
+    // keep the sparse map next to the container matching the container
+    let sparse_map_file = sparse_map::default_file(&param.in_file);
+    if !param.dry_run
+        && ref_block.is_meta()
+        && ref_block.get_SPR().unwrap().is_some()
+        && file_utils::check_if_file_exists(&sparse_map_file)
+    {
+        let mut sparse_map = SparseMap::read_from_file(&sparse_map_file)?;
+
+        if sparse_map.uid() == &old_uid {
+            sparse_map.set_uid(&new_uid);
+            sparse_map.write_to_file(&sparse_map_file)?;
+        }
+    }
+
+    Ok(())
+}
+
+fn copy_pass_times(stats: &mut Stats, pass_stats: &Mutex<PassStats>) {
+    let pass_stats = pass_stats.lock().unwrap();
+
+    if stats.start_time == 0. {
+        stats.start_time = pass_stats.start_time;
+    }
+    stats.end_time = pass_stats.end_time;
+}
+
+fn rebuild_metadata_blocks(
+    ctrlc_stop_flag: &AtomicBool,
+    param: &Param,
+    ref_block: &Block,
+    data_par_burst: Option<(usize, usize, usize)>,
+    stats: &mut Stats,
+) -> Result<(), Error> {
+    let version = ref_block.get_version();
+    let json_printer = &param.json_printer;
+
+    let header_pred = header_pred_same_ver_uid!(ref_block);
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let meta_block_pos_s = sbx_block::calc_meta_block_all_write_pos_s(version, data_par_burst);
+
+    let pass_stats = Arc::new(Mutex::new(PassStats::new(meta_block_pos_s.len() as u64)));
+
+    let reporter = ProgressReporter::new(
+        &pass_stats,
+        "SBX metadata block scanning progress",
+        "blocks",
+        param.pr_verbosity_level,
+        json_printer.json_enabled(),
+    );
+
+    let mut reader = FileReader::new(
+        &param.in_file,
+        FileReaderParam {
+            write: !param.dry_run,
+            buffered: false,
+        },
+    )?;
+
+    // copies which could be read, along with the block bytes if intact
+    let mut copies: Vec<(u64, Option<Vec<u8>>, bool)> = Vec::with_capacity(meta_block_pos_s.len());
+
+    reporter.start();
+
+    for &p in meta_block_pos_s.iter() {
+        if ctrlc_stop_flag.load(Ordering::SeqCst) {
+            reporter.stop();
+            return Ok(());
+        }
+
+        reader.seek(SeekFrom::Start(p))?;
+        let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
+
+        // copies past the end of the container are not added, as that would
+        // leave a hole in the container
+        break_if_eof_seen!(read_res);
+
+        let block_okay = match block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+            Ok(()) => true,
+            Err(_) => false,
+        } && block.is_meta();
+
+        let blank = misc_utils::buffer_is_blank(sbx_block::slice_buf(version, &buffer));
+
+        copies.push((
+            p,
+            if block_okay {
+                Some(sbx_block::slice_buf(version, &buffer).to_vec())
+            } else {
+                None
+            },
+            blank,
+        ));
+
+        pass_stats.lock().unwrap().units_processed += 1;
+    }
+
+    reporter.stop();
+
+    // the intact copy shared by most copies wins, ties are broken
+    // in favour of the copy closest to the start of the container
+    let consensus = {
+        let mut best: Option<(&Vec<u8>, usize)> = None;
+        for (_, bytes, _) in copies.iter() {
+            if let Some(bytes) = bytes {
+                let count = copies
+                    .iter()
+                    .filter(|(_, x, _)| x.as_ref() == Some(bytes))
+                    .count();
+                let better = match best {
+                    None => true,
+                    Some((_, best_count)) => count > best_count,
+                };
+                if better {
+                    best = Some((bytes, count));
+                }
+            }
+        }
+
+        match best {
+            None => return Err(Error::with_msg("Failed to find an intact metadata block")),
+            Some((bytes, _)) => bytes.clone(),
+        }
+    };
+
+    let mut journal = match param.journal_file {
+        Some(ref f) if !param.dry_run => Some(Journal::new(f, &param.in_file)?),
+        _ => None,
+    };
+
+    if param.verbose {
+        json_printer.print_open_bracket(Some("rebuilt metadata blocks"), BracketType::Square);
+    }
+    for (p, bytes, blank) in copies.iter() {
+        let reason = match bytes {
+            Some(x) if x == &consensus => continue,
+            Some(_) => "outdated",
+            None if *blank => "missing",
+            None => "corrupt",
+        };
+
+        if param.verbose {
+            if stats.meta_blocks_rebuilt > 0 {
+                print_if!(not_json => json_printer => "";);
+            }
+
+            json_printer.print_open_bracket(None, BracketType::Curly);
+            print_maybe_json!(json_printer, "Position : {}", p);
+            print_maybe_json!(json_printer, "Reason   : {}", reason);
+            json_printer.print_close_bracket();
+        }
+
+        if !param.dry_run {
+            journal::write_with_journal(&mut journal, &mut reader, *p, &consensus)?;
+        }
+
+        stats.meta_blocks_rebuilt += 1;
+    }
+    if param.verbose {
+        json_printer.print_close_bracket();
+
+        if stats.meta_blocks_rebuilt > 0 {
+            print_if!(not_json => json_printer => "";);
+        }
+    }
+
+    copy_pass_times(stats, &pass_stats);
+
+    Ok(())
+}
+
+fn add_metadata_block(
+    ctrlc_stop_flag: &Arc<AtomicBool>,
+    param: &Param,
+    ref_block: &Block,
+    ref_block_pos: u64,
+    stats: &mut Stats,
+) -> Result<bool, Error> {
+    let version = ref_block.get_version();
+    let block_size = ver_to_block_size(version) as u64;
+    let data_size = ver_to_data_size(version) as u64;
+    let json_printer = &param.json_printer;
+
+    // the metadata block goes in front of the first data block, which
+    // is only possible if the data blocks were laid out with a free slot
+    // for it, as is done by sort
+    if ref_block_pos % block_size != 0
+        || ref_block_pos / block_size != ref_block.get_seq_num() as u64
+    {
+        return Err(Error::with_msg(
+            "There is no free block at the start of the container for the metadata block, sort the container first to make space for it",
+        ));
+    }
+
+    let header_pred = header_pred_same_ver_uid!(ref_block);
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let file_size = file_utils::get_file_size(&param.in_file)?;
+
+    let mut reader = FileReader::new(
+        &param.in_file,
+        FileReaderParam {
+            write: !param.dry_run,
+            buffered: true,
+        },
+    )?;
+
+    let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
+    if !read_res.eof_seen {
+        if let Ok(()) = block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+            return Err(Error::with_msg(&format!(
+                "First block of the container is occupied by block with sequence number {}",
+                block.get_seq_num()
+            )));
+        }
+    }
+
+    // find the last data chunk to rebuild the file size, the padding added to
+    // the last data block during encoding cannot be told apart from data ending
+    // in 0x1A, so the size is only rebuilt if the last data chunk is full
+    let orig_file_size = match param.metas_to_update.iter().find_map(|m| match m {
+        Metadata::FSZ(x) => Some(*x),
+        _ => None,
+    }) {
+        Some(x) => x,
+        None => {
+            let pass_stats = Arc::new(Mutex::new(PassStats::new(file_size)));
+
+            let reporter = ProgressReporter::new(
+                &pass_stats,
+                "SBX container scanning progress",
+                "bytes",
+                param.pr_verbosity_level,
+                json_printer.json_enabled(),
+            );
+
+            let mut last_chunk: Option<(u32, bool)> = None;
+
+            reporter.start();
+
+            let mut pos = block_size;
+            reader.seek(SeekFrom::Start(pos))?;
+
+            loop {
+                if ctrlc_stop_flag.load(Ordering::SeqCst) {
+                    reporter.stop();
+                    return Ok(false);
+                }
+
+                let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
+
+                break_if_eof_seen!(read_res);
+
+                if let Ok(()) = block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+                    let seq_num = block.get_seq_num();
+
+                    let later = match last_chunk {
+                        None => true,
+                        Some((x, _)) => seq_num > x,
+                    };
+
+                    if block.is_data() && later {
+                        let data = sbx_block::slice_data_buf(version, &buffer);
+                        last_chunk = Some((seq_num, data.last() == Some(&0x1A)));
+                    }
+                }
+
+                pos += block_size;
+
+                pass_stats.lock().unwrap().units_processed = pos;
+            }
+
+            reporter.stop();
+
+            copy_pass_times(stats, &pass_stats);
+
+            match last_chunk {
+                None => return Err(Error::with_msg("Failed to find any data block")),
+                Some((_, true)) => return Err(Error::with_msg(
+                    "Failed to determine the file size as the last data chunk ends with 0x1A, which may be padding, please specify it via --set FSZ=SIZE",
+                )),
+                Some((seq_num, false)) => seq_num as u64 * data_size,
+            }
+        }
+    };
+
+    let mut metas: Vec<Metadata> = param.metas_to_update.to_vec();
+
+    if !metas
+        .iter()
+        .any(|m| sbx_block::meta_to_meta_id(m) == MetadataID::FSZ)
+    {
+        metas.push(Metadata::FSZ(orig_file_size));
+    }
+
+    // a hash given via --set is taken as is, otherwise the stored data is
+    // hashed unless the field is to be removed
+    let hash_given = param.hash_type.is_none()
+        && metas
+            .iter()
+            .any(|m| sbx_block::meta_to_meta_id(m) == MetadataID::HSH);
+
+    if !hash_given && !param.metas_to_remove.contains(&MetadataID::HSH) {
+        let hash_type = param.hash_type.unwrap_or(multihash::HashType::SHA256);
+
+        let (_, hash_res) = sbx_container_content::hash(
+            &json_printer,
+            param.pr_verbosity_level,
+            None,
+            ctrlc_stop_flag,
+            &param.in_file,
+            orig_file_size,
+            ref_block,
+            &None,
+            multihash::hash::Ctx::new(hash_type).unwrap(),
+        )?;
+
+        if ctrlc_stop_flag.load(Ordering::SeqCst) {
+            return Ok(false);
+        }
+
+        metas.retain(|m| sbx_block::meta_to_meta_id(m) != MetadataID::HSH);
+        metas.push(Metadata::HSH(hash_res));
+    }
+
+    let mut meta_block = Block::new(version, &ref_block.get_uid(), BlockType::Meta);
+    update_metas(&mut meta_block, &metas);
+
+    if let Err(sbx_block::Error::TooMuchMetadata(_)) = meta_block.sync_to_buffer(None, &mut buffer)
+    {
+        return Err(Error::with_msg(
+            "Failed to add metadata block due to too much metadata",
+        ));
+    }
+
+    if param.verbose {
+        json_printer.print_open_bracket(Some("added metadata block"), BracketType::Curly);
+        for m in metas.iter() {
+            print_maybe_json!(
+                json_printer,
+                "{} : {}",
+                sbx_block::meta_id_to_str(sbx_block::meta_to_meta_id(m)),
+                m
+            );
+        }
+        json_printer.print_close_bracket();
+
+        print_if!(not_json => json_printer => "";);
+    }
+
+    if !param.dry_run {
+        let mut journal = match param.journal_file {
+            Some(ref f) => Some(Journal::new(f, &param.in_file)?),
+            None => None,
+        };
+
+        journal::write_with_journal(
+            &mut journal,
+            &mut reader,
+            0,
+            sbx_block::slice_buf(version, &buffer),
+        )?;
+    }
+
+    stats.meta_blocks_rebuilt += 1;
+
+    Ok(true)
+}
+
 pub fn update_file(param: &mut Param) -> Result<Option<Stats>, Error> {
     let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
 
@@ -377,10 +1000,36 @@ pub fn update_file(param: &mut Param) -> Result<Option<Stats>, Error> {
                                                     None,
                                                     None,
                                                     json_printer,
-                                                    RefBlockChoice::MustBe(BlockType::Meta),
+                                                    if param.add_meta {
+                                                        RefBlockChoice::Prefer(BlockType::Meta)
+                                                    } else {
+                                                        RefBlockChoice::MustBe(BlockType::Meta)
+                                                    },
                                                     ctrlc_stop_flag
     );
 
+    // no metadata block was found, so the container was encoded without one
+    if ref_block.is_data() {
+        if ver_uses_rs(ref_block.get_version()) {
+            return Err(Error::with_msg("Reference block is not a metadata block"));
+        }
+
+        let mut stats = Stats::new(&ref_block, None, json_printer);
+        stats.add_meta = true;
+
+        if !add_metadata_block(
+            &ctrlc_stop_flag,
+            param,
+            &ref_block,
+            ref_block_pos,
+            &mut stats,
+        )? {
+            return Ok(None);
+        }
+
+        return Ok(Some(stats));
+    }
+
     let (orig_file_size, hash_ctx) = match param.hash_type {
         Some(ht) => {
             if ref_block.is_data() {
@@ -407,44 +1056,110 @@ pub fn update_file(param: &mut Param) -> Result<Option<Stats>, Error> {
     let data_par_burst =
         get_data_par_burst!(no_offset => param, ref_block_pos, ref_block, "update");
 
-    // test run once first to make sure metadata blocks have enough space
-    update_metadata_blocks(
-        &ctrlc_stop_flag,
-        param,
-        &ref_block,
-        &json_printer,
-        data_par_burst,
-        true,
-    )?;
+    {
+        let mut edits: Vec<MetadataEdit> = Vec::new();
+        for m in param.metas_to_update.iter() {
+            edits.push(MetadataEdit::Set(m.clone()));
+        }
+        for &id in param.metas_to_remove.iter() {
+            edits.push(MetadataEdit::Remove(id));
+        }
 
-    if let Some(hash_ctx) = hash_ctx {
-        let (_, hash_res) = sbx_container_content::hash(
-            &json_printer,
-            param.pr_verbosity_level,
+        metadata_edit::check_edits(ref_block.get_version(), data_par_burst, &edits)?;
+    }
+
+    if let Some(new_uid) = param.new_uid {
+        if new_uid == ref_block.get_uid() {
+            return Err(Error::with_msg("New UID is the same as the current UID"));
+        }
+    }
+
+    let mut rebuild_stats = Stats::new(&ref_block, data_par_burst, json_printer);
+    if param.add_meta {
+        rebuild_metadata_blocks(
+            &ctrlc_stop_flag,
+            param,
+            &ref_block,
             data_par_burst,
+            &mut rebuild_stats,
+        )?;
+
+        if ctrlc_stop_flag.load(Ordering::SeqCst) {
+            return Ok(None);
+        }
+    }
+
+    let meta_changes_requested = !param.metas_to_update.is_empty()
+        || !param.metas_to_remove.is_empty()
+        || (param.new_uid.is_none() && !param.add_meta);
+
+    let mut stats = if meta_changes_requested {
+        // test run once first to make sure metadata blocks have enough space
+        update_metadata_blocks(
             &ctrlc_stop_flag,
-            &param.in_file,
-            orig_file_size.unwrap(),
+            param,
             &ref_block,
-            hash_ctx,
+            &json_printer,
+            data_par_burst,
+            true,
         )?;
 
-        for meta in param.metas_to_update.iter_mut() {
-            if let Metadata::HSH(hs) = meta {
-                *hs = hash_res.clone()
+        if let Some(hash_ctx) = hash_ctx {
+            let sparse_map =
+                sparse_map::load(&json_printer, &ref_block, Some(&param.in_file), None)?;
+
+            let (_, hash_res) = sbx_container_content::hash(
+                &json_printer,
+                param.pr_verbosity_level,
+                data_par_burst,
+                &ctrlc_stop_flag,
+                &param.in_file,
+                orig_file_size.unwrap(),
+                &ref_block,
+                &sparse_map,
+                hash_ctx,
+            )?;
+
+            for meta in param.metas_to_update.iter_mut() {
+                if let Metadata::HSH(hs) = meta {
+                    *hs = hash_res.clone()
+                }
             }
         }
+
+        update_metadata_blocks(
+            &ctrlc_stop_flag,
+            param,
+            &ref_block,
+            &json_printer,
+            data_par_burst,
+            false,
+        )?
+    } else {
+        Stats::new(&ref_block, data_par_burst, json_printer)
+    };
+
+    if param.add_meta {
+        stats.add_meta = true;
+        stats.meta_blocks_rebuilt = rebuild_stats.meta_blocks_rebuilt;
+        if rebuild_stats.start_time != 0. {
+            stats.start_time = rebuild_stats.start_time;
+        }
+        if stats.end_time == 0. {
+            stats.end_time = rebuild_stats.end_time;
+        }
     }
 
-    match update_metadata_blocks(
-        &ctrlc_stop_flag,
-        param,
-        &ref_block,
-        &json_printer,
-        data_par_burst,
-        false,
-    ) {
-        Ok(s) => Ok(Some(s)),
-        Err(e) => Err(e),
+    if let Some(new_uid) = param.new_uid {
+        update_uid(
+            &ctrlc_stop_flag,
+            param,
+            &ref_block,
+            ref_block_pos,
+            new_uid,
+            &mut stats,
+        )?;
     }
+
+    Ok(Some(stats))
 }
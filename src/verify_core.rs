@@ -0,0 +1,594 @@
+use crate::bad_ranges::ChunkTracker;
+use crate::block_utils::RefBlockChoice;
+use crate::cli_utils::setup_ctrlc_handler;
+use crate::file_reader::{FileReader, FileReaderParam};
+use crate::file_utils;
+use crate::general_error::Error;
+use crate::json_printer::{BracketType, JSONPrinter};
+use crate::misc_utils;
+use crate::progress_report::*;
+use crate::sbx_block;
+use crate::sbx_block::Block;
+use crate::sbx_specs::{
+    ver_to_block_size, ver_to_data_size, ver_to_usize, ver_uses_rs, Version, SBX_LARGEST_BLOCK_SIZE,
+};
+use crate::sparse_map;
+use crate::time_utils;
+use std::fmt;
+use std::io::SeekFrom;
+use std::sync::{Arc, Mutex};
+
+pub struct Param {
+    ref_block_choice: RefBlockChoice,
+    in_file: String,
+    source_file: String,
+    burst: Option<usize>,
+    sparse_map_file: Option<String>,
+    json_printer: Arc<JSONPrinter>,
+    verbose: bool,
+    pr_verbosity_level: PRVerbosityLevel,
+}
+
+impl Param {
+    pub fn new(
+        ref_block_choice: RefBlockChoice,
+        in_file: &str,
+        source_file: &str,
+        burst: Option<usize>,
+        sparse_map_file: Option<&str>,
+        json_printer: &Arc<JSONPrinter>,
+        verbose: bool,
+        pr_verbosity_level: PRVerbosityLevel,
+    ) -> Param {
+        Param {
+            ref_block_choice,
+            in_file: String::from(in_file),
+            source_file: String::from(source_file),
+            burst,
+            sparse_map_file: match sparse_map_file {
+                None => None,
+                Some(x) => Some(String::from(x)),
+            },
+            json_printer: Arc::clone(json_printer),
+            verbose,
+            pr_verbosity_level,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Stats {
+    version: Version,
+    source_file_size: u64,
+    recorded_file_size: Option<u64>,
+    bytes_processed: u64,
+    total_bytes: u64,
+    pub data_blocks_matched: u64,
+    pub data_blocks_mismatched: u64,
+    pub blocks_decode_failed: u64,
+    mismatching_ranges: Vec<(u64, u64)>,
+    missing_ranges: Vec<(u64, u64)>,
+    verbose: bool,
+    start_time: f64,
+    end_time: f64,
+    json_printer: Arc<JSONPrinter>,
+}
+
+impl Stats {
+    pub fn new(
+        ref_block: &Block,
+        source_file_size: u64,
+        total_bytes: u64,
+        verbose: bool,
+        json_printer: &Arc<JSONPrinter>,
+    ) -> Stats {
+        Stats {
+            version: ref_block.get_version(),
+            source_file_size,
+            recorded_file_size: if ref_block.is_meta() {
+                ref_block.get_FSZ().unwrap()
+            } else {
+                None
+            },
+            bytes_processed: 0,
+            total_bytes,
+            data_blocks_matched: 0,
+            data_blocks_mismatched: 0,
+            blocks_decode_failed: 0,
+            mismatching_ranges: Vec::new(),
+            missing_ranges: Vec::new(),
+            verbose,
+            start_time: 0.,
+            end_time: 0.,
+            json_printer: Arc::clone(json_printer),
+        }
+    }
+
+    fn blocks_so_far(&self) -> u64 {
+        self.bytes_processed / ver_to_block_size(self.version) as u64
+    }
+
+    pub fn source_matches(&self) -> bool {
+        self.mismatching_ranges.is_empty() && self.missing_ranges.is_empty()
+    }
+}
+
+impl ProgressReport for Stats {
+    fn start_time_mut(&mut self) -> &mut f64 {
+        &mut self.start_time
+    }
+
+    fn end_time_mut(&mut self) -> &mut f64 {
+        &mut self.end_time
+    }
+
+    fn units_so_far(&self) -> u64 {
+        self.bytes_processed
+    }
+
+    fn total_units(&self) -> Option<u64> {
+        Some(self.total_bytes)
+    }
+}
+
+pub fn range_len_sum(ranges: &[(u64, u64)]) -> u64 {
+    ranges.iter().map(|(start, end_exc)| end_exc - start).sum()
+}
+
+pub fn write_range(
+    f: &mut fmt::Formatter,
+    json_printer: &JSONPrinter,
+    name: Option<&str>,
+    label: &str,
+    range: Option<&(u64, u64)>,
+) -> fmt::Result {
+    match range {
+        None => write_maybe_json!(
+            f,
+            json_printer,
+            "{} : {}",
+            label,
+            null_if_json_else_NA!(json_printer)
+        ),
+        Some((start, end_exc)) => {
+            if json_printer.json_enabled() {
+                json_printer.write_open_bracket(f, name, BracketType::Curly)?;
+                write_maybe_json!(f, json_printer, "start : {}", start)?;
+                write_maybe_json!(f, json_printer, "end exc : {}", end_exc)?;
+                json_printer.write_close_bracket(f)
+            } else {
+                writeln!(f, "{} : {} - {} (exclusive)", label, start, end_exc)
+            }
+        }
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let block_size = ver_to_block_size(self.version);
+        let time_elapsed = (self.end_time - self.start_time) as i64;
+        let (hour, minute, second) = time_utils::seconds_to_hms(time_elapsed);
+
+        let json_printer = &self.json_printer;
+
+        json_printer.write_open_bracket(f, Some("stats"), BracketType::Curly)?;
+
+        write_maybe_json!(
+            f,
+            json_printer,
+            "SBX version                          : {}",
+            ver_to_usize(self.version)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Block size used in verification      : {}",
+            block_size
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Source file size                     : {}",
+            self.source_file_size
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Recorded file size                   : {}",
+            match self.recorded_file_size {
+                None => null_if_json_else_NA!(json_printer).to_string(),
+                Some(x) => x.to_string(),
+            }
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks processed           : {}",
+            self.blocks_so_far()
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of data blocks matched        : {}",
+            self.data_blocks_matched
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of data blocks mismatched     : {}",
+            self.data_blocks_mismatched
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of blocks failed to decode    : {}",
+            self.blocks_decode_failed
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of mismatching bytes          : {}",
+            range_len_sum(&self.mismatching_ranges)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of mismatching ranges         : {}",
+            self.mismatching_ranges.len()
+        )?;
+        write_range(
+            f,
+            json_printer,
+            Some("first mismatching range"),
+            "First mismatching range             ",
+            self.mismatching_ranges.first(),
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of missing bytes              : {}",
+            range_len_sum(&self.missing_ranges)
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Number of missing ranges             : {}",
+            self.missing_ranges.len()
+        )?;
+        write_range(
+            f,
+            json_printer,
+            Some("first missing range"),
+            "First missing range                 ",
+            self.missing_ranges.first(),
+        )?;
+        if self.verbose {
+            for &(name, label, ranges) in [
+                (
+                    "mismatching ranges",
+                    "Mismatching range",
+                    &self.mismatching_ranges,
+                ),
+                ("missing ranges", "Missing range    ", &self.missing_ranges),
+            ]
+            .iter()
+            {
+                json_printer.write_open_bracket(f, Some(name), BracketType::Square)?;
+                for range in ranges.iter() {
+                    write_range(f, json_printer, None, label, Some(range))?;
+                }
+                json_printer.write_close_bracket(f)?;
+            }
+        }
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Time elapsed                         : {:02}:{:02}:{:02}",
+            hour,
+            minute,
+            second
+        )?;
+        write_maybe_json!(
+            f,
+            json_printer,
+            "Source matches container             : {}",
+            self.source_matches()
+        )?;
+        if self.source_matches() {
+            write_if!(not_json => f, json_printer => "The source file matches the data stored in the container";)?;
+        } else {
+            write_if!(not_json => f, json_printer => "The source file does NOT match the data stored in the container";)?;
+        }
+
+        json_printer.write_close_bracket(f)?;
+
+        Ok(())
+    }
+}
+
+fn add_range(ranges: &mut Vec<(u64, u64)>, start: u64, end_exc: u64) {
+    if start >= end_exc {
+        return;
+    }
+
+    match ranges.last_mut() {
+        Some(last) if last.1 == start => last.1 = end_exc,
+        _ => ranges.push((start, end_exc)),
+    }
+}
+
+/// Sorts the ranges and merges the overlapping or adjacent ones
+pub fn normalize_ranges(ranges: &mut Vec<(u64, u64)>) {
+    ranges.sort();
+
+    let mut res: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+
+    for &(start, end_exc) in ranges.iter() {
+        match res.last_mut() {
+            Some(last) if start <= last.1 => last.1 = std::cmp::max(last.1, end_exc),
+            _ => res.push((start, end_exc)),
+        }
+    }
+
+    *ranges = res;
+}
+
+/// Compares a data chunk placed at `pos` of the original file against the
+/// corresponding bytes of the source, and records the mismatching byte ranges
+///
+/// `source` holds the bytes of the source at `pos`, and is shorter than
+/// `chunk` if the source ends within the chunk. The chunk should be truncated
+/// to the recorded file size if known, otherwise trailing padding bytes of
+/// the chunk beyond the end of the source are not counted as mismatches.
+pub fn compare_chunk(
+    pos: u64,
+    chunk: &[u8],
+    source: &[u8],
+    file_size_known: bool,
+    ranges: &mut Vec<(u64, u64)>,
+) -> bool {
+    let mut matched = true;
+
+    let mut mismatch_start: Option<usize> = None;
+
+    let compare_len = std::cmp::min(chunk.len(), source.len());
+
+    for i in 0..compare_len {
+        if chunk[i] != source[i] {
+            matched = false;
+            if mismatch_start.is_none() {
+                mismatch_start = Some(i);
+            }
+        } else if let Some(start) = mismatch_start {
+            add_range(ranges, pos + start as u64, pos + i as u64);
+            mismatch_start = None;
+        }
+    }
+
+    // part of the chunk beyond the end of the source
+    let chunk_end = if file_size_known {
+        chunk.len()
+    } else {
+        match chunk[compare_len..].iter().rposition(|&x| x != 0x1A) {
+            None => compare_len,
+            Some(x) => compare_len + x + 1,
+        }
+    };
+
+    let mismatch_end = if chunk_end > compare_len {
+        matched = false;
+        if mismatch_start.is_none() {
+            mismatch_start = Some(compare_len);
+        }
+        chunk_end
+    } else {
+        compare_len
+    };
+
+    if let Some(start) = mismatch_start {
+        add_range(ranges, pos + start as u64, pos + mismatch_end as u64);
+    }
+
+    matched
+}
+
+pub fn verify_file(param: &Param) -> Result<Option<Stats>, Error> {
+    let ctrlc_stop_flag = setup_ctrlc_handler(param.json_printer.json_enabled());
+
+    let json_printer = &param.json_printer;
+
+    let (ref_block_pos, ref_block) = get_ref_block!(no_force_misalign =>
+                                                    param,
+                                                    None,
+                                                    None,
+                                                    json_printer,
+                                                    param.ref_block_choice,
+                                                    ctrlc_stop_flag
+    );
+
+    let version = ref_block.get_version();
+    let block_size = ver_to_block_size(version) as u64;
+    let data_size = ver_to_data_size(version) as u64;
+
+    // only the shard counts are needed to place the data chunks,
+    // so there is no need to guess the burst error resistance level
+    let data_par = if ver_uses_rs(version) {
+        Some((
+            get_RSD_from_ref_block!(ref_block_pos, ref_block, "verify"),
+            get_RSP_from_ref_block!(ref_block_pos, ref_block, "verify"),
+        ))
+    } else {
+        None
+    };
+
+    // omitted blocks stand for chunks of zeros
+    let sparse_map = sparse_map::load(
+        json_printer,
+        &ref_block,
+        Some(&param.in_file),
+        match param.sparse_map_file {
+            None => None,
+            Some(ref f) => Some(f),
+        },
+    )?;
+
+    // the position of a hole tells which block was omitted
+    let data_par_burst = if sparse_map.is_some() {
+        get_data_par_burst!(no_offset => param, ref_block_pos, &ref_block, "verify")
+    } else {
+        None
+    };
+
+    let container_size = file_utils::get_file_size(&param.in_file)?;
+    let source_file_size = file_utils::get_file_size(&param.source_file)?;
+
+    let stats = Arc::new(Mutex::new(Stats::new(
+        &ref_block,
+        source_file_size,
+        container_size,
+        param.verbose,
+        json_printer,
+    )));
+
+    let recorded_file_size = stats.lock().unwrap().recorded_file_size;
+
+    let reporter = ProgressReporter::new(
+        &stats,
+        "Data verification progress",
+        "bytes",
+        param.pr_verbosity_level,
+        json_printer.json_enabled(),
+    );
+
+    let mut reader = FileReader::new(
+        &param.in_file,
+        FileReaderParam {
+            write: false,
+            buffered: true,
+        },
+    )?;
+
+    let mut source_reader = FileReader::new(
+        &param.source_file,
+        FileReaderParam {
+            write: false,
+            buffered: true,
+        },
+    )?;
+
+    let header_pred = header_pred_same_ver_uid!(ref_block);
+
+    let mut block = Block::dummy();
+    let mut buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+    let mut source_buffer: [u8; SBX_LARGEST_BLOCK_SIZE] = [0; SBX_LARGEST_BLOCK_SIZE];
+
+    let mut chunk_tracker = ChunkTracker::new(version);
+    let mut mismatching_ranges: Vec<(u64, u64)> = Vec::new();
+
+    let mut pos = ref_block_pos % block_size;
+
+    reporter.start();
+
+    reader.seek(SeekFrom::Start(pos))?;
+
+    loop {
+        break_if_atomic_bool!(ctrlc_stop_flag);
+
+        let read_res = reader.read(sbx_block::slice_buf_mut(version, &mut buffer))?;
+
+        break_if_eof_seen!(read_res);
+
+        let mut stats = stats.lock().unwrap();
+
+        let seq_num =
+            sbx_block::calc_seq_num_at_index(pos / block_size, Some(true), data_par_burst);
+
+        if sparse_map::block_is_omitted(
+            &sparse_map,
+            seq_num,
+            sbx_block::slice_buf(version, &buffer),
+        ) {
+            sparse_map::fill_omitted_block(
+                version,
+                &ref_block.get_uid(),
+                seq_num,
+                sbx_block::slice_buf_mut(version, &mut buffer),
+            );
+        }
+
+        match block.sync_from_buffer(&buffer, Some(&header_pred), None) {
+            Ok(()) => {
+                if block.is_data() {
+                    if let Some(chunk_pos) =
+                        sbx_block::calc_data_chunk_write_pos(version, block.get_seq_num(), data_par)
+                    {
+                        let chunk_len = match recorded_file_size {
+                            Some(size) if chunk_pos >= size => 0,
+                            Some(size) => std::cmp::min(data_size, size - chunk_pos),
+                            None => data_size,
+                        };
+
+                        if chunk_len > 0 {
+                            let source_len = if chunk_pos >= source_file_size {
+                                0
+                            } else {
+                                source_reader.seek(SeekFrom::Start(chunk_pos))?;
+                                let len = std::cmp::min(chunk_len, source_file_size - chunk_pos);
+                                source_reader
+                                    .read(&mut source_buffer[..len as usize])?
+                                    .len_read
+                            };
+
+                            let chunk =
+                                &sbx_block::slice_data_buf(version, &buffer)[..chunk_len as usize];
+
+                            if compare_chunk(
+                                chunk_pos,
+                                chunk,
+                                &source_buffer[..source_len as usize],
+                                recorded_file_size.is_some(),
+                                &mut mismatching_ranges,
+                            ) {
+                                stats.data_blocks_matched += 1;
+                            } else {
+                                stats.data_blocks_mismatched += 1;
+                            }
+
+                            chunk_tracker.mark_recovered(chunk_pos / data_size);
+                        }
+                    }
+                }
+            }
+            Err(_) => {
+                if !misc_utils::buffer_is_blank(sbx_block::slice_buf(version, &buffer)) {
+                    stats.blocks_decode_failed += 1;
+                }
+            }
+        }
+
+        pos += block_size;
+
+        stats.bytes_processed = pos;
+    }
+
+    reporter.stop();
+
+    // the source has data past the file size recorded in the container
+    if let Some(size) = recorded_file_size {
+        add_range(&mut mismatching_ranges, size, source_file_size);
+    }
+
+    normalize_ranges(&mut mismatching_ranges);
+
+    let expected_size = match recorded_file_size {
+        Some(x) => x,
+        None => source_file_size,
+    };
+
+    let mut stats = stats.lock().unwrap().clone();
+
+    stats.mismatching_ranges = mismatching_ranges;
+    stats.missing_ranges = chunk_tracker.bad_ranges(Some(expected_size), true);
+
+    Ok(Some(stats))
+}
@@ -0,0 +1,76 @@
+#![cfg(test)]
+
+use crate::verify_core::*;
+
+#[test]
+fn test_compare_chunk_identical() {
+    let mut ranges = Vec::new();
+
+    assert!(compare_chunk(
+        100,
+        &[1, 2, 3],
+        &[1, 2, 3],
+        true,
+        &mut ranges
+    ));
+    assert!(ranges.is_empty());
+}
+
+#[test]
+fn test_compare_chunk_mismatches() {
+    let mut ranges = Vec::new();
+
+    assert!(!compare_chunk(
+        100,
+        &[1, 2, 3, 4, 5, 6],
+        &[1, 0, 0, 4, 5, 0],
+        true,
+        &mut ranges
+    ));
+    assert_eq!(vec![(101, 103), (105, 106)], ranges);
+}
+
+#[test]
+fn test_compare_chunk_source_too_short() {
+    let mut ranges = Vec::new();
+
+    // file size known, so padding beyond the source still counts
+    assert!(!compare_chunk(
+        0,
+        &[1, 2, 0x1A, 0x1A],
+        &[1, 2],
+        true,
+        &mut ranges
+    ));
+    assert_eq!(vec![(2, 4)], ranges);
+
+    // file size unknown, so trailing padding is ignored
+    let mut ranges = Vec::new();
+    assert!(compare_chunk(
+        0,
+        &[1, 2, 0x1A, 0x1A],
+        &[1, 2],
+        false,
+        &mut ranges
+    ));
+    assert!(ranges.is_empty());
+
+    let mut ranges = Vec::new();
+    assert!(!compare_chunk(
+        0,
+        &[1, 9, 3, 0x1A],
+        &[1],
+        false,
+        &mut ranges
+    ));
+    assert_eq!(vec![(1, 3)], ranges);
+}
+
+#[test]
+fn test_normalize_ranges() {
+    let mut ranges = vec![(10, 20), (0, 5), (5, 8), (15, 30), (40, 41)];
+
+    normalize_ranges(&mut ranges);
+
+    assert_eq!(vec![(0, 8), (10, 30), (40, 41)], ranges);
+}
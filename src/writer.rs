@@ -24,8 +24,9 @@ impl Writer {
     pub fn write(&mut self, buf: &[u8]) -> Result<usize, Error> {
         match self.writer {
             WriterType::File(ref mut f) => f.write(buf),
-            WriterType::Stdout(ref mut s) => match s.write(buf) {
-                Ok(len) => Ok(len),
+            // stdout may accept only part of the buffer, e.g. up to a newline
+            WriterType::Stdout(ref mut s) => match s.write_all(buf) {
+                Ok(()) => Ok(buf.len()),
                 Err(e) => Err(to_err(StdoutError::new(e.kind()))),
             },
         }
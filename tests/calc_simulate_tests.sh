@@ -0,0 +1,73 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+echo -n "Simulating burst within tolerance"
+output=$(./../blkar calc --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 12 \
+                --simulate burst=11K --trials 200 1048576)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".simulation.fullRecoveryProbability") == 1 ]] \
+  && [[ $(echo $output | jq -r ".simulation.worstCaseBytesLost") == 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Simulating burst beyond tolerance"
+output=$(./../blkar calc --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 0 \
+                --simulate burst=64K --trials 200 1048576)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".simulation.fullRecoveryProbability") == 0 ]] \
+  && [[ $(echo $output | jq -r ".simulation.worstCaseBytesLost") -gt 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Simulating solved configuration"
+output=$(./../blkar calc --json --solve burst=256K --simulate burst=256K --trials 200 10485760)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".simulation.fullRecoveryProbability") == 1 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking simulation with same seed is reproducible"
+output_a=$(./../blkar calc --json --simulate random=2%,burst=4K-64K,bursts=2 --seed 42 10485760)
+output_b=$(./../blkar calc --json --simulate random=2%,burst=4K-64K,bursts=2 --seed 42 10485760)
+if [[ $(echo $output_a | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $output_a == $output_b ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking invalid damage model is rejected"
+output=$(./../blkar calc --json --simulate burst=64K-4K 10485760)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
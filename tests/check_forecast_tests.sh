@@ -0,0 +1,130 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+echo -n "Encoding file"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 0 -f dummy check_forecast.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "17" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking intact container"
+output=$(./../blkar check --json --forecast check_forecast.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.minimumParityMargin") == 2 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.worstBlockSet") == null ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.fullyRepairable") == true ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+# block set 1 (seq num 13 - 24) loses 3 blocks, block set 2 (seq num 25 - 36) loses 1 block
+for seq_num in 13 14 15 25; do
+    corrupt $[(2 + $seq_num) * 512] check_forecast.sbx
+done
+
+echo -n "Checking damaged container"
+output=$(./../blkar check --json --forecast check_forecast.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.damagedBlockSets | length") == 2 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.damagedBlockSets[1].parityMargin") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.worstBlockSet.index") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.worstBlockSet.parityMargin") == -1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfUnrecoverableBlockSets") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.fullyRepairable") == false ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking without forecast"
+output=$(./../blkar check --json check_forecast.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.minimumParityMargin") == null ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Comparing forecast to repair results"
+output=$(./../blkar repair --json --dry-run check_forecast.sbx)
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksRepairedData") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToRepairData") == 3 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking forecast is rejected with log file"
+./../blkar check --json --forecast --log check_forecast.log check_forecast.sbx &>/dev/null
+if [[ $? != 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,125 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+echo -n "Encoding file"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 0 -f dummy check_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "17" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking heat map of intact container"
+output=$(./../blkar check --json --map check_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.healthMap.damagedCells | length") == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+# block set 1 (seq num 13 - 24) loses 3 blocks, block set 2 (seq num 25 - 36) loses 1 block
+for seq_num in 13 14 15 25; do
+    corrupt $[(2 + $seq_num) * 512] check_map.sbx
+done
+
+echo -n "Checking heat map of damaged container"
+output=$(./../blkar check --json --map --map-cell-blocks 1 check_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.healthMap.blocksPerCell") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.healthMap.damagedCells | length") == 4 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.healthMap.damagedCells[0].index") == 15 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.healthMap.damagedCells[0].corrupt") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.healthMap.damagedCells[3].index") == 27 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.healthMap.damagedCells[3].repairable") == 1 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking text heat map"
+output=$(./../blkar check --map --map-cell-blocks 1 check_map.sbx 2>/dev/null)
+if [[ $(echo "$output" | awk '$1 == "0" { print $2 }' | head -c 28) == "...............XXX.........o" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Writing heat map as SVG"
+output=$(./../blkar check --json --map-file check_map.svg check_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(head -c 4 check_map.svg) == "<svg" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Writing heat map as PNG"
+output=$(./../blkar check --json --map-file check_map.png check_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(head -c 4 check_map.png | tail -c 3) == "PNG" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Writing heat map in unrecognised format"
+output=$(./../blkar check --json --map-file check_map.txt check_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,114 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+# short names to fit metadata into the small blocks of version 2 and 18,
+# and a run of zeros so blocks are omitted from the sparse container
+dd if=/dev/urandom of=cvd bs=$[1024 * 1024] count=1 &>/dev/null
+dd if=/dev/zero bs=$[64 * 1024] count=1 >> cvd 2>/dev/null
+dd if=/dev/urandom bs=$[1024 * 1024] count=1 >> cvd 2>/dev/null
+dd if=/dev/urandom bs=1000 count=1 >> cvd 2>/dev/null
+
+for config in "1|17 --rs-data 10 --rs-parity 2 --burst 3" \
+              "17 --rs-data 10 --rs-parity 2 --burst 4|19 --rs-data 20 --rs-parity 6 --burst 2" \
+              "19 --rs-data 20 --rs-parity 6 --burst 2|1" \
+              "3|18 --rs-data 3 --rs-parity 5" \
+              "2 --sparse|3"; do
+  in_args=$(echo "$config" | cut -d'|' -f1)
+  out_args=$(echo "$config" | cut -d'|' -f2)
+  out_ver=$(echo $out_args | awk '{print $1}')
+
+  echo -n "Encoding in version $in_args"
+  output=$(./../blkar encode --json --sbx-version $in_args -f cvd cvi.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  uid=$(echo $output | jq -r ".stats.fileUID")
+  if [[ $uid != null ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Converting to version $out_args"
+  output=$(./../blkar convert --json --sbx-version $out_args -f cvi.sbx cvo.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfConvertedData") ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxContainerSize") == $(stat -c %s cvo.sbx) ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Checking converted container"
+  output=$(./../blkar show --json cvo.sbx)
+  if [[ $(echo $output | jq -r ".blocks[0].sbxContainerVersion") == $out_ver ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".blocks[0].fileUID") == $uid ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".blocks[0].sbxContainerName") == "cvo.sbx" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding converted container"
+  output=$(./../blkar decode --json -f cvo.sbx convert_decoded)
+  if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfOutputFile") ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  cmp cvd convert_decoded
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo -n "Checking corrupted input is rejected"
+./../blkar encode --json --sbx-version 1 -f cvd cvi.sbx &>/dev/null
+corrupt 10000 cvi.sbx
+output=$(./../blkar convert --json --sbx-version 3 -f cvi.sbx cvo.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking output cannot be the input"
+output=$(./../blkar convert --json -f cvi.sbx ./cvi.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,152 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+echo -n "Encoding file"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 0 -f dummy damage_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "17" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+# block set 0 stays recoverable, block set 1 loses more blocks than it has parity blocks
+corrupt 1024 damage_map.sbx
+corrupt 2048 damage_map.sbx
+corrupt 7680 damage_map.sbx
+corrupt 8192 damage_map.sbx
+corrupt 8704 damage_map.sbx
+
+echo -n "Checking container with damage map"
+output=$(./../blkar check --json --damage-map damage_map.map damage_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfDamagedBlockSets") == 2 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfUnrecoverableBlockSets") == 1 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking damage map content"
+if [[ $(grep -c "^meta range=1024-1536$" damage_map.map) == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(grep -c "^set=0 seq_num=2 range=2048-2560$" damage_map.map) == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(grep -c "^set=1 " damage_map.map) == 3 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Decoding with damage map"
+output=$(./../blkar decode --json -f --damage-map damage_map.map damage_map.sbx damage_map_decoded)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.unrecoverableRanges | length") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.unrecoverableRanges[0].from") == $[10 * 496] ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.unrecoverableRanges[0].to") == $[13 * 496 - 1] ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Repairing with damage map"
+output=$(./../blkar repair --json --damage-map damage_map.map damage_map.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksProcessed") == $[1 + 2 * 12] ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksRepairedMetadata") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksRepairedData") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToRepairData") == 3 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking container after repair"
+output=$(./../blkar check --json --damage-map damage_map.map damage_map.sbx)
+if [[ $(echo $output | jq -r ".stats.numberOfDamagedBlockSets") == 1 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking truncated container with damage map"
+file_size=$(ls -l damage_map.sbx | awk '{print $5}')
+truncate -s $[$file_size - 1024] damage_map.sbx
+output=$(./../blkar check --json --damage-map damage_map.map damage_map.sbx)
+if [[ $(echo $output | jq -r ".stats.numberOfDamagedBlockSets") == 2 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking damage map is rejected for other containers"
+./../blkar encode --json --sbx-version 17 -f dummy damage_map_other.sbx &>/dev/null
+output=$(./../blkar repair --json --damage-map damage_map.map damage_map_other.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,99 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+printf '<BLKAR:BAD DATA>%.0s' $(seq 1 31) > bad_ranges_marker
+
+cp dummy bad_ranges_zeros
+dd if=/dev/zero of=bad_ranges_zeros bs=1 count=496 seek=$[7 * 496] conv=notrunc 2>/dev/null
+dd if=/dev/zero of=bad_ranges_zeros bs=1 count=496 seek=$[10 * 496] conv=notrunc 2>/dev/null
+
+cp dummy bad_ranges_marked
+dd if=bad_ranges_marker of=bad_ranges_marked bs=1 count=496 seek=$[7 * 496] conv=notrunc 2>/dev/null
+dd if=bad_ranges_marker of=bad_ranges_marked bs=1 count=496 seek=$[10 * 496] conv=notrunc 2>/dev/null
+
+printf "range=%d-%d\nrange=%d-%d\n" $[7 * 496] $[8 * 496] $[10 * 496] $[11 * 496] > bad_ranges_expected
+
+echo "Encoding"
+output=$(./../blkar encode --sbx-version 1 --json -f dummy bad_ranges.sbx)
+if [[ $(echo $output | jq -r ".error") != "null" ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+
+echo "Corrupting container"
+corrupt 4096 bad_ranges.sbx
+corrupt 5632 bad_ranges.sbx
+
+for fill in "" "zeros" "marker" "hole"; do
+  for out in "file" "stdout"; do
+    if [[ $fill == "hole" && $out == "stdout" ]]; then
+      continue
+    fi
+
+    echo -n "Decoding to $out, fill policy : ${fill:-default}"
+    rm -f bad_ranges_list
+    if [[ $fill == "" ]]; then
+      fill_arg=""
+    else
+      fill_arg="--fill $fill"
+    fi
+    if [[ $out == "file" ]]; then
+      output=$(./../blkar decode --json -f --bad-ranges bad_ranges_list $fill_arg bad_ranges.sbx bad_ranges_decoded)
+    else
+      output=$(./../blkar decode --json -f --bad-ranges bad_ranges_list $fill_arg bad_ranges.sbx - 2>&1 > bad_ranges_decoded)
+    fi
+    if [[ $(echo $output | jq -r ".error") != "null" ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.unrecoverableRanges[1].from") == $[10 * 496] ]]; then
+      echo -n " ==> Okay"
+    else
+      echo -n " ==> NOT okay"
+      exit_code=1
+    fi
+    cmp bad_ranges_list bad_ranges_expected
+    if [[ $? == 0 ]]; then
+      echo -n " ==> Okay"
+    else
+      echo -n " ==> NOT okay"
+      exit_code=1
+    fi
+    if [[ $fill == "marker" ]]; then
+      cmp bad_ranges_decoded bad_ranges_marked
+    else
+      cmp bad_ranges_decoded bad_ranges_zeros
+    fi
+    if [[ $? == 0 ]]; then
+      echo " ==> Okay"
+    else
+      echo " ==> NOT okay"
+      exit_code=1
+    fi
+  done
+done
+
+echo -n "Checking holes cannot be left when output is stdout"
+output=$(./../blkar decode --json -f --fill hole bad_ranges.sbx - 2>&1 > /dev/null)
+if [[ $(echo $output | jq -r ".error") == "Error : Holes cannot be left in output when output is stdout" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking invalid fill policy is rejected"
+output=$(./../blkar decode --json -f --fill blank bad_ranges.sbx bad_ranges_decoded)
+if [[ $(echo $output | jq -r ".error") == "Invalid fill policy" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+rm -f bad_ranges_marker bad_ranges_zeros bad_ranges_marked bad_ranges_expected
+
+echo $exit_code > exit_code
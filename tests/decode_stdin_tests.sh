@@ -0,0 +1,101 @@
+#!/bin/bash
+
+exit_code=0
+
+VERSIONS=(1 17 19)
+
+source functions.sh
+
+file_size=$(ls -l dummy | awk '{ print $5 }')
+
+for ver in ${VERSIONS[*]}; do
+  if [[ $ver == 1 ]]; then
+    rs_arg=""
+    block_size=512
+  elif [[ $ver == 17 ]]; then
+    rs_arg="--rs-data 10 --rs-parity 2 --burst 3"
+    block_size=512
+  else
+    rs_arg="--rs-data 10 --rs-parity 2 --burst 3"
+    block_size=4096
+  fi
+
+  echo -n "Encoding in version $ver"
+  output=$(./../blkar encode --json --sbx-version $ver -f $rs_arg dummy decode_stdin.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  if [[ $ver != 1 ]]; then
+    echo "Corrupting at 2 random positions, burst error size is 3"
+    for (( p=0; p < 2; p++ )); do
+      pos=$(( RANDOM % $file_size ))
+      burst_corrupt $pos $block_size 3 decode_stdin.sbx
+    done
+  fi
+
+  for out in "file" "stdout"; do
+    echo -n "Decoding from stdin to $out"
+    rm -f decode_stdin_decoded
+    if [[ $out == "file" ]]; then
+      output=$(cat decode_stdin.sbx | ./../blkar decode --json -f - decode_stdin_decoded)
+    else
+      output=$(cat decode_stdin.sbx | ./../blkar decode --json - - 2>&1 > decode_stdin_decoded)
+    fi
+    if [[ $(echo $output | jq -r ".error") != null ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.sbxContainerSize") == $(stat -c %s decode_stdin.sbx) ]]; then
+      echo -n " ==> Okay"
+    else
+      echo -n " ==> NOT okay"
+      exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfOutputFile") ]]; then
+      echo -n " ==> Okay"
+    else
+      echo -n " ==> NOT okay"
+      exit_code=1
+    fi
+    cmp dummy decode_stdin_decoded
+    if [[ $? == 0 ]]; then
+      echo " ==> Okay"
+    else
+      echo " ==> NOT okay"
+      exit_code=1
+    fi
+  done
+done
+
+echo -n "Decoding from stdin a container streamed to stdout"
+rm -f decode_stdin_decoded
+cat dummy | ./../blkar encode --sbx-version 17 --rs-data 10 --rs-parity 2 - - 2>/dev/null \
+  | ./../blkar decode -f - decode_stdin_decoded &>/dev/null
+cmp dummy decode_stdin_decoded
+if [[ $? == 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking range options are rejected when input is stdin"
+output=$(cat decode_stdin.sbx | ./../blkar decode --json -f --from 512 - decode_stdin_decoded)
+if [[ $(echo $output | jq -r ".error") == "--from cannot be used when input is stdin" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+rm -f decode_stdin.sbx decode_stdin_decoded
+
+echo $exit_code > exit_code
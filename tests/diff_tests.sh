@@ -0,0 +1,256 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+file_size=$(ls -l dummy | awk '{ print $5 }')
+
+echo -n "Encoding in version 1"
+output=$(./../blkar encode --json --sbx-version 1 -f dummy diff_1.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "1" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Encoding in version 19 with burst error resistance"
+output=$(./../blkar encode --json --sbx-version 19 --rs-data 10 --rs-parity 2 --burst 3 -f dummy diff_19.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "19" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Comparing containers of the same file"
+output=$(./../blkar diff --json diff_1.sbx diff_19.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.dataIdentical") == true ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfChangedBytes") == 0 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r '.stats.metadataDifferences[] | select(.field == "VER") | .b') == "19" ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r '.stats.metadataDifferences[] | select(.field == "FSZ") | .field') == "" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+cp dummy dummy_diff
+corrupt 1000 dummy_diff
+echo -n "abcd" >> dummy_diff
+
+echo -n "Encoding modified file in version 2"
+output=$(./../blkar encode --json --sbx-version 2 -f dummy_diff diff_2.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "2" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Comparing containers of different files"
+output=$(./../blkar diff --json --verbose diff_19.sbx diff_2.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.dataIdentical") == false ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfChangedRanges") == 2 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.changedRanges[0].start") == 1000 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.changedRanges[1].start") == $file_size ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.changedRanges[1].endExc") == $[file_size + 4] ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r '.stats.metadataDifferences[] | select(.field == "FSZ") | .b') == $[file_size + 4] ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r '.stats.metadataDifferences[] | select(.field == "FNM") | .b') == "dummy_diff" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+head -c 100000 dummy > dummy_diff_short
+cp dummy_diff_short dummy_diff_long
+dd if=/dev/zero bs=50000 count=1 >> dummy_diff_long 2>/dev/null
+
+echo -n "Encoding files without metadata in version 1"
+output=$(./../blkar encode --json --sbx-version 1 --no-meta -f dummy_diff_short diff_short.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+output=$(./../blkar encode --json --sbx-version 1 --no-meta -f dummy_diff_long diff_long.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "1" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Comparing against longer container without file size"
+output=$(./../blkar diff --json --verbose diff_short.sbx diff_long.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.dataIdentical") == false ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.changedRanges[0].start") == 100000 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.changedRanges[0].endExc") == 150000 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfChangedBytes") == 50000 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+# damage the data block with seq num 1 of version 1 container
+corrupt $[512 + 20] diff_1.sbx
+
+echo -n "Comparing against container with damaged block"
+output=$(./../blkar diff --json diff_1.sbx diff_19.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfInvalidBlocksInA") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.firstUncomparedRange.start") == 0 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfChangedBytes") == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+head -c 10000 dummy > dummy_diff_sparse
+dd if=/dev/zero bs=1000 count=100 2>/dev/null >> dummy_diff_sparse
+head -c 5000 dummy >> dummy_diff_sparse
+
+echo -n "Encoding file with zeros with and without sparse enabled"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 -f dummy_diff_sparse diff_full.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 3 -f --sparse dummy_diff_sparse diff_sparse.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if (( $(echo $output | jq -r ".stats.numberOfBlankBlocksOmitted") > 0 )); then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+for files in "diff_full.sbx diff_sparse.sbx" "diff_sparse.sbx diff_full.sbx"; do
+  echo -n "Comparing full and sparse containers"
+  output=$(./../blkar diff --json $files)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.numberOfChangedBytes") == 0 ]]; then
+      echo -n " ==> Okay"
+  else
+      echo -n " ==> NOT okay"
+      exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.dataIdentical") == true ]]; then
+      echo " ==> Okay"
+  else
+      echo " ==> NOT okay"
+      exit_code=1
+  fi
+done
+
+echo $exit_code > exit_code
@@ -0,0 +1,144 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+# short names to fit metadata into the small blocks of version 2, and the
+# containers are kept in separate directories so their recorded names match
+mkdir -p erf erp erc
+dd if=/dev/urandom of=erd bs=$[1024 * 1024] count=8 &>/dev/null
+dd if=/dev/zero bs=$[1024 * 1024] count=4 >> erd 2>/dev/null
+dd if=/dev/urandom bs=$[1024 * 1024] count=4 >> erd 2>/dev/null
+
+for config in "2" "2 --sparse" "17 --rs-data 2 --rs-parity 1 --burst 0" "19 --rs-data 2 --rs-parity 1 --burst 0"; do
+  ver=$(echo $config | awk '{print $1}')
+  args=$(echo $config | cut -s -d' ' -f2-)
+
+  if [[ $ver == 2 ]]; then
+    block_size=128
+    meta_count=1
+  elif [[ $ver == 17 ]]; then
+    block_size=512
+    meta_count=2
+  else
+    block_size=4096
+    meta_count=2
+  fi
+
+  rm -f encode_resume_log
+
+  echo -n "Encoding in version $ver $args with log file"
+  output=$(./../blkar encode --json --sbx-version $ver $args -f --log encode_resume_log erd erf/er.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  next_seq_num=$(grep "^next_seq_num=" encode_resume_log | cut -d= -f2)
+  if [[ $next_seq_num -gt 1 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  # simulate an interruption right after the last recorded block set
+  cp erf/er.sbx erp/er.sbx
+  truncate -s $[($meta_count + $next_seq_num - 1) * $block_size] erp/er.sbx
+
+  echo -n "Checking corrupted tail is rejected"
+  cp erp/er.sbx erc/er.sbx
+  corrupt $[($meta_count + $next_seq_num - 2) * $block_size + 10] erc/er.sbx
+  output=$(./../blkar encode --json --sbx-version $ver $args --log encode_resume_log erd erc/er.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Continuing encoding from log file"
+  output=$(./../blkar encode --json --sbx-version $ver $args --log encode_resume_log erd erp/er.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxContainerSize") == $(stat -c %s erf/er.sbx) ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Comparing to container from uninterrupted run"
+  cmp erf/er.sbx erp/er.sbx
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding continued container"
+  output=$(./../blkar decode --json -f erp/er.sbx encode_resume_decoded)
+  if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfOutputFile") ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  cmp erd encode_resume_decoded
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo -n "Checking log file is rejected for different RS parameters"
+cp erf/er.sbx erp/er.sbx
+truncate -s $[(2 + $next_seq_num - 1) * 4096] erp/er.sbx
+output=$(./../blkar encode --json --sbx-version 19 --rs-data 3 --rs-parity 1 --burst 0 --log encode_resume_log erd erp/er.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking existing output is kept when log file has no block set recorded"
+sed -i "s/^next_seq_num=.*/next_seq_num=1/" encode_resume_log
+output=$(./../blkar encode --json --sbx-version 19 --rs-data 2 --rs-parity 1 --burst 0 --log encode_resume_log erd erp/er.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo -n " ==> Okay"
+else
+  echo -n " ==> NOT okay"
+  exit_code=1
+fi
+if [[ $(stat -c %s erp/er.sbx) == $[(2 + $next_seq_num - 1) * 4096] ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking log file is rejected when output is stdout"
+output=$(./../blkar encode --json --log encode_resume_log erd - 2>&1 >/dev/null)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking log file is rejected when input is stdin"
+output=$(cat erd | ./../blkar encode --json -f --log encode_resume_log - encode_resume_stdin.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,107 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+for ver in 1 17; do
+  if [[ $ver == 1 ]]; then
+    rs_arg=""
+  else
+    rs_arg="--rs-data 10 --rs-parity 2"
+  fi
+
+  for input in "file" "stdin"; do
+    echo -n "Encoding in version $ver from $input to stdout"
+    if [[ $input == "file" ]]; then
+      output=$(./../blkar encode --json --sbx-version $ver $rs_arg dummy - 2>&1 > stream.sbx)
+    else
+      output=$(cat dummy | ./../blkar encode --json --sbx-version $ver $rs_arg - - 2>&1 > stream.sbx)
+    fi
+    if [[ $(echo $output | jq -r ".error") != "null" ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.sbxContainerSize") == $(stat -c %s stream.sbx) ]]; then
+      echo " ==> Okay"
+    else
+      echo " ==> NOT okay"
+      exit_code=1
+    fi
+
+    echo -n "Checking trailing metadata block is preferred in show mode"
+    output=$(./../blkar show --json stream.sbx)
+    if [[ $(echo $output | jq -r ".blocks[0].foundAtByte") == $[$(stat -c %s stream.sbx) - 512] ]]; then
+      echo -n " ==> Okay"
+    else
+      echo -n " ==> NOT okay"
+      exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".blocks[0].fileSize") == $(stat -c %s dummy) ]]; then
+      echo " ==> Okay"
+    else
+      echo " ==> NOT okay"
+      exit_code=1
+    fi
+
+    echo -n "Checking container"
+    output=$(./../blkar check --json stream.sbx)
+    if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 0 ]]; then
+      echo " ==> Okay"
+    else
+      echo " ==> NOT okay"
+      exit_code=1
+    fi
+
+    for out in "file" "stdout"; do
+      echo -n "Decoding to $out"
+      rm -f stream_decoded
+      if [[ $out == "file" ]]; then
+        output=$(./../blkar decode --json -f stream.sbx stream_decoded)
+      else
+        output=$(./../blkar decode --json -f stream.sbx - 2>&1 > stream_decoded)
+      fi
+      if [[ $(echo $output | jq -r ".error") != "null" ]]; then
+        echo " ==> Invalid JSON"
+        exit_code=1
+      fi
+      if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfOutputFile") ]]; then
+        echo -n " ==> Okay"
+      else
+        echo -n " ==> NOT okay"
+        exit_code=1
+      fi
+      cmp dummy stream_decoded
+      if [[ $? == 0 ]]; then
+        echo " ==> Okay"
+      else
+        echo " ==> NOT okay"
+        exit_code=1
+      fi
+    done
+  done
+done
+
+echo -n "Checking sorting keeps the final metadata block"
+./../blkar sort --json -f stream.sbx stream_sorted.sbx > /dev/null
+output=$(./../blkar show --json stream_sorted.sbx)
+if [[ $(echo $output | jq -r ".blocks[0].foundAtByte") == 0 && \
+      $(echo $output | jq -r ".blocks[0].fileSize") == $(stat -c %s dummy) ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking burst error resistance level above 0 is rejected"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 2 dummy - 2>&1 > /dev/null)
+if [[ $(echo $output | jq -r ".error") == "Error : Burst error resistance level must be 0 when output is stdout" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+rm -f stream.sbx stream_sorted.sbx stream_decoded
+
+echo $exit_code > exit_code
@@ -0,0 +1,107 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+dd if=/dev/urandom of=protect_data bs=$[1024 * 1024] count=3 &>/dev/null
+
+echo -n "Solving burst goal in calc mode"
+output=$(./../blkar calc --json --solve burst=1M 104857600)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".protection.maxContiguousLoss") -ge 1048576 ]] \
+  && [[ $(echo $output | jq -r ".protection.sbxContainerSize") == $(echo $output | jq -r ".stats.sbxContainerSize") ]] \
+  && [[ $(echo $output | jq -r ".protection.rsParityShardCount") == $(echo $output | jq -r ".stats.rsParityShardCount") ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking unsatisfiable goals are rejected"
+output=$(./../blkar calc --json --solve burst=1M,overhead=1 1048576)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+for goals in "burst=64K" "burst=300K,overhead=40%" "random=0.5%"; do
+  echo -n "Encoding with protection goals $goals"
+  output=$(./../blkar encode --json -f --protect $goals protect_data protect.ecsbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  container_size=$(stat -c %s protect.ecsbx)
+  if [[ $(echo $output | jq -r ".protection.sbxContainerSize") == $container_size ]] \
+    && [[ $(echo $output | jq -r ".stats.sbxVersion") == $(echo $output | jq -r ".protection.sbxContainerVersion") ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  loss=$(echo $output | jq -r ".protection.maxContiguousLoss")
+  if [[ $goals == burst=64K ]]; then
+    loss=$[64 * 1024]
+  elif [[ $goals == burst=300K* ]]; then
+    loss=$[300 * 1024]
+  fi
+
+  pos=$[container_size / 4 + RANDOM % (container_size / 4)]
+  echo "Zeroing $loss bytes at $pos"
+  head -c $loss /dev/zero | dd of=protect.ecsbx bs=4096 seek=$pos oflag=seek_bytes conv=notrunc &>/dev/null
+
+  echo -n "Repairing"
+  output=$(./../blkar repair --json protect.ecsbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToRepairData") == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding"
+  output=$(./../blkar decode --json -f protect.ecsbx protect_decoded)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  cmp protect_data protect_decoded
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo -n "Checking burst error resistance is not used when output is stdout"
+output=$(./../blkar encode --json --protect burst=64K protect_data - 2>&1 > protect_stream.ecsbx)
+if [[ $(echo $output | jq -r ".protection.burstErrorResistanceLevel") == 0 ]] \
+  && [[ $(echo $output | jq -r ".stats.sbxContainerSize") == $(stat -c %s protect_stream.ecsbx) ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking protection goals are rejected when input is stdin"
+output=$(cat protect_data | ./../blkar encode --json -f --protect burst=64K - protect_stdin.ecsbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,126 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+VERSIONS=(17 18 19)
+
+for ver in ${VERSIONS[*]}; do
+    if   [[ $ver == 17 ]]; then
+        block_size=512
+    elif [[ $ver == 18 ]]; then
+        block_size=128
+    else
+        block_size=4096
+    fi
+
+    container_name=rep_$ver.sbx
+    out_name=rep_$ver.out.sbx
+
+    rm -f $out_name
+
+    echo -n "Encoding in version $ver"
+    output=$(./../blkar encode --json --sbx-version $ver -f dummy $container_name \
+                    --rs-data 5 --rs-parity 2 --burst 3)
+    if [[ $(echo $output | jq -r ".error") != null ]]; then
+        echo " ==> Invalid JSON"
+        exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+        echo " ==> Okay"
+    else
+        echo " ==> NOT okay"
+        exit_code=1
+    fi
+
+    corrupt $((2 * $block_size)) $container_name
+    corrupt $((9 * $block_size)) $container_name
+    corrupt $((30 * $block_size)) $container_name
+
+    cp $container_name $container_name.copy
+
+    echo -n "Repairing to separate container"
+    output=$(./../blkar repair --json --verbose $container_name $out_name)
+    if [[ $(echo $output | jq -r ".error") != null ]]; then
+        echo " ==> Invalid JSON"
+        exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 3 ]]; then
+        echo -n " ==> Okay"
+    else
+        echo -n " ==> NOT okay"
+        exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToRepairData") == 0 ]]; then
+        echo " ==> Okay"
+    else
+        echo " ==> NOT okay"
+        exit_code=1
+    fi
+
+    echo -n "Checking original container is untouched"
+    cmp $container_name $container_name.copy
+    if [[ $? == 0 ]]; then
+        echo " ==> Okay"
+    else
+        echo " ==> NOT okay"
+        exit_code=1
+    fi
+
+    echo -n "Checking repaired container"
+    output=$(./../blkar check --json $out_name)
+    if [[ $(echo $output | jq -r ".error") != null ]]; then
+        echo " ==> Invalid JSON"
+        exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 0 ]]; then
+        echo " ==> Okay"
+    else
+        echo " ==> NOT okay"
+        exit_code=1
+    fi
+
+    echo -n "Decoding repaired container"
+    output=$(./../blkar decode --json -f $out_name repair_out_$ver)
+    if [[ $(echo $output | jq -r ".error") != null ]]; then
+        echo " ==> Invalid JSON"
+        exit_code=1
+    fi
+    cmp dummy repair_out_$ver
+    if [[ $? == 0 ]]; then
+        echo " ==> Okay"
+    else
+        echo " ==> NOT okay"
+        exit_code=1
+    fi
+
+    echo -n "Repairing to existing container without --force"
+    output=$(./../blkar repair --json $container_name $out_name)
+    if [[ $(echo $output | jq -r ".error") != null ]]; then
+        echo " ==> Okay"
+    else
+        echo " ==> NOT okay"
+        exit_code=1
+    fi
+
+    corrupt $((5 * $block_size)) $out_name
+
+    echo -n "Repairing to existing container with --multi-pass"
+    output=$(./../blkar repair --json --multi-pass $container_name $out_name)
+    if [[ $(echo $output | jq -r ".error") != null ]]; then
+        echo " ==> Invalid JSON"
+        exit_code=1
+    fi
+    output=$(./../blkar check --json $out_name)
+    if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 0 ]]; then
+        echo " ==> Okay"
+    else
+        echo " ==> NOT okay"
+        exit_code=1
+    fi
+
+    rm -f $container_name.copy
+done
+
+echo $exit_code > exit_code
@@ -0,0 +1,96 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+echo -n "Encoding file"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 0 -f dummy salvage.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "17" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+# block 4 is destroyed, blocks 6 and 11 only have a few bytes flipped in the data portion,
+# so block set 0 has more invalid blocks than parity blocks
+corrupt $((4 * 512)) salvage.sbx
+corrupt $((6 * 512 + 100)) salvage.sbx
+corrupt $((6 * 512 + 200)) salvage.sbx
+corrupt $((11 * 512 + 300)) salvage.sbx
+
+cp salvage.sbx salvage.sbx.corrupted
+
+echo -n "Repairing without salvaging"
+output=$(./../blkar repair --json salvage.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToRepairData") == 3 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+cmp salvage.sbx salvage.sbx.corrupted
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Repairing with salvaging"
+output=$(./../blkar repair --json --verbose --salvage salvage.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksSalvagedData") == 3 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToRepairData") == 0 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".dataRepairs[0].salvaged") == true ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".dataRepairs[0].blocks[1].correctedBytes") == 2 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Decoding"
+output=$(./../blkar decode --json -f salvage.sbx salvage_decoded)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+cmp dummy salvage_decoded
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+rm -f salvage.sbx.corrupted
+
+echo $exit_code > exit_code
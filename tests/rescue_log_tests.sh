@@ -0,0 +1,122 @@
+#!/bin/bash
+
+exit_code=0
+
+echo "Generating random uid"
+uid=$(cat /dev/urandom | tr -dc 0-9A-F | fold -w 12 | head -n 1)
+
+echo -n "Encoding file"
+output=$(./../blkar encode --json --uid $uid -f dummy rescue_log.sbx)
+if [[ $(echo $output | jq -r ".stats.fileUID") == "$uid" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+file_size=$(ls -l rescue_log.sbx | awk '{print $5}')
+
+echo -n "Rescuing with log file"
+rm -rf rescued_log_data &>/dev/null
+mkdir rescued_log_data &>/dev/null
+rm rescue_log_log &>/dev/null
+output=$(./../blkar rescue --json rescue_log.sbx rescued_log_data rescue_log_log)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(head -n 1 rescue_log_log) == "version=2" ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(grep -c "^processed_range=0-$file_size$" rescue_log_log) == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(grep -c "^read_error_range=" rescue_log_log) == 0 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(grep -c "^uid=$uid .* named=false output=rescued_log_data/$uid$" rescue_log_log) == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [ ! -f rescue_log_log.tmp ]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+mv rescued_log_data/$uid rescue_log_first_run
+
+echo -n "Resuming with log file in format prior to versioning"
+rm -rf rescued_log_data &>/dev/null
+mkdir rescued_log_data &>/dev/null
+echo "bytes_processed=0" > rescue_log_log
+echo "blocks_processed=0" >> rescue_log_log
+echo "meta_blocks_processed=0" >> rescue_log_log
+echo "data_blocks_processed=0" >> rescue_log_log
+output=$(./../blkar rescue --json rescue_log.sbx rescued_log_data rescue_log_log)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBytesProcessed") == $file_size ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(head -n 1 rescue_log_log) == "version=2" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Comparing rescued data to first run"
+cmp rescue_log_first_run rescued_log_data/$uid
+if [[ $? == 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Resuming from processed range recorded in log file"
+rm -rf rescued_log_data &>/dev/null
+mkdir rescued_log_data &>/dev/null
+echo "version=2" > rescue_log_log
+echo "bytes_processed=0" >> rescue_log_log
+echo "blocks_processed=0" >> rescue_log_log
+echo "meta_blocks_processed=0" >> rescue_log_log
+echo "data_blocks_processed=0" >> rescue_log_log
+echo "processed_range=0-$file_size" >> rescue_log_log
+output=$(./../blkar rescue --json rescue_log.sbx rescued_log_data rescue_log_log)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBytesProcessed") == $file_size ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [ ! -f rescued_log_data/$uid ]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo $exit_code > exit_code
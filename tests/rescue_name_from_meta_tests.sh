@@ -0,0 +1,109 @@
+#!/bin/bash
+
+exit_code=0
+
+echo "Generating random uids"
+uid1=$(cat /dev/urandom | tr -dc 0-9A-F | fold -w 12 | head -n 1)
+uid2=$(cat /dev/urandom | tr -dc 0-9A-F | fold -w 12 | head -n 1)
+
+echo -n "Encoding files"
+rm -rf rescue_name_dir &>/dev/null
+mkdir rescue_name_dir
+output=$(./../blkar encode --json --uid $uid1 -f dummy rescue_name.sbx)
+if [[ $(echo $output | jq -r ".stats.fileUID") == "$uid1" ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+output=$(./../blkar encode --json --uid $uid2 -f dummy rescue_name_dir/rescue_name.sbx)
+if [[ $(echo $output | jq -r ".stats.fileUID") == "$uid2" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo "Crafting dummy disk file"
+rm rescue_name_disk &>/dev/null
+cat rescue_name.sbx >> rescue_name_disk
+cat rescue_name_dir/rescue_name.sbx >> rescue_name_disk
+
+echo -n "Rescuing from dummy disk"
+rm -rf rescued_name_data &>/dev/null
+mkdir rescued_name_data &>/dev/null
+rm rescue_name_log &>/dev/null
+output=$(./../blkar rescue --json --name-from-meta rescue_name_disk rescued_name_data rescue_name_log)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfOutputsNamed") == 2 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [ -f "rescued_name_data/rescue_name.sbx" ]; then
+  echo -n " ==> Okay"
+else
+  echo -n " ==> NOT okay"
+  exit_code=1
+fi
+if [ -f "rescued_name_data/rescue_name.sbx.1" ]; then
+  echo -n " ==> Okay"
+else
+  echo -n " ==> NOT okay"
+  exit_code=1
+fi
+if [[ $(grep -c "$uid1 rescue_name.sbx$" rescued_name_data/blkar_rescue_manifest) == 1 ]]; then
+  echo -n " ==> Okay"
+else
+  echo -n " ==> NOT okay"
+  exit_code=1
+fi
+if [[ $(grep -c "^uid=$uid2 .* named=true output=rescued_name_data/rescue_name.sbx.1$" rescue_name_log) == 1 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Resuming rescue with the log file"
+output=$(./../blkar rescue --json --name-from-meta rescue_name_disk rescued_name_data rescue_name_log)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [ ! -f "rescued_name_data/rescue_name.sbx.2" ]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+for uid in $uid1 $uid2; do
+  if [[ $uid == $uid1 ]]; then
+    name=rescue_name.sbx
+  else
+    name=rescue_name.sbx.1
+  fi
+
+  echo "Decoding rescued file "$name
+  output=$(./../blkar decode --json -f "rescued_name_data/"$name "rescued_name_data/"$name.decoded)
+  if [[ $(echo $output | jq -r ".stats.fileUID") != "$uid" ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+
+  echo -n "Comparing decoded data to original"
+  cmp dummy "rescued_name_data/"$name.decoded
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo $exit_code > exit_code
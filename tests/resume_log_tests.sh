@@ -0,0 +1,200 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+echo -n "Encoding file"
+output=$(./../blkar encode --json --sbx-version 1 -f dummy resume_log.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "1" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+file_size=$(ls -l resume_log.sbx | awk '{print $5}')
+block_count=$[$file_size / 512]
+
+echo -n "Decoding with log file"
+rm resume_log_decode_log &>/dev/null
+output=$(./../blkar decode --json -f --log resume_log_decode_log resume_log.sbx resume_log_full)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(grep -c "^bytes_processed=$file_size$" resume_log_decode_log) == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [ ! -f resume_log_decode_log.tmp ]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Resuming decode from log file"
+head -c $[1000 * 496] resume_log_full > resume_log_partial
+echo "bytes_processed=$[1001 * 512]" > resume_log_decode_log
+echo "meta_blocks_decoded=1" >> resume_log_decode_log
+echo "data_blocks_decoded=1000" >> resume_log_decode_log
+echo "parity_blocks_decoded=0" >> resume_log_decode_log
+echo "blocks_failed=0" >> resume_log_decode_log
+output=$(./../blkar decode --json --log resume_log_decode_log resume_log.sbx resume_log_partial)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksProcessed") == $block_count ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfOutputFile") ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Comparing resumed decode output to full run"
+cmp resume_log_full resume_log_partial
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking log file is rejected when decoding to stdout"
+output=$(./../blkar decode --json --log resume_log_decode_log resume_log.sbx - 2>/dev/null)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Resuming sort from log file"
+./../blkar sort -f resume_log.sbx resume_log_sorted_full &>/dev/null
+head -c $[1001 * 512] resume_log_sorted_full > resume_log_sorted_partial
+echo "bytes_processed=$[1001 * 512]" > resume_log_sort_log
+echo "meta_blocks_decoded=1" >> resume_log_sort_log
+echo "data_blocks_decoded=1000" >> resume_log_sort_log
+echo "parity_blocks_decoded=0" >> resume_log_sort_log
+echo "blocks_failed=0" >> resume_log_sort_log
+echo "okay_blank_blocks=0" >> resume_log_sort_log
+echo "meta_blocks_same_order=1" >> resume_log_sort_log
+echo "meta_blocks_diff_order=0" >> resume_log_sort_log
+echo "data_blocks_same_order=1000" >> resume_log_sort_log
+echo "data_blocks_diff_order=0" >> resume_log_sort_log
+echo "parity_blocks_same_order=0" >> resume_log_sort_log
+echo "parity_blocks_diff_order=0" >> resume_log_sort_log
+output=$(./../blkar sort --json --log resume_log_sort_log resume_log.sbx resume_log_sorted_partial)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksInSameOrderData") == $[$block_count - 1] ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+cmp resume_log_sorted_full resume_log_sorted_partial
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Resuming check from log file"
+cp resume_log.sbx resume_log_corrupted.sbx
+corrupt 1024 resume_log_corrupted.sbx
+echo "bytes_processed=$[1001 * 512]" > resume_log_check_log
+echo "meta_blocks_decoded=1" >> resume_log_check_log
+echo "data_blocks_decoded=1000" >> resume_log_check_log
+echo "blocks_failed=0" >> resume_log_check_log
+echo "okay_blank_blocks=0" >> resume_log_check_log
+output=$(./../blkar check --json --log resume_log_check_log resume_log_corrupted.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksProcessed") == $block_count ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Checking again without log file"
+output=$(./../blkar check --json resume_log_corrupted.sbx)
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 1 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Encoding file with RS"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 0 -f dummy resume_log_rs.sbx)
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "17" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Resuming repair from log file"
+corrupt 2048 resume_log_rs.sbx
+echo "next_seq_num=13" > resume_log_repair_log
+echo "meta_blocks_decoded=3" >> resume_log_repair_log
+echo "data_blocks_decoded=12" >> resume_log_repair_log
+echo "blocks_failed=0" >> resume_log_repair_log
+echo "meta_blocks_repaired=0" >> resume_log_repair_log
+echo "data_blocks_repaired=0" >> resume_log_repair_log
+echo "data_blocks_repair_failed=0" >> resume_log_repair_log
+output=$(./../blkar repair --json --log resume_log_repair_log resume_log_rs.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksRepairedData") == 0 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(grep -c "^next_seq_num=13$" resume_log_repair_log) == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Repairing again without log file"
+output=$(./../blkar repair --json resume_log_rs.sbx)
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksRepairedData") == 1 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo $exit_code > exit_code
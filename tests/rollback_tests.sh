@@ -0,0 +1,233 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+rm -f rollback.journal rollback_update.journal rollback_truncated.journal rollback_modified.journal rollback_appended.journal
+
+echo -n "Encoding file"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 2 -f dummy rollback.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "17" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+cp rollback.sbx rollback.sbx.orig
+
+corrupt 0 rollback.sbx
+corrupt 2048 rollback.sbx
+corrupt 10240 rollback.sbx
+
+cp rollback.sbx rollback.sbx.corrupted
+
+echo -n "Repairing with journal"
+output=$(./../blkar repair --json --journal rollback.journal rollback.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 3 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.orig
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Rolling back repair"
+output=$(./../blkar rollback --json rollback.journal)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksRestored") == 3 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.corrupted
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+cp rollback.sbx.orig rollback.sbx
+
+echo -n "Updating with journal"
+output=$(./../blkar update --json --journal rollback_update.journal --snm rollback_new_name rollback.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.orig &>/dev/null
+if [[ $? != 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Rolling back update"
+output=$(./../blkar rollback --json rollback_update.journal)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.orig
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+container_size=$(ls -l rollback.sbx | awk '{ print $5 }')
+truncate -s $(($container_size - 2048)) rollback.sbx
+cp rollback.sbx rollback.sbx.truncated
+
+echo -n "Repairing truncated container with journal"
+output=$(./../blkar repair --json --journal rollback_truncated.journal rollback.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.orig
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Rolling back repair of truncated container"
+output=$(./../blkar rollback --json rollback_truncated.journal)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.truncated
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+cp rollback.sbx.corrupted rollback.sbx
+
+echo -n "Repairing with journal"
+output=$(./../blkar repair --json --journal rollback_modified.journal rollback.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksRepairedData") == 2 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+corrupt 2100 rollback.sbx
+
+echo -n "Rolling back modified container without --force"
+output=$(./../blkar rollback --json rollback_modified.journal)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Rolling back modified container with --force"
+output=$(./../blkar rollback --json --force rollback_modified.journal)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksModifiedSinceRecorded") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.corrupted
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+cp rollback.sbx.corrupted rollback.sbx
+
+echo -n "Repairing with journal"
+output=$(./../blkar repair --json --journal rollback_appended.journal rollback.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.orig
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+# partially written entry left by an interrupted run
+echo -n "abcde" >> rollback_appended.journal
+
+echo -n "Updating with journal ending with incomplete entry"
+output=$(./../blkar update --json --journal rollback_appended.journal --snm rollback_new_name rollback.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.orig &>/dev/null
+if [[ $? != 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Rolling back both runs"
+output=$(./../blkar rollback --json rollback_appended.journal)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.incompleteLastEntryIgnored") == false ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+cmp rollback.sbx rollback.sbx.corrupted
+if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+rm -f rollback.sbx.orig rollback.sbx.corrupted rollback.sbx.truncated
+
+echo $exit_code > exit_code
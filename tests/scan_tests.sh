@@ -0,0 +1,98 @@
+#!/bin/bash
+
+exit_code=0
+
+echo "Generating random uids"
+uid1=$(cat /dev/urandom | tr -dc 0-9A-F | fold -w 12 | head -n 1)
+uid2=$(cat /dev/urandom | tr -dc 0-9A-F | fold -w 12 | head -n 1)
+
+echo -n "Encoding files"
+output=$(./../blkar encode --json --uid $uid1 -f dummy scan_uid1.sbx)
+if [[ $(echo $output | jq -r ".stats.fileUID") == "$uid1" ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+output=$(./../blkar encode --json --uid $uid2 -f --sbx-version 1 dummy scan_uid2.sbx)
+if [[ $(echo $output | jq -r ".stats.fileUID") == "$uid2" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo "Crafting dummy disk file"
+rm scan_disk &>/dev/null
+cat scan_uid1.sbx >> scan_disk
+cat scan_uid2.sbx >> scan_disk
+
+echo -n "Scanning dummy disk"
+output=$(./../blkar scan --json scan_disk)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfUIDsFound") == 2 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".containers[0].fileUID") == "$uid1" ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".containers[0].fullyRecoverable") == true ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".containers[1].fileUID") == "$uid2" ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".containers[1].missingDataBlocks") == 0 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo "Corrupting the version 1 container"
+truncate -s -1024 scan_disk
+
+echo -n "Scanning dummy disk"
+output=$(./../blkar scan --json scan_disk)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".containers[1].fullyRecoverable") == false ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".containers[1].missingDataBlocks") == 2 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Scanning dummy disk with only pick uid"
+output=$(./../blkar scan --json --only-pick-uid $uid2 scan_disk)
+if [[ $(echo $output | jq -r ".stats.numberOfUIDsFound") == 1 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,178 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+rm -f sip.sbx.sort_journal
+
+for config in "1" "17 --rs-data 10 --rs-parity 2 --burst 3|8" \
+              "18 --rs-data 5 --rs-parity 3 --burst 6|0" \
+              "19 --rs-data 20 --rs-parity 4 --burst 1|2"; do
+  enc_args=$(echo "$config" | cut -d'|' -f1)
+  new_burst=$(echo "$config" | cut -s -d'|' -f2)
+  ver=$(echo $enc_args | awk '{print $1}')
+
+  if [[ $new_burst == "" ]]; then
+    burst_args=""
+  else
+    burst_args="--burst $new_burst"
+  fi
+
+  echo -n "Encoding in version $enc_args"
+  output=$(./../blkar encode --json --sbx-version $enc_args -f dummy sip.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Sorting to separate file"
+  output=$(./../blkar sort --json -f $burst_args sip.sbx sip_ref.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Sorting in place"
+  output=$(./../blkar sort --json --in-place $burst_args sip.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.containerSizeAfterSorting") == $(stat -c %s sip.sbx) ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ ! -f sip.sbx.sort_journal ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Comparing to container sorted to separate file"
+  cmp sip.sbx sip_ref.sbx
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding"
+  output=$(./../blkar decode --json -f sip.sbx dummy_sip)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  cmp dummy dummy_sip
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo -n "Encoding container with duplicate blocks"
+output=$(./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 --burst 2 -f dummy sip.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+head -c 51200 sip.sbx >> sip.sbx
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "17" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Sorting in place"
+output=$(./../blkar sort --json --in-place --burst 5 sip.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfDuplicateBlocksRemoved") != 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking burst error resistance level"
+output=$(./../blkar show --json --guess-burst sip.sbx)
+if [[ $(echo $output | jq -r ".bestGuessForBurstErrorResistanceLevel") == 5 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Decoding"
+output=$(./../blkar decode --json -f sip.sbx dummy_sip)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+cmp dummy dummy_sip
+if [[ $? == 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Encoding container for interrupted sorting"
+output=$(./../blkar encode --json --sbx-version 18 --rs-data 10 --rs-parity 3 --burst 3 -f dummy sip.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "18" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+./../blkar sort --json -f --burst 12 sip.sbx sip_ref.sbx &>/dev/null
+
+echo -n "Interrupting in-place sorting"
+./../blkar sort --in-place --burst 12 sip.sbx &>/dev/null &
+pid=$!
+sleep 0.5
+kill -9 $pid &>/dev/null
+wait $pid &>/dev/null
+echo " ==> Okay"
+
+echo -n "Resuming in-place sorting"
+output=$(./../blkar sort --json --in-place sip.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+cmp sip.sbx sip_ref.sbx
+if [[ $? == 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
@@ -0,0 +1,114 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+for config in "1|512" "17 --rs-data 10 --rs-parity 2 --burst 3|4096" \
+              "18 --rs-data 5 --rs-parity 3 --burst 6|128"; do
+  enc_args=$(echo "$config" | cut -d'|' -f1)
+  block_size=$(echo "$config" | cut -d'|' -f2)
+  ver=$(echo $enc_args | awk '{print $1}')
+
+  echo -n "Encoding in version $enc_args"
+  output=$(./../blkar encode --json --sbx-version $enc_args -f dummy smg.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Splitting container into fragments"
+  total_blocks=$[$(stat -c %s smg.sbx) / $block_size]
+  half_blocks=$[$total_blocks / 2]
+  dd if=smg.sbx of=smg_a.sbx bs=$block_size count=$half_blocks &>/dev/null
+  dd if=smg.sbx of=smg_b.sbx bs=$block_size skip=$half_blocks &>/dev/null
+  echo " ==> Okay"
+
+  echo -n "Sorting fragments into one container"
+  output=$(./../blkar sort --json -f smg_b.sbx --merge smg_a.sbx smg_merged.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.inputs | length") == 2 ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.inputs[1].file") == "smg_a.sbx" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding"
+  output=$(./../blkar decode --json -f smg_merged.sbx dummy_smg)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  cmp dummy dummy_smg
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo -n "Encoding container"
+output=$(./../blkar encode --json --sbx-version 1 -f dummy smg.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "1" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Corrupting different parts of two copies"
+cp smg.sbx smg_a.sbx
+cp smg.sbx smg_b.sbx
+dd if=/dev/zero of=smg_a.sbx bs=512 seek=1 count=20 conv=notrunc &>/dev/null
+dd if=/dev/urandom of=smg_b.sbx bs=512 seek=30 count=20 conv=notrunc &>/dev/null
+echo " ==> Okay"
+
+echo -n "Sorting copies into one container"
+output=$(./../blkar sort --json -f smg_a.sbx --merge smg_b.sbx smg_merged.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.inputs[1].numberOfBlocksFailedToSort") == 20 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Decoding"
+output=$(./../blkar decode --json -f smg_merged.sbx dummy_smg)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+cmp dummy dummy_smg
+if [[ $? == 0 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
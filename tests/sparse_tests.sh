@@ -0,0 +1,163 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+head -c 10000 dummy > sparse_dummy
+dd if=/dev/zero bs=1000 count=100 2>/dev/null >> sparse_dummy
+head -c 5000 dummy >> sparse_dummy
+dd if=/dev/zero bs=1000 count=50 2>/dev/null >> sparse_dummy
+
+for ver in 1 17; do
+  if [[ $ver == 1 ]]; then
+    rs_arg=""
+  else
+    rs_arg="--rs-data 10 --rs-parity 2"
+  fi
+
+  echo -n "Encoding in version $ver with sparse enabled"
+  output=$(./../blkar encode --json --sbx-version $ver $rs_arg -f --sparse sparse_dummy sparse.sbx)
+  if [[ $(echo $output | jq -r ".error") != "null" ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  omitted=$(echo $output | jq -r ".stats.numberOfBlankBlocksOmitted")
+  if (( $omitted > 0 )); then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Checking container size"
+  if [[ $(echo $output | jq -r ".stats.sbxContainerSize") == $(stat -c %s sparse.sbx) ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Checking number of omitted blocks is recorded"
+  output=$(./../blkar show --json sparse.sbx)
+  if [[ $(echo $output | jq -r ".blocks[0].blankBlocksOmitted") == $omitted ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  for out in "file" "stdout"; do
+    echo -n "Decoding to $out"
+    rm -f sparse_decoded
+    if [[ $out == "file" ]]; then
+      output=$(./../blkar decode --json -f sparse.sbx sparse_decoded)
+    else
+      output=$(./../blkar decode --json -f sparse.sbx - 2>&1 > sparse_decoded)
+    fi
+    if [[ $(echo $output | jq -r ".error") != "null" ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.numberOfBlankBlocksSparse") == $omitted ]]; then
+      echo -n " ==> Okay"
+    else
+      echo -n " ==> NOT okay"
+      exit_code=1
+    fi
+    if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfOutputFile") ]]; then
+      echo -n " ==> Okay"
+    else
+      echo -n " ==> NOT okay"
+      exit_code=1
+    fi
+    cmp sparse_dummy sparse_decoded
+    if [[ $? == 0 ]]; then
+      echo " ==> Okay"
+    else
+      echo " ==> NOT okay"
+      exit_code=1
+    fi
+  done
+done
+
+echo -n "Checking sparse map is written"
+if [[ -f sparse.sbx.sparse ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking omitted blocks are not reported by check"
+output=$(./../blkar check --json --report-blank --hash sparse.sbx)
+if [[ $(echo $output | jq -r ".error") != "null" ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 0 ]]; then
+  echo -n " ==> Okay"
+else
+  echo -n " ==> NOT okay"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfStoredData") ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking corrupted blocks are not treated as blank blocks"
+corrupt 2048 sparse.sbx
+output=$(./../blkar decode --json -f sparse.sbx sparse_decoded)
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToDecode") == 1 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking zeroed blocks not in sparse map are failures"
+./../blkar encode --json --sbx-version 17 --rs-data 10 --rs-parity 2 -f --sparse sparse_dummy sparse.sbx > /dev/null
+dd if=/dev/zero of=sparse.sbx bs=512 count=1 seek=3 conv=notrunc 2>/dev/null
+output=$(./../blkar decode --json -f sparse.sbx sparse_decoded)
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToDecode") == 1 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking omitted blocks are failures without sparse map"
+./../blkar encode --json --sbx-version 1 -f --sparse sparse_dummy sparse.sbx > /dev/null
+rm -f sparse.sbx.sparse
+output=$(./../blkar decode -f sparse.sbx sparse_decoded 2>&1)
+if [[ $(echo "$output" | grep "Warning") != "" ]]; then
+  echo -n " ==> Okay"
+else
+  echo -n " ==> NOT okay"
+  exit_code=1
+fi
+output=$(./../blkar decode --json -f sparse.sbx sparse_decoded)
+if (( $(echo $output | jq -r ".stats.numberOfBlocksFailedToDecode") > 0 )); then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Checking blank blocks of non-sparse containers are failures"
+./../blkar encode --json --sbx-version 1 -f sparse_dummy sparse.sbx > /dev/null
+dd if=/dev/zero of=sparse.sbx bs=512 count=1 seek=3 conv=notrunc 2>/dev/null
+output=$(./../blkar decode --json -f sparse.sbx sparse_decoded)
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToDecode") == 1 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+rm -f sparse_dummy sparse_decoded sparse.sbx.sparse
+
+echo $exit_code > exit_code
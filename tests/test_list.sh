@@ -1,6 +1,8 @@
 #!/bin/bash
 
 tests=(
+    "check_forecast_tests"
+    "check_map_tests"
     "check_from_to_force_misalign"
     "check_from_to_rounding"
     "check_from_to_tests"
@@ -8,6 +10,8 @@ tests=(
     "check_ref_from_to_tests_force_misalign"
     "check_ref_from_to_tests_rounding"
     "compare_encode_file_and_stdin"
+    "damage_map_tests"
+    "diff_tests"
     "decode_from_to_tests_corruption_based"
     "decode_from_to_tests_corruption_based_force_misalign"
     "decode_from_to_tests_corruption_based_rounding"
@@ -26,10 +30,14 @@ tests=(
     "rescue_from_to_tests_encode_stdin"
     "rescue_from_to_tests_force_misalign"
     "rescue_from_to_tests_rounding"
+    "rescue_log_tests"
+    "rescue_name_from_meta_tests"
     "rescue_pick_uid_tests"
     "rescue_pick_uid_tests_decode_stdout"
     "rescue_pick_uid_tests_encode_stdin"
     "rescue_tests_decode_stdout"
+    "resume_log_tests"
+    "scan_tests"
     "show_from_to_tests"
     "show_from_to_tests_force_misalign"
     "show_from_to_tests_rounding"
@@ -47,6 +55,7 @@ tests=(
     "decode_blanks_decode_stdout"
     "decode_manual_burst_decode_stdout"
     "file_size_calc_tests"
+    "calc_simulate_tests"
     "hash_tests"
     "hash_tests_encode_stdin"
     "repair_truncated_tests_decode_stdout"
@@ -60,6 +69,10 @@ tests=(
     "update_no_hsh_tests"
     "update_no_snm_tests"
     "update_snm_tests"
+    "update_set_tests"
+    "update_new_uid_tests"
+    "update_add_meta_tests"
+    "verify_tests"
     "version_tests"
     "version_tests_decode_stdout"
     "version_tests_encode_stdin"
@@ -83,6 +96,18 @@ tests=(
     "decode_guess_burst_force_misalign"
     "repair_manual_burst"
     "repair_manual_burst_encode_stdin"
+    "repair_out_file_tests"
+    "repair_salvage_tests"
+    "rollback_tests"
+    "decode_bad_ranges_tests"
+    "sparse_tests"
+    "encode_stdout_tests"
+    "encode_resume_tests"
+    "protect_tests"
+    "convert_tests"
+    "sort_in_place_tests"
+    "sort_merge_tests"
+    "decode_stdin_tests"
     "show_guess_burst_force_misalign"
     "sort_tests_decode_stdout"
     "sort_tests"
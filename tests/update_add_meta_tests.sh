@@ -0,0 +1,230 @@
+#!/bin/bash
+
+exit_code=0
+
+# the size is not a multiple of the data size of any version,
+# so the last data block is padded
+head -c 100000 dummy > dummy_uam_in
+
+file_size=$(ls -l dummy_uam_in | awk '{print $5}')
+
+VERSIONS=(1 2 3)
+
+for ver in ${VERSIONS[*]}; do
+  echo -n "Encoding in version $ver without metadata block"
+  output=$(./../blkar encode --json --sbx-version $ver --no-meta -f dummy_uam_in uam$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Adding metadata block to unsorted uam$ver.sbx"
+  output=$(./../blkar update --json -y --add-meta uam$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Sorting uam$ver.sbx"
+  output=$(./../blkar sort --json -f uam$ver.sbx uam${ver}_sorted.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Adding metadata block to uam${ver}_sorted.sbx in dry run"
+  cp uam${ver}_sorted.sbx uam${ver}_sorted.sbx.orig
+  output=$(./../blkar update --json -y --dry-run --add-meta --set FSZ=$file_size uam${ver}_sorted.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  cmp uam${ver}_sorted.sbx uam${ver}_sorted.sbx.orig
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Adding metadata block to uam${ver}_sorted.sbx without file size"
+  output=$(./../blkar update --json -y --add-meta uam${ver}_sorted.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  cmp uam${ver}_sorted.sbx uam${ver}_sorted.sbx.orig
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Adding metadata block to uam${ver}_sorted.sbx"
+  output=$(./../blkar update --json -y --add-meta --set FSZ=$file_size --fnm dummy_uam_in uam${ver}_sorted.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.numberOfMetadataBlocksRebuilt") == 1 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Checking show output for uam${ver}_sorted.sbx"
+  output=$(./../blkar show --json uam${ver}_sorted.sbx 2>/dev/null)
+  if [[ $(echo $output | jq -r ".blocks[0].fileName") == "dummy_uam_in" ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".blocks[0].fileSize") == $file_size ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding"
+  output=$(./../blkar decode --json -f uam${ver}_sorted.sbx dummy_uam)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.recordedHash") == $(echo $output | jq -r ".stats.hashOfOutputFile") ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  cmp dummy_uam_in dummy_uam
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+# a file filling the last data block exactly does not need the file size
+head -c 9919 dummy > dummy_uam_full
+echo -n "e" >> dummy_uam_full
+
+echo -n "Encoding file filling the last data block in version 1 without metadata block"
+output=$(./../blkar encode --json --sbx-version 1 --no-meta -f dummy_uam_full uam_full.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "1" ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+./../blkar sort --json -f uam_full.sbx uam_full_sorted.sbx > /dev/null
+
+echo -n "Adding metadata block to uam_full_sorted.sbx without file size"
+output=$(./../blkar update --json -y --add-meta uam_full_sorted.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Invalid JSON"
+  exit_code=1
+fi
+output=$(./../blkar show --json uam_full_sorted.sbx 2>/dev/null)
+if [[ $(echo $output | jq -r ".blocks[0].fileSize") == 9920 ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+VERSIONS=(17 18 19)
+
+for ver in ${VERSIONS[*]}; do
+  echo -n "Encoding in version $ver"
+  output=$(./../blkar encode --json --sbx-version $ver -f dummy uam$ver.sbx \
+                      --rs-data 10 --rs-parity 2 --burst 3)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  cp uam$ver.sbx uam$ver.sbx.orig
+
+  output=$(./../blkar show --json --show-all uam$ver.sbx 2>/dev/null)
+  first_pos=$(echo $output | jq -r ".blocks[0].foundAtByte")
+  last_pos=$(echo $output | jq -r ".blocks[-1].foundAtByte")
+
+  echo -n "Corrupting metadata blocks of uam$ver.sbx"
+  dd if=/dev/zero of=uam$ver.sbx bs=1 seek=$first_pos count=16 conv=notrunc &>/dev/null
+  dd if=/dev/urandom of=uam$ver.sbx bs=1 seek=$[$last_pos + 20] count=16 conv=notrunc &>/dev/null
+  cmp uam$ver.sbx uam$ver.sbx.orig &>/dev/null
+  if [[ $? != 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Rebuilding metadata blocks of uam$ver.sbx"
+  output=$(./../blkar update --json -y --add-meta uam$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.numberOfMetadataBlocksRebuilt") == 2 ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  cmp uam$ver.sbx uam$ver.sbx.orig
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Rebuilding metadata blocks of intact uam$ver.sbx"
+  output=$(./../blkar update --json -y --add-meta uam$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.numberOfMetadataBlocksRebuilt") == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo $exit_code > exit_code
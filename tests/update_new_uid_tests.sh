@@ -0,0 +1,111 @@
+#!/bin/bash
+
+exit_code=0
+
+VERSIONS=(1 17 19)
+
+for ver in ${VERSIONS[*]}; do
+  echo -n "Encoding in version $ver"
+  output=$(./../blkar encode --json --sbx-version $ver -f dummy unu$ver.sbx \
+                      --rs-data 10 --rs-parity 2 --burst 3)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Changing UID of unu$ver.sbx in dry run"
+  cp unu$ver.sbx unu$ver.sbx.orig
+  output=$(./../blkar update --json -y --dry-run --new-uid random unu$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  cmp unu$ver.sbx unu$ver.sbx.orig
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  output=$(./../blkar check --json unu$ver.sbx 2>/dev/null)
+  valid_blocks=$[$(echo $output | jq -r ".stats.numberOfBlocksPassedCheckMetadata") \
+                 + $(echo $output | jq -r ".stats.numberOfBlocksPassedCheckData")]
+
+  echo -n "Changing UID of unu$ver.sbx"
+  output=$(./../blkar update --json -y --new-uid 0A0B0C0D0E0F unu$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.newUID") == "0A0B0C0D0E0F" ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.numberOfBlocksWithUIDChanged") == $valid_blocks ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Checking show output for unu$ver.sbx"
+  output=$(./../blkar show --json unu$ver.sbx 2>/dev/null)
+  if [[ $(echo $output | jq -r ".blocks[0].fileUID") == "0A0B0C0D0E0F" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Checking unu$ver.sbx"
+  output=$(./../blkar check --json unu$ver.sbx 2>/dev/null)
+  if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedCheck") == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding"
+  output=$(./../blkar decode --json -f unu$ver.sbx dummy_unu)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  cmp dummy dummy_unu
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Changing UID of unu$ver.sbx to the same UID"
+  output=$(./../blkar update --json -y --new-uid 0A0B0C0D0E0F unu$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo -n "Changing UID to invalid hex string"
+output=$(./../blkar update --json -y --new-uid 0A0B0C unu1.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
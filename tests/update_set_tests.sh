@@ -0,0 +1,134 @@
+#!/bin/bash
+
+exit_code=0
+
+VERSIONS=(1 17)
+
+for ver in ${VERSIONS[*]}; do
+  echo -n "Encoding in version $ver"
+  output=$(./../blkar encode --json --sbx-version $ver -f dummy ust$ver.sbx \
+                      --rs-data 10 --rs-parity 2)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Setting fields of ust$ver.sbx"
+  output=$(./../blkar update --json -y -v --set "FDT=2017-07-14 02:40:00" --set sdt=0 --set FSZ=100 ust$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".metadataChanges[0].changes[0].field") == "FDT" ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".metadataChanges[0].changes[2].to") == "100" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Checking show output for ust$ver.sbx"
+  output=$(./../blkar show --json ust$ver.sbx 2>/dev/null)
+  if [[ $(echo $output | jq -r ".blocks[0].fileModificationTime" | cut -c 1-25) == "2017-07-14 02:40:00 (UTC)" ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".blocks[0].sbxEncodingTime" | cut -c 1-25) == "1970-01-01 00:00:00 (UTC)" ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".blocks[0].fileSize") == "100" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Applying changes from JSON file to ust$ver.sbx"
+  echo '{ "FNM": "new name", "SNM": null, "FSZ": '$(cat dummy_file_size)' }' > ust_changes.json
+  output=$(./../blkar update --json -y --from-json ust_changes.json ust$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  output=$(./../blkar show --json ust$ver.sbx 2>/dev/null)
+  if [[ $(echo $output | jq -r ".blocks[0].fileName") == "new name" ]]; then
+    echo -n " ==> Okay"
+  else
+    echo -n " ==> NOT okay"
+    exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".blocks[0].sbxContainerName") == "null" ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+
+  echo -n "Decoding"
+  output=$(./../blkar decode --json -f ust$ver.sbx dummy_ust)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+  fi
+  cmp dummy dummy_ust
+  if [[ $? == 0 ]]; then
+    echo " ==> Okay"
+  else
+    echo " ==> NOT okay"
+    exit_code=1
+  fi
+done
+
+echo -n "Setting RS field of version 1 container"
+output=$(./../blkar update --json -y --set RSD=10 ust1.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Setting too many RS shards"
+output=$(./../blkar update --json -y --set RSD=200 --set RSP=100 ust17.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Setting same field twice"
+output=$(./../blkar update --json -y --set FNM=abc --fnm def ust17.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo -n "Setting encoding managed field"
+output=$(./../blkar update --json -y --set TRL=1 ust17.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+  echo " ==> Okay"
+else
+  echo " ==> NOT okay"
+  exit_code=1
+fi
+
+echo $exit_code > exit_code
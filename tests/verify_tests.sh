@@ -0,0 +1,193 @@
+#!/bin/bash
+
+exit_code=0
+
+source functions.sh
+
+VERSIONS=(1 17 19)
+
+file_size=$(ls -l dummy | awk '{ print $5 }')
+
+for ver in ${VERSIONS[*]}; do
+  echo -n "Encoding in version $ver"
+  output=$(./../blkar encode --json --sbx-version $ver --rs-data 10 --rs-parity 2 -f dummy verify_$ver.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sbxVersion") == "$ver" ]]; then
+      echo " ==> Okay"
+  else
+      echo " ==> NOT okay"
+      exit_code=1
+  fi
+
+  echo -n "Verifying against original file"
+  output=$(./../blkar verify --json verify_$ver.sbx dummy)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sourceMatchesContainer") == true ]]; then
+      echo -n " ==> Okay"
+  else
+      echo -n " ==> NOT okay"
+      exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.recordedFileSize") == $file_size ]]; then
+      echo " ==> Okay"
+  else
+      echo " ==> NOT okay"
+      exit_code=1
+  fi
+done
+
+cp dummy dummy_verify_bad
+corrupt 1000 dummy_verify_bad
+
+echo -n "Verifying against modified file"
+output=$(./../blkar verify --json verify_17.sbx dummy_verify_bad)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sourceMatchesContainer") == false ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfMismatchingBytes") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.firstMismatchingRange.start") == 1000 ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+head -c $[file_size - 100] dummy > dummy_verify_short
+
+echo -n "Verifying against truncated file"
+output=$(./../blkar verify --json verify_19.sbx dummy_verify_short)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.firstMismatchingRange.start") == $[file_size - 100] ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.firstMismatchingRange.endExc") == $file_size ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Encoding in version 1 without metadata block"
+output=$(./../blkar encode --json --sbx-version 1 --no-meta -f dummy verify_no_meta.sbx)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sbxVersion") == "1" ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+echo -n "Verifying container without metadata block"
+output=$(./../blkar verify --json verify_no_meta.sbx dummy)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.recordedFileSize") == null ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sourceMatchesContainer") == true ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+# damage the block with seq num 2 (the second block, as there is no metadata block),
+# which holds the second data chunk
+corrupt $[512 + 20] verify_no_meta.sbx
+
+echo -n "Verifying container with missing data chunk"
+output=$(./../blkar verify --json verify_no_meta.sbx dummy)
+if [[ $(echo $output | jq -r ".error") != null ]]; then
+    echo " ==> Invalid JSON"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.numberOfBlocksFailedToDecode") == 1 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.firstMissingRange.start") == 496 ]]; then
+    echo -n " ==> Okay"
+else
+    echo -n " ==> NOT okay"
+    exit_code=1
+fi
+if [[ $(echo $output | jq -r ".stats.sourceMatchesContainer") == false ]]; then
+    echo " ==> Okay"
+else
+    echo " ==> NOT okay"
+    exit_code=1
+fi
+
+head -c 10000 dummy > dummy_verify_sparse
+dd if=/dev/zero bs=1000 count=100 2>/dev/null >> dummy_verify_sparse
+head -c 5000 dummy >> dummy_verify_sparse
+
+for ver in 1 17; do
+  echo -n "Encoding sparse container in version $ver"
+  output=$(./../blkar encode --json --sbx-version $ver --rs-data 10 --rs-parity 2 -f --sparse dummy_verify_sparse verify_sparse.sbx)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+  fi
+  if (( $(echo $output | jq -r ".stats.numberOfBlankBlocksOmitted") > 0 )); then
+      echo " ==> Okay"
+  else
+      echo " ==> NOT okay"
+      exit_code=1
+  fi
+
+  echo -n "Verifying sparse container"
+  output=$(./../blkar verify --json verify_sparse.sbx dummy_verify_sparse)
+  if [[ $(echo $output | jq -r ".error") != null ]]; then
+      echo " ==> Invalid JSON"
+      exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.numberOfMissingBytes") == 0 ]]; then
+      echo -n " ==> Okay"
+  else
+      echo -n " ==> NOT okay"
+      exit_code=1
+  fi
+  if [[ $(echo $output | jq -r ".stats.sourceMatchesContainer") == true ]]; then
+      echo " ==> Okay"
+  else
+      echo " ==> NOT okay"
+      exit_code=1
+  fi
+done
+
+echo $exit_code > exit_code